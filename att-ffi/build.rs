@@ -0,0 +1,27 @@
+use std::env;
+
+/// Set to write the header to include/att.h instead of the build directory,
+/// e.g. after changing the exported functions.
+const UPDATE_HEADER_VAR: &str = "ATT_FFI_UPDATE_HEADER";
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("Could not read cbindgen.toml");
+
+    let header = match env::var_os(UPDATE_HEADER_VAR) {
+        Some(_) => format!("{}/include/att.h", crate_dir),
+        None => format!("{}/att.h", env::var("OUT_DIR").expect("OUT_DIR is not set")),
+    };
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate C header")
+        .write_to_file(header);
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed={}", UPDATE_HEADER_VAR);
+}
@@ -0,0 +1,85 @@
+#ifndef ATT_H
+#define ATT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Returned by functions with an out-parameter when they succeed.
+#define ATT_OK 0
+
+// Returned by functions with an out-parameter when they fail.
+#define ATT_ERROR -1
+
+// Opaque handle to a parsed attack tree. It must stay on the thread that
+// created it.
+typedef struct AttTree AttTree;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses the attack tree in `att_text` using the criteria definition in
+// `criteria_json` (the contents of a criteria.json file).
+//
+// Returns NULL on error.
+//
+// # Safety
+//
+// Both arguments must be NULL or valid NUL-terminated strings.
+struct AttTree *att_tree_parse(const char *att_text, const char *criteria_json);
+
+// Releases a tree returned by `att_tree_parse`.
+//
+// # Safety
+//
+// `tree` must be NULL or a pointer returned by `att_tree_parse` that has not
+// been freed yet.
+void att_tree_free(struct AttTree *tree);
+
+// Returns the title of the tree's root node.
+//
+// # Safety
+//
+// `tree` must be NULL or a valid tree handle.
+char *att_tree_title(const struct AttTree *tree);
+
+// Stores the feasibility value (sum of the aggregated assessments) of the
+// tree's root node in `value`.
+//
+// Returns `ATT_OK`, or `ATT_ERROR` if an argument is NULL or the tree has no
+// feasibility value.
+//
+// # Safety
+//
+// `tree` must be NULL or a valid tree handle, `value` NULL or valid for
+// writes.
+int32_t att_tree_feasibility_value(const struct AttTree *tree, uint32_t *value);
+
+// Renders the tree to a Graphviz DOT document.
+//
+// # Safety
+//
+// `tree` must be NULL or a valid tree handle.
+char *att_tree_to_dot(const struct AttTree *tree);
+
+// Releases a string returned by this library.
+//
+// # Safety
+//
+// `s` must be NULL or a string returned by this library that has not been
+// freed yet.
+void att_string_free(char *s);
+
+// Returns the message of the last error on the calling thread, or NULL.
+//
+// The pointer stays valid until the next call into this library on the same
+// thread and must not be freed.
+const char *att_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ATT_H */
@@ -0,0 +1,377 @@
+//! C ABI for embedding the attack tree tool into other applications.
+//!
+//! All strings crossing the boundary are NUL-terminated UTF-8. Strings returned
+//! by this library must be released with `att_string_free`, trees with
+//! `att_tree_free`. When a function fails it returns NULL or `ATT_ERROR` and
+//! the reason can be queried with `att_last_error`.
+//!
+//! The library is not thread-safe: a tree must only be used and freed on the
+//! thread that parsed it, and `att_last_error` reports the errors of the
+//! calling thread.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    io::Cursor,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    rc::Rc,
+};
+
+use att::{
+    model::{feasible_step::FeasibleStep, traversal::release_tree, FeasibilityCriteria},
+    parser::AttackTreeParser,
+    render::render_to_dot_string,
+};
+
+/// Returned by functions with an out-parameter when they succeed.
+pub const ATT_OK: i32 = 0;
+/// Returned by functions with an out-parameter when they fail.
+pub const ATT_ERROR: i32 = -1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Opaque handle to a parsed attack tree. It must stay on the thread that
+/// created it.
+pub struct AttTree {
+    root: Rc<dyn FeasibleStep>,
+}
+
+impl Drop for AttTree {
+    fn drop(&mut self) {
+        release_tree(&self.root);
+    }
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| e.replace(Some(message)));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|e| e.replace(None));
+}
+
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_last_error(format!("{} is NULL", name));
+        return None;
+    }
+
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_last_error(format!("{} is not valid UTF-8", name));
+            None
+        }
+    }
+}
+
+fn to_c_string(s: &str) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(_) => {
+            set_last_error("string contains an interior NUL byte".to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+fn parse_tree(att_text: &str, criteria_json: &str) -> Result<AttTree, String> {
+    let definition = FeasibilityCriteria::from_json(criteria_json)
+        .map_err(|e| format!("criteria error: {}", e))?;
+    let definition = Rc::new(definition);
+
+    let mut parser = AttackTreeParser::new();
+    let root = parser
+        .parse(&mut Cursor::new(att_text), &definition)
+        .map_err(|e| {
+            let mut message = format!("tree error: {}", e);
+            for d in parser.diagnostics() {
+                message.push_str(&format!("\n{}", d));
+            }
+            message
+        })?;
+
+    Ok(AttTree { root })
+}
+
+/// Parses the attack tree in `att_text` using the criteria definition in
+/// `criteria_json` (the contents of a criteria.json file).
+///
+/// Returns NULL on error.
+///
+/// # Safety
+///
+/// Both arguments must be NULL or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn att_tree_parse(
+    att_text: *const c_char,
+    criteria_json: *const c_char,
+) -> *mut AttTree {
+    clear_last_error();
+
+    let (Some(att_text), Some(criteria_json)) = (
+        read_str(att_text, "att_text"),
+        read_str(criteria_json, "criteria_json"),
+    ) else {
+        return ptr::null_mut();
+    };
+
+    // a panic must not unwind into C
+    let result = panic::catch_unwind(AssertUnwindSafe(|| parse_tree(att_text, criteria_json)))
+        .unwrap_or_else(|_| Err("tree error: parser panicked".to_string()));
+
+    match result {
+        Ok(tree) => Box::into_raw(Box::new(tree)),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a tree returned by `att_tree_parse`.
+///
+/// # Safety
+///
+/// `tree` must be NULL or a pointer returned by `att_tree_parse` that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn att_tree_free(tree: *mut AttTree) {
+    if !tree.is_null() {
+        drop(Box::from_raw(tree));
+    }
+}
+
+/// Returns the title of the tree's root node.
+///
+/// # Safety
+///
+/// `tree` must be NULL or a valid tree handle.
+#[no_mangle]
+pub unsafe extern "C" fn att_tree_title(tree: *const AttTree) -> *mut c_char {
+    clear_last_error();
+
+    match tree.as_ref() {
+        Some(tree) => to_c_string(tree.root.title()),
+        None => {
+            set_last_error("tree is NULL".to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Stores the feasibility value (sum of the aggregated assessments) of the
+/// tree's root node in `value`.
+///
+/// Returns `ATT_OK`, or `ATT_ERROR` if an argument is NULL or the tree has no
+/// feasibility value.
+///
+/// # Safety
+///
+/// `tree` must be NULL or a valid tree handle, `value` NULL or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn att_tree_feasibility_value(tree: *const AttTree, value: *mut u32) -> i32 {
+    clear_last_error();
+
+    let Some(tree) = tree.as_ref() else {
+        set_last_error("tree is NULL".to_string());
+        return ATT_ERROR;
+    };
+    if value.is_null() {
+        set_last_error("value is NULL".to_string());
+        return ATT_ERROR;
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        tree.root
+            .feasibility()
+            .map(|f| f.sum())
+            .map_err(|e| e.to_string())
+    }))
+    .unwrap_or_else(|_| Err("feasibility computation panicked".to_string()));
+
+    match result {
+        Ok(sum) => {
+            *value = sum;
+            ATT_OK
+        }
+        Err(e) => {
+            set_last_error(format!("tree error: {}", e));
+            ATT_ERROR
+        }
+    }
+}
+
+/// Renders the tree to a Graphviz DOT document.
+///
+/// # Safety
+///
+/// `tree` must be NULL or a valid tree handle.
+#[no_mangle]
+pub unsafe extern "C" fn att_tree_to_dot(tree: *const AttTree) -> *mut c_char {
+    clear_last_error();
+
+    let Some(tree) = tree.as_ref() else {
+        set_last_error("tree is NULL".to_string());
+        return ptr::null_mut();
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        render_to_dot_string(&tree.root).map_err(|e| e.to_string())
+    }))
+    .unwrap_or_else(|_| Err("renderer panicked".to_string()));
+
+    match result {
+        Ok(dot) => to_c_string(&dot),
+        Err(e) => {
+            set_last_error(format!("render error: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library that has not been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn att_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Returns the message of the last error on the calling thread, or NULL.
+///
+/// The pointer stays valid until the next call into this library on the same
+/// thread and must not be freed.
+#[no_mangle]
+pub extern "C" fn att_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match e.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRITERIA: &str =
+        r#"[{"id": "Eq", "name": "Equipment"}, {"id": "Kn", "name": "Knowledge"}]"#;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    #[test]
+    fn a_tree_can_be_parsed_and_computed() {
+        let text = c("Enter house;|\n    Trick people; Kn=6, Eq=0\n    Pick lock; Kn=5, Eq=3");
+        let criteria = c(CRITERIA);
+
+        unsafe {
+            let tree = att_tree_parse(text.as_ptr(), criteria.as_ptr());
+            assert!(!tree.is_null());
+
+            let mut value = 0;
+            assert_eq!(att_tree_feasibility_value(tree, &mut value), ATT_OK);
+            assert_eq!(value, 6);
+
+            let title = att_tree_title(tree);
+            assert_eq!(CStr::from_ptr(title).to_str().unwrap(), "Enter house");
+            att_string_free(title);
+
+            let dot = att_tree_to_dot(tree);
+            assert!(CStr::from_ptr(dot)
+                .to_str()
+                .unwrap()
+                .starts_with("digraph G {"));
+            att_string_free(dot);
+
+            att_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn freed_trees_release_their_nodes() {
+        let text = c("Enter house;|\n    Trick people; Kn=6, Eq=0\n    Pick lock; Kn=5, Eq=3");
+        let criteria = c(CRITERIA);
+
+        unsafe {
+            let tree = att_tree_parse(text.as_ptr(), criteria.as_ptr());
+            let root = Rc::downgrade(&(*tree).root);
+            let leaf = Rc::downgrade(&(*tree).root.get_children()[0]);
+            // the handle and the parent links of both children
+            assert_eq!(root.strong_count(), 3);
+
+            att_tree_free(tree);
+
+            assert!(root.upgrade().is_none());
+            assert!(leaf.upgrade().is_none());
+        }
+    }
+
+    #[test]
+    fn parse_errors_are_reported_through_last_error() {
+        let text = c("Break into house;  Kn=5.1, Eq=3");
+        let criteria = c(CRITERIA);
+
+        unsafe {
+            let tree = att_tree_parse(text.as_ptr(), criteria.as_ptr());
+            assert!(tree.is_null());
+
+            let error = CStr::from_ptr(att_last_error()).to_str().unwrap();
+            assert_eq!(
+                error,
+                "tree error: Syntax error in line 1\nline 1: invalid value '5.1' for 'Kn': expected a non-negative integer"
+            );
+        }
+    }
+
+    #[test]
+    fn null_arguments_are_rejected() {
+        unsafe {
+            assert!(att_tree_parse(ptr::null(), ptr::null()).is_null());
+            assert!(!att_last_error().is_null());
+            let mut value = 7;
+            assert_eq!(
+                att_tree_feasibility_value(ptr::null(), &mut value),
+                ATT_ERROR
+            );
+            assert_eq!(value, 7);
+            assert_eq!(
+                CStr::from_ptr(att_last_error()).to_str().unwrap(),
+                "tree is NULL"
+            );
+        }
+    }
+
+    #[test]
+    fn trees_without_a_feasibility_value_report_an_error() {
+        let text = c("Enter house;&");
+        let criteria = c(CRITERIA);
+
+        unsafe {
+            let tree = att_tree_parse(text.as_ptr(), criteria.as_ptr());
+            assert!(!tree.is_null());
+
+            let mut value = 7;
+            assert_eq!(att_tree_feasibility_value(tree, &mut value), ATT_ERROR);
+            assert_eq!(value, 7);
+            assert_eq!(
+                CStr::from_ptr(att_last_error()).to_str().unwrap(),
+                "tree error: 'Enter house' has no children"
+            );
+
+            assert_eq!(att_tree_feasibility_value(tree, ptr::null_mut()), ATT_ERROR);
+            att_tree_free(tree);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::{io::Cursor, rc::Rc};
+
+use att::{
+    model::{feasible_step::FeasibleStep, FeasibilityCriteria, FeasiblityCriterion},
+    parser::AttackTreeParser,
+    render::render_to_dot_string,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+/// Children per inner node and depth of the synthetic tree, giving 11111 nodes.
+const BRANCHING: usize = 10;
+const DEPTH: usize = 4;
+
+fn criteria() -> Rc<FeasibilityCriteria> {
+    Rc::new(FeasibilityCriteria(
+        vec![
+            FeasiblityCriterion::new("Kn", "Knowledge"),
+            FeasiblityCriterion::new("Eq", "Equipment"),
+            FeasiblityCriterion::new("Tm", "Time"),
+        ],
+        vec![],
+    ))
+}
+
+/// Builds a tree file with alternating AND and OR levels.
+fn synthetic_tree(branching: usize, depth: usize) -> String {
+    fn write_node(result: &mut String, path: &str, level: usize, branching: usize, depth: usize) {
+        let indentation = " ".repeat(4 * level);
+        if level == depth {
+            let seed = path.bytes().map(|b| b as usize).sum::<usize>();
+            result.push_str(&format!(
+                "{}Step {}; Kn={}, Eq={}, Tm={}\n",
+                indentation,
+                path,
+                seed % 5,
+                seed % 7,
+                seed % 3
+            ));
+            return;
+        }
+
+        let operator = if level.is_multiple_of(2) { "&" } else { "|" };
+        result.push_str(&format!("{}Step {};{}\n", indentation, path, operator));
+        for i in 0..branching {
+            write_node(
+                result,
+                &format!("{}.{}", path, i),
+                level + 1,
+                branching,
+                depth,
+            );
+        }
+    }
+
+    let mut result = String::new();
+    write_node(&mut result, "0", 0, branching, depth);
+    result
+}
+
+fn parse(text: &str, definition: &Rc<FeasibilityCriteria>) -> Rc<dyn FeasibleStep> {
+    AttackTreeParser::new()
+        .parse(&mut Cursor::new(text), definition)
+        .unwrap()
+}
+
+fn bench_parsing(c: &mut Criterion) {
+    let definition = criteria();
+    let text = synthetic_tree(BRANCHING, DEPTH);
+
+    c.bench_function("parse 11k nodes", |b| {
+        b.iter(|| parse(black_box(&text), &definition))
+    });
+}
+
+fn bench_feasibility(c: &mut Criterion) {
+    let definition = criteria();
+    let text = synthetic_tree(BRANCHING, DEPTH);
+
+    // a fresh tree per iteration, the result is cached after the first call
+    c.bench_function("feasibility of 11k nodes", |b| {
+        b.iter_batched(
+            || parse(&text, &definition),
+            |root| root.feasibility_value(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn bench_rendering(c: &mut Criterion) {
+    let definition = criteria();
+    let root = parse(&synthetic_tree(BRANCHING, DEPTH), &definition);
+
+    c.bench_function("render 11k nodes to dot", |b| {
+        b.iter(|| render_to_dot_string(black_box(&root)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_parsing, bench_feasibility, bench_rendering);
+criterion_main!(benches);
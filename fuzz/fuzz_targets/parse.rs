@@ -0,0 +1,24 @@
+#![no_main]
+
+use std::{io::Cursor, rc::Rc};
+
+use att::{
+    model::{FeasibilityCriteria, FeasiblityCriterion},
+    parser::AttackTreeParser,
+    render::{render_to_att_string, render_to_dot_string},
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let definition = Rc::new(FeasibilityCriteria(vec![
+        FeasiblityCriterion::new("Eq", "Equipment"),
+        FeasiblityCriterion::new("Kn", "Knowledge"),
+    ]));
+
+    let mut parser = AttackTreeParser::new();
+    if let Ok(root) = parser.parse(&mut Cursor::new(data), &definition) {
+        root.feasibility_value();
+        let _ = render_to_dot_string(&root);
+        render_to_att_string(&root);
+    }
+});
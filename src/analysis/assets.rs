@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, path::PathBuf, rc::Rc};
+
+use crate::model::{
+    feasible_step::{FeasibleStep, NAMESPACE_SEPARATOR},
+    traversal::preorder,
+};
+
+use super::NodeLocation;
+
+/// The easiest attack on the nodes sharing the value of a custom attribute,
+/// e.g. on all subtrees marked with `x-asset=ECU`.
+#[derive(Debug, PartialEq)]
+pub struct AttributeSummary {
+    pub value: String,
+    /// The lowest feasibility value of the nodes with the value.
+    pub feasibility: u32,
+    /// The node with the lowest value.
+    pub easiest: NodeLocation,
+    /// Number of trees with the value on at least one node.
+    pub trees: usize,
+}
+
+/// Summarizes the feasibility of the nodes per value of the attribute `key`,
+/// sorted by value. Values of trees with a namespace are qualified with it,
+/// e.g. `Door::ECU`. Nodes without feasibility are left out.
+pub fn aggregate_by_attribute(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    key: &str,
+) -> Vec<AttributeSummary> {
+    let mut summaries: BTreeMap<String, AttributeSummary> = BTreeMap::new();
+
+    for (file, root) in attack_trees {
+        let mut counted = vec![];
+
+        for node in preorder(root) {
+            let Some(value) = node.attributes().get(key) else {
+                continue;
+            };
+            let value = &match node.namespace() {
+                Some(namespace) => format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, value),
+                None => value.clone(),
+            };
+            let Ok(feasibility) = node.feasibility().map(|f| f.sum()) else {
+                continue;
+            };
+            let location = NodeLocation {
+                file: file.clone(),
+                path: titles_from_root(&node),
+            };
+
+            let summary = summaries
+                .entry(value.clone())
+                .or_insert_with(|| AttributeSummary {
+                    value: value.clone(),
+                    feasibility,
+                    easiest: location.clone(),
+                    trees: 0,
+                });
+            if feasibility < summary.feasibility {
+                summary.feasibility = feasibility;
+                summary.easiest = location;
+            }
+            if !counted.contains(value) {
+                counted.push(value.clone());
+                summary.trees += 1;
+            }
+        }
+    }
+
+    summaries.into_values().collect()
+}
+
+fn titles_from_root(node: &Rc<dyn FeasibleStep>) -> Vec<String> {
+    let mut titles = vec![node.title().to_string()];
+    let mut parent = node.get_parent();
+    while let Some(p) = parent {
+        titles.push(p.title().to_string());
+        parent = p.get_parent();
+    }
+    titles.reverse();
+
+    titles
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn the_easiest_node_with_a_value_determines_its_feasibility() {
+        let definition = build_criteria(&["Kn"]);
+        let parse = |text: &str| {
+            AttackTreeParser::new()
+                .parse(&mut Cursor::new(text), &definition)
+                .unwrap()
+        };
+        let trees = vec![
+            (
+                PathBuf::from("flash.att"),
+                parse("Flash firmware;| x-asset=ECU\n    Via debug port; Kn=5\n    Via update; Kn=7"),
+            ),
+            (
+                PathBuf::from("spoof.att"),
+                parse("Spoof messages;&\n    Access bus; Kn=2, x-asset=CAN\n    Unlock ECU; Kn=3, x-asset=ECU"),
+            ),
+        ];
+
+        let summaries = aggregate_by_attribute(&trees, "x-asset");
+
+        assert_eq!(
+            summaries,
+            vec![
+                AttributeSummary {
+                    value: "CAN".to_string(),
+                    feasibility: 2,
+                    easiest: NodeLocation {
+                        file: PathBuf::from("spoof.att"),
+                        path: vec!["Spoof messages".to_string(), "Access bus".to_string()],
+                    },
+                    trees: 1,
+                },
+                AttributeSummary {
+                    value: "ECU".to_string(),
+                    feasibility: 3,
+                    easiest: NodeLocation {
+                        file: PathBuf::from("spoof.att"),
+                        path: vec!["Spoof messages".to_string(), "Unlock ECU".to_string()],
+                    },
+                    trees: 2,
+                },
+            ]
+        );
+        assert!(aggregate_by_attribute(&trees, "x-owner").is_empty());
+    }
+
+    #[test]
+    fn values_are_kept_apart_by_namespace() {
+        let definition = build_criteria(&["Kn"]);
+        let parse = |text: &str| {
+            AttackTreeParser::new()
+                .parse(&mut Cursor::new(text), &definition)
+                .unwrap()
+        };
+        let trees = vec![
+            (
+                PathBuf::from("door.att"),
+                parse("Open door;| namespace=Door\n    Unlock ECU; Kn=5, x-asset=ECU"),
+            ),
+            (
+                PathBuf::from("flash.att"),
+                parse("Flash firmware;|\n    Unlock ECU; Kn=3, x-asset=ECU"),
+            ),
+        ];
+
+        let values: Vec<_> = aggregate_by_attribute(&trees, "x-asset")
+            .into_iter()
+            .map(|s| (s.value, s.feasibility))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![("Door::ECU".to_string(), 5), ("ECU".to_string(), 3)]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::{path::PathBuf, rc::Rc};
+
+use crate::model::feasible_step::FeasibleStep;
+
+use super::NodeLocation;
+
+/// An assumption with the node it belongs to.
+#[derive(Debug, PartialEq)]
+pub struct AssumptionReference {
+    pub description: String,
+    pub note: Option<String>,
+    pub location: NodeLocation,
+}
+
+/// Collects the assumptions of all trees, in the order of the trees and their
+/// nodes.
+pub fn collect_assumptions(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> Vec<AssumptionReference> {
+    let mut result = vec![];
+
+    for (file, root) in attack_trees {
+        collect(root, file, &mut vec![], &mut result);
+    }
+
+    result
+}
+
+fn collect(
+    node: &Rc<dyn FeasibleStep>,
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    result: &mut Vec<AssumptionReference>,
+) {
+    path.push(node.title().to_string());
+
+    for a in node.assumptions() {
+        result.push(AssumptionReference {
+            description: a.description.clone(),
+            note: a.note.clone(),
+            location: NodeLocation {
+                file: file.clone(),
+                path: path.clone(),
+            },
+        });
+    }
+
+    for c in node.get_children() {
+        collect(&c, file, path, result);
+    }
+
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn assumptions_are_collected_with_their_node() {
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Root;|\n    No insider;~\n    Door; Kn=1\n        Lock is standard;~\n            > DIN 18252\n    Window; Kn=2",
+                ),
+                &build_criteria(&["Kn"]),
+            )
+            .unwrap();
+
+        let result = collect_assumptions(&[(PathBuf::from("a.att"), root)]);
+
+        let location = |path: &[&str]| NodeLocation {
+            file: PathBuf::from("a.att"),
+            path: path.iter().map(|p| p.to_string()).collect(),
+        };
+        assert_eq!(
+            result,
+            vec![
+                AssumptionReference {
+                    description: "No insider".to_string(),
+                    note: None,
+                    location: location(&["Root"]),
+                },
+                AssumptionReference {
+                    description: "Lock is standard".to_string(),
+                    note: Some("DIN 18252".to_string()),
+                    location: location(&["Root", "Door"]),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    content::fnv1a,
+    feasible_step::{FeasibleStep, NodeKind},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeStatus {
+    New,
+    Changed,
+}
+
+impl Display for ChangeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeStatus::New => write!(f, "new"),
+            ChangeStatus::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// Change status of the nodes of the current run, keyed by node id. Nodes
+/// that did not change are not contained.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChangeSet(pub HashMap<u32, ChangeStatus>);
+
+impl ChangeSet {
+    pub fn status(&self, node_id: u32) -> Option<ChangeStatus> {
+        self.0.get(&node_id).copied()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BaselineEntry {
+    pub file: String,
+    pub path: Vec<String>,
+    pub hash: String,
+    /// Missing in baselines written by older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feasibility_value: Option<u32>,
+}
+
+/// A threat that became riskier since the baseline was written.
+#[derive(Debug, PartialEq)]
+pub enum Regression {
+    /// The feasibility value of the threat decreased.
+    Easier {
+        file: String,
+        threat: String,
+        before: u32,
+        after: u32,
+    },
+    /// A threat not contained in the baseline is rated as high risk.
+    NewHighRisk {
+        file: String,
+        threat: String,
+        value: u32,
+    },
+}
+
+impl Display for Regression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Regression::Easier {
+                file,
+                threat,
+                before,
+                after,
+            } => write!(
+                f,
+                "{}: '{}' became easier, feasibility {} -> {}",
+                file, threat, before, after
+            ),
+            Regression::NewHighRisk {
+                file,
+                threat,
+                value,
+            } => write!(
+                f,
+                "{}: new threat '{}' is high risk, feasibility {}",
+                file, threat, value
+            ),
+        }
+    }
+}
+
+/// How a threat changed since the baseline, for the changelog.
+#[derive(Debug, PartialEq)]
+pub enum ThreatChange {
+    Added {
+        file: String,
+        threat: String,
+        value: u32,
+    },
+    Removed {
+        file: String,
+        threat: String,
+    },
+    /// The feasibility value of the threat changed.
+    Rerated {
+        file: String,
+        threat: String,
+        before: u32,
+        after: u32,
+    },
+}
+
+/// A snapshot of the content hashes of all nodes, used to highlight what was
+/// added or modified since the last assessment.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Baseline {
+    pub nodes: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn from_trees(attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)]) -> Baseline {
+        let mut nodes = vec![];
+
+        for (file, root) in attack_trees {
+            visit(root, &file_name(file), &mut vec![], &mut |_, entry| {
+                nodes.push(entry)
+            });
+        }
+
+        Baseline { nodes }
+    }
+
+    pub fn compare(&self, attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)]) -> ChangeSet {
+        let known: HashMap<(&str, &[String]), &str> = self
+            .nodes
+            .iter()
+            .map(|e| ((e.file.as_str(), e.path.as_slice()), e.hash.as_str()))
+            .collect();
+
+        let mut changes = HashMap::new();
+
+        for (file, root) in attack_trees {
+            visit(
+                root,
+                &file_name(file),
+                &mut vec![],
+                &mut |node, entry| match known.get(&(entry.file.as_str(), entry.path.as_slice())) {
+                    None => {
+                        changes.insert(node.id(), ChangeStatus::New);
+                    }
+                    Some(hash) if *hash != entry.hash => {
+                        changes.insert(node.id(), ChangeStatus::Changed);
+                    }
+                    _ => {}
+                },
+            );
+        }
+
+        ChangeSet(changes)
+    }
+
+    /// The title paths of the nodes of a tree that are in the baseline but
+    /// no longer in the tree, parents before their children.
+    pub fn removed_nodes(&self, file: &Path, root: &Rc<dyn FeasibleStep>) -> Vec<&[String]> {
+        let file = file_name(file);
+        let mut current = HashSet::new();
+        visit(root, &file, &mut vec![], &mut |_, entry| {
+            current.insert(entry.path);
+        });
+
+        self.nodes
+            .iter()
+            .filter(|e| e.file == file && !current.contains(&e.path))
+            .map(|e| e.path.as_slice())
+            .collect()
+    }
+
+    /// Compares the root feasibility values to the baseline. Threats not in
+    /// the baseline are regressions if their value is at or below
+    /// `high_risk_max`.
+    pub fn find_regressions(
+        &self,
+        attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+        high_risk_max: Option<u32>,
+    ) -> Vec<Regression> {
+        let known: HashMap<(&str, &str), Option<u32>> = self
+            .nodes
+            .iter()
+            .filter(|e| e.path.len() == 1)
+            .map(|e| ((e.file.as_str(), e.path[0].as_str()), e.feasibility_value))
+            .collect();
+
+        let mut regressions = vec![];
+        for (file, root) in attack_trees {
+            let file = file_name(file);
+            let threat = root.title().to_string();
+            let value = root.feasibility_value();
+
+            match known.get(&(file.as_str(), threat.as_str())).copied() {
+                Some(Some(before)) if value < before => regressions.push(Regression::Easier {
+                    file,
+                    threat,
+                    before,
+                    after: value,
+                }),
+                None if high_risk_max.is_some_and(|max| value <= max) => {
+                    regressions.push(Regression::NewHighRisk {
+                        file,
+                        threat,
+                        value,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        regressions
+    }
+
+    /// The threats added, removed or rerated since the baseline. Threats are
+    /// identified by file and title, so a renamed threat is removed and added.
+    pub fn threat_changes(
+        &self,
+        attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    ) -> Vec<ThreatChange> {
+        let known: HashMap<(&str, &str), Option<u32>> = self
+            .nodes
+            .iter()
+            .filter(|e| e.path.len() == 1)
+            .map(|e| ((e.file.as_str(), e.path[0].as_str()), e.feasibility_value))
+            .collect();
+
+        let mut changes = vec![];
+        let mut current = vec![];
+        for (file, root) in attack_trees {
+            let file = file_name(file);
+            let threat = root.title().to_string();
+            let value = root.feasibility_value();
+            current.push((file.clone(), threat.clone()));
+
+            match known.get(&(file.as_str(), threat.as_str())).copied() {
+                None => changes.push(ThreatChange::Added {
+                    file,
+                    threat,
+                    value,
+                }),
+                Some(Some(before)) if before != value => changes.push(ThreatChange::Rerated {
+                    file,
+                    threat,
+                    before,
+                    after: value,
+                }),
+                _ => {}
+            }
+        }
+
+        for e in self.nodes.iter().filter(|e| e.path.len() == 1) {
+            if !current.contains(&(e.file.clone(), e.path[0].clone())) {
+                changes.push(ThreatChange::Removed {
+                    file: e.file.clone(),
+                    threat: e.path[0].clone(),
+                });
+            }
+        }
+
+        changes
+    }
+
+    /// Replaces `old` by `new` in the node paths, so that renamed nodes are
+    /// not reported as new. Returns the number of changed entries.
+    pub fn rename(&mut self, old: &str, new: &str) -> usize {
+        let mut count = 0;
+
+        for entry in &mut self.nodes {
+            let mut changed = false;
+            for title in entry.path.iter_mut().filter(|t| *t == old) {
+                *title = new.to_string();
+                changed = true;
+            }
+            count += usize::from(changed);
+        }
+
+        count
+    }
+}
+
+fn file_name(file: &Path) -> String {
+    file.file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn visit(
+    node: &Rc<dyn FeasibleStep>,
+    file: &str,
+    path: &mut Vec<String>,
+    f: &mut dyn FnMut(&Rc<dyn FeasibleStep>, BaselineEntry),
+) {
+    path.push(node.title().to_string());
+
+    f(
+        node,
+        BaselineEntry {
+            file: file.to_string(),
+            path: path.clone(),
+            hash: format!("{:016x}", node_hash(node)),
+            feasibility_value: Some(node.feasibility_value()),
+        },
+    );
+
+    for c in node.get_children() {
+        visit(&c, file, path, f);
+    }
+
+    path.pop();
+}
+
+/// FNV-1a hash of a node's own content (type and assessment), which stays
+/// stable across runs and compiler versions.
+fn node_hash(node: &Rc<dyn FeasibleStep>) -> u64 {
+    let mut content = node.kind().to_string();
+
+    if node.kind() == NodeKind::Leaf {
+        if let Ok(f) = node.feasibility() {
+            for (c, v) in f.iter() {
+                content.push_str(&format!(";{}={:?}", c.id, v));
+            }
+        }
+    }
+
+    fnv1a(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(text: &str) -> Vec<(PathBuf, Rc<dyn FeasibleStep>)> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        vec![(PathBuf::from("dir/tree.att"), root)]
+    }
+
+    #[test]
+    fn an_unmodified_tree_has_no_changes() {
+        let text = "Root;&\n    A; Kn=1, Eq=1\n    B; Kn=2, Eq=2";
+        let baseline = Baseline::from_trees(&parse(text));
+
+        assert!(baseline.compare(&parse(text)).0.is_empty());
+    }
+
+    #[test]
+    fn renamed_nodes_stay_known() {
+        let mut baseline = Baseline::from_trees(&parse(
+            "Root;&\n    A;|\n        B; Kn=1, Eq=1\n    C; Kn=2, Eq=2",
+        ));
+
+        assert_eq!(baseline.rename("A", "X"), 2);
+        assert!(baseline
+            .compare(&parse(
+                "Root;&\n    X;|\n        B; Kn=1, Eq=1\n    C; Kn=2, Eq=2"
+            ))
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn added_and_modified_nodes_are_detected() {
+        let baseline = Baseline::from_trees(&parse("Root;&\n    A; Kn=1, Eq=1\n    B; Kn=2, Eq=2"));
+
+        let trees = parse("Root;&\n    A; Kn=1, Eq=1\n    B; Kn=3, Eq=2\n    C; Kn=1, Eq=1");
+        let changes = baseline.compare(&trees);
+
+        let children = trees[0].1.get_children();
+        assert_eq!(changes.0.len(), 2);
+        assert_eq!(changes.status(children[0].id()), None);
+        assert_eq!(
+            changes.status(children[1].id()),
+            Some(ChangeStatus::Changed)
+        );
+        assert_eq!(changes.status(children[2].id()), Some(ChangeStatus::New));
+    }
+
+    #[test]
+    fn easier_threats_and_new_high_risk_threats_are_regressions() {
+        let mut trees = parse("Root;|\n    A; Kn=3, Eq=3\n    B; Kn=5, Eq=5");
+        trees.extend(parse("Other; Kn=9, Eq=9"));
+        let baseline = Baseline::from_trees(&trees);
+
+        let mut trees = parse("Root;|\n    A; Kn=1, Eq=3\n    B; Kn=5, Eq=5");
+        trees.extend(parse("Other; Kn=9, Eq=10"));
+        trees.extend(parse("New; Kn=1, Eq=1"));
+        let regressions = baseline.find_regressions(&trees, Some(4));
+
+        assert_eq!(
+            regressions,
+            vec![
+                Regression::Easier {
+                    file: "tree.att".to_string(),
+                    threat: "Root".to_string(),
+                    before: 6,
+                    after: 4,
+                },
+                Regression::NewHighRisk {
+                    file: "tree.att".to_string(),
+                    threat: "New".to_string(),
+                    value: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            regressions[0].to_string(),
+            "tree.att: 'Root' became easier, feasibility 6 -> 4"
+        );
+        assert_eq!(baseline.find_regressions(&trees, None).len(), 1);
+    }
+
+    #[test]
+    fn added_removed_and_rerated_threats_are_listed() {
+        let mut trees = parse("Root;|\n    A; Kn=3, Eq=3\n    B; Kn=5, Eq=5");
+        trees.extend(parse("Old; Kn=9, Eq=9"));
+        trees.extend(parse("Same; Kn=2, Eq=2"));
+        let baseline = Baseline::from_trees(&trees);
+
+        let mut trees = parse("Root;|\n    A; Kn=1, Eq=3\n    B; Kn=5, Eq=5");
+        trees.extend(parse("Same; Kn=2, Eq=2"));
+        trees.extend(parse("New; Kn=1, Eq=1"));
+
+        assert_eq!(
+            baseline.threat_changes(&trees),
+            vec![
+                ThreatChange::Rerated {
+                    file: "tree.att".to_string(),
+                    threat: "Root".to_string(),
+                    before: 6,
+                    after: 4,
+                },
+                ThreatChange::Added {
+                    file: "tree.att".to_string(),
+                    threat: "New".to_string(),
+                    value: 2,
+                },
+                ThreatChange::Removed {
+                    file: "tree.att".to_string(),
+                    threat: "Old".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn baselines_without_feasibility_values_have_no_regressions() {
+        let baseline: Baseline = serde_json::from_str(
+            r#"{"nodes": [{"file": "tree.att", "path": ["Root"], "hash": "0"}]}"#,
+        )
+        .unwrap();
+
+        assert!(baseline
+            .find_regressions(&parse("Root; Kn=0, Eq=0"), Some(4))
+            .is_empty());
+    }
+
+    #[test]
+    fn nodes_missing_from_the_tree_are_removed() {
+        let baseline = Baseline::from_trees(&parse(
+            "Root;&\n    A;|\n        B; Kn=1, Eq=1\n    C; Kn=2, Eq=2",
+        ));
+        let trees = parse("Root;&\n    C; Kn=2, Eq=2\n    D; Kn=1, Eq=1");
+
+        let removed = baseline.removed_nodes(&trees[0].0, &trees[0].1);
+
+        assert_eq!(
+            removed,
+            vec![
+                &["Root".to_string(), "A".to_string()][..],
+                &["Root".to_string(), "A".to_string(), "B".to_string()][..]
+            ]
+        );
+    }
+
+    #[test]
+    fn a_baseline_survives_serialization() {
+        let baseline = Baseline::from_trees(&parse("Root;|\n    A; Kn=1, Eq=1"));
+
+        let json = serde_json::to_string(&baseline).unwrap();
+
+        assert_eq!(serde_json::from_str::<Baseline>(&json).unwrap(), baseline);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{collections::BTreeSet, path::PathBuf, rc::Rc};
+
+use crate::model::{feasible_step::FeasibleStep, Requirement};
+
+use super::NodeLocation;
+
+/// Which threats address which requirement.
+#[derive(Debug, PartialEq)]
+pub struct CoverageMatrix {
+    /// Titles of the root nodes, one column each.
+    pub threats: Vec<String>,
+    pub rows: Vec<RequirementCoverage>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RequirementCoverage {
+    pub requirement: Requirement,
+    /// One entry per threat, true if any of its nodes references the requirement.
+    pub covered: Vec<bool>,
+}
+
+impl RequirementCoverage {
+    pub fn is_covered(&self) -> bool {
+        self.covered.iter().any(|c| *c)
+    }
+}
+
+/// A node referencing a requirement id that requirements.json does not define.
+#[derive(Debug, PartialEq)]
+pub struct UnknownRequirement {
+    pub location: NodeLocation,
+    pub id: String,
+}
+
+pub fn build_coverage_matrix(
+    requirements: &[Requirement],
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> CoverageMatrix {
+    let referenced: Vec<BTreeSet<String>> = attack_trees
+        .iter()
+        .map(|(_, root)| {
+            let mut ids = BTreeSet::new();
+            collect_ids(root, &mut ids);
+            ids
+        })
+        .collect();
+
+    CoverageMatrix {
+        threats: attack_trees
+            .iter()
+            .map(|(_, root)| root.title().to_string())
+            .collect(),
+        rows: requirements
+            .iter()
+            .map(|r| RequirementCoverage {
+                requirement: r.clone(),
+                covered: referenced.iter().map(|ids| ids.contains(&r.id)).collect(),
+            })
+            .collect(),
+    }
+}
+
+fn collect_ids(node: &Rc<dyn FeasibleStep>, ids: &mut BTreeSet<String>) {
+    ids.extend(node.requirements().iter().cloned());
+
+    for c in node.get_children() {
+        collect_ids(&c, ids);
+    }
+}
+
+/// Finds references to requirement ids missing from `requirements`.
+pub fn find_unknown_requirements(
+    requirements: &[Requirement],
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> Vec<UnknownRequirement> {
+    let known: BTreeSet<&str> = requirements.iter().map(|r| r.id.as_str()).collect();
+    let mut result = vec![];
+
+    for (file, root) in attack_trees {
+        collect_unknown(root, file, &mut vec![], &known, &mut result);
+    }
+
+    result
+}
+
+fn collect_unknown(
+    node: &Rc<dyn FeasibleStep>,
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    known: &BTreeSet<&str>,
+    result: &mut Vec<UnknownRequirement>,
+) {
+    path.push(node.title().to_string());
+
+    for id in node.requirements() {
+        if !known.contains(id.as_str()) {
+            result.push(UnknownRequirement {
+                location: NodeLocation {
+                    file: file.clone(),
+                    path: path.clone(),
+                },
+                id: id.clone(),
+            });
+        }
+    }
+
+    for c in node.get_children() {
+        collect_unknown(&c, file, path, known, result);
+    }
+
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn requirement(id: &str) -> Requirement {
+        Requirement {
+            id: id.to_string(),
+            title: format!("Requirement {}", id),
+        }
+    }
+
+    fn parse_trees(sources: &[&str]) -> Vec<(PathBuf, Rc<dyn FeasibleStep>)> {
+        let definition = build_criteria(&["Kn"]);
+        sources
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let root = AttackTreeParser::new()
+                    .parse(&mut Cursor::new(s), &definition)
+                    .unwrap();
+                (PathBuf::from(format!("{}.att", i)), root)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn requirements_referenced_anywhere_in_a_tree_cover_the_threat() {
+        let trees = parse_trees(&[
+            "Steal data;| requirement=SR-1\n    Phish; Kn=1, requirement=SR-2",
+            "Sabotage;|\n    Cut cable; Kn=1, requirement=SR-2",
+        ]);
+
+        let result = build_coverage_matrix(
+            &[
+                requirement("SR-1"),
+                requirement("SR-2"),
+                requirement("SR-3"),
+            ],
+            &trees,
+        );
+
+        assert_eq!(result.threats, ["Steal data", "Sabotage"]);
+        assert_eq!(result.rows[0].covered, [true, false]);
+        assert_eq!(result.rows[1].covered, [true, true]);
+        assert_eq!(result.rows[2].covered, [false, false]);
+        assert!(!result.rows[2].is_covered());
+    }
+
+    #[test]
+    fn references_to_undefined_requirements_are_found() {
+        let trees = parse_trees(&["Root;|\n    Step; Kn=1, requirement=SR-9"]);
+
+        let result = find_unknown_requirements(&[requirement("SR-1")], &trees);
+
+        assert_eq!(
+            result,
+            vec![UnknownRequirement {
+                location: NodeLocation {
+                    file: PathBuf::from("0.att"),
+                    path: vec!["Root".to_string(), "Step".to_string()],
+                },
+                id: "SR-9".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::{collections::BTreeMap, io::Cursor, path::PathBuf, rc::Rc};
+
+use crate::{
+    model::{FeasibilityCriteria, TreeError},
+    parser::{AttackTreeParser, Diagnostic, ParserOptions},
+};
+
+/// The criteria used in the .att files that the definition does not have,
+/// with the files and lines using them. Unlike a run, which stops at the
+/// first file that fails, this lists every use at once, e.g. after criteria
+/// were renamed or removed.
+pub fn unknown_criteria(
+    files: &[(PathBuf, String)],
+    definition: &Rc<FeasibilityCriteria>,
+    options: &ParserOptions,
+) -> BTreeMap<String, Vec<(PathBuf, u32)>> {
+    let mut unknown: BTreeMap<String, Vec<(PathBuf, u32)>> = BTreeMap::new();
+
+    for (file, text) in files {
+        for d in diagnostics(text, definition, options) {
+            if let Some(TreeError::UnknownCriterion(criterion)) = d.error {
+                unknown
+                    .entry(criterion)
+                    .or_default()
+                    .push((file.clone(), d.line));
+            }
+        }
+    }
+
+    unknown
+}
+
+/// The problems that parsing the files with the criteria `new` finds and
+/// parsing them with `old` does not, e.g. leaves using a removed criterion.
+pub fn invalidated_by(
+    files: &[(PathBuf, String)],
+    old: &Rc<FeasibilityCriteria>,
+    new: &Rc<FeasibilityCriteria>,
+    options: &ParserOptions,
+) -> Vec<(PathBuf, Diagnostic)> {
+    let mut invalidated = vec![];
+
+    for (file, text) in files {
+        let before = diagnostics(text, old, options);
+        for d in diagnostics(text, new, options) {
+            if !before
+                .iter()
+                .any(|b| b.line == d.line && b.message == d.message)
+            {
+                invalidated.push((file.clone(), d));
+            }
+        }
+    }
+
+    invalidated
+}
+
+/// The diagnostics of a file, whether it parses or not.
+fn diagnostics(
+    text: &str,
+    definition: &Rc<FeasibilityCriteria>,
+    options: &ParserOptions,
+) -> Vec<Diagnostic> {
+    let mut parser = AttackTreeParser::new();
+    let _ = parser.parse_with_options(&mut Cursor::new(text), definition, options);
+
+    parser.diagnostics().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tests::build_criteria;
+
+    #[test]
+    fn all_uses_of_unknown_criteria_are_listed_by_criterion() {
+        let files = [
+            (
+                PathBuf::from("a.att"),
+                "Root;&\n    A; Kn=1, Ti=2\n    B; Wo=1\n        Lock;! Ti=1".to_string(),
+            ),
+            (PathBuf::from("b.att"), "Root; Ti=3, Eq=1".to_string()),
+            (PathBuf::from("c.att"), "Root; Kn=3".to_string()),
+        ];
+
+        let unknown = unknown_criteria(
+            &files,
+            &build_criteria(&["Kn", "Eq"]),
+            &ParserOptions::default(),
+        );
+
+        assert_eq!(
+            unknown,
+            BTreeMap::from([
+                (
+                    "Ti".to_string(),
+                    vec![
+                        (PathBuf::from("a.att"), 2),
+                        (PathBuf::from("a.att"), 4),
+                        (PathBuf::from("b.att"), 1)
+                    ]
+                ),
+                ("Wo".to_string(), vec![(PathBuf::from("a.att"), 3)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn only_problems_caused_by_the_new_criteria_are_reported() {
+        let files = [
+            (
+                PathBuf::from("a.att"),
+                "Root;&\n    A; Kn=1, Eq=2\n    B; Kn=1, Xy=1".to_string(),
+            ),
+            (PathBuf::from("b.att"), "Root; Kn=3".to_string()),
+        ];
+
+        let invalidated = invalidated_by(
+            &files,
+            &build_criteria(&["Kn", "Eq"]),
+            &build_criteria(&["Kn"]),
+            &ParserOptions::default(),
+        );
+
+        assert_eq!(
+            invalidated
+                .iter()
+                .map(|(f, d)| (f.to_str().unwrap(), d.line, d.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("a.att", 2, "unknown criterion 'Eq'")]
+        );
+    }
+}
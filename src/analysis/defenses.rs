@@ -0,0 +1,73 @@
+use std::{path::PathBuf, rc::Rc};
+
+use crate::model::{apply_defenses, count_defenses, feasible_step::FeasibleStep};
+
+/// The feasibility of a threat without and with its defenses in place.
+#[derive(Debug, PartialEq)]
+pub struct ResidualFeasibility {
+    pub file: PathBuf,
+    pub threat: String,
+    pub without_defenses: u32,
+    pub with_defenses: u32,
+    pub defenses: usize,
+}
+
+/// Compares the feasibility of every tree that has defenses with and without
+/// them.
+pub fn compare_defenses(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> Vec<ResidualFeasibility> {
+    attack_trees
+        .iter()
+        .filter_map(|(file, root)| {
+            let defenses = count_defenses(root);
+            (defenses > 0).then(|| ResidualFeasibility {
+                file: file.clone(),
+                threat: root.title().to_string(),
+                without_defenses: root.feasibility_value(),
+                with_defenses: apply_defenses(root).feasibility_value(),
+                defenses,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(text: &str) -> Rc<dyn FeasibleStep> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap()
+    }
+
+    #[test]
+    fn only_trees_with_defenses_are_compared() {
+        let trees = vec![
+            (
+                PathBuf::from("a.att"),
+                parse("Enter house;|\n    Alarm;! Kn=2\n    Pick lock; Kn=5, Eq=3\n    Break window; Kn=1, Eq=1\n        Laminated glass;! Eq=10"),
+            ),
+            (PathBuf::from("b.att"), parse("Steal car; Kn=1, Eq=1")),
+        ];
+
+        let result = compare_defenses(&trees);
+
+        // the window now costs 14, picking the lock 10
+        assert_eq!(
+            result,
+            vec![ResidualFeasibility {
+                file: PathBuf::from("a.att"),
+                threat: "Enter house".to_string(),
+                without_defenses: 2,
+                with_defenses: 10,
+                defenses: 2,
+            }]
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::HashMap, path::PathBuf, rc::Rc};
+
+use crate::model::{
+    content::content_hash_ignoring_numbers,
+    feasible_step::{FeasibleStep, NodeKind},
+};
+
+use super::NodeLocation;
+
+/// A subtree that occurs more than once with identical structure, titles and
+/// assessments.
+#[derive(Debug, PartialEq)]
+pub struct DuplicateSubtree {
+    pub title: String,
+    pub node_count: usize,
+    pub locations: Vec<NodeLocation>,
+}
+
+struct Occurrence {
+    location: NodeLocation,
+    node_count: usize,
+    parent_key: Option<u64>,
+}
+
+/// Finds subtrees with children that appear more than once across all trees.
+/// Duplicates that are only part of a larger duplicate are not reported.
+pub fn find_duplicate_subtrees(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> Vec<DuplicateSubtree> {
+    let mut occurrences: HashMap<u64, Vec<Occurrence>> = HashMap::new();
+    let mut keys_in_order = vec![];
+
+    for (file, root) in attack_trees {
+        collect(
+            root,
+            file,
+            &mut vec![],
+            None,
+            &mut occurrences,
+            &mut keys_in_order,
+        );
+    }
+
+    let is_duplicated = |key: &u64| occurrences.get(key).is_some_and(|o| o.len() > 1);
+
+    keys_in_order
+        .iter()
+        .filter(|key| is_duplicated(key))
+        .filter(|key| {
+            // skip duplicates that only occur inside copies of a larger duplicate
+            let occurrences = &occurrences[*key];
+            let parent_key = &occurrences[0].parent_key;
+            !(parent_key.as_ref().is_some_and(is_duplicated)
+                && occurrences.iter().all(|o| &o.parent_key == parent_key))
+        })
+        .map(|key| {
+            let occurrences = &occurrences[key];
+            let first = &occurrences[0];
+
+            DuplicateSubtree {
+                title: first.location.path.last().cloned().unwrap_or_default(),
+                node_count: first.node_count,
+                locations: occurrences.iter().map(|o| o.location.clone()).collect(),
+            }
+        })
+        .collect()
+}
+
+fn collect(
+    node: &Rc<dyn FeasibleStep>,
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    parent_key: Option<u64>,
+    occurrences: &mut HashMap<u64, Vec<Occurrence>>,
+    keys_in_order: &mut Vec<u64>,
+) -> usize {
+    path.push(node.title().to_string());
+
+    // uuids and numbers differ between copies of a subtree, the hash skips them
+    let key = content_hash_ignoring_numbers(node);
+    let mut node_count = 1;
+
+    for c in node.get_children() {
+        node_count += collect(&c, file, path, Some(key), occurrences, keys_in_order);
+    }
+
+    if node.kind() != NodeKind::Leaf && !node.get_children().is_empty() {
+        let entry = occurrences.entry(key).or_default();
+        if entry.is_empty() {
+            keys_in_order.push(key);
+        }
+
+        entry.push(Occurrence {
+            location: NodeLocation {
+                file: file.clone(),
+                path: path.clone(),
+            },
+            node_count,
+            parent_key,
+        });
+    }
+
+    path.pop();
+    node_count
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        model::tests::build_criteria,
+        parser::{assign_uuids, AttackTreeParser},
+    };
+
+    fn parse(file: &str, text: &str) -> (PathBuf, Rc<dyn FeasibleStep>) {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        (PathBuf::from(file), root)
+    }
+
+    #[test]
+    fn identical_subtrees_in_different_files_are_found() {
+        let trees = vec![
+            parse(
+                "a.att",
+                "Root A;&\n    Get key;|\n        Steal; Kn=1, Eq=1\n        Copy; Kn=2, Eq=3\n    Open; Kn=1, Eq=1",
+            ),
+            parse(
+                "b.att",
+                "Root B;|\n    Get key;|\n        Steal; Kn=1, Eq=1\n        Copy; Kn=2, Eq=3",
+            ),
+        ];
+
+        let result = find_duplicate_subtrees(&trees);
+
+        assert_eq!(
+            result,
+            vec![DuplicateSubtree {
+                title: "Get key".to_string(),
+                node_count: 3,
+                locations: vec![
+                    NodeLocation {
+                        file: PathBuf::from("a.att"),
+                        path: vec!["Root A".to_string(), "Get key".to_string()],
+                    },
+                    NodeLocation {
+                        file: PathBuf::from("b.att"),
+                        path: vec!["Root B".to_string(), "Get key".to_string()],
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn copies_with_different_uuids_and_numbers_are_duplicates() {
+        let text = "Root;&\n    Get key;|\n        Steal; Kn=1, Eq=1\n        Copy; Kn=2, Eq=3";
+        let mut counter = 0;
+        let mut with_uuids = || {
+            let (text, _) = assign_uuids(text, || {
+                counter += 1;
+                format!("00000000-0000-4000-8000-{:012}", counter)
+            })
+            .unwrap();
+            text
+        };
+        let a = with_uuids();
+        let b = with_uuids()
+            .replace("Root;&", "Other root;|")
+            .replace("Get key;| ", "Get key;| number=2, ");
+        let trees = vec![parse("a.att", &a), parse("b.att", &b)];
+
+        let result = find_duplicate_subtrees(&trees);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].title, "Get key");
+        assert_eq!(result[0].locations.len(), 2);
+    }
+
+    #[test]
+    fn subtrees_with_different_assessments_are_not_duplicates() {
+        let trees = vec![
+            parse("a.att", "Root;&\n    Get key;|\n        Steal; Kn=1, Eq=1"),
+            parse("b.att", "Root;&\n    Get key;|\n        Steal; Kn=1, Eq=2"),
+        ];
+
+        assert!(find_duplicate_subtrees(&trees).is_empty());
+    }
+
+    #[test]
+    fn only_the_largest_duplicate_is_reported() {
+        let text = "Root;&\n    Get key;|\n        Steal;&\n            Wait; Kn=1, Eq=1";
+        let trees = vec![parse("a.att", text), parse("b.att", text)];
+
+        let result = find_duplicate_subtrees(&trees);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].title, "Root");
+        assert_eq!(result[0].node_count, 4);
+    }
+}
@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use crate::model::feasible_step::FeasibleStep;
+
+/// The key under which threats count as the same logical threat: the title
+/// in lower case without qualifiers in parentheses and punctuation. For
+/// example `Spoof messages (CAN)` and `Spoof messages` share a key.
+pub fn threat_key(title: &str) -> String {
+    let mut key = String::new();
+    let mut depth: usize = 0;
+    for c in title.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if depth == 0 && c.is_alphanumeric() => key.extend(c.to_lowercase()),
+            _ if depth == 0 => key.push(' '),
+            _ => {}
+        }
+    }
+
+    key.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The indices of the roots grouped by namespace and `threat_key`, in the
+/// order of the first root of each group.
+pub fn group_threats(roots: &[&Rc<dyn FeasibleStep>]) -> Vec<Vec<usize>> {
+    let mut keys: Vec<(Option<String>, String)> = vec![];
+    let mut groups: Vec<Vec<usize>> = vec![];
+
+    for (index, root) in roots.iter().enumerate() {
+        let key = (root.namespace(), threat_key(root.title()));
+        match keys.iter().position(|k| *k == key) {
+            Some(group) => groups[group].push(index),
+            None => {
+                keys.push(key);
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn titles_differing_in_case_punctuation_and_qualifiers_are_grouped() {
+        assert_eq!(threat_key("Spoof  messages (CAN)"), "spoof messages");
+        assert_eq!(threat_key("Spoof messages!"), "spoof messages");
+        assert_eq!(threat_key("Spoof-messages"), "spoof messages");
+
+        let definition = build_criteria(&["Kn"]);
+        let roots: Vec<_> = [
+            "Spoof messages (CAN); Kn=1",
+            "Flash firmware; Kn=2",
+            "spoof messages (Ethernet); Kn=3",
+        ]
+        .iter()
+        .map(|t| {
+            AttackTreeParser::new()
+                .parse(&mut Cursor::new(*t), &definition)
+                .unwrap()
+        })
+        .collect();
+
+        assert_eq!(
+            group_threats(&roots.iter().collect::<Vec<_>>()),
+            vec![vec![0, 2], vec![1]]
+        );
+    }
+
+    #[test]
+    fn threats_of_different_namespaces_are_not_grouped() {
+        let definition = build_criteria(&["Kn"]);
+        let roots: Vec<_> = [
+            "Spoof messages;| namespace=Gateway\n    Replay; Kn=1",
+            "Spoof messages;| namespace=Infotainment\n    Replay; Kn=2",
+            "Spoof messages (CAN);| namespace=Gateway\n    Inject; Kn=3",
+        ]
+        .iter()
+        .map(|t| {
+            AttackTreeParser::new()
+                .parse(&mut Cursor::new(*t), &definition)
+                .unwrap()
+        })
+        .collect();
+
+        assert_eq!(
+            group_threats(&roots.iter().collect::<Vec<_>>()),
+            vec![vec![0, 2], vec![1]]
+        );
+    }
+}
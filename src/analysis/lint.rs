@@ -0,0 +1,213 @@
+use std::{collections::HashMap, fmt::Display, path::Path, rc::Rc};
+
+use serde::Deserialize;
+
+use crate::model::feasible_step::{FeasibleStep, NodeKind};
+
+use super::NodeLocation;
+
+pub const MAX_DEPTH: &str = "max-depth";
+pub const MAX_CHILDREN: &str = "max-children";
+pub const COMPLETE_ASSESSMENT: &str = "complete-assessment";
+pub const UNIQUE_TITLES: &str = "unique-titles";
+pub const SINGLE_CHILD: &str = "single-child";
+
+pub const RULES: [&str; 5] = [
+    MAX_DEPTH,
+    MAX_CHILDREN,
+    COMPLETE_ASSESSMENT,
+    UNIQUE_TITLES,
+    SINGLE_CHILD,
+];
+
+/// Modeling conventions checked by `att lint`, read from the `[lint]` section
+/// of att.toml. The depth and children limits only apply when set.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LintRules {
+    /// Deepest level of a node below the root, which is at level 0.
+    pub max_depth: Option<usize>,
+    pub max_children: Option<usize>,
+    /// Names of the rules to skip, e.g. "unique-titles".
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+impl LintRules {
+    fn is_enabled(&self, rule: &str) -> bool {
+        !self.disable.iter().any(|r| r == rule)
+    }
+}
+
+/// A violated modeling convention.
+#[derive(Debug, PartialEq)]
+pub struct LintWarning {
+    pub location: NodeLocation,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} [{}]", self.location, self.message, self.rule)
+    }
+}
+
+/// Checks a tree against the enabled rules, in the order of the nodes.
+pub fn lint_tree(file: &Path, root: &Rc<dyn FeasibleStep>, rules: &LintRules) -> Vec<LintWarning> {
+    let mut linter = Linter {
+        file,
+        rules,
+        path: vec![],
+        titles: HashMap::new(),
+        warnings: vec![],
+    };
+    linter.visit(root);
+
+    linter.warnings
+}
+
+struct Linter<'a> {
+    file: &'a Path,
+    rules: &'a LintRules,
+    path: Vec<String>,
+    titles: HashMap<String, Vec<String>>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter<'_> {
+    fn visit(&mut self, node: &Rc<dyn FeasibleStep>) {
+        self.path.push(node.title().to_string());
+        let depth = self.path.len() - 1;
+        let children = node.get_children();
+
+        if let Some(max) = self.rules.max_depth {
+            if depth == max + 1 && self.rules.is_enabled(MAX_DEPTH) {
+                self.warn(
+                    MAX_DEPTH,
+                    format!("node is at depth {}, the maximum is {}", depth, max),
+                );
+            }
+        }
+
+        if let Some(max) = self.rules.max_children {
+            if children.len() > max && self.rules.is_enabled(MAX_CHILDREN) {
+                self.warn(
+                    MAX_CHILDREN,
+                    format!(
+                        "node has {} children, the maximum is {}",
+                        children.len(),
+                        max
+                    ),
+                );
+            }
+        }
+
+        if node.kind() == NodeKind::Leaf {
+            if let Err(e) = node.feasibility().and_then(|a| a.check_complete()) {
+                if self.rules.is_enabled(COMPLETE_ASSESSMENT) {
+                    self.warn(COMPLETE_ASSESSMENT, e.to_string());
+                }
+            }
+        } else if children.len() == 1 && self.rules.is_enabled(SINGLE_CHILD) {
+            self.warn(
+                SINGLE_CHILD,
+                format!("{} node has a single child", node.kind()),
+            );
+        }
+
+        if self.rules.is_enabled(UNIQUE_TITLES) {
+            match self.titles.get(node.title()) {
+                Some(first) => {
+                    let message = format!("title is also used at {}", first.join(" > "));
+                    self.warn(UNIQUE_TITLES, message);
+                }
+                None => {
+                    self.titles
+                        .insert(node.title().to_string(), self.path.clone());
+                }
+            }
+        }
+
+        for c in children {
+            self.visit(&c);
+        }
+
+        self.path.pop();
+    }
+
+    fn warn(&mut self, rule: &'static str, message: String) {
+        self.warnings.push(LintWarning {
+            location: NodeLocation {
+                file: self.file.to_path_buf(),
+                path: self.path.clone(),
+            },
+            rule,
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn lint(text: &str, rules: &LintRules) -> Vec<String> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        lint_tree(Path::new("a.att"), &root, rules)
+            .iter()
+            .map(|w| w.to_string())
+            .collect()
+    }
+
+    const TREE: &str = "Root;|
+    Get in;&
+        Open door; Kn=1
+    Get in;|
+        Pick lock; Kn=1, Eq=2
+        Break window; Kn=1, Eq=1
+        Climb; Kn=2, Eq=1";
+
+    #[test]
+    fn default_rules_report_single_children_incomplete_leaves_and_duplicate_titles() {
+        assert_eq!(
+            lint(TREE, &LintRules::default()),
+            vec![
+                "a.att: Root > Get in: AND node has a single child [single-child]",
+                "a.att: Root > Get in > Open door: missing assessment for 'Eq' [complete-assessment]",
+                "a.att: Root > Get in: title is also used at Root > Get in [unique-titles]",
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_apply_when_set_and_rules_can_be_disabled() {
+        let rules = LintRules {
+            max_depth: Some(1),
+            max_children: Some(2),
+            disable: vec![
+                SINGLE_CHILD.to_string(),
+                COMPLETE_ASSESSMENT.to_string(),
+                UNIQUE_TITLES.to_string(),
+            ],
+        };
+
+        assert_eq!(
+            lint(TREE, &rules),
+            vec![
+                "a.att: Root > Get in > Open door: node is at depth 2, the maximum is 1 [max-depth]",
+                "a.att: Root > Get in: node has 3 children, the maximum is 2 [max-children]",
+                "a.att: Root > Get in > Pick lock: node is at depth 2, the maximum is 1 [max-depth]",
+                "a.att: Root > Get in > Break window: node is at depth 2, the maximum is 1 [max-depth]",
+                "a.att: Root > Get in > Climb: node is at depth 2, the maximum is 1 [max-depth]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fmt::Display, path::Path, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{content::fnv1a, feasible_step::FeasibleStep};
+
+/// The source of a tree and the feasibility computed from it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockedTree {
+    pub file: String,
+    /// FNV-1a hash of the contents of the .att file.
+    pub hash: String,
+    pub feasibility_value: u32,
+}
+
+/// The hashes of the inputs of a run and the results computed from them, so
+/// that a later run can show that the committed reports match the sources.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Lockfile {
+    /// FNV-1a hash of the criteria definition.
+    pub criteria: String,
+    /// The trees sorted by file name.
+    pub trees: Vec<LockedTree>,
+}
+
+/// A difference between a lockfile and the recomputed results.
+#[derive(Debug, PartialEq)]
+pub enum LockDifference {
+    Criteria,
+    Source(String),
+    Feasibility {
+        file: String,
+        locked: u32,
+        recomputed: u32,
+    },
+    Added(String),
+    Removed(String),
+}
+
+impl Display for LockDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockDifference::Criteria => write!(f, "the criteria differ from the locked ones"),
+            LockDifference::Source(file) => {
+                write!(f, "{}: the source differs from the locked one", file)
+            }
+            LockDifference::Feasibility {
+                file,
+                locked,
+                recomputed,
+            } => write!(
+                f,
+                "{}: feasibility {} is locked, {} is recomputed",
+                file, locked, recomputed
+            ),
+            LockDifference::Added(file) => write!(f, "{}: not in the lockfile", file),
+            LockDifference::Removed(file) => write!(f, "{}: locked but not found", file),
+        }
+    }
+}
+
+impl Lockfile {
+    /// An empty lockfile for the source of the criteria definition.
+    pub fn new(criteria: &[u8]) -> Lockfile {
+        Lockfile {
+            criteria: hash(criteria),
+            trees: vec![],
+        }
+    }
+
+    /// Locks the tree read from `source`.
+    pub fn add(&mut self, file: &Path, source: &[u8], root: &Rc<dyn FeasibleStep>) {
+        let file = file
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("")
+            .to_string();
+        let position = self.trees.partition_point(|t| t.file < file);
+
+        self.trees.insert(
+            position,
+            LockedTree {
+                file,
+                hash: hash(source),
+                feasibility_value: root.feasibility_value(),
+            },
+        );
+    }
+
+    /// The differences of the recomputed results to the locked ones.
+    pub fn compare(&self, recomputed: &Lockfile) -> Vec<LockDifference> {
+        let mut differences = vec![];
+        if self.criteria != recomputed.criteria {
+            differences.push(LockDifference::Criteria);
+        }
+
+        for tree in &recomputed.trees {
+            match self.trees.iter().find(|t| t.file == tree.file) {
+                None => differences.push(LockDifference::Added(tree.file.clone())),
+                Some(locked) => {
+                    if locked.hash != tree.hash {
+                        differences.push(LockDifference::Source(tree.file.clone()));
+                    }
+                    if locked.feasibility_value != tree.feasibility_value {
+                        differences.push(LockDifference::Feasibility {
+                            file: tree.file.clone(),
+                            locked: locked.feasibility_value,
+                            recomputed: tree.feasibility_value,
+                        });
+                    }
+                }
+            }
+        }
+        for locked in &self.trees {
+            if !recomputed.trees.iter().any(|t| t.file == locked.file) {
+                differences.push(LockDifference::Removed(locked.file.clone()));
+            }
+        }
+
+        differences
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::PathBuf};
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn lock(criteria: &str, trees: &[(&str, &str)]) -> Lockfile {
+        let definition = build_criteria(&["Kn"]);
+        let mut lockfile = Lockfile::new(criteria.as_bytes());
+        for (file, source) in trees {
+            let root = AttackTreeParser::new()
+                .parse(&mut Cursor::new(*source), &definition)
+                .unwrap();
+            lockfile.add(&PathBuf::from("dir").join(file), source.as_bytes(), &root);
+        }
+        lockfile
+    }
+
+    #[test]
+    fn recomputed_results_are_compared_to_the_locked_ones() {
+        let locked = lock(
+            "[]",
+            &[
+                ("b.att", "B; Kn=2"),
+                ("a.att", "A; Kn=1"),
+                ("c.att", "C; Kn=3"),
+            ],
+        );
+        assert_eq!(
+            locked
+                .trees
+                .iter()
+                .map(|t| t.file.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a.att", "b.att", "c.att"]
+        );
+        assert!(locked.compare(&locked.clone()).is_empty());
+
+        let recomputed = lock(
+            "[ ]",
+            &[
+                ("a.att", "A; Kn=1"),
+                ("b.att", "B; Kn=4"),
+                ("d.att", "D; Kn=1"),
+            ],
+        );
+        let differences = locked.compare(&recomputed);
+
+        assert_eq!(
+            differences,
+            vec![
+                LockDifference::Criteria,
+                LockDifference::Source("b.att".to_string()),
+                LockDifference::Feasibility {
+                    file: "b.att".to_string(),
+                    locked: 2,
+                    recomputed: 4
+                },
+                LockDifference::Added("d.att".to_string()),
+                LockDifference::Removed("c.att".to_string()),
+            ]
+        );
+        assert_eq!(
+            differences[2].to_string(),
+            "b.att: feasibility 2 is locked, 4 is recomputed"
+        );
+    }
+}
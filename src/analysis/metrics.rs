@@ -0,0 +1,121 @@
+use std::{path::PathBuf, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::feasible_step::FeasibleStep;
+
+/// Key figures of one run over all threats, appended to a metrics file to
+/// follow the risk over time.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunMetrics {
+    /// RFC 3339 time of the run.
+    pub timestamp: String,
+    pub threat_count: usize,
+    /// Mean root feasibility value, 0 without threats.
+    pub average_feasibility: f64,
+    /// Threats with a feasibility value at or below the high risk limit,
+    /// unset without a limit.
+    pub high_risk_count: Option<usize>,
+}
+
+pub fn compute_metrics(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    timestamp: &str,
+    high_risk_max: Option<u32>,
+) -> RunMetrics {
+    let values: Vec<u32> = attack_trees
+        .iter()
+        .map(|(_, root)| root.feasibility_value())
+        .collect();
+
+    let average_feasibility = if values.is_empty() {
+        0.0
+    } else {
+        values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64
+    };
+
+    RunMetrics {
+        timestamp: timestamp.to_string(),
+        threat_count: values.len(),
+        average_feasibility,
+        high_risk_count: high_risk_max.map(|max| values.iter().filter(|v| **v <= max).count()),
+    }
+}
+
+/// Adds the metrics to the JSON array of earlier runs, `existing` is empty
+/// for a new file.
+pub fn append_metrics_to_json(
+    existing: &str,
+    metrics: RunMetrics,
+) -> Result<String, serde_json::Error> {
+    let mut runs: Vec<RunMetrics> = if existing.trim().is_empty() {
+        vec![]
+    } else {
+        serde_json::from_str(existing)?
+    };
+    runs.push(metrics);
+
+    serde_json::to_string_pretty(&runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn trees(texts: &[&str]) -> Vec<(PathBuf, Rc<dyn FeasibleStep>)> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        texts
+            .iter()
+            .map(|t| {
+                let root = AttackTreeParser::new()
+                    .parse(&mut Cursor::new(*t), &definition)
+                    .unwrap();
+                (PathBuf::from("a.att"), root)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn threats_at_or_below_the_limit_are_high_risk() {
+        let trees = trees(&[
+            "A; Kn=1, Eq=2",
+            "B; Kn=5, Eq=5",
+            "C;|\n    D; Kn=2, Eq=2\n    E; Kn=9, Eq=9",
+        ]);
+
+        let metrics = compute_metrics(&trees, "2024-06-01T12:00:00+02:00", Some(4));
+
+        assert_eq!(
+            metrics,
+            RunMetrics {
+                timestamp: "2024-06-01T12:00:00+02:00".to_string(),
+                threat_count: 3,
+                average_feasibility: 17.0 / 3.0,
+                high_risk_count: Some(2),
+            }
+        );
+        assert_eq!(compute_metrics(&trees, "", None).high_risk_count, None);
+        assert_eq!(compute_metrics(&[], "", None).average_feasibility, 0.0);
+    }
+
+    #[test]
+    fn runs_are_appended_to_the_json_array() {
+        let run = |count| RunMetrics {
+            timestamp: "t".to_string(),
+            threat_count: count,
+            average_feasibility: 1.5,
+            high_risk_count: None,
+        };
+
+        let first = append_metrics_to_json("", run(1)).unwrap();
+        let second = append_metrics_to_json(&first, run(2)).unwrap();
+
+        let runs: Vec<RunMetrics> = serde_json::from_str(&second).unwrap();
+        assert_eq!(runs, vec![run(1), run(2)]);
+        assert!(append_metrics_to_json("{", run(3)).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use std::{fmt::Display, path::PathBuf};
+
+pub mod assets;
+pub mod assumptions;
+pub mod baseline;
+pub mod coverage;
+pub mod criteria_check;
+pub mod defenses;
+pub mod duplicates;
+pub mod grouping;
+pub mod lint;
+pub mod lock;
+pub mod metrics;
+pub mod simulation;
+pub mod staleness;
+pub mod stats;
+pub mod techniques;
+pub mod validation;
+pub mod weakest;
+
+/// Where a node is found: the tree file and the titles from the root down to
+/// the node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeLocation {
+    pub file: PathBuf,
+    pub path: Vec<String>,
+}
+
+impl Display for NodeLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file.display(), self.path.join(" > "))
+    }
+}
@@ -0,0 +1,175 @@
+use std::{path::PathBuf, rc::Rc};
+
+use crate::model::{feasible_step::FeasibleStep, resolve_ranges};
+
+use super::stats::HistogramBucket;
+
+/// The root feasibility values of a threat over all samples of the ranges
+/// of its leaves.
+#[derive(Debug, PartialEq)]
+pub struct Simulation {
+    pub file: PathBuf,
+    pub threat: String,
+    /// Sorted ascending.
+    pub samples: Vec<u32>,
+}
+
+impl Simulation {
+    /// The value `percent` of the samples are at or below, nearest rank.
+    pub fn percentile(&self, percent: u32) -> u32 {
+        let rank = (percent.min(100) as usize * self.samples.len()).div_ceil(100);
+        self.samples[rank.saturating_sub(1)]
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.samples.iter().map(|s| *s as f64).sum::<f64>() / self.samples.len() as f64
+    }
+
+    /// Counts of samples per range of values, from the lowest sample to the
+    /// highest in at most `buckets` buckets.
+    pub fn histogram(&self, buckets: u32) -> Vec<HistogramBucket> {
+        let (Some(min), Some(max)) = (self.samples.first(), self.samples.last()) else {
+            return vec![];
+        };
+        let width = (max - min + 1).div_ceil(buckets.max(1));
+
+        (0..=(max - min) / width)
+            .map(|i| {
+                let from = min + i * width;
+                let to = from + width - 1;
+
+                HistogramBucket {
+                    from,
+                    to,
+                    count: self
+                        .samples
+                        .iter()
+                        .filter(|s| (from..=to).contains(*s))
+                        .count(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Samples the value of every leaf range uniformly `iterations` times and
+/// records the root feasibility. Trees without ranges are left out. The same
+/// seed gives the same samples.
+pub fn simulate(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    iterations: u32,
+    seed: u64,
+) -> Vec<Simulation> {
+    let mut random = SplitMix64(seed);
+
+    attack_trees
+        .iter()
+        .filter(|(_, root)| root.feasibility_interval().is_some())
+        .map(|(file, root)| {
+            let mut samples: Vec<u32> = (0..iterations.max(1))
+                .map(|_| resolve_ranges(root, &mut |a, b| random.between(a, b)).feasibility_value())
+                .collect();
+            samples.sort_unstable();
+
+            Simulation {
+                file: file.clone(),
+                threat: root.title().to_string(),
+                samples,
+            }
+        })
+        .collect()
+}
+
+/// Small generator with reproducible output, statistical quality is enough
+/// for sampling assessments.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value between `a` and `b`, both included, in any order.
+    fn between(&mut self, a: u32, b: u32) -> u32 {
+        let (low, high) = (a.min(b), a.max(b));
+        low + (self.next() % (u64::from(high - low) + 1)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(text: &str) -> Rc<dyn FeasibleStep> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap()
+    }
+
+    #[test]
+    fn samples_stay_within_the_feasibility_interval() {
+        let trees = vec![
+            (
+                PathBuf::from("a.att"),
+                parse("Enter house;|\n    Pick lock; Kn=1..5, Eq=2\n    Break door; Kn=3, Eq=2..3"),
+            ),
+            (PathBuf::from("b.att"), parse("Steal car; Kn=1, Eq=1")),
+        ];
+
+        let result = simulate(&trees, 500, 7);
+
+        assert_eq!(result.len(), 1);
+        let simulation = &result[0];
+        assert_eq!(simulation.samples.len(), 500);
+        assert_eq!(simulation.samples.first(), Some(&3));
+        assert_eq!(simulation.samples.last(), Some(&6));
+        assert_eq!(simulate(&trees, 500, 7), result);
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let simulation = Simulation {
+            file: PathBuf::from("a.att"),
+            threat: "A".to_string(),
+            samples: vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+        };
+
+        assert_eq!(simulation.percentile(0), 1);
+        assert_eq!(simulation.percentile(50), 5);
+        assert_eq!(simulation.percentile(95), 10);
+        assert_eq!(simulation.mean(), 5.5);
+        assert_eq!(
+            simulation.histogram(4),
+            vec![
+                HistogramBucket {
+                    from: 1,
+                    to: 3,
+                    count: 3
+                },
+                HistogramBucket {
+                    from: 4,
+                    to: 6,
+                    count: 3
+                },
+                HistogramBucket {
+                    from: 7,
+                    to: 9,
+                    count: 3
+                },
+                HistogramBucket {
+                    from: 10,
+                    to: 12,
+                    count: 1
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::{path::PathBuf, rc::Rc};
+
+use chrono::NaiveDate;
+
+use crate::model::feasible_step::FeasibleStep;
+
+use super::NodeLocation;
+
+/// A leaf whose assessment has not been reviewed for longer than allowed.
+#[derive(Debug, PartialEq)]
+pub struct StaleAssessment {
+    pub location: NodeLocation,
+    pub assessed: NaiveDate,
+    pub age_days: i64,
+}
+
+/// Finds leaves assessed more than `max_age_days` days before `today`. Leaves
+/// without an assessment date are not reported. The oldest come first.
+pub fn find_stale_assessments(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    today: NaiveDate,
+    max_age_days: i64,
+) -> Vec<StaleAssessment> {
+    let mut result = vec![];
+
+    for (file, root) in attack_trees {
+        collect(root, file, &mut vec![], today, max_age_days, &mut result);
+    }
+
+    result.sort_by_key(|s| s.assessed);
+    result
+}
+
+fn collect(
+    node: &Rc<dyn FeasibleStep>,
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    today: NaiveDate,
+    max_age_days: i64,
+    result: &mut Vec<StaleAssessment>,
+) {
+    path.push(node.title().to_string());
+
+    if let Some(assessed) = node.assessed() {
+        let age_days = (today - assessed).num_days();
+        if age_days > max_age_days {
+            result.push(StaleAssessment {
+                location: NodeLocation {
+                    file: file.clone(),
+                    path: path.clone(),
+                },
+                assessed,
+                age_days,
+            });
+        }
+    }
+
+    for c in node.get_children() {
+        collect(&c, file, path, today, max_age_days, result);
+    }
+
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn only_assessments_older_than_the_maximum_age_are_reported() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Root;|\n    Old; Kn=1, assessed=2023-01-01\n    Recent; Kn=1, assessed=2024-05-01\n    Undated; Kn=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+        let trees = vec![(PathBuf::from("a.att"), root)];
+
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let result = find_stale_assessments(&trees, today, 365);
+
+        assert_eq!(
+            result,
+            vec![StaleAssessment {
+                location: NodeLocation {
+                    file: PathBuf::from("a.att"),
+                    path: vec!["Root".to_string(), "Old".to_string()],
+                },
+                assessed: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+                age_days: 517,
+            }]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf, rc::Rc};
+
+use serde::Serialize;
+
+use crate::model::feasible_step::{FeasibleStep, NodeKind};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TreeStatistics {
+    pub file: String,
+    pub title: String,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub depth: usize,
+    pub feasibility_value: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct HistogramBucket {
+    pub from: u32,
+    pub to: u32,
+    pub count: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Statistics {
+    pub tree_count: usize,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    /// Number of trees per tree depth, a single leaf has depth 1.
+    pub depth_distribution: BTreeMap<usize, usize>,
+    /// Number of trees per range of root feasibility values.
+    pub feasibility_histogram: Vec<HistogramBucket>,
+    pub trees: Vec<TreeStatistics>,
+}
+
+pub fn compute_statistics(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    bucket_width: u32,
+) -> Statistics {
+    let trees: Vec<TreeStatistics> = attack_trees
+        .iter()
+        .map(|(file, root)| {
+            let (node_count, leaf_count, depth) = measure(root);
+
+            TreeStatistics {
+                file: file
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or("")
+                    .to_string(),
+                title: root.title().to_string(),
+                node_count,
+                leaf_count,
+                depth,
+                feasibility_value: root.feasibility_value(),
+            }
+        })
+        .collect();
+
+    let mut depth_distribution = BTreeMap::new();
+    for t in &trees {
+        *depth_distribution.entry(t.depth).or_insert(0) += 1;
+    }
+
+    Statistics {
+        tree_count: trees.len(),
+        node_count: trees.iter().map(|t| t.node_count).sum(),
+        leaf_count: trees.iter().map(|t| t.leaf_count).sum(),
+        depth_distribution,
+        feasibility_histogram: histogram(&trees, bucket_width.max(1)),
+        trees,
+    }
+}
+
+/// Returns node count, leaf count and depth of a tree.
+fn measure(node: &Rc<dyn FeasibleStep>) -> (usize, usize, usize) {
+    let mut node_count = 1;
+    let mut leaf_count = usize::from(node.kind() == NodeKind::Leaf);
+    let mut depth = 0;
+
+    for c in node.get_children() {
+        let (n, l, d) = measure(&c);
+        node_count += n;
+        leaf_count += l;
+        depth = depth.max(d);
+    }
+
+    (node_count, leaf_count, depth + 1)
+}
+
+fn histogram(trees: &[TreeStatistics], bucket_width: u32) -> Vec<HistogramBucket> {
+    let Some(max_value) = trees.iter().map(|t| t.feasibility_value).max() else {
+        return vec![];
+    };
+
+    (0..=max_value / bucket_width)
+        .map(|i| {
+            let from = i * bucket_width;
+            let to = from + bucket_width - 1;
+
+            HistogramBucket {
+                from,
+                to,
+                count: trees
+                    .iter()
+                    .filter(|t| (from..=to).contains(&t.feasibility_value))
+                    .count(),
+            }
+        })
+        .collect()
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Trees:  {}", self.tree_count)?;
+        writeln!(f, "Nodes:  {}", self.node_count)?;
+        writeln!(f, "Leaves: {}", self.leaf_count)?;
+
+        writeln!(f, "\nDepth distribution:")?;
+        for (depth, count) in &self.depth_distribution {
+            writeln!(f, "  depth {:>3}: {}", depth, count)?;
+        }
+
+        writeln!(f, "\nFeasibility histogram:")?;
+        for b in &self.feasibility_histogram {
+            writeln!(f, "  {:>4}-{:<4} {}", b.from, b.to, "#".repeat(b.count))?;
+        }
+
+        writeln!(f, "\nTrees:")?;
+        for t in &self.trees {
+            writeln!(
+                f,
+                "  {}: {} nodes, {} leaves, depth {}, feasibility {} ({})",
+                t.title, t.node_count, t.leaf_count, t.depth, t.feasibility_value, t.file
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(file: &str, text: &str) -> (PathBuf, Rc<dyn FeasibleStep>) {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        (PathBuf::from(file), root)
+    }
+
+    #[test]
+    fn statistics_are_computed_over_all_trees() {
+        let trees = vec![
+            parse(
+                "dir/a.att",
+                "A;&\n    B;|\n        C; Kn=1, Eq=1\n        D; Kn=5, Eq=5\n    E; Kn=3, Eq=4",
+            ),
+            parse("dir/b.att", "Single; Kn=2, Eq=1"),
+        ];
+
+        let result = compute_statistics(&trees, 5);
+
+        assert_eq!(result.tree_count, 2);
+        assert_eq!(result.node_count, 6);
+        assert_eq!(result.leaf_count, 4);
+        assert_eq!(result.depth_distribution, BTreeMap::from([(1, 1), (3, 1)]));
+        assert_eq!(
+            result.feasibility_histogram,
+            vec![
+                HistogramBucket {
+                    from: 0,
+                    to: 4,
+                    count: 1
+                },
+                HistogramBucket {
+                    from: 5,
+                    to: 9,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            result.trees[0],
+            TreeStatistics {
+                file: "a.att".to_string(),
+                title: "A".to_string(),
+                node_count: 5,
+                leaf_count: 3,
+                depth: 3,
+                feasibility_value: 3 + 4,
+            }
+        );
+    }
+}
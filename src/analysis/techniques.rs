@@ -0,0 +1,96 @@
+use std::{collections::BTreeMap, path::PathBuf, rc::Rc};
+
+use crate::model::feasible_step::FeasibleStep;
+
+use super::NodeLocation;
+
+/// The leaves referencing one ATT&CK technique.
+#[derive(Debug, PartialEq)]
+pub struct TechniqueReferences {
+    pub technique: String,
+    pub locations: Vec<NodeLocation>,
+}
+
+/// Groups all leaves by the ATT&CK techniques they reference, ordered by
+/// technique id.
+pub fn group_by_technique(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> Vec<TechniqueReferences> {
+    let mut groups = BTreeMap::new();
+
+    for (file, root) in attack_trees {
+        collect(root, file, &mut vec![], &mut groups);
+    }
+
+    groups
+        .into_iter()
+        .map(|(technique, locations)| TechniqueReferences {
+            technique,
+            locations,
+        })
+        .collect()
+}
+
+fn collect(
+    node: &Rc<dyn FeasibleStep>,
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    groups: &mut BTreeMap<String, Vec<NodeLocation>>,
+) {
+    path.push(node.title().to_string());
+
+    for t in node.techniques() {
+        groups.entry(t.clone()).or_default().push(NodeLocation {
+            file: file.clone(),
+            path: path.clone(),
+        });
+    }
+
+    for c in node.get_children() {
+        collect(&c, file, path, groups);
+    }
+
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn leaves_are_grouped_by_technique() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Root;|\n    Phish; Kn=1, attack=T1566\n    Login; Kn=2, attack=T1078, attack=T1566",
+                ),
+                &definition,
+            )
+            .unwrap();
+        let trees = vec![(PathBuf::from("a.att"), root)];
+
+        let result = group_by_technique(&trees);
+
+        let location = |title: &str| NodeLocation {
+            file: PathBuf::from("a.att"),
+            path: vec!["Root".to_string(), title.to_string()],
+        };
+        assert_eq!(
+            result,
+            vec![
+                TechniqueReferences {
+                    technique: "T1078".to_string(),
+                    locations: vec![location("Login")],
+                },
+                TechniqueReferences {
+                    technique: "T1566".to_string(),
+                    locations: vec![location("Phish"), location("Login")],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::model::{
+    feasible_step::{FeasibleStep, NodeKind},
+    traversal::preorder_with_depth,
+};
+
+use super::NodeLocation;
+
+/// A problem in a parsed tree that prevents a meaningful feasibility value.
+#[derive(Debug, PartialEq)]
+pub struct ValidationError {
+    pub location: NodeLocation,
+    pub message: String,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Checks a tree for AND and OR nodes without children.
+pub fn validate_tree(file: &Path, root: &Rc<dyn FeasibleStep>) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    collect(root, file, &mut vec![], &mut errors);
+
+    errors
+}
+
+/// Reports nodes whose uuid a node of an earlier file already uses. The
+/// parser reports the uuids used twice within a file.
+pub fn find_duplicate_uuids(trees: &[(PathBuf, Rc<dyn FeasibleStep>)]) -> Vec<ValidationError> {
+    let mut files: HashMap<String, &Path> = HashMap::new();
+    let mut errors = vec![];
+
+    for (file, root) in trees {
+        let mut path = vec![];
+        for (depth, node) in preorder_with_depth(root) {
+            path.truncate(depth);
+            path.push(node.title().to_string());
+            let Some(uuid) = node.uuid() else {
+                continue;
+            };
+            let first = *files.entry(uuid.to_string()).or_insert(file);
+            if first != file {
+                errors.push(ValidationError {
+                    location: NodeLocation {
+                        file: file.clone(),
+                        path: path.clone(),
+                    },
+                    message: format!("uuid '{}' is already used in {}", uuid, first.display()),
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+fn collect(
+    node: &Rc<dyn FeasibleStep>,
+    file: &Path,
+    path: &mut Vec<String>,
+    errors: &mut Vec<ValidationError>,
+) {
+    path.push(node.title().to_string());
+
+    let children = node.get_children();
+    if node.kind() != NodeKind::Leaf && children.is_empty() {
+        errors.push(ValidationError {
+            location: NodeLocation {
+                file: file.to_path_buf(),
+                path: path.clone(),
+            },
+            message: format!("{} node '{}' has no children", node.kind(), node.title()),
+        });
+    }
+
+    for c in children {
+        collect(&c, file, path, errors);
+    }
+
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::PathBuf};
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn childless_inner_nodes_are_reported_with_their_location() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Root;|\n    Step; Kn=1\n    Todo;&"),
+                &definition,
+            )
+            .unwrap();
+
+        let errors = validate_tree(Path::new("a.att"), &root);
+
+        assert_eq!(
+            errors,
+            vec![ValidationError {
+                location: NodeLocation {
+                    file: PathBuf::from("a.att"),
+                    path: vec!["Root".to_string(), "Todo".to_string()],
+                },
+                message: "AND node 'Todo' has no children".to_string(),
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "a.att: Root > Todo: AND node 'Todo' has no children"
+        );
+    }
+
+    #[test]
+    fn uuids_used_in_another_file_are_reported() {
+        let definition = build_criteria(&["Kn"]);
+        let parse = |text: &str| {
+            AttackTreeParser::new()
+                .parse(&mut Cursor::new(text), &definition)
+                .unwrap()
+        };
+        let trees = vec![
+            (
+                PathBuf::from("a.att"),
+                parse("Root;|\n    Step; Kn=1, uuid=0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a"),
+            ),
+            (
+                PathBuf::from("b.att"),
+                parse("Other;|\n    Copy; Kn=1, uuid=0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a"),
+            ),
+        ];
+
+        let errors = find_duplicate_uuids(&trees);
+
+        assert_eq!(
+            errors,
+            vec![ValidationError {
+                location: NodeLocation {
+                    file: PathBuf::from("b.att"),
+                    path: vec!["Other".to_string(), "Copy".to_string()],
+                },
+                message: "uuid '0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a' is already used in a.att"
+                    .to_string(),
+            }]
+        );
+    }
+}
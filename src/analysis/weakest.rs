@@ -0,0 +1,202 @@
+use std::{path::PathBuf, rc::Rc};
+
+use crate::model::{
+    feasible_step::{FeasibleStep, NodeKind},
+    voting_node::find_easiest_combination,
+};
+
+use super::NodeLocation;
+
+/// A leaf whose values end up in the feasibility of the root.
+#[derive(Debug, PartialEq)]
+pub struct WeakestLeaf {
+    pub location: NodeLocation,
+    /// Ids of the criteria of the root assessment the leaf provides.
+    pub criteria: Vec<String>,
+}
+
+/// The leaves a threat's feasibility value is computed from.
+#[derive(Debug, PartialEq)]
+pub struct TreeWeakestLeaves {
+    pub file: PathBuf,
+    pub threat: String,
+    pub feasibility_value: u32,
+    pub leaves: Vec<WeakestLeaf>,
+}
+
+/// Follows every tree from the root to the leaves that determine its
+/// feasibility: the easiest child of OR nodes, the easiest combination of
+/// voting nodes and, for each criterion, the hardest child of AND nodes.
+pub fn find_weakest_leaves(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+) -> Vec<TreeWeakestLeaves> {
+    attack_trees
+        .iter()
+        .filter_map(|(file, root)| {
+            let assessment = root.feasibility().ok()?;
+            let criteria: Vec<usize> = (0..assessment.values().len())
+                .filter(|i| assessment.values()[*i].is_some())
+                .collect();
+
+            let mut leaves = vec![];
+            collect(root, file, &mut vec![], &criteria, &mut leaves);
+
+            Some(TreeWeakestLeaves {
+                file: file.clone(),
+                threat: root.title().to_string(),
+                feasibility_value: assessment.sum(),
+                leaves,
+            })
+        })
+        .collect()
+}
+
+/// `criteria` are the positions of the criteria the node provides to the
+/// root.
+fn collect(
+    node: &Rc<dyn FeasibleStep>,
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    criteria: &[usize],
+    leaves: &mut Vec<WeakestLeaf>,
+) {
+    path.push(node.title().to_string());
+
+    let children = node.get_children();
+    match node.kind() {
+        NodeKind::Leaf => {
+            if let Ok(assessment) = node.feasibility() {
+                let definition = assessment.definition();
+                leaves.push(WeakestLeaf {
+                    location: NodeLocation {
+                        file: file.clone(),
+                        path: path.clone(),
+                    },
+                    criteria: criteria
+                        .iter()
+                        .map(|i| definition.0[*i].id.clone())
+                        .collect(),
+                });
+            }
+        }
+        NodeKind::Or => {
+            // the first of the easiest children, like the OR node itself
+            let easiest = children
+                .iter()
+                .filter(|c| c.feasibility().is_ok())
+                .min_by_key(|c| c.feasibility_value());
+            if let Some(child) = easiest {
+                collect(child, file, path, criteria, leaves);
+            }
+        }
+        NodeKind::And => collect_hardest(&children, file, path, criteria, leaves),
+        NodeKind::Voting => {
+            let combination = find_easiest_combination(
+                node.title(),
+                node.threshold().unwrap_or(1),
+                children.iter().map(|c| c.feasibility()).collect(),
+            );
+            if let Ok((positions, _)) = combination {
+                let chosen: Vec<_> = positions.iter().map(|i| children[*i].clone()).collect();
+                collect_hardest(&chosen, file, path, criteria, leaves);
+            }
+        }
+    }
+
+    path.pop();
+}
+
+/// Descends into the child with the hardest value of each criterion, the
+/// first one on ties.
+fn collect_hardest(
+    children: &[Rc<dyn FeasibleStep>],
+    file: &PathBuf,
+    path: &mut Vec<String>,
+    criteria: &[usize],
+    leaves: &mut Vec<WeakestLeaf>,
+) {
+    let assessments: Vec<_> = children.iter().map(|c| c.feasibility().ok()).collect();
+    let mut provided: Vec<Vec<usize>> = vec![vec![]; children.len()];
+
+    for i in criteria {
+        let mut hardest: Option<(usize, u32)> = None;
+        for (position, assessment) in assessments.iter().enumerate() {
+            let Some(assessment) = assessment else {
+                continue;
+            };
+            let value = assessment.values()[*i];
+            if value.is_none() {
+                continue;
+            }
+            let difficulty = assessment.definition().0[*i].difficulty(value);
+            if hardest.is_none_or(|(_, d)| difficulty > d) {
+                hardest = Some((position, difficulty));
+            }
+        }
+        if let Some((position, _)) = hardest {
+            provided[position].push(*i);
+        }
+    }
+
+    for (child, criteria) in children.iter().zip(&provided) {
+        if !criteria.is_empty() {
+            collect(child, file, path, criteria, leaves);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn weakest_leaves(text: &str) -> Vec<(String, Vec<String>)> {
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &build_criteria(&["Kn", "Eq"]))
+            .unwrap();
+
+        find_weakest_leaves(&[(PathBuf::from("tree.att"), root)])
+            .remove(0)
+            .leaves
+            .into_iter()
+            .map(|l| (l.location.path.join(" > "), l.criteria))
+            .collect()
+    }
+
+    #[test]
+    fn the_easiest_path_and_the_hardest_steps_are_followed() {
+        let leaves = weakest_leaves(
+            "Root;|\n    Door;&\n        Pick lock; Kn=5, Eq=1\n        Open door; Kn=1, Eq=3\n    Window; Kn=9, Eq=9",
+        );
+
+        assert_eq!(
+            leaves,
+            vec![
+                (
+                    "Root > Door > Pick lock".to_string(),
+                    vec!["Kn".to_string()]
+                ),
+                (
+                    "Root > Door > Open door".to_string(),
+                    vec!["Eq".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn voting_nodes_follow_their_easiest_combination() {
+        let leaves =
+            weakest_leaves("Root;2/3\n    A; Kn=9, Eq=9\n    B; Kn=2, Eq=1\n    C; Kn=1, Eq=1");
+
+        assert_eq!(
+            leaves,
+            vec![(
+                "Root > B".to_string(),
+                vec!["Kn".to_string(), "Eq".to_string()]
+            )]
+        );
+    }
+}
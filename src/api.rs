@@ -0,0 +1,119 @@
+use std::{collections::BTreeMap, io::Cursor, rc::Rc};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    model::{
+        feasible_step::FeasibleStep, traversal::preorder, CriteriaError, FeasibilityCriteria,
+        TreeError,
+    },
+    parser::{AttackTreeParser, Diagnostic, TreeFileError},
+};
+
+#[derive(Error, Debug)]
+pub enum ComputeError {
+    #[error(transparent)]
+    Criteria(#[from] CriteriaError),
+    #[error("{}", describe(.error, .diagnostics))]
+    Tree {
+        error: TreeFileError,
+        /// The problems found, by line.
+        diagnostics: Vec<Diagnostic>,
+    },
+    #[error(transparent)]
+    Feasibility(#[from] TreeError),
+}
+
+fn describe(error: &TreeFileError, diagnostics: &[Diagnostic]) -> String {
+    match diagnostics.is_empty() {
+        true => error.to_string(),
+        false => diagnostics
+            .iter()
+            .map(Diagnostic::to_string)
+            .collect::<Vec<_>>()
+            .join("; "),
+    }
+}
+
+/// The feasibility of the root of a tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub title: String,
+    pub feasibility_value: u32,
+    /// The lowest and highest value if assessments are given as ranges.
+    pub feasibility_range: Option<(u32, u32)>,
+    /// The values of the root by criterion id, None if unrated.
+    pub criteria: BTreeMap<String, Option<u32>>,
+    /// The number of nodes of the tree.
+    pub nodes: usize,
+}
+
+/// Computes the feasibility of the tree in `att_text`, the contents of a
+/// .att file, with the criteria in `criteria_json`, the contents of a
+/// criteria.json file.
+pub fn compute_feasibility(att_text: &str, criteria_json: &str) -> Result<Summary, ComputeError> {
+    let definition = Rc::new(FeasibilityCriteria::from_json(criteria_json)?);
+
+    let mut parser = AttackTreeParser::new();
+    let root = parser
+        .parse(&mut Cursor::new(att_text), &definition)
+        .map_err(|error| ComputeError::Tree {
+            error,
+            diagnostics: parser.diagnostics().to_vec(),
+        })?;
+    Ok(summarize(&root)?)
+}
+
+/// The summary of a parsed tree.
+pub fn summarize(root: &Rc<dyn FeasibleStep>) -> Result<Summary, TreeError> {
+    let feasibility = root.feasibility()?;
+
+    Ok(Summary {
+        title: root.title().to_string(),
+        feasibility_value: feasibility.sum(),
+        feasibility_range: root.feasibility_interval(),
+        criteria: feasibility.iter().map(|(c, v)| (c.id.clone(), v)).collect(),
+        nodes: preorder(root).len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRITERIA: &str =
+        r#"[{"id": "Kn", "name": "Knowledge"}, {"id": "Eq", "name": "Equipment"}]"#;
+
+    #[test]
+    fn the_feasibility_is_computed_from_strings() {
+        let summary = compute_feasibility(
+            "Enter house;|\n    Pick lock; Kn=2..4, Eq=3\n    Break window; Kn=1, Eq=5",
+            CRITERIA,
+        )
+        .unwrap();
+
+        assert_eq!(
+            summary,
+            Summary {
+                title: "Enter house".to_string(),
+                feasibility_value: 5,
+                feasibility_range: Some((5, 6)),
+                criteria: BTreeMap::from([
+                    ("Eq".to_string(), Some(3)),
+                    ("Kn".to_string(), Some(2))
+                ]),
+                nodes: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn errors_name_the_problems_of_the_input() {
+        let error = compute_feasibility("Root;&\n    Step; Xy=1", CRITERIA).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown criterion 'Xy'");
+
+        let error = compute_feasibility("Root; Kn=1", "[").unwrap_err();
+        assert!(matches!(error, ComputeError::Criteria(_)));
+    }
+}
@@ -0,0 +1,386 @@
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    analysis::lint::{LintRules, RULES},
+    model::RatingLevel,
+    parser::ParserOptions,
+    render::{Engine, LayoutOptions},
+};
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("config file parser error: {0}")]
+    ParseError(#[from] toml::de::Error),
+    #[error("invalid file pattern '{0}': {1}")]
+    InvalidPattern(String, glob::PatternError),
+    #[error("unknown lint rule '{0}', expected one of: {rules}", rules = RULES.join(", "))]
+    UnknownLintRule(String),
+}
+
+/// Directory-level defaults read from att.toml. Command line arguments take
+/// precedence over all values.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Graphviz output format of the images.
+    pub format: Option<String>,
+    pub engine: Option<Engine>,
+    /// Backend rendering the images, e.g. `dot` for the DOT sources only.
+    pub renderer: Option<String>,
+    /// Layout settings, taking precedence over style.json.
+    #[serde(default)]
+    pub style: LayoutOptions,
+    #[serde(default)]
+    pub parser: ParserConfig,
+    #[serde(default)]
+    pub lint: LintRules,
+    pub max_assessment_age: Option<i64>,
+    pub report_template: Option<PathBuf>,
+    pub tree_template: Option<PathBuf>,
+    /// Bands of feasibility values shown as rating in the report.
+    #[serde(default)]
+    pub ratings: Vec<RatingLevel>,
+    /// Glob patterns of .att files to skip, relative to the directory.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Deployment profile selecting the subtrees to analyze.
+    pub profile: Option<String>,
+    /// Overlay file replacing assessments of the leaves, relative to the
+    /// directory.
+    pub overlay: Option<PathBuf>,
+    /// Custom attributes to summarize the feasibility by, e.g. `x-asset`.
+    #[serde(default)]
+    pub aggregate_by: Vec<String>,
+    /// Impact levels of the risk matrix, the lowest first.
+    #[serde(default)]
+    pub impacts: Vec<String>,
+    /// Show threats with similar titles in one row of the overview.
+    #[serde(default)]
+    pub group_threats: bool,
+    /// Compare the children of every OR node on the tree pages.
+    #[serde(default)]
+    pub compare_or: bool,
+    /// Image settings of single trees, the first entry matching a file
+    /// wins.
+    #[serde(default)]
+    pub images: Vec<ImageSettings>,
+    #[serde(skip)]
+    ignore_patterns: Vec<Pattern>,
+    #[serde(skip)]
+    image_patterns: Vec<Pattern>,
+    #[serde(skip)]
+    selected_files: Option<Vec<PathBuf>>,
+}
+
+/// An `[[images]]` entry of att.toml, rendering the trees matching `files`
+/// differently from the others, e.g. as PNG for slides and as PDF for print.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ImageSettings {
+    /// Glob pattern of the .att files, relative to the directory.
+    pub files: String,
+    /// Graphviz output format of the images.
+    pub format: Option<String>,
+    pub dpi: Option<u32>,
+    /// Largest width of the images in pixels.
+    pub max_width: Option<u32>,
+}
+
+impl ImageSettings {
+    /// The layout options of the images, taking precedence over the ones of
+    /// the directory.
+    pub fn layout(&self) -> LayoutOptions {
+        LayoutOptions {
+            dpi: self.dpi,
+            max_width: self.max_width,
+            ..Default::default()
+        }
+    }
+}
+
+/// The parser checks as they are selected in att.toml or on the command line.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ParserConfig {
+    /// Enables all checks, with an indentation width of 4 unless given.
+    #[serde(default)]
+    pub strict: bool,
+    pub indentation_width: Option<u32>,
+    #[serde(default)]
+    pub require_assessments: bool,
+    #[serde(default)]
+    pub forbid_empty_nodes: bool,
+    #[serde(default)]
+    pub ignore_criteria_case: bool,
+    pub max_depth: Option<usize>,
+    pub max_nodes: Option<usize>,
+}
+
+impl ParserConfig {
+    /// Combines two configurations, checks selected in either are enabled and
+    /// the indentation width and limits of `overrides` win.
+    pub fn merge(&self, overrides: &ParserConfig) -> ParserConfig {
+        ParserConfig {
+            strict: self.strict || overrides.strict,
+            indentation_width: overrides.indentation_width.or(self.indentation_width),
+            require_assessments: self.require_assessments || overrides.require_assessments,
+            forbid_empty_nodes: self.forbid_empty_nodes || overrides.forbid_empty_nodes,
+            ignore_criteria_case: self.ignore_criteria_case || overrides.ignore_criteria_case,
+            max_depth: overrides.max_depth.or(self.max_depth),
+            max_nodes: overrides.max_nodes.or(self.max_nodes),
+        }
+    }
+
+    pub fn to_options(&self) -> ParserOptions {
+        let defaults = if self.strict {
+            ParserOptions::strict()
+        } else {
+            ParserOptions::default()
+        };
+
+        ParserOptions {
+            indentation_width: self.indentation_width.or(defaults.indentation_width),
+            require_assessments: self.require_assessments || defaults.require_assessments,
+            forbid_empty_nodes: self.forbid_empty_nodes || defaults.forbid_empty_nodes,
+            ignore_criteria_case: self.ignore_criteria_case,
+            max_depth: self.max_depth,
+            max_nodes: self.max_nodes,
+        }
+    }
+}
+
+impl Config {
+    /// Reads and validates the contents of an att.toml file.
+    pub fn from_toml(text: &str) -> Result<Config, ConfigError> {
+        let mut config: Config = toml::from_str(text)?;
+
+        if let Some(rule) = config
+            .lint
+            .disable
+            .iter()
+            .find(|r| !RULES.contains(&r.as_str()))
+        {
+            return Err(ConfigError::UnknownLintRule(rule.clone()));
+        }
+
+        let patterns = std::mem::take(&mut config.ignore);
+        config.add_ignore_patterns(&patterns)?;
+
+        for settings in &config.images {
+            let pattern = Pattern::new(&settings.files)
+                .map_err(|e| ConfigError::InvalidPattern(settings.files.clone(), e))?;
+            config.image_patterns.push(pattern);
+        }
+
+        Ok(config)
+    }
+
+    /// Adds glob patterns of .att files to skip, e.g. from `--exclude`.
+    pub fn add_ignore_patterns(&mut self, patterns: &[String]) -> Result<(), ConfigError> {
+        for p in patterns {
+            let pattern = Pattern::new(p).map_err(|e| ConfigError::InvalidPattern(p.clone(), e))?;
+            self.ignore.push(p.clone());
+            self.ignore_patterns.push(pattern);
+        }
+
+        Ok(())
+    }
+
+    /// Limits the .att files to `files`, given relative to the directory, on
+    /// top of the ignore patterns.
+    pub fn select_files(&mut self, files: Vec<PathBuf>) {
+        self.selected_files = Some(files);
+    }
+
+    /// Whether only some of the files are selected, see `select_files`.
+    pub fn is_partial(&self) -> bool {
+        self.selected_files.is_some()
+    }
+
+    /// The `[[images]]` entry of a file given relative to the directory.
+    pub fn image_settings(&self, relative_path: &Path) -> Option<&ImageSettings> {
+        self.images
+            .iter()
+            .zip(&self.image_patterns)
+            .find(|(_, p)| p.matches_path(relative_path))
+            .map(|(settings, _)| settings)
+    }
+
+    /// Whether a file, given relative to the model directory, matches one of
+    /// the ignore patterns or is not selected.
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        let selected = self
+            .selected_files
+            .as_ref()
+            .is_none_or(|files| files.iter().any(|f| f == relative_path));
+
+        !selected
+            || self
+                .ignore_patterns
+                .iter()
+                .any(|p| p.matches_path(relative_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::RankDir;
+
+    use super::*;
+
+    #[test]
+    fn all_sections_are_read() {
+        let config = Config::from_toml(
+            r#"
+format = "svg"
+engine = "neato"
+max_assessment_age = 180
+ignore = ["draft-*.att"]
+profile = "remote-only"
+overlay = "prod.overlay.json"
+
+[style]
+rankdir = "LR"
+
+[parser]
+strict = true
+
+[lint]
+max_depth = 5
+disable = ["unique-titles"]
+
+[[ratings]]
+name = "High"
+max = 13
+
+[[ratings]]
+name = "Low"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.format.as_deref(), Some("svg"));
+        assert_eq!(config.engine, Some(Engine::Neato));
+        assert_eq!(config.max_assessment_age, Some(180));
+        assert_eq!(config.style.rankdir, Some(RankDir::LR));
+        assert_eq!(config.parser.to_options(), ParserOptions::strict());
+        assert_eq!(config.lint.max_depth, Some(5));
+        assert_eq!(config.ratings.len(), 2);
+        assert!(config.is_ignored(Path::new("draft-login.att")));
+        assert!(!config.is_ignored(Path::new("login.att")));
+        assert_eq!(config.profile.as_deref(), Some("remote-only"));
+        assert_eq!(config.overlay, Some(PathBuf::from("prod.overlay.json")));
+    }
+
+    #[test]
+    fn unknown_keys_and_invalid_patterns_are_rejected() {
+        assert!(matches!(
+            Config::from_toml("colour = \"red\""),
+            Err(ConfigError::ParseError(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("ignore = [\"[\"]"),
+            Err(ConfigError::InvalidPattern(..))
+        ));
+        assert!(matches!(
+            Config::from_toml("[lint]\ndisable = [\"unique-title\"]"),
+            Err(ConfigError::UnknownLintRule(_))
+        ));
+    }
+
+    #[test]
+    fn added_patterns_are_checked_along_with_the_configured_ones() {
+        let mut config = Config::from_toml("ignore = [\"draft-*.att\"]").unwrap();
+
+        config
+            .add_ignore_patterns(&["archive/*.att".to_string()])
+            .unwrap();
+
+        assert!(config.is_ignored(Path::new("draft-login.att")));
+        assert!(config.is_ignored(Path::new("archive/old.att")));
+        assert!(!config.is_ignored(Path::new("login.att")));
+        assert!(config.add_ignore_patterns(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn selected_files_are_limited_by_the_ignore_patterns() {
+        let mut config = Config::from_toml("ignore = [\"draft-*.att\"]").unwrap();
+        assert!(!config.is_partial());
+
+        config.select_files(vec![
+            PathBuf::from("login.att"),
+            PathBuf::from("draft-door.att"),
+        ]);
+
+        assert!(config.is_partial());
+        assert!(!config.is_ignored(Path::new("login.att")));
+        assert!(config.is_ignored(Path::new("draft-door.att")));
+        assert!(config.is_ignored(Path::new("window.att")));
+    }
+
+    #[test]
+    fn the_first_matching_image_settings_apply() {
+        let config = Config::from_toml(
+            r#"
+            [[images]]
+            files = "slides/*.att"
+            format = "png"
+            dpi = 192
+
+            [[images]]
+            files = "*.att"
+            format = "pdf"
+            max_width = 1200
+            "#,
+        )
+        .unwrap();
+
+        let slides = config
+            .image_settings(Path::new("slides/login.att"))
+            .unwrap();
+        assert_eq!(slides.format.as_deref(), Some("png"));
+        assert_eq!(slides.layout().dpi, Some(192));
+        assert_eq!(
+            config
+                .image_settings(Path::new("door.att"))
+                .and_then(|s| s.max_width),
+            Some(1200)
+        );
+        assert!(Config::default()
+            .image_settings(Path::new("door.att"))
+            .is_none());
+        assert!(matches!(
+            Config::from_toml("[[images]]\nfiles = \"[\""),
+            Err(ConfigError::InvalidPattern(..))
+        ));
+    }
+
+    #[test]
+    fn command_line_checks_add_to_the_configured_ones() {
+        let file = ParserConfig {
+            indentation_width: Some(2),
+            require_assessments: true,
+            ..Default::default()
+        };
+        let cli = ParserConfig {
+            forbid_empty_nodes: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            file.merge(&cli).to_options(),
+            ParserOptions {
+                indentation_width: Some(2),
+                require_assessments: true,
+                forbid_empty_nodes: true,
+                ignore_criteria_case: false,
+                max_depth: None,
+                max_nodes: None,
+            }
+        );
+    }
+}
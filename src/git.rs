@@ -0,0 +1,61 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("git could not be run: {0}")]
+    NotRunnable(#[from] io::Error),
+    #[error("'git {0}' failed: {1}")]
+    Failed(String, String),
+}
+
+/// Files of `directory` that differ from the last commit, staged or not,
+/// and untracked files that are not ignored. Deleted files are left out. The
+/// paths are relative to `directory`.
+pub fn changed_files(directory: &Path) -> Result<Vec<PathBuf>, GitError> {
+    let mut files = run_git(
+        directory,
+        &[
+            "diff",
+            "-z",
+            "--name-only",
+            "--relative",
+            "--diff-filter=d",
+            "HEAD",
+        ],
+    )?;
+    files.extend(run_git(
+        directory,
+        &["ls-files", "-z", "--others", "--exclude-standard"],
+    )?);
+
+    files.sort();
+    files.dedup();
+    Ok(files.into_iter().map(PathBuf::from).collect())
+}
+
+fn run_git(directory: &Path, args: &[&str]) -> Result<Vec<String>, GitError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(GitError::Failed(
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    // -z keeps paths with special characters unquoted
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
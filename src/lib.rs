@@ -1,3 +1,9 @@
+pub mod analysis;
+pub mod api;
+pub mod config;
 pub mod model;
 pub mod parser;
-pub mod render;
\ No newline at end of file
+pub mod presets;
+pub mod render;
+
+pub use api::{compute_feasibility, summarize, ComputeError, Summary};
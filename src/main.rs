@@ -1,119 +1,3145 @@
+mod git;
+mod quick;
+#[cfg(feature = "serve")]
+mod serve;
+#[cfg(feature = "tui")]
+mod tui;
+
 use std::{
-    env,
+    collections::BTreeMap,
     ffi::OsStr,
+    fmt::Display,
     fs::{self, metadata, DirEntry, File},
-    io::BufReader,
+    io::{self, BufReader, IsTerminal, Read},
     path::{Path, PathBuf},
-    process::exit,
+    process::{exit, ExitCode},
     rc::Rc,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "sqlite")]
+use att::render::{render_to_sqlite, SQLITE_SCHEMA};
+use att::{
+    analysis::{
+        assets::aggregate_by_attribute,
+        assumptions::collect_assumptions,
+        baseline::Baseline,
+        coverage::{build_coverage_matrix, find_unknown_requirements},
+        criteria_check::unknown_criteria,
+        defenses::compare_defenses,
+        duplicates::find_duplicate_subtrees,
+        lint::lint_tree,
+        lock::Lockfile,
+        metrics::{append_metrics_to_json, compute_metrics, RunMetrics},
+        simulation::simulate,
+        staleness::find_stale_assessments,
+        stats::compute_statistics,
+        techniques::group_by_technique,
+        validation::{find_duplicate_uuids, validate_tree},
+        weakest::find_weakest_leaves,
+    },
+    config::{Config, ParserConfig},
+    model::{
+        apply_defenses, feasible_step::FeasibleStep, merge::merge_trees, number_nodes,
+        overlay::Overlay, read_requirements, redact_trees, select_profile, select_subtree,
+        traversal::preorder, FeasibilityCriteria, NodeNumbers, RatingLevel, Requirement,
+        SubtreeSelector,
+    },
+    parser::{
+        assign_uuids, check_title, import_csv, rename_title, AttackTreeParser, ParserOptions,
+        CUSTOM_ATTRIBUTE_PREFIX,
+    },
+    presets::{find_preset, PRESETS},
+    render::{
+        add_changelog_entry, build_jira_issues, render_assumptions_to_markdown,
+        render_attribute_summaries_to_markdown, render_changelog_entry,
+        render_coverage_matrix_to_markdown, render_duplicates_to_markdown, render_jira_csv,
+        render_jira_json, render_metrics_to_csv, render_rating_method_to_markdown,
+        render_report_with_template, render_residual_feasibility_to_markdown,
+        render_simulation_to_csv, render_simulation_to_markdown,
+        render_stale_assessments_to_markdown, render_techniques_to_markdown, render_to_att_string,
+        render_to_csv, render_to_graphml, render_to_json, render_to_otm, render_tree_to_markdown,
+        render_tree_with_template, render_weakest_leaves_to_markdown,
+    },
+    render::{
+        graphviz_available, render_diff_to_dot_string, render_dot_sources_to_images,
+        render_to_dot_string_with_options, render_to_image, write_atomically, DotRenderer,
+        Renderers, DEFAULT_RENDERER,
+    },
+    render::{
+        render_projects_to_markdown, render_to_markdown_table_with_options, Engine, LayoutOptions,
+        ProjectSummary, RankDir, RenderOptions, RiskMatrix, Splines, Theme, DEFAULT_IMPACT_LEVELS,
+    },
 };
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use indicatif::{ProgressBar, ProgressStyle};
+use uuid::Uuid;
+
+const CRITERIA_FILE: &str = "criteria.json";
+const BASELINE_FILE: &str = "baseline.json";
+const STYLE_FILE: &str = "style.json";
+const REQUIREMENTS_FILE: &str = "requirements.json";
+const CONFIG_FILE: &str = "att.toml";
+const CHANGELOG_FILE: &str = "CHANGELOG.threats.md";
+/// Snapshot of the threats of the previous run, which the changelog is
+/// compared to.
+const LAST_RUN_FILE: &str = ".att-last-run.json";
+const LOCK_FILE: &str = "att.lock";
+#[cfg(feature = "sqlite")]
+const SQLITE_FILE: &str = "threats.db";
+const DEFAULT_MAX_ASSESSMENT_AGE: i64 = 365;
+const DEFAULT_AGGREGATE_ATTRIBUTE: &str = "x-asset";
+
+#[derive(Parser)]
+#[command(
+    name = "att",
+    about = "Attack tree tool",
+    long_about = "Attack tree tool\n\nRenders every .att file of a directory to an image and writes threats.md, threats.json, threats.csv, threats.graphml, threats.otm.json, att.lock and one page per tree to trees/, and adds the changes since the previous run to CHANGELOG.threats.md. The directory must contain criteria.json; att.toml, style.json, requirements.json and baseline.json are read when present. Command line arguments take precedence over att.toml.",
+    after_help = "Examples:\n  att model/\n  att model/ -T svg --rankdir LR\n  att model/ --changed-only\n  att stats model/ --json\n  att completions bash > /etc/bash_completion.d/att",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Directory containing criteria.json and the .att files to render
+    directory: Option<String>,
+
+    #[command(flatten)]
+    layout: LayoutArgs,
+
+    /// Graphviz layout engine: dot, neato, twopi, circo or fdp [default: dot]
+    #[arg(long)]
+    engine: Option<Engine>,
+
+    /// Graphviz output format of the images, e.g. png, svg, pdf or eps [default: png]
+    #[arg(short = 'T', long)]
+    format: Option<String>,
+
+    /// Backend rendering the images: graphviz, or dot for the DOT sources
+    /// [default: graphviz]
+    #[arg(long)]
+    renderer: Option<String>,
+
+    #[command(flatten)]
+    report: ReportArgs,
+
+    #[command(flatten)]
+    parser: ParserArgs,
+
+    /// Skip .att files matching this glob, in addition to the ignore list of
+    /// att.toml; may be repeated
+    #[arg(long, global = true, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Only include subtrees tagged with this profile, nodes without a
+    /// profile are always included
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Replace leaf assessments with those of an overlay file, e.g.
+    /// prod.overlay.json, without changing the .att files
+    #[arg(long, global = true, value_name = "FILE")]
+    overlay: Option<PathBuf>,
+
+    /// Only validate and render the .att files changed since the last git
+    /// commit, e.g. in a pre-commit hook; threats.md and the exports are not
+    /// updated
+    #[arg(long)]
+    changed_only: bool,
+
+    /// Recompute the feasibility values and compare them and the hashes of
+    /// the sources to att.lock instead of rendering, failing on differences
+    #[arg(long, conflicts_with = "changed_only")]
+    check_lock: bool,
+}
+
+/// Checks applied when parsing .att files, all off unless selected.
+#[derive(Args)]
+struct ParserArgs {
+    /// Enable all checks, with an indentation width of 4 unless given
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Require indentation in multiples of this number of spaces
+    #[arg(long, global = true)]
+    indentation_width: Option<u32>,
+
+    /// Require every leaf to rate all criteria
+    #[arg(long, global = true)]
+    require_assessments: bool,
+
+    /// Reject AND and OR nodes without children
+    #[arg(long, global = true)]
+    forbid_empty_nodes: bool,
+
+    /// Match criteria ids and aliases in assessments regardless of case
+    #[arg(long, global = true)]
+    ignore_criteria_case: bool,
+
+    /// Reject trees with more levels than this [default: 1000]
+    #[arg(long, global = true)]
+    max_depth: Option<usize>,
+
+    /// Reject files with more nodes than this [default: 100000]
+    #[arg(long, global = true)]
+    max_nodes: Option<usize>,
+}
+
+impl From<&ParserArgs> for ParserConfig {
+    fn from(args: &ParserArgs) -> Self {
+        ParserConfig {
+            strict: args.strict,
+            indentation_width: args.indentation_width,
+            require_assessments: args.require_assessments,
+            forbid_empty_nodes: args.forbid_empty_nodes,
+            ignore_criteria_case: args.ignore_criteria_case,
+            max_depth: args.max_depth,
+            max_nodes: args.max_nodes,
+        }
+    }
+}
+
+/// Settings for the generated markdown reports.
+#[derive(Args)]
+struct ReportArgs {
+    /// Age in days after which leaf assessments are reported as stale [default: 365]
+    #[arg(long)]
+    max_assessment_age: Option<i64>,
+
+    /// Tera template replacing the overview table of threats.md
+    #[arg(long)]
+    report_template: Option<PathBuf>,
+
+    /// Tera template for the pages in trees/
+    #[arg(long)]
+    tree_template: Option<PathBuf>,
+
+    /// Compute feasibility values with the defense nodes in effect
+    #[arg(long)]
+    with_defenses: bool,
+
+    /// Sample the ranges of the assessments this many times and report the
+    /// distribution of the feasibility in threats.md and threats.simulation.csv
+    #[arg(long, value_name = "ITERATIONS")]
+    simulate: Option<u32>,
+
+    /// Seed of the sampling, the same seed gives the same distribution
+    #[arg(long, default_value_t = 1, requires = "simulate")]
+    seed: u64,
+
+    /// Append the threat count, average feasibility and high risk count of
+    /// this run to a .csv or .json file, e.g. for trend charts
+    #[arg(long, value_name = "FILE")]
+    metrics: Option<PathBuf>,
+
+    /// Highest feasibility value counted as high risk in the metrics, the
+    /// maximum of the first rating level if not given
+    #[arg(long, value_name = "VALUE", requires = "metrics")]
+    high_risk_max: Option<u32>,
+
+    /// Write the images, pages and reports to a staging directory first and
+    /// move them into place only if all of them could be written
+    #[arg(long)]
+    staged: bool,
+
+    /// Keep the previous threats.md as threats.md.bak
+    #[arg(long)]
+    backup: bool,
+
+    /// Number the threats and their nodes in the reports, e.g. T1.2.3. Set
+    /// `number=N` on a node to keep its number when siblings are inserted
+    #[arg(long)]
+    numbers: bool,
+
+    /// Summarize the easiest attack per value of this custom attribute, e.g.
+    /// x-asset; may be repeated [default: x-asset]
+    #[arg(long, value_name = "ATTRIBUTE")]
+    aggregate_by: Vec<String>,
+
+    /// Show threats with the same title in one row of the overview, ignoring
+    /// case, punctuation and qualifiers in parentheses
+    #[arg(long)]
+    group_threats: bool,
+
+    /// Compare the children of every OR node in a table on the tree pages
+    #[arg(long)]
+    compare_or: bool,
+
+    /// Print the time spent in each phase and on the slowest files
+    #[arg(long)]
+    timings: bool,
+}
+
+/// Graphviz layout settings, overriding the values of style.json.
+#[derive(Args)]
+struct LayoutArgs {
+    /// Direction of the graph layout: TB, LR, BT or RL
+    #[arg(long)]
+    rankdir: Option<RankDir>,
+
+    /// Minimum distance between ranks in inches
+    #[arg(long)]
+    ranksep: Option<f32>,
+
+    /// Minimum distance between nodes of the same rank in inches
+    #[arg(long)]
+    nodesep: Option<f32>,
+
+    /// Edge style: spline, line, polyline, ortho, curved or none
+    #[arg(long)]
+    splines: Option<Splines>,
+
+    /// Resolution of the rendered images
+    #[arg(long)]
+    dpi: Option<u32>,
+
+    /// Largest width of the rendered images in pixels, wider images are
+    /// scaled down
+    #[arg(long)]
+    max_width: Option<u32>,
+
+    /// Add a legend of the node shapes, criteria and ratings to the images
+    #[arg(long)]
+    legend: bool,
+
+    /// Add the title, version, date and owner of the tree above the images
+    #[arg(long)]
+    banner: bool,
+
+    /// Colors of the images: light, dark or print [default: light]
+    #[arg(long)]
+    theme: Option<Theme>,
+
+    /// Draw a box around each subtree of the nodes at this depth, the
+    /// children of the root if no depth is given
+    #[arg(long, value_name = "DEPTH", num_args = 0..=1, require_equals = true, default_missing_value = "1")]
+    clusters: Option<usize>,
+
+    /// Label the edges with the feasibility value of the child and highlight
+    /// the child deciding the value of each OR node
+    #[arg(long)]
+    edge_labels: bool,
+}
+
+impl From<&LayoutArgs> for LayoutOptions {
+    fn from(args: &LayoutArgs) -> Self {
+        LayoutOptions {
+            rankdir: args.rankdir,
+            ranksep: args.ranksep,
+            nodesep: args.nodesep,
+            splines: args.splines,
+            dpi: args.dpi,
+            max_width: args.max_width,
+            legend: args.legend.then_some(true),
+            banner: args.banner.then_some(true),
+            theme: args.theme,
+            icons: None,
+            clusters: args.clusters,
+            edge_labels: args.edge_labels.then_some(true),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Merge two trees sharing a root title into a single .att file
+    #[command(after_help = "Example:\n  att merge a.att b.att -o merged.att")]
+    Merge {
+        /// First tree, its values win on conflicts
+        file_a: PathBuf,
+        /// Second tree, merged into the first
+        file_b: PathBuf,
+
+        /// File to write the merged tree to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Criteria definition, defaults to criteria.json next to the first file
+        #[arg(short, long)]
+        criteria: Option<PathBuf>,
+    },
+    /// Render one subtree of an .att file to an image of its own
+    #[command(
+        after_help = "Example:\n  att subtree model/login.att --root \"Steal credentials\" -o credentials.png"
+    )]
+    #[command(group(clap::ArgGroup::new("selection").required(true).args(["root", "node_id"])))]
+    Subtree {
+        /// Tree file containing the subtree
+        file: PathBuf,
+
+        /// Title of the node at the top of the subtree
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Position of the node in depth-first order, the N of the node-N
+        /// anchors on the tree pages
+        #[arg(long)]
+        node_id: Option<usize>,
+
+        /// Image to write, named after the file and the subtree if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Graphviz output format, e.g. png or svg, defaults to the one of att.toml
+        #[arg(short = 'T', long)]
+        format: Option<String>,
+
+        /// Criteria definition, defaults to criteria.json next to the file
+        #[arg(short, long)]
+        criteria: Option<PathBuf>,
+    },
+    /// Create .att files from a CSV with one row per attack step, e.g. a
+    /// spreadsheet of threats. Every row without a parent becomes a tree.
+    #[command(
+        after_help = "The CSV needs a title column. The optional parent column references the\ntitle of the parent row, the optional type column holds and, or or leaf.\nAll other columns are criterion ids or names.\n\nExample:\n  att import threats.csv -o model/"
+    )]
+    Import {
+        /// CSV file to read
+        file: PathBuf,
+
+        /// Directory to write the .att files to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Criteria definition, defaults to criteria.json in the output directory
+        #[arg(short, long)]
+        criteria: Option<PathBuf>,
+
+        /// Overwrite existing .att files
+        #[arg(long)]
+        force: bool,
+    },
+    /// Record the current state of all nodes; later runs of the directory mark
+    /// nodes added or modified since then
+    Baseline {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// File to write the snapshot to, defaults to baseline.json in the directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Compare the threats of a directory to a baseline and list those that
+    /// became easier, e.g. to block risk regressions in CI
+    #[command(
+        after_help = "Example:\n  att compare model/ --baseline release/baseline.json --fail-on-worse"
+    )]
+    Compare {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Snapshot written by `att baseline`, defaults to baseline.json in the directory
+        #[arg(short, long)]
+        baseline: Option<PathBuf>,
+
+        /// Exit with an error if a threat became easier or a new high risk
+        /// threat appeared
+        #[arg(long)]
+        fail_on_worse: bool,
+
+        /// Highest feasibility value of a high risk threat, the maximum of the
+        /// first rating level if not given
+        #[arg(long, value_name = "VALUE")]
+        high_risk_max: Option<u32>,
+
+        /// Render the trees changed since the baseline to images in DIR, with
+        /// added nodes green, re-rated ones yellow and removed ones red
+        #[arg(long, value_name = "DIR")]
+        diff_images: Option<PathBuf>,
+    },
+    /// Check the trees of a directory against the modeling rules of the
+    /// [lint] section of att.toml
+    Lint {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Deepest level of a node below the root
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// Largest number of children of a node
+        #[arg(long)]
+        max_children: Option<usize>,
+    },
+    /// List every use of a criterion that criteria.json does not define in
+    /// the .att files of a directory, e.g. after changing the criteria
+    #[command(after_help = "Example:\n  att check-criteria model/ --criteria new-criteria.json")]
+    CheckCriteria {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Check against this criteria file instead of criteria.json
+        #[arg(long, value_name = "FILE")]
+        criteria: Option<PathBuf>,
+    },
+    /// Rename all nodes with a title in the .att files of a directory and in
+    /// its baseline
+    #[command(
+        after_help = "Example:\n  att rename model/ \"Pick lock\" \"Pick the front door lock\""
+    )]
+    Rename {
+        /// Directory containing the .att files
+        directory: PathBuf,
+
+        /// Current title of the nodes
+        old: String,
+
+        /// Title to give the nodes
+        new: String,
+
+        /// Print the files that would change without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Give every node of the .att files of a directory a persistent `uuid`
+    /// attribute, e.g. to reference nodes from other tools. Nodes that have
+    /// one keep it.
+    Ids {
+        /// Directory containing the .att files
+        directory: PathBuf,
+
+        /// Print the files that would change without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print statistics about the trees of a directory
+    Stats {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Width of the feasibility histogram buckets
+        #[arg(long, default_value_t = 5)]
+        bucket_width: u32,
+    },
+    /// List the trees of a directory with their owner and review status
+    List {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Only list trees that are neither reviewed nor approved
+        #[arg(long)]
+        unreviewed: bool,
+
+        /// Only list trees of this owner
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Render several directories, each with its own criteria.json, and write
+    /// a combined report with one section per directory
+    #[command(after_help = "Example:\n  att combine body/ ecu/ gateway/ -o threats.md")]
+    Combine {
+        /// Directories containing criteria.json and the .att files
+        #[arg(required = true)]
+        directories: Vec<String>,
+
+        /// File to write the combined report to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Export one issue per threat for the Jira CSV or JSON importer
+    #[command(
+        after_help = "Example:\n  att jira model/ --image-url https://wiki.example.com/model -o threats-jira.csv"
+    )]
+    Jira {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// File to write the issues to, printed to stdout if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write the format of the JSON importer instead of CSV
+        #[arg(long)]
+        json: bool,
+
+        /// Where the rendered directory is published, image links in the
+        /// descriptions are relative to the directory if not given
+        #[arg(long, value_name = "URL")]
+        image_url: Option<String>,
+
+        /// Jira issue type of the created issues
+        #[arg(long, default_value = "Task")]
+        issue_type: String,
+    },
+    /// Write a copy of the trees without titles, notes and other details,
+    /// e.g. to share the structure and feasibility values with a third party.
+    /// Nodes are named by their number like T1.2.
+    #[command(after_help = "Example:\n  att redact model/ -o shared/")]
+    Redact {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Directory to write the redacted .att files and criteria.json to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Write the trees of a directory with their nodes, assessments and
+    /// computed values to a SQLite database, e.g. to query the model with SQL
+    #[cfg(feature = "sqlite")]
+    #[command(after_help = "Examples:\n  att sqlite model/ -o threats.db\n  att sqlite --schema")]
+    Sqlite {
+        /// Directory containing criteria.json and the .att files
+        #[arg(required_unless_present = "schema")]
+        directory: Option<String>,
+
+        /// Database to write, replaced if it exists, defaults to threats.db in the directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Print the documented schema of the database instead
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Render a tree from stdin, or the clipboard if nothing is piped in,
+    /// and open the image, e.g. to sketch a tree in a discussion
+    #[command(
+        after_help = "Examples:\n  att quick\n  echo 'Enter house; Pick lock | Break window' | att quick -o house.png"
+    )]
+    Quick {
+        /// Image to write, a file in the temporary directory if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Criteria definition, Knowledge (Kn) and Equipment (Eq) if not given
+        #[arg(short, long)]
+        criteria: Option<PathBuf>,
+
+        /// Only write the image, without opening it
+        #[arg(long)]
+        no_open: bool,
+    },
+    /// Start a local web server showing the overview, tree pages and images,
+    /// rendered from the current files on every request
+    #[cfg(feature = "serve")]
+    #[command(after_help = "Example:\n  att serve model/ --port 8080")]
+    Serve {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+
+        /// Port to listen on, only connections from this machine are accepted
+        #[arg(long, default_value_t = 8000)]
+        port: u16,
+
+        /// Also serve the trees and their feasibility as JSON below /api/ and
+        /// re-render the directory on POST /api/render
+        #[arg(long)]
+        api: bool,
+    },
+    /// Browse the trees of a directory in the terminal, with the feasibility
+    /// of every node
+    #[cfg(feature = "tui")]
+    #[command(after_help = "Example:\n  att tui model/")]
+    Tui {
+        /// Directory containing criteria.json and the .att files
+        directory: String,
+    },
+    /// Print a shell completion script to stdout
+    #[command(
+        after_help = "Examples:\n  att completions bash > ~/.local/share/bash-completion/completions/att\n  att completions zsh > ~/.zfunc/_att\n  att completions fish > ~/.config/fish/completions/att.fish"
+    )]
+    Completions {
+        /// Shell to generate the script for
+        shell: Shell,
+    },
+    /// Create criteria.json and att.toml with the criteria and rating levels
+    /// of a standard
+    #[command(after_help = preset_help())]
+    Init {
+        /// Directory to create the files in
+        #[arg(default_value = ".")]
+        directory: PathBuf,
+
+        /// Name of the built-in criteria, see below
+        #[arg(long)]
+        preset: String,
+
+        /// Overwrite existing files
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+fn preset_help() -> String {
+    let presets: String = PRESETS
+        .iter()
+        .map(|p| format!("\n  {:<17}{}", p.name, p.description))
+        .collect();
+
+    format!(
+        "Presets:{}\n\nExample:\n  att init model/ --preset iso21434",
+        presets
+    )
+}
+
+/// Errors that did not stop a run. Each is reported when it occurs, the run
+/// ends with a summary and a non-zero exit code.
+#[derive(Default)]
+struct Failures {
+    messages: Vec<String>,
+    /// Progress bar the messages are printed above while it is shown.
+    progress: Option<ProgressBar>,
+}
+
+impl Failures {
+    fn record(&mut self, path: &Path, error: impl Display) {
+        let message = format!("{}: {}", path.display(), error);
+        match &self.progress {
+            Some(bar) => bar.suspend(|| eprintln!("{}", message)),
+            None => eprintln!("{}", message),
+        }
+        self.messages.push(message);
+    }
+
+    fn count(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn exit_code(&self) -> ExitCode {
+        if self.messages.is_empty() {
+            return ExitCode::SUCCESS;
+        }
+
+        eprintln!("\n{} error(s) occurred:", self.messages.len());
+        for message in &self.messages {
+            eprintln!("  {}", message);
+        }
+
+        ExitCode::FAILURE
+    }
+}
+
+/// Progress of a run on stderr and the time its steps took. The bar is only
+/// drawn on a terminal.
+struct Progress {
+    bar: ProgressBar,
+    phase: &'static str,
+    /// The running step and when it started.
+    current: Option<(String, Instant)>,
+    /// Phase, name and duration of every step.
+    timings: Vec<(&'static str, String, Duration)>,
+}
+
+impl Progress {
+    /// Number of steps listed by `print_timings`.
+    const SLOWEST_STEPS: usize = 10;
+
+    fn new() -> Progress {
+        let bar = ProgressBar::new(0).with_style(
+            ProgressStyle::with_template("{prefix:>8} [{bar:30}] {pos}/{len} {wide_msg}")
+                .expect("the template is valid")
+                .progress_chars("=> "),
+        );
+
+        Progress {
+            bar,
+            phase: "",
+            current: None,
+            timings: vec![],
+        }
+    }
+
+    fn hidden() -> Progress {
+        Progress {
+            bar: ProgressBar::hidden(),
+            ..Progress::new()
+        }
+    }
+
+    /// Starts a phase of `length` steps.
+    fn start(&mut self, phase: &'static str, length: usize) {
+        self.end_step();
+        self.phase = phase;
+        self.bar.set_prefix(phase);
+        self.bar.set_length(length as u64);
+        self.bar.set_position(0);
+    }
+
+    /// Starts a step of the current phase, it lasts until the next step,
+    /// phase or `finish`.
+    fn step(&mut self, name: &str) {
+        self.end_step();
+        self.bar.set_message(name.to_string());
+        self.current = Some((name.to_string(), Instant::now()));
+    }
+
+    fn end_step(&mut self) {
+        if let Some((name, start)) = self.current.take() {
+            self.timings.push((self.phase, name, start.elapsed()));
+            self.bar.inc(1);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.end_step();
+        self.bar.finish_and_clear();
+    }
+
+    fn print_timings(&self, directory_name: &str) {
+        let mut phases: Vec<(&str, Duration)> = vec![];
+        for (phase, _, duration) in &self.timings {
+            match phases.iter_mut().find(|(p, _)| p == phase) {
+                Some((_, total)) => *total += *duration,
+                None => phases.push((phase, *duration)),
+            }
+        }
+
+        eprintln!("Timings of {}:", directory_name);
+        for (phase, total) in &phases {
+            eprintln!("  {:<10} {:>10.1?}", phase, total);
+        }
+
+        let mut slowest: Vec<_> = self.timings.iter().collect();
+        slowest.sort_by_key(|(_, _, duration)| std::cmp::Reverse(*duration));
+        eprintln!("Slowest steps:");
+        for (phase, name, duration) in slowest.iter().take(Self::SLOWEST_STEPS) {
+            eprintln!("  {:<10} {:<40} {:>10.1?}", phase, name, duration);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let parser_args = ParserConfig::from(&cli.parser);
+
+    let failures = match cli.command {
+        Some(Command::Merge {
+            file_a,
+            file_b,
+            output,
+            criteria,
+        }) => {
+            let config = read_config(
+                file_a.parent().unwrap_or(Path::new(".")),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            merge(&file_a, &file_b, &output, criteria, &config)
+        }
+        Some(Command::Import {
+            file,
+            output,
+            criteria,
+            force,
+        }) => import(&file, &output, criteria, force),
+        Some(Command::Baseline { directory, output }) => {
+            let config = read_config(
+                Path::new(&directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            write_baseline(&directory, output, &config)
+        }
+        Some(Command::Subtree {
+            ref file,
+            ref root,
+            node_id,
+            ref output,
+            ref format,
+            ref criteria,
+        }) => {
+            let config = read_config(
+                file.parent().unwrap_or(Path::new(".")),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            let selector = match (root, node_id) {
+                (Some(title), _) => SubtreeSelector::Title(title.clone()),
+                (None, position) => SubtreeSelector::Position(position.unwrap_or(0)),
+            };
+            let mut options = render_options(&cli, &config);
+            options.format = format.clone().or(options.format);
+            render_subtree(
+                file,
+                &selector,
+                output.clone(),
+                criteria.clone(),
+                &options,
+                &config,
+            )
+        }
+        Some(Command::Compare {
+            ref directory,
+            ref baseline,
+            fail_on_worse,
+            high_risk_max,
+            ref diff_images,
+        }) => {
+            let config = read_config(
+                Path::new(directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            let baseline = baseline
+                .clone()
+                .unwrap_or_else(|| Path::new(directory).join(BASELINE_FILE));
+            let high_risk_max =
+                high_risk_max.or_else(|| config.ratings.first().and_then(|l| l.max));
+            compare(
+                directory,
+                &baseline,
+                fail_on_worse,
+                high_risk_max,
+                diff_images.as_deref(),
+                &render_options(&cli, &config),
+                &config,
+            )
+        }
+        Some(Command::Lint {
+            directory,
+            max_depth,
+            max_children,
+        }) => {
+            let mut config = read_config(
+                Path::new(&directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            config.lint.max_depth = max_depth.or(config.lint.max_depth);
+            config.lint.max_children = max_children.or(config.lint.max_children);
+            lint(&directory, &config)
+        }
+        Some(Command::CheckCriteria {
+            directory,
+            criteria,
+        }) => {
+            let config = read_config(
+                Path::new(&directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            let criteria = criteria.unwrap_or_else(|| Path::new(&directory).join(CRITERIA_FILE));
+            check_criteria(&directory, &criteria, &config)
+        }
+        Some(Command::Rename {
+            directory,
+            old,
+            new,
+            dry_run,
+        }) => {
+            let config = read_config(
+                &directory,
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            rename(&directory, &old, &new, dry_run, &config)
+        }
+        Some(Command::Ids { directory, dry_run }) => {
+            let config = read_config(
+                &directory,
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            assign_ids(&directory, dry_run, &config)
+        }
+        Some(Command::Stats {
+            directory,
+            json,
+            bucket_width,
+        }) => {
+            let config = read_config(
+                Path::new(&directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            statistics(&directory, json, bucket_width, &config)
+        }
+        Some(Command::List {
+            directory,
+            unreviewed,
+            owner,
+        }) => {
+            let config = read_config(
+                Path::new(&directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            list_trees(&directory, unreviewed, owner, &config)
+        }
+        Some(Command::Jira {
+            ref directory,
+            ref output,
+            json,
+            ref image_url,
+            ref issue_type,
+        }) => {
+            let config = read_config(
+                Path::new(directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            let options = render_options(&cli, &config);
+            export_jira(
+                directory,
+                output.as_deref(),
+                json,
+                image_url.as_deref(),
+                issue_type,
+                &options,
+                &config,
+            )
+        }
+        Some(Command::Redact {
+            ref directory,
+            ref output,
+        }) => {
+            let config = read_config(
+                Path::new(directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            export_redacted(directory, output, &config)
+        }
+        Some(Command::Combine {
+            ref directories,
+            ref output,
+        }) => combine(directories, output, &cli, &parser_args),
+        #[cfg(feature = "sqlite")]
+        Some(Command::Sqlite {
+            ref directory,
+            ref output,
+            schema,
+        }) => match (schema, directory) {
+            (false, Some(directory)) => {
+                let config = read_config(
+                    Path::new(directory),
+                    &parser_args,
+                    &cli.exclude,
+                    cli.profile.as_deref(),
+                    cli.overlay.as_deref(),
+                );
+                let output = output
+                    .clone()
+                    .unwrap_or_else(|| Path::new(directory).join(SQLITE_FILE));
+                export_sqlite(directory, &output, &config)
+            }
+            _ => {
+                print!("{}", SQLITE_SCHEMA.trim_start());
+                Failures::default()
+            }
+        },
+        Some(Command::Quick {
+            ref output,
+            ref criteria,
+            no_open,
+        }) => {
+            // there is no directory whose att.toml could be read
+            let mut config = Config::default();
+            config.parser = config.parser.merge(&parser_args);
+            let options = render_options(&cli, &config);
+            quick_render(
+                output.clone(),
+                criteria.as_deref(),
+                no_open,
+                &options,
+                &config,
+            )
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve {
+            ref directory,
+            port,
+            api,
+        }) => {
+            let config = read_config(
+                Path::new(directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            serve::serve(directory, port, api, &cli, &config)
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::Tui { ref directory }) => {
+            let config = read_config(
+                Path::new(directory),
+                &parser_args,
+                &cli.exclude,
+                cli.profile.as_deref(),
+                cli.overlay.as_deref(),
+            );
+            tui::browse(directory, &config)
+        }
+        Some(Command::Init {
+            ref directory,
+            ref preset,
+            force,
+        }) => init_directory(directory, preset, force),
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "att", &mut io::stdout());
+            Failures::default()
+        }
+        None => match cli.directory {
+            Some(ref directory_name) => {
+                let mut config = read_config(
+                    Path::new(directory_name),
+                    &parser_args,
+                    &cli.exclude,
+                    cli.profile.as_deref(),
+                    cli.overlay.as_deref(),
+                );
+                if cli.check_lock {
+                    return check_lock(directory_name, &cli.report, &config).exit_code();
+                }
+                if cli.changed_only && !select_changed_files(directory_name, &mut config) {
+                    return ExitCode::SUCCESS;
+                }
+                let mut failures = Failures::default();
+                render_directory(
+                    directory_name,
+                    render_options(&cli, &config),
+                    &cli.report,
+                    &config,
+                    &mut failures,
+                );
+                failures
+            }
+            None => {
+                let _ = Cli::command().print_help();
+                exit(1);
+            }
+        },
+    };
+
+    failures.exit_code()
+}
+
+/// Limits `config` to the .att files changed according to git. Returns false
+/// if there is nothing to do. Changes to files shared by all trees select
+/// every tree.
+fn select_changed_files(directory_name: &str, config: &mut Config) -> bool {
+    let changed = match git::changed_files(Path::new(directory_name)) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{}: {}", directory_name, e);
+            exit(1);
+        }
+    };
+
+    let shared = [CRITERIA_FILE, CONFIG_FILE, STYLE_FILE, REQUIREMENTS_FILE];
+    if changed
+        .iter()
+        .any(|f| shared.iter().any(|s| f == Path::new(s)))
+    {
+        println!("Shared files changed, checking all trees.");
+        return true;
+    }
+
+    let trees: Vec<PathBuf> = changed
+        .into_iter()
+        .filter(|f| f.extension().is_some_and(|e| e == "att"))
+        .collect();
+    if trees.is_empty() {
+        println!("No changed .att files in {}.", directory_name);
+        return false;
+    }
+
+    config.select_files(trees);
+    true
+}
+
+/// Layout and output settings of the command line on top of att.toml.
+fn render_options(cli: &Cli, config: &Config) -> RenderOptions {
+    RenderOptions {
+        layout: config.style.merge(&(&cli.layout).into()),
+        engine: cli.engine.or(config.engine).unwrap_or_default(),
+        format: cli.format.clone().or(config.format.clone()),
+        renderer: cli.renderer.clone().or(config.renderer.clone()),
+        ratings: config.ratings.clone(),
+        ..Default::default()
+    }
+}
+
+/// Renders each directory like a single one and writes a report linking
+/// them all to `output`.
+fn combine(
+    directories: &[String],
+    output: &Path,
+    cli: &Cli,
+    parser_args: &ParserConfig,
+) -> Failures {
+    let mut failures = Failures::default();
+    let output_dir = output.parent().unwrap_or(Path::new(""));
+
+    let mut projects = vec![];
+    for directory_name in directories {
+        let config = read_config(
+            Path::new(directory_name),
+            parser_args,
+            &cli.exclude,
+            cli.profile.as_deref(),
+            cli.overlay.as_deref(),
+        );
+        let (definition, attack_trees) = render_directory(
+            directory_name,
+            render_options(cli, &config),
+            &cli.report,
+            &config,
+            &mut failures,
+        );
+        projects.push((directory_name, definition, attack_trees));
+    }
+
+    let summaries: Vec<ProjectSummary> = projects
+        .iter()
+        .map(|(directory_name, definition, attack_trees)| {
+            let directory = relative_path(output_dir, Path::new(directory_name));
+            ProjectSummary {
+                name: Path::new(directory_name)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(directory_name)
+                    .to_string(),
+                report: directory.join("threats.md"),
+                definition,
+                trees: attack_trees
+                    .iter()
+                    .map(|(file, root)| (tree_page_path(&directory.join("trees"), file), root))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    write_file(
+        output,
+        render_projects_to_markdown(&summaries),
+        &mut failures,
+    );
+
+    failures
+}
+
+/// The path of `target` as seen from the directory `base`, for links.
+fn relative_path(base: &Path, target: &Path) -> PathBuf {
+    let absolute = |p: &Path| {
+        let p = if p.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            p
+        };
+        fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf())
+    };
+    let (base, target) = (absolute(base), absolute(target));
+
+    let common = base
+        .components()
+        .zip(target.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut result = PathBuf::new();
+    for _ in base.components().skip(common) {
+        result.push("..");
+    }
+    for c in target.components().skip(common) {
+        result.push(c);
+    }
+
+    result
+}
+
+fn merge(
+    file_a: &Path,
+    file_b: &Path,
+    output: &Path,
+    criteria: Option<PathBuf>,
+    config: &Config,
+) -> Failures {
+    let mut failures = Failures::default();
+    let criteria_path = criteria.unwrap_or_else(|| {
+        file_a
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(CRITERIA_FILE)
+    });
+    let definition = read_criteria(&criteria_path);
+
+    let parser_options = config.parser.to_options();
+    let tree_a = parse_attack_tree_file(file_a, &definition, &parser_options, &mut failures);
+    let tree_b = parse_attack_tree_file(file_b, &definition, &parser_options, &mut failures);
+    let (Some(tree_a), Some(tree_b)) = (tree_a, tree_b) else {
+        return failures;
+    };
+
+    let result = match merge_trees(&tree_a, &tree_b) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!(
+                "Error merging {} and {}: {}",
+                file_a.display(),
+                file_b.display(),
+                e
+            );
+            exit(1);
+        }
+    };
+
+    for conflict in &result.conflicts {
+        eprintln!("conflict: {}", conflict);
+    }
+
+    if let Err(e) = fs::write(output, render_to_att_string(&result.root) + "\n") {
+        failures.record(output, e);
+    }
+
+    failures
+}
+
+fn render_subtree(
+    file: &Path,
+    selector: &SubtreeSelector,
+    output: Option<PathBuf>,
+    criteria: Option<PathBuf>,
+    options: &RenderOptions,
+    config: &Config,
+) -> Failures {
+    let mut failures = Failures::default();
+    let criteria_path =
+        criteria.unwrap_or_else(|| file.parent().unwrap_or(Path::new(".")).join(CRITERIA_FILE));
+    let definition = read_criteria(&criteria_path);
+
+    let Some(tree) = parse_attack_tree_file(
+        file,
+        &definition,
+        &config.parser.to_options(),
+        &mut failures,
+    ) else {
+        return failures;
+    };
+
+    let subtree = match select_subtree(&tree, selector) {
+        Ok(s) => s,
+        Err(e) => {
+            failures.record(file, e);
+            return failures;
+        }
+    };
+
+    let output = output.unwrap_or_else(|| {
+        let stem = file.file_stem().and_then(OsStr::to_str).unwrap_or("tree");
+        let format = options.format();
+        PathBuf::from(format!(
+            "{}-{}.{}",
+            stem,
+            file_stem_for(subtree.title()),
+            format.split(':').next().unwrap_or(format)
+        ))
+    });
+
+    match render_to_image(&subtree, &output, options) {
+        Ok(()) => println!("{}", output.display()),
+        Err(e) => failures.record(&output, e),
+    }
+
+    failures
+}
+
+/// Renders the tree read from stdin or the clipboard and opens the image.
+fn quick_render(
+    output: Option<PathBuf>,
+    criteria: Option<&Path>,
+    no_open: bool,
+    options: &RenderOptions,
+    config: &Config,
+) -> Failures {
+    let mut failures = Failures::default();
+    let definition = match criteria {
+        Some(path) => read_criteria(path),
+        None => Rc::new(
+            FeasibilityCriteria::from_json(quick::QUICK_CRITERIA)
+                .expect("the quick criteria are valid"),
+        ),
+    };
+
+    let (source, text) = match io::stdin().is_terminal() {
+        true => (Path::new("clipboard"), quick::read_clipboard()),
+        false => {
+            let mut text = String::new();
+            (
+                Path::new("stdin"),
+                io::stdin().read_to_string(&mut text).map(|_| text),
+            )
+        }
+    };
+    let text = match text {
+        Ok(t) => t,
+        Err(e) => {
+            failures.record(source, e);
+            return failures;
+        }
+    };
+
+    let mut parser = AttackTreeParser::new();
+    let tree = match parser.parse_with_options(
+        &mut io::Cursor::new(text),
+        &definition,
+        &config.parser.to_options(),
+    ) {
+        Ok(tree) => tree,
+        Err(e) => {
+            failures.record(source, e);
+            for d in parser.diagnostics() {
+                eprintln!("{}:{}: {}", source.display(), d.line, d.message);
+            }
+            return failures;
+        }
+    };
+
+    let output = output.unwrap_or_else(|| {
+        let format = options.format();
+        std::env::temp_dir().join(format!(
+            "att-quick.{}",
+            format.split(':').next().unwrap_or(format)
+        ))
+    });
+    if let Err(e) = render_to_image(&tree, &output, options) {
+        failures.record(&output, e);
+        return failures;
+    }
+    println!("{}", output.display());
+
+    if !no_open {
+        if let Err(e) = quick::open_with_viewer(&output) {
+            failures.record(&output, e);
+        }
+    }
+
+    failures
+}
+
+/// Writes one .att file per tree of the CSV, named after the root node.
+fn import(file: &Path, output: &Path, criteria: Option<PathBuf>, force: bool) -> Failures {
+    let mut failures = Failures::default();
+    let definition = read_criteria(&criteria.unwrap_or_else(|| output.join(CRITERIA_FILE)));
+
+    let text = match fs::read_to_string(file) {
+        Ok(t) => t,
+        Err(e) => {
+            failures.record(file, e);
+            return failures;
+        }
+    };
+    let trees = match import_csv(&text, &definition) {
+        Ok(t) => t,
+        Err(diagnostics) => {
+            for d in diagnostics {
+                failures.record(file, d);
+            }
+            return failures;
+        }
+    };
+
+    let mut written: Vec<PathBuf> = vec![];
+    for root in &trees {
+        let stem = file_stem_for(root.title());
+        let mut path = output.join(format!("{}.att", stem));
+        let mut n = 2;
+        while written.contains(&path) {
+            path = output.join(format!("{}-{}.att", stem, n));
+            n += 1;
+        }
+
+        if path.exists() && !force {
+            failures.record(&path, "already exists, use --force to overwrite it");
+            continue;
+        }
+        match fs::write(&path, render_to_att_string(root) + "\n") {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(e) => failures.record(&path, e),
+        }
+        written.push(path);
+    }
+
+    failures
+}
+
+/// Lower case file name from a node title, words joined by dashes.
+fn file_stem_for(title: &str) -> String {
+    let stem = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if stem.is_empty() {
+        "tree".to_string()
+    } else {
+        stem
+    }
+}
+
+fn read_criteria(path: &Path) -> Rc<FeasibilityCriteria> {
+    let file_contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            exit(1);
+        }
+    };
+
+    match FeasibilityCriteria::from_json(&file_contents) {
+        Ok(criteria) => Rc::new(criteria),
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            exit(1);
+        }
+    }
+}
+
+type AttackTrees = Vec<(PathBuf, Rc<dyn FeasibleStep>)>;
+
+/// Reads criteria.json and parses all .att files of a model directory. Files
+/// that cannot be parsed are recorded as failures and left out.
+fn load_directory(
+    directory_name: &str,
+    config: &Config,
+    progress: &mut Progress,
+    failures: &mut Failures,
+) -> (Rc<FeasibilityCriteria>, AttackTrees) {
+    let md = match metadata(directory_name) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{}: {}", directory_name, e);
+            exit(1);
+        }
+    };
+
+    if !md.is_dir() {
+        eprintln!("'{}' is not a directory.", &directory_name);
+        exit(1);
+    }
+
+    // parse criteria.json with FeasibilityCriteria
+    let definition = read_criteria(&Path::new(directory_name).join(CRITERIA_FILE));
+
+    // filter attack tree files
+    let paths = match fs::read_dir(directory_name) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}: {}", directory_name, e);
+            exit(1);
+        }
+    };
+    let (attack_tree_files, skipped_files): (Vec<DirEntry>, Vec<DirEntry>) = paths
+        .filter_map(Result::ok)
+        .filter(|e| {
+            if let Some(e) = e.path().extension() {
+                e == "att"
+            } else {
+                false
+            }
+        })
+        .partition(|e| !config.is_ignored(Path::new(&e.file_name())));
+
+    // parse attack tree files
+    let attack_trees = parse_attack_trees(
+        &attack_tree_files,
+        &definition,
+        &config.parser.to_options(),
+        progress,
+        failures,
+    );
+
+    for (file_path, root) in &attack_trees {
+        for e in validate_tree(file_path, root) {
+            let path = e.location.path.join(" > ");
+            failures.record(file_path, format!("{}: {}", path, e.message));
+        }
+    }
+    for e in find_duplicate_uuids(&attack_trees) {
+        let path = e.location.path.join(" > ");
+        failures.record(&e.location.file, format!("{}: {}", path, e.message));
+    }
+
+    let attack_trees = match &config.profile {
+        Some(profile) => attack_trees
+            .into_iter()
+            .filter_map(|(f, r)| select_profile(&r, profile).map(|r| (f, r)))
+            .collect(),
+        None => attack_trees,
+    };
+
+    let attack_trees = match &config.overlay {
+        Some(path) => {
+            // overrides of skipped files are not reported as unknown
+            let all_files: Vec<String> = attack_tree_files
+                .iter()
+                .chain(&skipped_files)
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect();
+            apply_overlay(path, attack_trees, &all_files, failures)
+        }
+        None => attack_trees,
+    };
+
+    (definition, attack_trees)
+}
+
+/// Replaces the assessments of the leaves by those of the overlay file.
+fn apply_overlay(
+    path: &Path,
+    attack_trees: AttackTrees,
+    all_files: &[String],
+    failures: &mut Failures,
+) -> AttackTrees {
+    let overlay = match fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| Overlay::from_json(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(overlay) => overlay,
+        Err(e) => {
+            failures.record(path, e);
+            return attack_trees;
+        }
+    };
+
+    let names: Vec<&str> = all_files.iter().map(String::as_str).collect();
+    for e in overlay.unknown_files(&names) {
+        failures.record(path, e);
+    }
+
+    attack_trees
+        .into_iter()
+        .map(|(file, root)| {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            let (root, errors) = overlay.apply(&name, &root);
+            for e in errors {
+                failures.record(path, e);
+            }
+            (file, root)
+        })
+        .collect()
+}
+
+fn rename(directory: &Path, old: &str, new: &str, dry_run: bool, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+
+    if let Err(e) = check_title(new) {
+        failures.record(directory, e);
+        return failures;
+    }
+
+    let files = read_att_files(directory, config, &mut failures);
+    if failures.count() > 0 {
+        return failures;
+    }
+
+    let mut total = 0;
+    for (file, text) in &files {
+        match rename_title(text, old, new) {
+            Ok((_, 0)) => {}
+            Ok((renamed, count)) => {
+                println!("{}: {} node(s)", file.display(), count);
+                total += count;
+                if !dry_run {
+                    write_file(file, renamed, &mut failures);
+                }
+            }
+            Err(e) => failures.record(file, e),
+        }
+    }
+
+    if total == 0 {
+        failures.record(directory, format!("no node is titled '{}'", old));
+        return failures;
+    }
+
+    let baseline_path = directory.join(BASELINE_FILE);
+    if let Some(mut baseline) = directory.to_str().and_then(read_baseline) {
+        let count = baseline.rename(old.trim(), new.trim());
+        if count > 0 {
+            println!("{}: {} entries", baseline_path.display(), count);
+            if !dry_run {
+                let json =
+                    serde_json::to_string_pretty(&baseline).expect("baseline serialization error");
+                write_file(&baseline_path, json, &mut failures);
+            }
+        }
+    }
+
+    failures
+}
+
+/// The paths and contents of the .att files of a directory that are not
+/// ignored.
+fn read_att_files(
+    directory: &Path,
+    config: &Config,
+    failures: &mut Failures,
+) -> Vec<(PathBuf, String)> {
+    let paths = match att_files(directory) {
+        Ok(paths) => paths,
+        Err(e) => {
+            failures.record(directory, e);
+            return vec![];
+        }
+    };
+
+    let mut files = vec![];
+    for path in paths {
+        if config.is_ignored(Path::new(path.file_name().unwrap_or_default())) {
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(text) => files.push((path, text)),
+            Err(e) => failures.record(&path, e),
+        }
+    }
+
+    files
+}
+
+fn check_criteria(directory_name: &str, criteria: &Path, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let definition = read_criteria(criteria);
+
+    let files = read_att_files(Path::new(directory_name), config, &mut failures);
+    let unknown = unknown_criteria(&files, &definition, &config.parser.to_options());
+    for (criterion, uses) in &unknown {
+        println!(
+            "Unknown criterion '{}' used {} time(s):",
+            criterion,
+            uses.len()
+        );
+        for (file, line) in uses {
+            println!("  {}:{}", file.display(), line);
+        }
+    }
+
+    match unknown.len() {
+        0 if failures.count() == 0 => println!(
+            "All criteria used in {} file(s) are defined in {}.",
+            files.len(),
+            criteria.display()
+        ),
+        0 => {}
+        count => failures.record(
+            criteria,
+            format!("{} criteria used in the trees are not defined", count),
+        ),
+    }
+
+    failures
+}
+
+/// The .att files of the directory, sorted by path.
+fn att_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension() == Some(OsStr::new("att")))
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+fn assign_ids(directory: &Path, dry_run: bool, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let files = read_att_files(directory, config, &mut failures);
+
+    let mut total = 0;
+    for (file, text) in &files {
+        match assign_uuids(text, || Uuid::new_v4().to_string()) {
+            Ok((_, 0)) => {}
+            Ok((assigned, count)) => {
+                println!("{}: {} node(s)", file.display(), count);
+                total += count;
+                if !dry_run {
+                    write_file(file, assigned, &mut failures);
+                }
+            }
+            Err(e) => failures.record(file, e),
+        }
+    }
+    if total == 0 && failures.count() == 0 {
+        println!("All nodes have an id.");
+    }
+
+    failures
+}
+
+fn lint(directory_name: &str, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+
+    let mut count = 0;
+    for (file_path, root) in &attack_trees {
+        for w in lint_tree(file_path, root, &config.lint) {
+            println!("{}", w);
+            count += 1;
+        }
+    }
+
+    if count > 0 {
+        failures.record(
+            Path::new(directory_name),
+            format!("{} lint warning(s)", count),
+        );
+    }
+
+    failures
+}
+
+fn statistics(directory_name: &str, json: bool, bucket_width: u32, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+    let statistics = compute_statistics(&attack_trees, bucket_width);
+
+    if json {
+        match serde_json::to_string_pretty(&statistics) {
+            Ok(s) => println!("{}", s),
+            Err(e) => {
+                eprintln!("Error serializing statistics: {}", e);
+                exit(1);
+            }
+        }
+    } else {
+        print!("{}", statistics);
+    }
+
+    failures
+}
+
+fn list_trees(
+    directory_name: &str,
+    unreviewed: bool,
+    owner: Option<String>,
+    config: &Config,
+) -> Failures {
+    let mut failures = Failures::default();
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+
+    for (file_path, root) in &attack_trees {
+        let review = root.review();
+        if unreviewed && review.is_reviewed() {
+            continue;
+        }
+        if owner.is_some() && review.owner != owner {
+            continue;
+        }
+
+        println!(
+            "{}: {} (owner: {}, status: {})",
+            file_path.display(),
+            root.title(),
+            review.owner.as_deref().unwrap_or("-"),
+            review
+                .status
+                .map(|s| s.to_string())
+                .unwrap_or("-".to_string())
+        );
+    }
+
+    failures
+}
+
+fn export_jira(
+    directory_name: &str,
+    output: Option<&Path>,
+    json: bool,
+    image_url: Option<&str>,
+    issue_type: &str,
+    options: &RenderOptions,
+    config: &Config,
+) -> Failures {
+    let mut failures = Failures::default();
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+
+    let trees = attack_trees
+        .iter()
+        .map(|(f, r)| {
+            let format = tree_image_format(config, f, options.format());
+            let image = to_image_path(Path::new("images"), f, format)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let url = match image_url {
+                Some(base) => format!("{}/{}", base.trim_end_matches('/'), image),
+                None => image,
+            };
+            (url, r)
+        })
+        .collect();
+    let issues = build_jira_issues(trees, &options.ratings);
+
+    let contents = if json {
+        match render_jira_json(&issues, issue_type) {
+            Ok(j) => j,
+            Err(e) => {
+                failures.record(Path::new(directory_name), e);
+                return failures;
+            }
+        }
+    } else {
+        render_jira_csv(&issues, issue_type)
+    };
+
+    match output {
+        Some(path) => write_file(path, contents, &mut failures),
+        None => print!("{}", contents),
+    }
+
+    failures
+}
+
+fn init_directory(directory: &Path, preset_name: &str, force: bool) -> Failures {
+    let mut failures = Failures::default();
+    let Some(preset) = find_preset(preset_name) else {
+        let names: Vec<&str> = PRESETS.iter().map(|p| p.name).collect();
+        failures.record(
+            directory,
+            format!(
+                "unknown preset '{}', use one of: {}",
+                preset_name,
+                names.join(", ")
+            ),
+        );
+        return failures;
+    };
+
+    let files = [
+        (directory.join(CRITERIA_FILE), preset.criteria),
+        (directory.join(CONFIG_FILE), preset.config),
+    ];
+    if !force {
+        for (path, _) in files.iter().filter(|(p, _)| p.exists()) {
+            failures.record(path, "already exists, use --force to overwrite it");
+        }
+        if failures.count() > 0 {
+            return failures;
+        }
+    }
+
+    if !create_output_dir(directory, &mut failures) {
+        return failures;
+    }
+    for (path, contents) in files {
+        write_file(&path, contents.to_string(), &mut failures);
+    }
+    if failures.count() == 0 {
+        println!(
+            "Created {} and {} for {}",
+            CRITERIA_FILE, CONFIG_FILE, preset.description
+        );
+    }
+
+    failures
+}
+
+fn export_redacted(directory_name: &str, output: &Path, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    if output.canonicalize().ok() == Path::new(directory_name).canonicalize().ok() {
+        failures.record(
+            output,
+            "the redacted trees must be written to a different directory",
+        );
+        return failures;
+    }
+
+    let (_, mut attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+    if !create_output_dir(output, &mut failures) {
+        return failures;
+    }
+
+    // numbered by file name like the reports, the file names are not kept
+    attack_trees.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let roots: Vec<_> = attack_trees.iter().map(|(_, r)| r).collect();
+    for tree in redact_trees(&roots) {
+        let path = output.join(format!("{}.att", tree.title()));
+        write_file(&path, render_to_att_string(&tree), &mut failures);
+    }
+
+    let criteria = Path::new(directory_name).join(CRITERIA_FILE);
+    if let Err(e) = fs::copy(&criteria, output.join(CRITERIA_FILE)) {
+        failures.record(&criteria, e);
+    }
+
+    failures
+}
+
+#[cfg(feature = "sqlite")]
+fn export_sqlite(directory_name: &str, output: &Path, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let (definition, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+
+    // sorted and relative to the directory like the threats of the reports
+    let mut trees: Vec<_> = attack_trees
+        .into_iter()
+        .map(|(f, r)| {
+            let file = f.strip_prefix(directory_name).unwrap_or(&f).to_path_buf();
+            (file, r)
+        })
+        .collect();
+    trees.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    match render_to_sqlite(&trees, &definition, output) {
+        Ok(()) => println!("{}", output.display()),
+        Err(e) => failures.record(output, e),
+    }
+
+    failures
+}
 
-use att::{
-    model::{feasible_step::FeasibleStep, FeasibilityCriteria, FeasiblityCriterion},
-    parser::AttackTreeParser,
-    render::render_to_markdown_table,
-    render::render_to_png,
-};
+fn write_baseline(directory_name: &str, output: Option<PathBuf>, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+    let output = output.unwrap_or_else(|| Path::new(directory_name).join(BASELINE_FILE));
+
+    let baseline = Baseline::from_trees(&attack_trees);
+    let json = serde_json::to_string_pretty(&baseline).expect("baseline serialization error");
+
+    write_file(&output, json, &mut failures);
+
+    failures
+}
+
+fn compare(
+    directory_name: &str,
+    baseline_path: &Path,
+    fail_on_worse: bool,
+    high_risk_max: Option<u32>,
+    diff_images: Option<&Path>,
+    options: &RenderOptions,
+    config: &Config,
+) -> Failures {
+    let mut failures = Failures::default();
+
+    let baseline: Baseline = match fs::read_to_string(baseline_path)
+        .map_err(|e| e.to_string())
+        .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+    {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            failures.record(baseline_path, e);
+            return failures;
+        }
+    };
+
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+    let regressions = baseline.find_regressions(&attack_trees, high_risk_max);
+
+    for r in &regressions {
+        println!("{}", r);
+    }
+    if regressions.is_empty() {
+        println!("No regressions against {}", baseline_path.display());
+    } else if fail_on_worse {
+        failures.record(
+            baseline_path,
+            format!("{} regression(s)", regressions.len()),
+        );
+    }
+
+    if let Some(dir) = diff_images {
+        write_diff_images(&baseline, &attack_trees, dir, options, &mut failures);
+    }
+
+    failures
+}
+
+/// Renders the trees with nodes added, changed or removed since the baseline
+/// to images in `dir`, or to DOT files without Graphviz.
+fn write_diff_images(
+    baseline: &Baseline,
+    attack_trees: &AttackTrees,
+    dir: &Path,
+    options: &RenderOptions,
+    failures: &mut Failures,
+) {
+    if !create_output_dir(dir, failures) {
+        return;
+    }
+    let with_graphviz = graphviz_available(options.engine);
+    let format = match with_graphviz {
+        true => options.format(),
+        false => {
+            eprintln!(
+                "Graphviz '{}' not found, writing .dot files instead of images.",
+                options.engine
+            );
+            "dot"
+        }
+    };
+
+    let changes = baseline.compare(attack_trees);
+    let mut written = 0;
+    let mut dot_sources = vec![];
+    for (file, root) in attack_trees {
+        let removed = baseline.removed_nodes(file, root);
+        if removed.is_empty()
+            && preorder(root)
+                .iter()
+                .all(|n| changes.status(n.id()).is_none())
+        {
+            continue;
+        }
+
+        let image_path = to_image_path(dir, file, format);
+        match render_diff_to_dot_string(root, &changes, &removed, options) {
+            Ok(source) if with_graphviz => dot_sources.push((source, image_path)),
+            Ok(source) => match write_atomically(&image_path, source.as_bytes()) {
+                Ok(()) => written += 1,
+                Err(e) => failures.record(&image_path, e),
+            },
+            Err(e) => failures.record(&image_path, e),
+        }
+    }
+
+    if !dot_sources.is_empty() {
+        match render_dot_sources_to_images(&dot_sources, options) {
+            Ok(failed) => {
+                written += dot_sources.len() - failed.len();
+                for (image_path, e) in failed {
+                    failures.record(&image_path, e);
+                }
+            }
+            Err(e) => failures.record(dir, e),
+        }
+    }
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+    println!("{} diff image(s) written to {}", written, dir.display());
+}
+
+fn read_baseline(directory_name: &str) -> Option<Baseline> {
+    let path = Path::new(directory_name).join(BASELINE_FILE);
+    let contents = fs::read_to_string(&path).ok()?;
+
+    match serde_json::from_str(&contents) {
+        Ok(baseline) => Some(baseline),
+        Err(e) => {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn read_style(directory_name: &str) -> LayoutOptions {
+    let path = Path::new(directory_name).join(STYLE_FILE);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return LayoutOptions::default();
+    };
+
+    let layout: LayoutOptions = match serde_json::from_str(&contents) {
+        Ok(layout) => layout,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            exit(1);
+        }
+    };
 
-    if args.len() != 1 {
-        eprintln!("Usage: att <file or directory name>");
+    let unknown = layout.unknown_icon_keys();
+    if !unknown.is_empty() {
+        eprintln!(
+            "{}: unknown icon keys {}, use a node type (and, or, voting, leaf) or a custom attribute like x-vector=remote",
+            path.display(),
+            unknown.join(", ")
+        );
         exit(1);
     }
 
-    let directory_name = args[0].clone();
+    layout
+}
 
-    let md = match metadata(&directory_name) {
-        Ok(m) => m,
+fn read_requirements_file(directory_name: &str) -> Option<Vec<Requirement>> {
+    let path = Path::new(directory_name).join(REQUIREMENTS_FILE);
+    let contents = fs::read_to_string(&path).ok()?;
+
+    match read_requirements(&contents) {
+        Ok(requirements) => Some(requirements),
         Err(e) => {
-            println!("{}: {}", e, directory_name);
+            eprintln!("{}: {}", path.display(), e);
             exit(1);
         }
+    }
+}
+
+/// Reads att.toml of a directory, adding the parser checks and exclusions
+/// selected on the command line.
+fn read_config(
+    directory: &Path,
+    parser_args: &ParserConfig,
+    exclude: &[String],
+    profile: Option<&str>,
+    overlay: Option<&Path>,
+) -> Config {
+    let path = directory.join(CONFIG_FILE);
+    let mut config = match fs::read_to_string(&path) {
+        Ok(contents) => match Config::from_toml(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                exit(1);
+            }
+        },
+        Err(_) => Config::default(),
     };
 
-    if !md.is_dir() {
-        println!("'{}' is not a directory.", &directory_name);
+    config.parser = config.parser.merge(parser_args);
+    if let Err(e) = config.add_ignore_patterns(exclude) {
+        eprintln!("{}", e);
         exit(1);
     }
+    if let Some(profile) = profile {
+        config.profile = Some(profile.to_string());
+    }
+    config.overlay = match overlay {
+        Some(overlay) => Some(overlay.to_path_buf()),
+        None => config.overlay.map(|o| directory.join(o)),
+    };
 
-    // parse criteria.json with FeasibilityCriteria
-    let definition_file_path = format!("{}/{}", &directory_name, "criteria.json");
-    let file_contents = fs::read_to_string(&definition_file_path)
-        .expect(&format!("Could not read file {}", &definition_file_path));
-    let criteria: Vec<FeasiblityCriterion> =
-        serde_json::from_str(&file_contents).expect("criteria file parser error");
-    let definition = Rc::new(FeasibilityCriteria(criteria));
+    config
+}
 
-    // filter attack tree files
-    let paths = fs::read_dir(&directory_name).expect("Error listing files");
-    let attack_tree_files: Vec<DirEntry> = paths
-        .filter_map(Result::ok)
-        .filter(|e| {
-            if let Some(e) = e.path().extension() {
-                e == "att"
-            } else {
-                false
+fn read_template(path: &Option<PathBuf>) -> Option<String> {
+    let path = path.as_ref()?;
+
+    match fs::read_to_string(path) {
+        Ok(template) => Some(template),
+        Err(e) => {
+            eprintln!("Could not read template {}: {}", path.display(), e);
+            exit(1);
+        }
+    }
+}
+
+/// Adds the metrics of this run to the history in `path`. The whole file is
+/// rewritten through `outputs`, so a failed run adds no row.
+fn append_metrics(
+    path: &Path,
+    metrics: RunMetrics,
+    outputs: &mut Outputs,
+    failures: &mut Failures,
+) {
+    let existing = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            failures.record(path, e);
+            return;
+        }
+    };
+
+    if path.extension() == Some(OsStr::new("json")) {
+        match append_metrics_to_json(&existing, metrics) {
+            Ok(json) => outputs.write(path, json, failures),
+            Err(e) => failures.record(path, e),
+        }
+    } else {
+        let row = render_metrics_to_csv(&metrics, existing.is_empty());
+        outputs.write(path, existing + &row, failures);
+    }
+}
+
+fn write_file(path: &Path, contents: String, failures: &mut Failures) {
+    if let Err(e) = fs::write(path, contents) {
+        failures.record(path, e);
+    }
+}
+
+/// Creates a directory for the outputs. A failure ends the run with one
+/// explanation instead of an error for every file that would go there.
+fn create_output_dir(path: &Path, failures: &mut Failures) -> bool {
+    let Err(e) = fs::create_dir_all(path) else {
+        return true;
+    };
+
+    let hint = match e.kind() {
+        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+            ", check that the directory is writable and not part of a read-only checkout"
+        }
+        _ => "",
+    };
+    failures.record(
+        path,
+        format!("cannot create the output directory: {}{}", e, hint),
+    );
+
+    false
+}
+
+/// Where the outputs of a run go. Staged outputs are written to a directory
+/// next to them and moved into place by `commit` only if the run had no
+/// errors, a failed run keeps the outputs of the previous one.
+struct Outputs {
+    staging_dir: Option<PathBuf>,
+    /// Staged files and their destinations.
+    staged: Vec<(PathBuf, PathBuf)>,
+    /// Outputs whose previous version is kept as a .bak file.
+    backups: Vec<PathBuf>,
+}
+
+impl Outputs {
+    const STAGING_DIR: &'static str = ".att-staging";
+
+    fn new(directory_name: &str, staged: bool, failures: &mut Failures) -> Option<Outputs> {
+        let staging_dir = staged.then(|| Path::new(directory_name).join(Self::STAGING_DIR));
+        if let Some(dir) = &staging_dir {
+            // leftovers of an aborted run
+            let _ = fs::remove_dir_all(dir);
+            if !create_output_dir(dir, failures) {
+                return None;
             }
+        }
+
+        Some(Outputs {
+            staging_dir,
+            staged: vec![],
+            backups: vec![],
         })
-        .collect();
+    }
 
-    // parse attack tree files
-    let attack_trees = parse_attack_trees(&attack_tree_files, &definition);
+    /// Keeps the previous version of `target` when it is replaced.
+    fn back_up(&mut self, target: &Path) {
+        self.backups.push(target.to_path_buf());
+    }
+
+    fn keep_previous(&self, target: &Path) -> io::Result<()> {
+        if !self.backups.iter().any(|b| b == target) || !target.exists() {
+            return Ok(());
+        }
+
+        let mut backup = target.as_os_str().to_owned();
+        backup.push(".bak");
+        fs::copy(target, backup).map(|_| ())
+    }
+
+    /// The path to write the output `target` to.
+    fn path(&mut self, target: &Path) -> PathBuf {
+        let Some(dir) = &self.staging_dir else {
+            return target.to_path_buf();
+        };
+
+        // numbered, tree pages and images may have the same file name
+        let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+        let path = dir.join(format!("{}-{}", self.staged.len(), file_name));
+        self.staged.push((path.clone(), target.to_path_buf()));
+        path
+    }
+
+    /// The destination of a path returned by `path`, for error messages.
+    fn target<'a>(&'a self, path: &'a Path) -> &'a Path {
+        self.staged
+            .iter()
+            .find(|(staged, _)| staged == path)
+            .map_or(path, |(_, target)| target)
+    }
+
+    fn write(&mut self, target: &Path, contents: String, failures: &mut Failures) {
+        let result = if self.staging_dir.is_some() {
+            fs::write(self.path(target), contents)
+        } else {
+            self.keep_previous(target)
+                .and_then(|_| write_atomically(target, contents.as_bytes()))
+        };
+        if let Err(e) = result {
+            failures.record(target, e);
+        }
+    }
+
+    /// Moves the staged outputs into place unless more than
+    /// `earlier_failures` errors occurred.
+    fn commit(self, failures: &mut Failures, earlier_failures: usize) {
+        let Some(dir) = &self.staging_dir else {
+            return;
+        };
+
+        if failures.count() == earlier_failures {
+            for (staged, target) in &self.staged {
+                // the metrics history may be on another file system
+                let result = self.keep_previous(target).and_then(|_| {
+                    fs::rename(staged, target).or_else(|_| fs::copy(staged, target).map(|_| ()))
+                });
+                if let Err(e) = result {
+                    failures.record(target, e);
+                }
+            }
+        } else {
+            eprintln!("The outputs were left unchanged because of the errors.");
+        }
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+fn render_directory(
+    directory_name: &str,
+    options: RenderOptions,
+    report_args: &ReportArgs,
+    config: &Config,
+    failures: &mut Failures,
+) -> (Rc<FeasibilityCriteria>, AttackTrees) {
+    let mut progress = Progress::new();
+    failures.progress = Some(progress.bar.clone());
+
+    let result = render_directory_with_progress(
+        directory_name,
+        options,
+        report_args,
+        config,
+        &mut progress,
+        failures,
+    );
+
+    progress.finish();
+    failures.progress = None;
+    if report_args.timings {
+        progress.print_timings(directory_name);
+    }
+
+    result
+}
+
+fn render_directory_with_progress(
+    directory_name: &str,
+    options: RenderOptions,
+    report_args: &ReportArgs,
+    config: &Config,
+    progress: &mut Progress,
+    failures: &mut Failures,
+) -> (Rc<FeasibilityCriteria>, AttackTrees) {
+    // template paths in att.toml are relative to the directory
+    let configured =
+        |path: &Option<PathBuf>| path.as_ref().map(|p| Path::new(directory_name).join(p));
+    let report_template = read_template(
+        &report_args
+            .report_template
+            .clone()
+            .or_else(|| configured(&config.report_template)),
+    );
+    let tree_template = read_template(
+        &report_args
+            .tree_template
+            .clone()
+            .or_else(|| configured(&config.tree_template)),
+    );
+    let max_assessment_age = report_args
+        .max_assessment_age
+        .or(config.max_assessment_age)
+        .unwrap_or(DEFAULT_MAX_ASSESSMENT_AGE);
+
+    let earlier_failures = failures.count();
+    let (definition, attack_trees) = load_directory(directory_name, config, progress, failures);
+    // the changelog follows the full model, runs that miss trees or change
+    // their values would log changes that the next run reverts
+    let complete_model = failures.count() == earlier_failures
+        && config.profile.is_none()
+        && config.overlay.is_none()
+        && !report_args.with_defenses;
+
+    let residuals = compare_defenses(&attack_trees);
+    let attack_trees: AttackTrees = if report_args.with_defenses {
+        attack_trees
+            .iter()
+            .map(|(f, r)| (f.clone(), apply_defenses(r)))
+            .collect()
+    } else {
+        attack_trees
+    };
+
+    let requirements = read_requirements_file(directory_name);
+    if let Some(requirements) = &requirements {
+        for u in find_unknown_requirements(requirements, &attack_trees) {
+            failures.record(
+                &u.location.file,
+                format!(
+                    "{}: unknown requirement '{}'",
+                    u.location.path.join(" > "),
+                    u.id
+                ),
+            );
+        }
+    }
+
+    // threats are numbered by file name, which does not depend on the
+    // order the directory is read in
+    let mut by_file: Vec<_> = attack_trees.iter().collect();
+    by_file.sort_by_key(|(f, _)| f);
+    let roots: Vec<_> = by_file.iter().map(|(_, r)| r).collect();
+    // the risk matrix shows the threat numbers even if the titles have none
+    let threat_numbers = number_nodes(&roots);
+    let mut options = RenderOptions {
+        changes: read_baseline(directory_name).map(|b| b.compare(&attack_trees)),
+        layout: read_style(directory_name).merge(&options.layout),
+        numbers: report_args.numbers.then(|| threat_numbers.clone()),
+        group_threats: report_args.group_threats || config.group_threats,
+        compare_or: report_args.compare_or || config.compare_or,
+        ..options
+    };
+    let risk_matrix = match config.is_partial() {
+        true => None,
+        false => build_risk_matrix(&by_file, &threat_numbers, &options, config, failures),
+    };
+
+    let renderers = Renderers::default();
+    let Some(mut renderer) = renderers.get(options.renderer()) else {
+        failures.record(
+            Path::new(directory_name),
+            format!(
+                "unknown renderer '{}', use one of: {}",
+                options.renderer(),
+                renderers.names().join(", ")
+            ),
+        );
+        return (definition, attack_trees);
+    };
+    // Graphviz renders all trees at once below, other renderers one by one
+    let mut without_graphviz = options.renderer() != DEFAULT_RENDERER;
+    if !without_graphviz && !graphviz_available(options.engine) {
+        eprintln!(
+            "Graphviz '{}' not found, writing .dot files instead of images. Install Graphviz and make sure it is on the PATH to render images.",
+            options.engine
+        );
+        renderer = &DotRenderer;
+        without_graphviz = true;
+    }
+    let format = renderer.extension(&options);
+    // the [[images]] settings of att.toml only apply to Graphviz images
+    let tree_format = |file: &Path| match without_graphviz {
+        true => format.clone(),
+        false => tree_image_format(config, file, &format).to_string(),
+    };
 
     let images_dir = Path::new("images");
-    let absolute_images_dir = Path::new(&directory_name).join(images_dir);
-    if fs::create_dir_all(&absolute_images_dir).is_err() {
-        println!("Could not create {:?}", &absolute_images_dir)
+    let absolute_images_dir = Path::new(directory_name).join(images_dir);
+    let trees_dir = Path::new("trees");
+    options.tree_pages = Some(trees_dir.to_path_buf());
+    let absolute_trees_dir = Path::new(directory_name).join(trees_dir);
+    if !create_output_dir(&absolute_images_dir, failures)
+        || !create_output_dir(&absolute_trees_dir, failures)
+    {
+        return (definition, attack_trees);
+    }
+    let Some(mut outputs) = Outputs::new(directory_name, report_args.staged, failures) else {
+        return (definition, attack_trees);
+    };
+    let threats_file_path = Path::new(directory_name).join("threats.md");
+    if report_args.backup {
+        outputs.back_up(&threats_file_path);
     }
 
-    // render each tree to png
+    // render all trees to images with as few Graphviz processes as possible
+    progress.start("graphs", attack_trees.len());
+    let mut dot_sources: BTreeMap<String, Vec<_>> = BTreeMap::new();
     for (file_path, attack_tree_root) in &attack_trees {
-        let image_file_path = &to_image_path(&absolute_images_dir, file_path);
-        render_to_png(&attack_tree_root, image_file_path)
-            .expect(&format!("Error rendering file {:?}", image_file_path));
+        progress.step(&file_path.file_name().unwrap_or_default().to_string_lossy());
+        let image_format = tree_format(file_path);
+        let image_file_path = to_image_path(&absolute_images_dir, file_path, &image_format);
+        options.node_links = node_links(&options, trees_dir, file_path, without_graphviz);
+        if without_graphviz {
+            let path = outputs.path(&image_file_path);
+            let written = renderer
+                .render(attack_tree_root, &options)
+                .and_then(|a| Ok(write_atomically(&path, &a.contents)?));
+            if let Err(e) = written {
+                failures.record(&image_file_path, e);
+            }
+            continue;
+        }
+        let layout = options.layout.clone();
+        if let Some(settings) = config.image_settings(&tree_file_name(file_path)) {
+            options.layout = layout.merge(&settings.layout());
+        }
+        match render_to_dot_string_with_options(attack_tree_root, &options) {
+            Ok(source) => dot_sources
+                .entry(image_format)
+                .or_default()
+                .push((source, outputs.path(&image_file_path))),
+            Err(e) => failures.record(&image_file_path, e),
+        }
+        options.layout = layout;
+    }
+    let mut risk_matrix_image = None;
+    if let Some(matrix) = &risk_matrix {
+        let image_file_path = absolute_images_dir
+            .join("risk-matrix")
+            .with_extension(&format);
+        if without_graphviz {
+            outputs.write(&image_file_path, matrix.to_dot(), failures);
+        } else {
+            dot_sources
+                .entry(format.clone())
+                .or_default()
+                .push((matrix.to_dot(), outputs.path(&image_file_path)));
+        }
+        risk_matrix_image = Some(images_dir.join("risk-matrix").with_extension(&format));
+    }
+    if !dot_sources.is_empty() {
+        progress.start("graphviz", dot_sources.len());
+        let default_format = options.format.clone();
+        for (image_format, sources) in &dot_sources {
+            progress.step(&format!("{} {} images", sources.len(), image_format));
+            options.format = Some(image_format.clone());
+            match render_dot_sources_to_images(sources, &options) {
+                Ok(failed) => {
+                    for (image_file_path, e) in failed {
+                        failures.record(outputs.target(&image_file_path), e);
+                    }
+                }
+                Err(e) => failures.record(&absolute_images_dir, e),
+            }
+        }
+        options.format = default_format;
     }
 
-    // render to markdown overview file
-    let threats_file_path = format!("{}/threats.md", directory_name);
+    // the overview and the exports need all trees, partial runs only update
+    // the images and pages of the selected ones
+    if !config.is_partial() {
+        progress.start("reports", 1);
+        progress.step("threats.md and exports");
 
-    let root_nodes: Vec<_> = attack_trees
-        .iter()
-        .map(|(f, r)| (to_image_path(images_dir, f), r))
-        .collect();
+        // render to markdown overview file
+        let simulations = match report_args.simulate {
+            Some(iterations) => simulate(&attack_trees, iterations, report_args.seed),
+            None => vec![],
+        };
+
+        let root_nodes: Vec<_> = attack_trees
+            .iter()
+            .map(|(f, r)| (to_image_path(images_dir, f, &tree_format(f)), r))
+            .collect();
+
+        let report = match &report_template {
+            Some(template) => {
+                render_report_with_template(template, root_nodes.clone(), &definition)
+            }
+            None => Ok(render_to_markdown_table_with_options(
+                root_nodes.clone(),
+                &options,
+            )),
+        };
+
+        match report {
+            Ok(mut report) => {
+                if let Some(image) = &risk_matrix_image {
+                    report.insert_str(
+                        0,
+                        &risk_matrix_to_markdown(image, &by_file, &threat_numbers, &options),
+                    );
+                }
+
+                let weakest_leaves = find_weakest_leaves(&attack_trees);
+                if !weakest_leaves.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_weakest_leaves_to_markdown(&weakest_leaves));
+                }
+
+                for key in aggregated_attributes(report_args, config) {
+                    let summaries = aggregate_by_attribute(&attack_trees, &key);
+                    if !summaries.is_empty() {
+                        report.push('\n');
+                        report.push_str(&render_attribute_summaries_to_markdown(
+                            &key,
+                            &summaries,
+                            &options.ratings,
+                        ));
+                    }
+                }
+
+                let assumptions = collect_assumptions(&attack_trees);
+                if !assumptions.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_assumptions_to_markdown(&assumptions));
+                }
+
+                let duplicates = find_duplicate_subtrees(&attack_trees);
+                if !duplicates.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_duplicates_to_markdown(&duplicates));
+                }
+
+                let today = chrono::Local::now().date_naive();
+                let stale = find_stale_assessments(&attack_trees, today, max_assessment_age);
+                if !stale.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_stale_assessments_to_markdown(
+                        &stale,
+                        max_assessment_age,
+                    ));
+                }
+
+                if let Some(requirements) = &requirements {
+                    report.push('\n');
+                    report.push_str(&render_coverage_matrix_to_markdown(&build_coverage_matrix(
+                        requirements,
+                        &attack_trees,
+                    )));
+                }
+
+                if !residuals.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_residual_feasibility_to_markdown(&residuals));
+                }
+
+                if !simulations.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_simulation_to_markdown(&simulations));
+                }
+
+                let techniques = group_by_technique(&attack_trees);
+                if !techniques.is_empty() {
+                    report.push('\n');
+                    report.push_str(&render_techniques_to_markdown(&techniques));
+                }
+
+                if !definition.1.is_empty()
+                    || definition
+                        .0
+                        .iter()
+                        .any(|c| c.description.is_some() || !c.guidance.is_empty())
+                {
+                    report.push('\n');
+                    report.push_str(&render_rating_method_to_markdown(&definition));
+                }
+
+                outputs.write(&threats_file_path, report, failures);
+            }
+            Err(e) => failures.record(&threats_file_path, e),
+        }
+
+        // export all nodes with their aggregated feasibility
+        let json_file_path = Path::new(directory_name).join("threats.json");
+        match render_to_json(root_nodes) {
+            Ok(json) => outputs.write(&json_file_path, json, failures),
+            Err(e) => failures.record(&json_file_path, e),
+        }
+
+        let csv_file_path = Path::new(directory_name).join("threats.csv");
+        let csv = render_to_csv(attack_trees.iter().map(|(_, r)| r).collect(), &definition);
+        outputs.write(&csv_file_path, csv, failures);
+
+        if report_args.simulate.is_some() {
+            let simulation_file_path = Path::new(directory_name).join("threats.simulation.csv");
+            outputs.write(
+                &simulation_file_path,
+                render_simulation_to_csv(&simulations),
+                failures,
+            );
+        }
+
+        let graphml_file_path = Path::new(directory_name).join("threats.graphml");
+        let graphml = render_to_graphml(attack_trees.iter().map(|(_, r)| r).collect(), &definition);
+        outputs.write(&graphml_file_path, graphml, failures);
+
+        // the history keeps its rows, so a run with errors adds none even
+        // when its outputs are not staged
+        if let Some(metrics_path) = report_args
+            .metrics
+            .as_ref()
+            .filter(|_| failures.count() == earlier_failures)
+        {
+            let high_risk_max = report_args
+                .high_risk_max
+                .or_else(|| config.ratings.first().and_then(|l| l.max));
+            let metrics = compute_metrics(
+                &attack_trees,
+                &chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                high_risk_max,
+            );
+            append_metrics(metrics_path, metrics, &mut outputs, failures);
+        }
+
+        let otm_file_path = Path::new(directory_name).join("threats.otm.json");
+        let project_name = fs::canonicalize(directory_name)
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or(directory_name.to_string());
+        let otm_trees = attack_trees.iter().map(|(f, r)| (f.clone(), r)).collect();
+        match render_to_otm(&project_name, otm_trees, &definition) {
+            Ok(otm) => outputs.write(&otm_file_path, otm, failures),
+            Err(e) => failures.record(&otm_file_path, e),
+        }
+
+        if complete_model {
+            update_changelog(
+                directory_name,
+                &attack_trees,
+                &options.ratings,
+                &mut outputs,
+                failures,
+            );
+        }
+
+        if let Some(lockfile) = build_lockfile(directory_name, &attack_trees, failures) {
+            let json =
+                serde_json::to_string_pretty(&lockfile).expect("lockfile serialization error");
+            outputs.write(&Path::new(directory_name).join(LOCK_FILE), json, failures);
+        }
+    }
+
+    // render one markdown page per tree
+    progress.start("pages", attack_trees.len());
+    for (file_path, attack_tree_root) in &attack_trees {
+        let page_path = tree_page_path(&absolute_trees_dir, file_path);
+        progress.step(&page_path.file_name().unwrap_or_default().to_string_lossy());
+        options.node_links = node_links(&options, trees_dir, file_path, without_graphviz);
+        let image_path = to_image_path(
+            &Path::new("..").join(images_dir),
+            file_path,
+            &tree_format(file_path),
+        );
+        let page = match &tree_template {
+            Some(template) => {
+                render_tree_with_template(template, &image_path, attack_tree_root, &definition)
+            }
+            None => Ok(render_tree_to_markdown(
+                &image_path,
+                attack_tree_root,
+                &definition,
+                &options,
+            )),
+        };
+
+        match page {
+            Ok(page) => outputs.write(&page_path, page, failures),
+            Err(e) => failures.record(&page_path, e),
+        }
+    }
+
+    outputs.commit(failures, earlier_failures);
+
+    (definition, attack_trees)
+}
 
-    if let Err(e) = fs::write(&threats_file_path, render_to_markdown_table(root_nodes)) {
-        println!("Error writing file {}: {}", &threats_file_path, e);
+/// Adds the threats changed since the previous run to the changelog and
+/// keeps a snapshot of this run for the next one. Without a snapshot, the
+/// changes are those since the baseline, or all threats are new.
+fn update_changelog(
+    directory_name: &str,
+    attack_trees: &AttackTrees,
+    ratings: &[RatingLevel],
+    outputs: &mut Outputs,
+    failures: &mut Failures,
+) {
+    let snapshot_path = Path::new(directory_name).join(LAST_RUN_FILE);
+    let previous = fs::read_to_string(&snapshot_path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .or_else(|| read_baseline(directory_name))
+        .unwrap_or(Baseline { nodes: vec![] });
+
+    let changes = previous.threat_changes(attack_trees);
+    if !changes.is_empty() {
+        let changelog_path = Path::new(directory_name).join(CHANGELOG_FILE);
+        let changelog = fs::read_to_string(&changelog_path).unwrap_or_default();
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let entry = render_changelog_entry(&timestamp, &changes, ratings);
+        outputs.write(
+            &changelog_path,
+            add_changelog_entry(&changelog, &entry),
+            failures,
+        );
+    }
+
+    let snapshot = Baseline::from_trees(attack_trees);
+    let json = serde_json::to_string_pretty(&snapshot).expect("snapshot serialization error");
+    outputs.write(&snapshot_path, json, failures);
+}
+
+/// Hashes the sources of the trees and the criteria definition and locks the
+/// feasibility values computed from them.
+fn build_lockfile(
+    directory_name: &str,
+    attack_trees: &AttackTrees,
+    failures: &mut Failures,
+) -> Option<Lockfile> {
+    let criteria_path = Path::new(directory_name).join(CRITERIA_FILE);
+    let criteria = match fs::read(&criteria_path) {
+        Ok(criteria) => criteria,
+        Err(e) => {
+            failures.record(&criteria_path, e);
+            return None;
+        }
+    };
+
+    let mut lockfile = Lockfile::new(&criteria);
+    for (file, root) in attack_trees {
+        match fs::read(file) {
+            Ok(source) => lockfile.add(file, &source, root),
+            Err(e) => failures.record(file, e),
+        }
+    }
+
+    Some(lockfile)
+}
+
+/// Compares the results computed from the current sources to att.lock.
+fn check_lock(directory_name: &str, report_args: &ReportArgs, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let lock_path = Path::new(directory_name).join(LOCK_FILE);
+    let locked: Lockfile = match fs::read_to_string(&lock_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(locked) => locked,
+        Err(e) => {
+            failures.record(&lock_path, e);
+            return failures;
+        }
+    };
+
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+    let attack_trees: AttackTrees = match report_args.with_defenses {
+        true => attack_trees
+            .iter()
+            .map(|(f, r)| (f.clone(), apply_defenses(r)))
+            .collect(),
+        false => attack_trees,
+    };
+    let Some(recomputed) = build_lockfile(directory_name, &attack_trees, &mut failures) else {
+        return failures;
+    };
+
+    let differences = locked.compare(&recomputed);
+    for d in &differences {
+        failures.record(&lock_path, d);
+    }
+    if differences.is_empty() && failures.count() == 0 {
+        println!("The results match {}.", lock_path.display());
+    }
+
+    failures
+}
+
+/// The tree page, relative to the images, that the nodes of SVG images link to.
+fn node_links(
+    options: &RenderOptions,
+    trees_dir: &Path,
+    attack_tree_path: &Path,
+    without_graphviz: bool,
+) -> Option<String> {
+    if without_graphviz || !options.supports_links() {
+        return None;
+    }
+
+    let page = tree_page_path(&Path::new("..").join(trees_dir), attack_tree_path);
+    // forward slashes on all platforms, the path ends up in a URL
+    Some(page.to_string_lossy().replace('\\', "/"))
+}
+
+/// The threats with an impact in the matrix of their rating and impact, or
+/// nothing when no ratings are configured or no tree has an impact.
+fn build_risk_matrix(
+    trees: &[&(PathBuf, Rc<dyn FeasibleStep>)],
+    numbers: &NodeNumbers,
+    options: &RenderOptions,
+    config: &Config,
+    failures: &mut Failures,
+) -> Option<RiskMatrix> {
+    if options.ratings.is_empty() {
+        return None;
+    }
+    let impacts: Vec<String> = match config.impacts.is_empty() {
+        true => DEFAULT_IMPACT_LEVELS.map(String::from).to_vec(),
+        false => config.impacts.clone(),
+    };
+
+    let mut matrix = RiskMatrix::new(&options.ratings, &impacts);
+    for (file, root) in trees {
+        let (Some(impact), Some(id)) = (&root.review().impact, numbers.get(root.id())) else {
+            continue;
+        };
+        if let Err(e) = matrix.add(id, root.feasibility_value(), impact) {
+            failures.record(file, e);
+        }
+    }
+
+    (!matrix.is_empty()).then_some(matrix)
+}
+
+/// The image of the risk matrix, with the titles of the threats below it
+/// unless the overview shows their numbers already.
+fn risk_matrix_to_markdown(
+    image: &Path,
+    trees: &[&(PathBuf, Rc<dyn FeasibleStep>)],
+    numbers: &NodeNumbers,
+    options: &RenderOptions,
+) -> String {
+    let mut result = format!(
+        "![Risk matrix]({})\n\n",
+        image.to_string_lossy().replace('\\', "/")
+    );
+    if options.numbers.is_none() {
+        for (_, root) in trees.iter().filter(|(_, r)| r.review().impact.is_some()) {
+            if let Some(id) = numbers.get(root.id()) {
+                result.push_str(&format!("- {}: {}\n", id, root.title()));
+            }
+        }
+        result.push('\n');
     }
+
+    result
+}
+
+/// The custom attributes to summarize the feasibility by, with the prefix
+/// added where it is left out.
+fn aggregated_attributes(report_args: &ReportArgs, config: &Config) -> Vec<String> {
+    let keys = if !report_args.aggregate_by.is_empty() {
+        &report_args.aggregate_by
+    } else {
+        &config.aggregate_by
+    };
+    if keys.is_empty() {
+        return vec![DEFAULT_AGGREGATE_ATTRIBUTE.to_string()];
+    }
+
+    keys.iter()
+        .map(|k| match k.starts_with(CUSTOM_ATTRIBUTE_PREFIX) {
+            true => k.clone(),
+            false => format!("{}{}", CUSTOM_ATTRIBUTE_PREFIX, k),
+        })
+        .collect()
+}
+
+fn tree_page_path(trees_dir: &Path, attack_tree_path: &Path) -> PathBuf {
+    trees_dir
+        .join(attack_tree_path.file_name().unwrap_or(OsStr::new("tree")))
+        .with_extension("md")
+}
+
+/// The file name of a tree, as the patterns of att.toml match it.
+fn tree_file_name(attack_tree_path: &Path) -> PathBuf {
+    PathBuf::from(attack_tree_path.file_name().unwrap_or_default())
+}
+
+/// The Graphviz format of the image of a tree, from its `[[images]]` entry in
+/// att.toml if it has one.
+fn tree_image_format<'a>(config: &'a Config, attack_tree_path: &Path, format: &'a str) -> &'a str {
+    config
+        .image_settings(&tree_file_name(attack_tree_path))
+        .and_then(|s| s.format.as_deref())
+        .unwrap_or(format)
 }
 
-fn to_image_path(images_dir: &Path, attack_tree_path: &PathBuf) -> PathBuf {
-    
+fn to_image_path(images_dir: &Path, attack_tree_path: &Path, format: &str) -> PathBuf {
+    // formats like "svg:cairo" select a renderer, the extension is the part before it
+    let extension = format.split(':').next().unwrap_or(format);
     images_dir.join(
         Path::new(attack_tree_path.file_name().unwrap_or(OsStr::new("image")))
-            .with_extension("png"),
+            .with_extension(extension),
     )
 }
 
 fn parse_attack_trees(
     tree_files: &[DirEntry],
     definition: &Rc<FeasibilityCriteria>,
-) -> Vec<(PathBuf, Rc<dyn FeasibleStep>)> {
-    let mut steps = vec![];
+    parser_options: &ParserOptions,
+    progress: &mut Progress,
+    failures: &mut Failures,
+) -> AttackTrees {
+    progress.start("parse", tree_files.len());
+    tree_files
+        .iter()
+        .filter_map(|file_entry| {
+            let file_path = file_entry.path();
+            progress.step(&file_entry.file_name().to_string_lossy());
+            let attack_tree_root =
+                parse_attack_tree_file(&file_path, definition, parser_options, failures)?;
+            Some((file_path, attack_tree_root))
+        })
+        .collect()
+}
 
-    for file_entry in tree_files {
-        let file_path = file_entry.path();
-        let f = File::open(&file_path)
-            .expect(&format!("Could not read file {:?}", file_entry.file_name()));
-        let mut f = BufReader::new(f);
+fn parse_attack_tree_file(
+    file_path: &Path,
+    definition: &Rc<FeasibilityCriteria>,
+    parser_options: &ParserOptions,
+    failures: &mut Failures,
+) -> Option<Rc<dyn FeasibleStep>> {
+    let f = match File::open(file_path) {
+        Ok(f) => f,
+        Err(e) => {
+            failures.record(file_path, e);
+            return None;
+        }
+    };
+    let mut f = BufReader::new(f);
 
-        let mut parser = AttackTreeParser::new();
-        let attack_tree_root = parser
-            .parse(&mut f, definition)
-            .expect("Error in tree file");
-        steps.push((file_path, attack_tree_root));
+    let mut parser = AttackTreeParser::new();
+    parser.set_file(file_path);
+    match parser.parse_with_options(&mut f, definition, parser_options) {
+        Ok(root) => Some(root),
+        Err(e) => {
+            failures.record(file_path, e);
+            for d in parser.diagnostics() {
+                eprintln!("{}:{}: {}", file_path.display(), d.line, d.message);
+            }
+            None
+        }
     }
-
-    steps
 }
@@ -0,0 +1,9 @@
+/// A premise the analysis of a node relies on, e.g. "the attacker has no
+/// physical access". It does not change the feasibility of the node and is
+/// listed in the report for review.
+#[derive(Clone, Debug)]
+pub struct Assumption {
+    pub id: u32,
+    pub description: String,
+    pub note: Option<String>,
+}
@@ -0,0 +1,31 @@
+use std::cell::RefCell;
+
+use super::{FeasibilityAssessment, TreeError};
+
+/// Remembers the feasibility of an inner node until its subtree changes.
+#[derive(Default)]
+pub struct FeasibilityCache(RefCell<Option<Result<FeasibilityAssessment, TreeError>>>);
+
+impl FeasibilityCache {
+    pub fn get_or_compute<F>(&self, compute: F) -> Result<FeasibilityAssessment, TreeError>
+    where
+        F: FnOnce() -> Result<FeasibilityAssessment, TreeError>,
+    {
+        if let Some(cached) = self.0.borrow().as_ref() {
+            return cached.clone();
+        }
+
+        let result = compute();
+        *self.0.borrow_mut() = Some(result.clone());
+        result
+    }
+
+    /// Drops the value, returns whether there was one.
+    pub fn invalidate(&self) -> bool {
+        self.0.borrow_mut().take().is_some()
+    }
+
+    pub fn is_cached(&self) -> bool {
+        self.0.borrow().is_some()
+    }
+}
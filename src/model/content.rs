@@ -0,0 +1,217 @@
+use std::rc::Rc;
+
+use super::{feasible_step::FeasibleStep, FeasibilityAssessment};
+
+/// Whether two subtrees describe the same attack: node types, titles,
+/// assessments, notes, defenses, assumptions and attributes. Ids and source
+/// spans are ignored, children are compared in order.
+pub fn same_content(a: &Rc<dyn FeasibleStep>, b: &Rc<dyn FeasibleStep>) -> bool {
+    let (a_children, b_children) = (a.get_children(), b.get_children());
+
+    own_content(a, true) == own_content(b, true)
+        && a_children.len() == b_children.len()
+        && a_children
+            .iter()
+            .zip(&b_children)
+            .all(|(a, b)| same_content(a, b))
+}
+
+/// Like `same_content`, but the children of a node may be in any order.
+pub fn same_content_ignoring_order(a: &Rc<dyn FeasibleStep>, b: &Rc<dyn FeasibleStep>) -> bool {
+    let (a_children, mut b_children) = (a.get_children(), b.get_children());
+    if own_content(a, true) != own_content(b, true) || a_children.len() != b_children.len() {
+        return false;
+    }
+
+    a_children.iter().all(|a| {
+        match b_children
+            .iter()
+            .position(|b| same_content_ignoring_order(a, b))
+        {
+            Some(i) => {
+                b_children.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// A hash of the content compared by `same_content`. It stays the same
+/// across runs, platforms and versions of the compiler.
+pub fn content_hash(node: &Rc<dyn FeasibleStep>) -> u64 {
+    let child_hashes: Vec<u64> = node.get_children().iter().map(content_hash).collect();
+
+    combine(node, &child_hashes, true)
+}
+
+/// Like `content_hash`, but the fixed positions of the `number` attributes
+/// are ignored, so that numbered copies of a subtree hash the same.
+pub fn content_hash_ignoring_numbers(node: &Rc<dyn FeasibleStep>) -> u64 {
+    let child_hashes: Vec<u64> = node
+        .get_children()
+        .iter()
+        .map(content_hash_ignoring_numbers)
+        .collect();
+
+    combine(node, &child_hashes, false)
+}
+
+/// A hash of the content compared by `same_content_ignoring_order`.
+pub fn content_hash_ignoring_order(node: &Rc<dyn FeasibleStep>) -> u64 {
+    let mut child_hashes: Vec<u64> = node
+        .get_children()
+        .iter()
+        .map(content_hash_ignoring_order)
+        .collect();
+    child_hashes.sort_unstable();
+
+    combine(node, &child_hashes, true)
+}
+
+/// FNV-1a, simple and stable unlike the hasher of the standard library.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn combine(node: &Rc<dyn FeasibleStep>, child_hashes: &[u64], numbers: bool) -> u64 {
+    let mut bytes = own_content(node, numbers);
+    for h in child_hashes {
+        bytes.extend_from_slice(&h.to_le_bytes());
+    }
+
+    fnv1a(&bytes)
+}
+
+/// The content of the node without its children. Every field is prefixed
+/// with its length, so that no two different nodes give the same bytes.
+fn own_content(node: &Rc<dyn FeasibleStep>, numbers: bool) -> Vec<u8> {
+    let mut content = Content::default();
+
+    content.field(&node.kind().to_string());
+    content.field(node.title());
+    content.optional(node.threshold());
+    if node.get_children().is_empty() {
+        // the assessment of inner nodes follows from the children
+        match node.feasibility_range() {
+            Ok((easiest, hardest)) => {
+                content.assessment(&easiest);
+                content.assessment(&hardest);
+            }
+            Err(e) => content.field(&e.to_string()),
+        }
+    }
+
+    let review = node.review();
+    content.optional(review.owner.as_ref());
+    content.optional(review.status);
+    content.optional(review.version.as_ref());
+    content.optional(review.date);
+    content.optional(node.note());
+    content.optional(node.number().filter(|_| numbers));
+    content.optional(node.assessed());
+    content.optional(node.detection());
+    content.list(node.requirements());
+    content.list(node.profiles());
+    content.list(node.techniques());
+    content.list(&node.attributes().iter().collect::<Vec<_>>());
+    for d in node.defenses() {
+        content.field(&d.description);
+        content.assessment(&d.effect);
+        content.optional(d.note.as_ref());
+    }
+    for a in node.assumptions() {
+        content.field(&a.description);
+        content.optional(a.note.as_ref());
+    }
+
+    content.0
+}
+
+#[derive(Default)]
+struct Content(Vec<u8>);
+
+impl Content {
+    fn field(&mut self, text: &str) {
+        self.0
+            .extend_from_slice(format!("{}:", text.len()).as_bytes());
+        self.0.extend_from_slice(text.as_bytes());
+    }
+
+    fn optional(&mut self, value: Option<impl std::fmt::Display>) {
+        match value {
+            Some(v) => self.field(&format!("+{}", v)),
+            None => self.field("-"),
+        }
+    }
+
+    fn list(&mut self, values: &[impl std::fmt::Debug]) {
+        self.field(&values.len().to_string());
+        for v in values {
+            self.field(&format!("{:?}", v));
+        }
+    }
+
+    fn assessment(&mut self, assessment: &FeasibilityAssessment) {
+        for (c, v) in assessment.iter() {
+            self.field(&c.id);
+            self.optional(v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(text: &str) -> Rc<dyn FeasibleStep> {
+        AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &build_criteria(&["Kn", "Eq"]))
+            .unwrap()
+    }
+
+    #[test]
+    fn equal_trees_have_the_same_content_and_hash() {
+        let a = parse("Root;&\n    A; Kn=1, Eq=2\n    B; Kn=3, Eq=4");
+        let b = parse("Root;&\n\n    A; Kn=1, Eq=2\n    B;  Kn=3,Eq=4");
+
+        assert!(same_content(&a, &b));
+        assert_eq!(content_hash(&a), content_hash(&b));
+
+        for other in [
+            "Root;|\n    A; Kn=1, Eq=2\n    B; Kn=3, Eq=4",
+            "Root;&\n    A; Kn=1, Eq=2\n    B; Kn=3, Eq=5",
+            "Root;&\n    A; Kn=1, Eq=2\n    B; Kn=3, Eq=4, x-ticket=1",
+            "Root;&\n    A; Kn=1, Eq=2\n        > note\n    B; Kn=3, Eq=4",
+            "Root;&\n    A; Kn=1, Eq=2\n    B; Kn=3, Eq=4\n        Lock; ! Kn=1",
+        ] {
+            let other = parse(other);
+            assert!(!same_content(&a, &other));
+            assert_ne!(content_hash(&a), content_hash(&other));
+        }
+    }
+
+    #[test]
+    fn the_order_of_children_can_be_ignored() {
+        let a = parse("Root;|\n    A; Kn=1, Eq=2\n    B;&\n        C; Kn=1\n        D; Kn=2");
+        let b = parse("Root;|\n    B;&\n        D; Kn=2\n        C; Kn=1\n    A; Kn=1, Eq=2");
+
+        assert!(!same_content(&a, &b));
+        assert_ne!(content_hash(&a), content_hash(&b));
+        assert!(same_content_ignoring_order(&a, &b));
+        assert_eq!(
+            content_hash_ignoring_order(&a),
+            content_hash_ignoring_order(&b)
+        );
+
+        let c = parse("Root;|\n    A; Kn=1, Eq=2\n    A; Kn=1, Eq=2");
+        let d = parse("Root;|\n    A; Kn=1, Eq=2\n    B;&\n        C; Kn=1\n        D; Kn=2");
+        assert!(!same_content_ignoring_order(&c, &d));
+        assert!(!same_content_ignoring_order(&d, &c));
+    }
+}
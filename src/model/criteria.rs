@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{expression::Expression, TreeError};
+
+#[derive(Error, Debug)]
+pub enum CriteriaError {
+    #[error("criteria file parser error: {0}")]
+    ParseError(#[from] serde_json::Error),
+    #[error("criterion '{0}' uses a higher-is-easier scale and needs a 'max' value")]
+    MissingMaximum(String),
+    #[error("unit '{1}' of criterion '{0}' must be letters or symbols without digits, spaces, ',', '=' or '.'")]
+    InvalidUnit(String, String),
+    #[error("'{0}' names more than one criterion")]
+    AmbiguousName(String),
+    #[error("invalid expression of criterion '{0}': {1}")]
+    InvalidExpression(String, String),
+    #[error("the weighted maximum of criterion '{0}' is larger than {max}", max = u32::MAX)]
+    WeightTooLarge(String),
+}
+
+/// The criteria assessed on the leaves and those derived from them.
+#[derive(Debug)]
+pub struct FeasibilityCriteria(pub Vec<FeasiblityCriterion>, pub Vec<DerivedCriterion>);
+
+impl FeasibilityCriteria {
+    /// Reads and validates the contents of a criteria.json file. Entries
+    /// with an `expression` are derived criteria.
+    pub fn from_json(json: &str) -> Result<FeasibilityCriteria, CriteriaError> {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+        let (derived, criteria): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|e| e.get("expression").is_some());
+        let criteria: Vec<FeasiblityCriterion> = criteria
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?;
+
+        let mut weighted_max = Some(0u32);
+        for c in &criteria {
+            if c.scale == Scale::HigherIsEasier && c.max.is_none() {
+                return Err(CriteriaError::MissingMaximum(c.id.clone()));
+            }
+            if let Some(max) = c.max_value() {
+                weighted_max = c
+                    .weight
+                    .checked_mul(max)
+                    .and_then(|m| weighted_max?.checked_add(m));
+                if weighted_max.is_none() {
+                    return Err(CriteriaError::WeightTooLarge(c.id.clone()));
+                }
+            }
+            if let Some(unit) = &c.unit {
+                // the unit has to be told apart from the value and the
+                // separators of an assessment like `ET=10d..30d, Kn=2`
+                let invalid = |ch: char| {
+                    ch.is_ascii_digit() || ch.is_whitespace() || matches!(ch, ',' | '=' | '.')
+                };
+                if unit.is_empty() || unit.chars().any(invalid) {
+                    return Err(CriteriaError::InvalidUnit(c.id.clone(), unit.clone()));
+                }
+            }
+        }
+
+        let ids: Vec<&str> = criteria.iter().map(|c| c.id.as_str()).collect();
+        let derived = derived
+            .into_iter()
+            .map(|entry| {
+                let entry: DerivedEntry = serde_json::from_value(entry)?;
+                let expression = Expression::parse(&entry.expression, &ids)
+                    .map_err(|e| CriteriaError::InvalidExpression(entry.id.clone(), e))?;
+                Ok(DerivedCriterion {
+                    name: entry.name,
+                    id: entry.id,
+                    description: entry.description,
+                    formula: entry.expression,
+                    expression,
+                })
+            })
+            .collect::<Result<Vec<_>, CriteriaError>>()?;
+
+        let mut names: Vec<&str> = criteria
+            .iter()
+            .flat_map(|c| c.names())
+            .chain(derived.iter().map(|d| d.id.as_str()))
+            .collect();
+        names.sort_unstable();
+        if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(CriteriaError::AmbiguousName(pair[0].to_string()));
+        }
+
+        Ok(FeasibilityCriteria(criteria, derived))
+    }
+
+    /// The ids and names of the criteria whose values the reports show: the
+    /// derived ones if there are any, else the assessed ones.
+    pub fn shown(&self) -> Vec<(&str, &str)> {
+        match self.1.is_empty() {
+            true => self
+                .0
+                .iter()
+                .map(|c| (c.id.as_str(), c.name.as_str()))
+                .collect(),
+            false => self
+                .1
+                .iter()
+                .map(|d| (d.id.as_str(), d.name.as_str()))
+                .collect(),
+        }
+    }
+
+    /// The index of the criterion with the given id.
+    pub fn position(&self, id: &str) -> Result<usize, TreeError> {
+        self.0
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or_else(|| TreeError::UnknownCriterion(id.to_string()))
+    }
+
+    /// The index of the criterion with the given id or alias, as written in
+    /// an assessment. An exact match wins over one ignoring case.
+    pub fn lookup(&self, name: &str, ignore_case: bool) -> Result<usize, TreeError> {
+        let matching = |exact: bool| {
+            self.0.iter().position(|c| {
+                c.names().any(|n| {
+                    if exact {
+                        n == name
+                    } else {
+                        n.eq_ignore_ascii_case(name)
+                    }
+                })
+            })
+        };
+
+        matching(true)
+            .or_else(|| if ignore_case { matching(false) } else { None })
+            .ok_or_else(|| TreeError::UnknownCriterion(name.to_string()))
+    }
+
+    /// The feasibility value of an attack rated hardest on every criterion,
+    /// if all criteria have a known maximum.
+    pub fn max_feasibility_value(&self) -> Option<u32> {
+        self.0.iter().try_fold(0u32, |sum, c| {
+            sum.checked_add(c.weight.checked_mul(c.max_value()?)?)
+        })
+    }
+
+    /// Scales a feasibility value to the range 0 to 1, making values of
+    /// different criteria sets comparable.
+    pub fn normalize(&self, value: u32) -> Option<f64> {
+        match self.max_feasibility_value()? {
+            0 => None,
+            max => Some(value as f64 / max as f64),
+        }
+    }
+}
+
+/// Whether larger values of a criterion make an attack harder (e.g. required
+/// knowledge) or easier (e.g. likelihood).
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scale {
+    #[default]
+    HigherIsHarder,
+    HigherIsEasier,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FeasiblityCriterion {
+    pub name: String,
+    pub id: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub scale: Scale,
+    /// The largest value on the scale, needed to invert higher-is-easier scales.
+    #[serde(default)]
+    pub max: Option<u32>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// What the individual values of the scale mean, e.g. `{"0": "Layman"}`.
+    #[serde(default)]
+    pub guidance: BTreeMap<u32, String>,
+    /// Unit of the values, e.g. `d` or `EUR`. Assessments may repeat it
+    /// after the value, like `ET=30d`.
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Further names assessments may use instead of the id, e.g. `Knowledge`.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// A criterion computed from the assessed ones, e.g. the attack potential
+/// `AP = Kn + Eq + Wo`. It cannot be assessed itself.
+#[derive(Debug)]
+pub struct DerivedCriterion {
+    pub name: String,
+    pub id: String,
+    pub description: Option<String>,
+    /// The expression as written in criteria.json.
+    pub formula: String,
+    pub expression: Expression,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DerivedEntry {
+    name: String,
+    id: String,
+    #[serde(default)]
+    description: Option<String>,
+    expression: String,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl FeasiblityCriterion {
+    pub fn new(id: &str, name: &str) -> FeasiblityCriterion {
+        FeasiblityCriterion {
+            name: name.to_string(),
+            id: id.to_string(),
+            weight: default_weight(),
+            scale: Scale::default(),
+            max: None,
+            description: None,
+            guidance: BTreeMap::new(),
+            unit: None,
+            aliases: vec![],
+        }
+    }
+
+    /// The id followed by the aliases.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.id.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// The largest value on the scale, from `max` or else the highest value
+    /// with guidance.
+    pub fn max_value(&self) -> Option<u32> {
+        self.max
+            .or_else(|| self.guidance.keys().next_back().copied())
+    }
+
+    /// The value with the unit of the criterion, e.g. `30d` or `500 EUR`.
+    /// Units longer than one character are separated by a space.
+    pub fn format_value(&self, value: u32) -> String {
+        match self.unit.as_deref() {
+            Some(unit) if unit.chars().count() == 1 => format!("{}{}", value, unit),
+            Some(unit) => format!("{} {}", value, unit),
+            None => value.to_string(),
+        }
+    }
+
+    /// Maps a value of this criterion to a scale where higher always means harder.
+    pub fn difficulty(&self, value: Option<u32>) -> u32 {
+        match (self.scale, value) {
+            (_, None) => 0,
+            (Scale::HigherIsHarder, Some(v)) => v,
+            (Scale::HigherIsEasier, Some(v)) => self.max.unwrap_or(0).saturating_sub(v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weight_and_scale_have_defaults() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "Eq", "name": "Equipment"}, {"id": "Kn", "name": "Knowledge", "weight": 2}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(criteria.0[0].weight, 1);
+        assert_eq!(criteria.0[0].scale, Scale::HigherIsHarder);
+        assert_eq!(criteria.0[1].weight, 2);
+    }
+
+    #[test]
+    fn an_inverse_scale_is_read_with_its_maximum() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "Li", "name": "Likelihood", "scale": "higher_is_easier", "max": 5}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(criteria.0[0].scale, Scale::HigherIsEasier);
+        assert_eq!(criteria.0[0].difficulty(Some(4)), 1);
+    }
+
+    #[test]
+    fn values_are_normalized_by_the_largest_possible_value() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "Kn", "name": "Knowledge", "weight": 2, "max": 5},
+                {"id": "Eq", "name": "Equipment", "guidance": {"0": "None", "10": "Lab"}}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(criteria.max_feasibility_value(), Some(20));
+        assert_eq!(criteria.normalize(5), Some(0.25));
+    }
+
+    #[test]
+    fn weights_overflowing_the_feasibility_value_are_rejected() {
+        let result = FeasibilityCriteria::from_json(
+            r#"[{"id": "Kn", "name": "Knowledge", "max": 5},
+                {"id": "Eq", "name": "Equipment", "weight": 1000000000, "max": 5}]"#,
+        );
+
+        assert!(matches!(result, Err(CriteriaError::WeightTooLarge(id)) if id == "Eq"));
+    }
+
+    #[test]
+    fn criteria_without_maximum_cannot_be_normalized() {
+        let criteria =
+            FeasibilityCriteria::from_json(r#"[{"id": "Eq", "name": "Equipment"}]"#).unwrap();
+
+        assert_eq!(criteria.normalize(5), None);
+    }
+
+    #[test]
+    fn an_inverse_scale_without_maximum_is_rejected() {
+        let result = FeasibilityCriteria::from_json(
+            r#"[{"id": "Li", "name": "Likelihood", "scale": "higher_is_easier"}]"#,
+        );
+
+        assert!(matches!(result, Err(CriteriaError::MissingMaximum(id)) if id == "Li"));
+    }
+
+    #[test]
+    fn description_and_value_guidance_are_read() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "Kn", "name": "Knowledge", "description": "Expertise needed", "guidance": {"0": "Layman", "3": "Proficient"}}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            criteria.0[0].description.as_deref(),
+            Some("Expertise needed")
+        );
+        assert_eq!(
+            criteria.0[0].guidance.get(&3).map(String::as_str),
+            Some("Proficient")
+        );
+    }
+
+    #[test]
+    fn values_are_shown_with_the_unit_and_invalid_units_are_rejected() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "ET", "name": "Elapsed time", "unit": "d"},
+                {"id": "Co", "name": "Cost", "unit": "EUR"},
+                {"id": "Kn", "name": "Knowledge"}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(criteria.0[0].format_value(30), "30d");
+        assert_eq!(criteria.0[1].format_value(500), "500 EUR");
+        assert_eq!(criteria.0[2].format_value(2), "2");
+
+        let result = FeasibilityCriteria::from_json(
+            r#"[{"id": "ET", "name": "Elapsed time", "unit": "1d"}]"#,
+        );
+        assert!(matches!(result, Err(CriteriaError::InvalidUnit(id, _)) if id == "ET"));
+    }
+
+    #[test]
+    fn criteria_are_found_by_id_or_alias() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "Eq", "name": "Equipment"},
+                {"id": "Kn", "name": "Knowledge", "aliases": ["Knowledge", "K"]}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(criteria.lookup("K", false).unwrap(), 1);
+        assert!(criteria.lookup("knowledge", false).is_err());
+        assert_eq!(criteria.lookup("knowledge", true).unwrap(), 1);
+        assert_eq!(criteria.lookup("EQ", true).unwrap(), 0);
+
+        let result = FeasibilityCriteria::from_json(
+            r#"[{"id": "Eq", "name": "Equipment", "aliases": ["E"]},
+                {"id": "E", "name": "Expertise"}]"#,
+        );
+        assert!(matches!(result, Err(CriteriaError::AmbiguousName(name)) if name == "E"));
+    }
+
+    #[test]
+    fn derived_criteria_are_computed_from_the_assessed_ones() {
+        let criteria = FeasibilityCriteria::from_json(
+            r#"[{"id": "Kn", "name": "Knowledge", "guidance": {"0": "Layman"}},
+                {"id": "AP", "name": "Attack potential", "expression": "Kn + 2 * Eq"},
+                {"id": "Eq", "name": "Equipment"}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(criteria.0.len(), 2);
+        assert_eq!(criteria.1[0].formula, "Kn + 2 * Eq");
+        assert_eq!(
+            criteria.1[0].expression.evaluate(&[Some(1), Some(3)]),
+            Some(7)
+        );
+        assert_eq!(criteria.shown(), vec![("AP", "Attack potential")]);
+        assert!(criteria.lookup("AP", false).is_err());
+
+        let result = FeasibilityCriteria::from_json(
+            r#"[{"id": "AP", "name": "Attack potential", "expression": "Kn + Eq"},
+                {"id": "Kn", "name": "Knowledge"}]"#,
+        );
+        assert!(matches!(
+            result,
+            Err(CriteriaError::InvalidExpression(id, message)) if id == "AP" && message == "unknown criterion 'Eq'"
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use std::rc::Rc;
+
+use super::{
+    feasible_step::{FeasibleStep, NodeKind},
+    merge::{copy_leaf, new_inner_node},
+    FeasibilityAssessment,
+};
+
+/// A control attached to an attack node. It makes the attack harder by adding
+/// its values to the criteria of the node.
+#[derive(Clone, Debug)]
+pub struct Defense {
+    pub id: u32,
+    pub description: String,
+    /// Values added to the criteria of the countered node, unrated criteria
+    /// are left unchanged.
+    pub effect: FeasibilityAssessment,
+    pub note: Option<String>,
+}
+
+impl Defense {
+    /// The added values, e.g. `Kn+2, Eq+1`.
+    pub fn describe_effect(&self) -> String {
+        self.effect
+            .iter()
+            .filter_map(|(c, v)| v.map(|v| format!("{}+{}", c.id, c.format_value(v))))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl FeasibilityAssessment {
+    /// Adds the values of `effect` to this assessment.
+    pub fn strengthened_by(&self, effect: &FeasibilityAssessment) -> FeasibilityAssessment {
+        let values: Vec<Option<u32>> = self
+            .iter()
+            .map(|(c, v)| match (v, effect.by_id().get(&c.id)) {
+                (Some(v), Some(e)) => Some(v + e),
+                (v, _) => v,
+            })
+            .collect();
+
+        FeasibilityAssessment::from_values(&self.definition, &values)
+    }
+}
+
+/// Copies the tree with all defenses in effect. The defenses of an inner node
+/// are added to every leaf below it, which shifts the combined value of the
+/// node by the same amount.
+pub fn apply_defenses(root: &Rc<dyn FeasibleStep>) -> Rc<dyn FeasibleStep> {
+    copy_defended(root, None, &[])
+}
+
+fn copy_defended(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    inherited: &[FeasibilityAssessment],
+) -> Rc<dyn FeasibleStep> {
+    let mut effects = inherited.to_vec();
+    effects.extend(node.defenses().iter().map(|d| d.effect.clone()));
+
+    if node.kind() == NodeKind::Leaf {
+        if let Ok((easiest, hardest)) = node.feasibility_range() {
+            let strengthen =
+                |a: FeasibilityAssessment| effects.iter().fold(a, |a, e| a.strengthened_by(e));
+            return copy_leaf(node, parent, (strengthen(easiest), strengthen(hardest)));
+        }
+    }
+
+    let copy = new_inner_node(node, parent);
+    for c in node.get_children() {
+        copy.add_child(&copy_defended(&c, Some(copy.clone()), &effects));
+    }
+
+    copy
+}
+
+/// Counts the defenses in the tree below and including `node`.
+pub fn count_defenses(node: &Rc<dyn FeasibleStep>) -> usize {
+    node.defenses().len()
+        + node
+            .get_children()
+            .iter()
+            .map(count_defenses)
+            .sum::<usize>()
+}
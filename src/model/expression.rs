@@ -0,0 +1,177 @@
+use std::{iter::Peekable, str::CharIndices};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// Arithmetic on the values of criteria, e.g. `Kn + 2 * (Eq + Wo)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(i64),
+    /// The value of the criterion at this position of the definition.
+    Criterion(usize),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+impl Expression {
+    /// Parses the expression, resolving the criteria by their position in
+    /// `ids`.
+    pub fn parse(text: &str, ids: &[&str]) -> Result<Expression, String> {
+        let mut parser = ExpressionParser {
+            text,
+            chars: text.char_indices().peekable(),
+            ids,
+        };
+        let expression = parser.sum()?;
+
+        parser.skip_whitespace();
+        match parser.chars.peek() {
+            None => Ok(expression),
+            Some((_, c)) => Err(format!("unexpected '{}'", c)),
+        }
+    }
+
+    /// The value for the criteria `values`, None if a criterion is unrated
+    /// or a division by zero occurs. Negative results count as 0.
+    pub fn evaluate(&self, values: &[Option<u32>]) -> Option<u32> {
+        let value = self.evaluate_signed(values)?;
+        Some(value.clamp(0, i64::from(u32::MAX)) as u32)
+    }
+
+    fn evaluate_signed(&self, values: &[Option<u32>]) -> Option<i64> {
+        match self {
+            Expression::Number(n) => Some(*n),
+            Expression::Criterion(index) => values.get(*index).copied().flatten().map(i64::from),
+            Expression::Binary(left, operator, right) => {
+                let (a, b) = (
+                    left.evaluate_signed(values)?,
+                    right.evaluate_signed(values)?,
+                );
+                match operator {
+                    Operator::Add => a.checked_add(b),
+                    Operator::Subtract => a.checked_sub(b),
+                    Operator::Multiply => a.checked_mul(b),
+                    Operator::Divide => a.checked_div(b),
+                }
+            }
+        }
+    }
+}
+
+struct ExpressionParser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    ids: &'a [&'a str],
+}
+
+impl ExpressionParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn next_operator(&mut self, operators: &[(char, Operator)]) -> Option<Operator> {
+        self.skip_whitespace();
+        let (_, c) = self.chars.peek()?;
+        let operator = operators.iter().find(|(o, _)| o == c)?.1;
+        self.chars.next();
+        Some(operator)
+    }
+
+    fn sum(&mut self) -> Result<Expression, String> {
+        let mut expression = self.product()?;
+        while let Some(operator) =
+            self.next_operator(&[('+', Operator::Add), ('-', Operator::Subtract)])
+        {
+            expression =
+                Expression::Binary(Box::new(expression), operator, Box::new(self.product()?));
+        }
+        Ok(expression)
+    }
+
+    fn product(&mut self) -> Result<Expression, String> {
+        let mut expression = self.operand()?;
+        while let Some(operator) =
+            self.next_operator(&[('*', Operator::Multiply), ('/', Operator::Divide)])
+        {
+            expression =
+                Expression::Binary(Box::new(expression), operator, Box::new(self.operand()?));
+        }
+        Ok(expression)
+    }
+
+    fn operand(&mut self) -> Result<Expression, String> {
+        self.skip_whitespace();
+        let Some((start, c)) = self.chars.next() else {
+            return Err("unexpected end".to_string());
+        };
+
+        if c == '(' {
+            let expression = self.sum()?;
+            self.skip_whitespace();
+            return match self.chars.next() {
+                Some((_, ')')) => Ok(expression),
+                _ => Err("missing ')'".to_string()),
+            };
+        }
+
+        let word = |c: &char| c.is_alphanumeric() || *c == '_';
+        if !word(&c) {
+            return Err(format!("unexpected '{}'", c));
+        }
+        let mut end = start + c.len_utf8();
+        while let Some((i, c)) = self.chars.next_if(|(_, c)| word(c)) {
+            end = i + c.len_utf8();
+        }
+        let token = &self.text[start..end];
+
+        if c.is_ascii_digit() {
+            return token
+                .parse()
+                .map(Expression::Number)
+                .map_err(|_| format!("invalid number '{}'", token));
+        }
+        self.ids
+            .iter()
+            .position(|id| *id == token)
+            .map(Expression::Criterion)
+            .ok_or_else(|| format!("unknown criterion '{}'", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDS: [&str; 3] = ["Kn", "Eq", "Wo"];
+
+    fn evaluate(text: &str, values: &[Option<u32>]) -> Option<u32> {
+        Expression::parse(text, &IDS).unwrap().evaluate(values)
+    }
+
+    #[test]
+    fn expressions_follow_the_usual_precedence() {
+        let values = [Some(3), Some(4), Some(5)];
+
+        assert_eq!(evaluate("Kn + Eq + Wo", &values), Some(12));
+        assert_eq!(evaluate("Kn + 2 * Eq", &values), Some(11));
+        assert_eq!(evaluate("(Kn + 2) * Eq", &values), Some(20));
+        assert_eq!(evaluate("Wo - Eq - Kn", &values), Some(0));
+        assert_eq!(evaluate("Wo / (Eq - 4)", &values), None);
+        assert_eq!(evaluate("Kn + Wo", &[Some(3), Some(4), None]), None);
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        let error = |text: &str| Expression::parse(text, &IDS).unwrap_err();
+
+        assert_eq!(error("Kn + Ex"), "unknown criterion 'Ex'");
+        assert_eq!(error("(Kn + Eq"), "missing ')'");
+        assert_eq!(error("Kn +"), "unexpected end");
+        assert_eq!(error("Kn Eq"), "unexpected 'E'");
+        assert_eq!(error("Kn % Eq"), "unexpected '%'");
+    }
+}
@@ -1,10 +1,39 @@
-use std::rc::Rc;
+use std::{collections::BTreeMap, fmt::Display, rc::Rc};
 
-use super::{FeasibilityAssessment, TreeError};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use super::{Assumption, Defense, FeasibilityAssessment, Review, SourceSpan, TreeError};
+
+/// Separates the namespace from the title in qualified titles.
+pub const NAMESPACE_SEPARATOR: &str = "::";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    And,
+    Or,
+    /// k-out-of-n, see `threshold`.
+    Voting,
+    Leaf,
+}
+
+impl Display for NodeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeKind::And => write!(f, "AND"),
+            NodeKind::Or => write!(f, "OR"),
+            NodeKind::Voting => write!(f, "VOTING"),
+            NodeKind::Leaf => write!(f, "Leaf"),
+        }
+    }
+}
 
 pub trait FeasibleStep {
     fn id(&self) -> u32;
 
+    fn kind(&self) -> NodeKind;
+
     // todo: add_child does not make sense for leafs. What would be a better design?
     fn add_child(&self, child: &Rc<dyn FeasibleStep>);
 
@@ -12,6 +41,11 @@ pub trait FeasibleStep {
 
     fn title(&self) -> &str;
 
+    /// Number of children needed for the attack, only set on voting nodes.
+    fn threshold(&self) -> Option<usize> {
+        None
+    }
+
     fn feasibility_value(&self) -> u32 {
         let feasibility = self.feasibility();
         match feasibility {
@@ -22,32 +56,193 @@ pub trait FeasibleStep {
 
     fn feasibility(&self) -> Result<FeasibilityAssessment, TreeError>;
 
+    /// The easiest and the hardest assessment within the ranges of the
+    /// leaves below. Both equal `feasibility` for point estimates.
+    fn feasibility_range(
+        &self,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        let f = self.feasibility()?;
+        Ok((f.clone(), f))
+    }
+
+    /// The lowest and highest feasibility value, None unless a range of a
+    /// leaf below makes a difference.
+    fn feasibility_interval(&self) -> Option<(u32, u32)> {
+        match self.feasibility_range() {
+            Ok((easiest, hardest)) if easiest.sum() != hardest.sum() => {
+                Some((easiest.sum(), hardest.sum()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `feasibility` returns without evaluating the children, as
+    /// it does for leaves and for inner nodes with a cached value.
+    fn feasibility_is_cached(&self) -> bool {
+        true
+    }
+
+    /// Drops the cached feasibility of the node, returns whether there was
+    /// one.
+    fn clear_feasibility_cache(&self) -> bool {
+        false
+    }
+
+    /// The range of an inner node from the ranges of its children, see
+    /// `feasibility_range`.
+    fn range_from_children(
+        &self,
+        _children: Vec<Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>>,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        self.feasibility_range()
+    }
+
+    /// Drops cached feasibility values of this node and its ancestors after
+    /// the subtree changed.
+    fn invalidate_feasibility(&self) {
+        self.clear_feasibility_cache();
+
+        // a node is only cached if all nodes below it are, so the ancestors
+        // of an uncached node are not cached either
+        let mut parent = self.get_parent();
+        while let Some(p) = parent {
+            if !p.clear_feasibility_cache() {
+                break;
+            }
+            parent = p.get_parent();
+        }
+    }
+
     fn render(&self) -> String;
 
     fn get_children(&self) -> Vec<Rc<dyn FeasibleStep>>;
+
+    /// Removes the children and returns them, see `release_tree`.
+    fn take_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
+        vec![]
+    }
+
+    /// Owner and review status, only set on root nodes.
+    fn review(&self) -> &Review;
+
+    /// The namespace of the tree the node belongs to, set on the root.
+    fn namespace(&self) -> Option<String> {
+        match self.get_parent() {
+            Some(parent) => parent.namespace(),
+            None => self.review().namespace.clone(),
+        }
+    }
+
+    /// The title after the namespace of the tree, e.g. `Door::Pick lock`.
+    fn qualified_title(&self) -> String {
+        match self.namespace() {
+            Some(namespace) => format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, self.title()),
+            None => self.title().to_string(),
+        }
+    }
+
+    /// Free text from the note block below the node.
+    fn note(&self) -> Option<&str>;
+
+    /// Position of the node among its siblings set with the `number`
+    /// attribute, see `number_nodes`.
+    fn number(&self) -> Option<u32>;
+
+    /// Id of the node that stays the same across runs, unlike `id`. Set
+    /// with the `uuid` attribute, see `att ids`.
+    fn uuid(&self) -> Option<&str>;
+
+    /// Ids of the requirements the node addresses.
+    fn requirements(&self) -> &[String];
+
+    /// The date the assessment of a leaf was last reviewed, if recorded.
+    fn assessed(&self) -> Option<NaiveDate> {
+        None
+    }
+
+    /// Probability that the attack step, or the attack below an inner node,
+    /// is detected. None if no leaf below has a detection rating.
+    fn detection(&self) -> Option<f64> {
+        None
+    }
+
+    /// Controls countering the node, see `apply_defenses`.
+    fn defenses(&self) -> &[Defense];
+
+    /// Premises of the analysis of the node, they do not affect feasibility.
+    fn assumptions(&self) -> &[Assumption];
+
+    /// Deployment profiles the node is limited to, see `select_profile`.
+    fn profiles(&self) -> &[String];
+
+    /// Custom attributes the crate does not interpret, keys start with
+    /// `x-`.
+    fn attributes(&self) -> &BTreeMap<String, String>;
+
+    /// ATT&CK techniques referenced by a leaf.
+    fn techniques(&self) -> &[String] {
+        &[]
+    }
+
+    /// The lines the node was parsed from, unset for nodes built in code.
+    fn span(&self) -> Option<&SourceSpan>;
 }
 
 pub fn render(step: &dyn FeasibleStep, shape_str: &str) -> String {
-    let assessment = step.feasibility();
+    // computed once, feasibility_value() would walk the subtree a second time
+    let assessment = match step.feasibility() {
+        Ok(a) => a,
+        Err(_) => {
+            return format!(
+                r#"label="{}"{}"#,
+                escape_dot_string(step.title()),
+                shape_str
+            )
+        }
+    };
 
-    if assessment.is_err() {
-        return format!(r#"label="{}"#, step.title());
-    }
+    let value = match step.feasibility_interval() {
+        Some((lowest, highest)) => format!("{}..{}", lowest, highest),
+        None => assessment.sum().to_string(),
+    };
+    let assessment_strings: Vec<String> = if assessment.definition.1.is_empty() {
+        assessment
+            .iter()
+            .map(|(c, v)| format!("{}={}", c.id, c.format_value(v.unwrap_or(0))))
+            .collect()
+    } else {
+        assessment
+            .definition
+            .shown()
+            .iter()
+            .zip(assessment.shown_values())
+            .map(|((id, _), v)| format!("{}={}", id, v.as_deref().unwrap_or("?")))
+            .collect()
+    };
 
-    let assessment = assessment.unwrap();
-    let assessment_strings: Vec<String> = assessment
-        .definition
-        .0
-        .iter()
-        .zip(assessment.assessments.0)
-        .map(|(c, v)| format!("{}={}", c.id, v.unwrap_or(0)))
-        .collect();
+    let detection = match step.detection() {
+        Some(d) => format!("\\nD={}", format_probability(d)),
+        None => String::new(),
+    };
 
     format!(
-        r#"label="{}\n{}\n{}"{}"#,
-        step.title(),
-        step.feasibility_value(),
+        r#"label="{}\n{}\n{}{}"{}"#,
+        escape_dot_string(step.title()),
+        value,
         assessment_strings.join(", "),
+        detection,
         shape_str
     )
 }
+
+/// Escapes quotes, backslashes and line breaks for a quoted DOT string.
+pub fn escape_dot_string(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Formats a probability as percentage, e.g. `35%`.
+pub fn format_probability(p: f64) -> String {
+    format!("{:.0}%", p * 100.0)
+}
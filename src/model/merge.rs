@@ -0,0 +1,345 @@
+use std::{fmt::Display, rc::Rc};
+
+use thiserror::Error;
+
+use super::{
+    feasible_step::{FeasibleStep, NodeKind},
+    generate_id,
+    or_node::OrNode,
+    voting_node::VotingNode,
+    AndNode, FeasibilityAssessment, Leaf,
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MergeError {
+    #[error("the trees have different root titles: '{0}' and '{1}'")]
+    RootTitleMismatch(String, String),
+}
+
+/// A node that exists in both trees but could not be merged. The merged tree
+/// keeps the version of the first tree.
+#[derive(Debug, PartialEq)]
+pub struct MergeConflict {
+    pub path: Vec<String>,
+    pub message: String,
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.join(" > "), self.message)
+    }
+}
+
+pub struct MergeResult {
+    pub root: Rc<dyn FeasibleStep>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Unifies two trees with the same root title and namespace. Children are
+/// matched by title, nodes only present in one tree are taken over.
+pub fn merge_trees(
+    a: &Rc<dyn FeasibleStep>,
+    b: &Rc<dyn FeasibleStep>,
+) -> Result<MergeResult, MergeError> {
+    if a.qualified_title() != b.qualified_title() {
+        return Err(MergeError::RootTitleMismatch(
+            a.qualified_title(),
+            b.qualified_title(),
+        ));
+    }
+
+    let mut conflicts = vec![];
+    let mut path = vec![];
+    let root = merge_nodes(a, b, None, &mut path, &mut conflicts);
+
+    Ok(MergeResult { root, conflicts })
+}
+
+fn merge_nodes(
+    a: &Rc<dyn FeasibleStep>,
+    b: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    path: &mut Vec<String>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Rc<dyn FeasibleStep> {
+    path.push(a.title().to_string());
+
+    let merged = if a.kind() != b.kind() {
+        conflicts.push(MergeConflict {
+            path: path.clone(),
+            message: format!("node type {} conflicts with {}", a.kind(), b.kind()),
+        });
+        copy_subtree(a, parent)
+    } else if a.kind() == NodeKind::Leaf {
+        let values_a = a.feasibility().map(|f| f.values().to_vec()).ok();
+        let values_b = b.feasibility().map(|f| f.values().to_vec()).ok();
+
+        if values_a != values_b {
+            conflicts.push(MergeConflict {
+                path: path.clone(),
+                message: format!(
+                    "assessment {} conflicts with {}",
+                    format_assessment(a),
+                    format_assessment(b)
+                ),
+            });
+        }
+        copy_subtree(a, parent)
+    } else {
+        let node = new_inner_node(a, parent);
+        let children_b = b.get_children();
+
+        for child_a in a.get_children() {
+            let merged_child = match children_b.iter().find(|c| c.title() == child_a.title()) {
+                Some(child_b) => {
+                    merge_nodes(&child_a, child_b, Some(node.clone()), path, conflicts)
+                }
+                None => copy_subtree(&child_a, Some(node.clone())),
+            };
+            node.add_child(&merged_child);
+        }
+
+        for child_b in &children_b {
+            if !a
+                .get_children()
+                .iter()
+                .any(|c| c.title() == child_b.title())
+            {
+                node.add_child(&copy_subtree(child_b, Some(node.clone())));
+            }
+        }
+
+        node
+    };
+
+    path.pop();
+    merged
+}
+
+pub(super) fn new_inner_node(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+) -> Rc<dyn FeasibleStep> {
+    new_inner_node_with_uuid(node, parent, node.uuid().map(str::to_string))
+}
+
+fn new_inner_node_with_uuid(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    uuid: Option<String>,
+) -> Rc<dyn FeasibleStep> {
+    match node.kind() {
+        NodeKind::Or => Rc::new(OrNode {
+            review: node.review().clone(),
+            note: node.note().map(str::to_string),
+            number: node.number(),
+            uuid,
+            requirements: node.requirements().to_vec(),
+            defenses: node.defenses().to_vec(),
+            assumptions: node.assumptions().to_vec(),
+            profiles: node.profiles().to_vec(),
+            attributes: node.attributes().clone(),
+            span: node.span().cloned(),
+            ..OrNode::new(node.title(), parent, generate_id)
+        }),
+        NodeKind::Voting => Rc::new(VotingNode {
+            review: node.review().clone(),
+            note: node.note().map(str::to_string),
+            number: node.number(),
+            uuid,
+            requirements: node.requirements().to_vec(),
+            defenses: node.defenses().to_vec(),
+            assumptions: node.assumptions().to_vec(),
+            profiles: node.profiles().to_vec(),
+            attributes: node.attributes().clone(),
+            span: node.span().cloned(),
+            ..VotingNode::new(
+                node.title(),
+                node.threshold().unwrap_or(1),
+                parent,
+                generate_id,
+            )
+        }),
+        _ => Rc::new(AndNode {
+            review: node.review().clone(),
+            note: node.note().map(str::to_string),
+            number: node.number(),
+            uuid,
+            requirements: node.requirements().to_vec(),
+            defenses: node.defenses().to_vec(),
+            assumptions: node.assumptions().to_vec(),
+            profiles: node.profiles().to_vec(),
+            attributes: node.attributes().clone(),
+            span: node.span().cloned(),
+            ..AndNode::new(node.title(), parent, generate_id)
+        }),
+    }
+}
+
+/// Creates a deep copy of `node` and its descendants below a new parent.
+pub fn copy_subtree(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+) -> Rc<dyn FeasibleStep> {
+    copy_nodes(node, parent, true)
+}
+
+/// Copies the subtree repeated by an alias. The copies get no uuids, those
+/// identify the anchored nodes.
+pub fn copy_alias(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+) -> Rc<dyn FeasibleStep> {
+    copy_nodes(node, parent, false)
+}
+
+fn copy_nodes(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    keep_uuids: bool,
+) -> Rc<dyn FeasibleStep> {
+    let uuid = node.uuid().filter(|_| keep_uuids).map(str::to_string);
+    if node.kind() == NodeKind::Leaf {
+        return match node.feasibility_range() {
+            Ok(range) => copy_leaf_with_uuid(node, parent, range, uuid),
+            // leaves always have an assessment, fall back to an empty inner node
+            Err(_) => new_inner_node_with_uuid(node, parent, uuid),
+        };
+    }
+
+    let copy = new_inner_node_with_uuid(node, parent, uuid);
+    for c in node.get_children() {
+        copy.add_child(&copy_nodes(&c, Some(copy.clone()), keep_uuids));
+    }
+
+    copy
+}
+
+/// Copies a leaf with a different easiest and hardest assessment.
+pub(super) fn copy_leaf(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    range: (FeasibilityAssessment, FeasibilityAssessment),
+) -> Rc<dyn FeasibleStep> {
+    copy_leaf_with_uuid(node, parent, range, node.uuid().map(str::to_string))
+}
+
+fn copy_leaf_with_uuid(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    (criteria, hardest): (FeasibilityAssessment, FeasibilityAssessment),
+    uuid: Option<String>,
+) -> Rc<dyn FeasibleStep> {
+    let hardest = (hardest.values() != criteria.values()).then_some(hardest);
+
+    Rc::new(Leaf {
+        id: generate_id(),
+        description: node.title().to_string(),
+        parent,
+        criteria,
+        hardest,
+        assessed: node.assessed(),
+        review: node.review().clone(),
+        note: node.note().map(str::to_string),
+        number: node.number(),
+        uuid,
+        techniques: node.techniques().to_vec(),
+        detection: node.detection(),
+        requirements: node.requirements().to_vec(),
+        defenses: node.defenses().to_vec(),
+        assumptions: node.assumptions().to_vec(),
+        profiles: node.profiles().to_vec(),
+        attributes: node.attributes().clone(),
+        span: node.span().cloned(),
+    })
+}
+
+fn format_assessment(node: &Rc<dyn FeasibleStep>) -> String {
+    match node.feasibility() {
+        Ok(f) => f
+            .iter()
+            .map(|(c, v)| match v {
+                Some(v) => format!("{}={}", c.id, c.format_value(v)),
+                None => format!("{}=?", c.id),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        Err(_) => "(none)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        model::tests::build_criteria, parser::AttackTreeParser, render::render_to_att_string,
+    };
+
+    fn parse(text: &str) -> Rc<dyn FeasibleStep> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap()
+    }
+
+    #[test]
+    fn identical_subtrees_are_merged_and_new_nodes_are_added() {
+        let a = parse("Root;&\n    Shared;|\n        A; Kn=1, Eq=2\n    Only in a; Kn=3, Eq=3");
+        let b = parse("Root;&\n    Shared;|\n        A; Kn=1, Eq=2\n        B; Kn=5, Eq=5\n    Only in b; Kn=4, Eq=4");
+
+        let result = merge_trees(&a, &b).unwrap();
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            render_to_att_string(&result.root),
+            "Root;&\n    Shared;|\n        A; Kn=1, Eq=2\n        B; Kn=5, Eq=5\n    Only in a; Kn=3, Eq=3\n    Only in b; Kn=4, Eq=4"
+        );
+    }
+
+    #[test]
+    fn conflicting_assessments_are_reported_and_the_first_tree_wins() {
+        let a = parse("Root;&\n    Step; Kn=1, Eq=2");
+        let b = parse("Root;&\n    Step; Kn=4, Eq=2");
+
+        let result = merge_trees(&a, &b).unwrap();
+
+        assert_eq!(
+            result.conflicts,
+            vec![MergeConflict {
+                path: vec!["Root".to_string(), "Step".to_string()],
+                message: "assessment Kn=1, Eq=2 conflicts with Kn=4, Eq=2".to_string(),
+            }]
+        );
+        assert_eq!(
+            render_to_att_string(&result.root),
+            "Root;&\n    Step; Kn=1, Eq=2"
+        );
+    }
+
+    #[test]
+    fn trees_with_different_roots_cannot_be_merged() {
+        let a = parse("Root A; Kn=1");
+        let b = parse("Root B; Kn=1");
+
+        assert!(matches!(
+            merge_trees(&a, &b),
+            Err(MergeError::RootTitleMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn trees_of_different_namespaces_cannot_be_merged() {
+        let a = parse("Root;| namespace=Door\n    A; Kn=1, Eq=1");
+        let b = parse("Root;| namespace=Window\n    A; Kn=1, Eq=1");
+
+        assert_eq!(
+            merge_trees(&a, &b).err(),
+            Some(MergeError::RootTitleMismatch(
+                "Door::Root".to_string(),
+                "Window::Root".to_string()
+            ))
+        );
+    }
+}
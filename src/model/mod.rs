@@ -1,15 +1,56 @@
 use std::{
     cell::RefCell,
+    collections::BTreeMap,
+    fmt::Display,
     rc::Rc,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+use chrono::NaiveDate;
 use feasible_step::*;
-use serde::Deserialize;
 use thiserror::Error;
-
+use traversal::{cache_feasibility, children_ranges};
+
+pub mod assumption;
+pub mod cache;
+pub mod content;
+pub mod criteria;
+pub mod defense;
+pub mod expression;
 pub mod feasible_step;
+pub mod merge;
+pub mod numbering;
 pub mod or_node;
+pub mod overlay;
+pub mod profile;
+pub mod rating;
+pub mod redact;
+pub mod requirements;
+pub mod review;
+pub mod span;
+pub mod subtree;
+pub mod traversal;
+pub mod uncertainty;
+pub mod voting_node;
+
+pub use assumption::Assumption;
+pub use cache::FeasibilityCache;
+pub use content::{
+    content_hash, content_hash_ignoring_order, same_content, same_content_ignoring_order,
+};
+pub use criteria::{
+    CriteriaError, DerivedCriterion, FeasibilityCriteria, FeasiblityCriterion, Scale,
+};
+pub use defense::{apply_defenses, count_defenses, Defense};
+pub use numbering::{number_nodes, NodeNumbers};
+pub use profile::select_profile;
+pub use rating::{rate, RatingLevel};
+pub use redact::redact_trees;
+pub use requirements::{read_requirements, Requirement};
+pub use review::{Review, ReviewStatus};
+pub use span::SourceSpan;
+pub use subtree::{select_subtree, SubtreeError, SubtreeSelector};
+pub use uncertainty::resolve_ranges;
 
 static OBJECT_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -17,17 +58,105 @@ pub fn generate_id() -> u32 {
     OBJECT_COUNTER.fetch_add(1, Ordering::SeqCst) as u32
 }
 
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum TreeError {
     #[error("Length mismatch between assessment vector and definition")]
     AssessmentVectorMismatch,
+    #[error("'{0}' has no children")]
+    EmptyNode(String),
+    #[error("unknown criterion '{0}'")]
+    UnknownCriterion(String),
+    #[error("missing assessment for '{0}'")]
+    MissingAssessment(String),
+    #[error("'{0}' needs at least {1} children")]
+    NotEnoughChildren(String, usize),
+}
+
+/// Combines the feasibility of all children that have one with `combine`. If
+/// none has, the error of the first child is returned.
+fn combine_children<I, F>(
+    title: &str,
+    children: I,
+    combine: F,
+) -> Result<FeasibilityAssessment, TreeError>
+where
+    I: IntoIterator<Item = Result<FeasibilityAssessment, TreeError>>,
+    F: Fn(FeasibilityAssessment, FeasibilityAssessment) -> Result<FeasibilityAssessment, TreeError>,
+{
+    let mut result = None;
+    let mut first_error = None;
+
+    for c in children {
+        match c {
+            Ok(f) => {
+                result = Some(match result {
+                    Some(r) => combine(r, f)?,
+                    None => f,
+                })
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match (result, first_error) {
+        (Some(r), _) => Ok(r),
+        (None, Some(e)) => Err(e),
+        (None, None) => Err(TreeError::EmptyNode(title.to_string())),
+    }
+}
+
+/// Combines the easiest and the hardest ends of the children's ranges
+/// separately, which gives the range of the node as `combine` only grows with
+/// its arguments.
+fn combine_ranges<F>(
+    title: &str,
+    ranges: Vec<Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>>,
+    combine: F,
+) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>
+where
+    F: Fn(FeasibilityAssessment, FeasibilityAssessment) -> Result<FeasibilityAssessment, TreeError>,
+{
+    let easiest = combine_children(
+        title,
+        ranges.iter().map(|r| r.clone().map(|(e, _)| e)),
+        &combine,
+    )?;
+    let hardest = combine_children(
+        title,
+        ranges.into_iter().map(|r| r.map(|(_, h)| h)),
+        &combine,
+    )?;
+
+    Ok((easiest, hardest))
 }
 
 pub struct AndNode {
     pub id: u32,
     pub description: String,
     pub parent: Option<Rc<dyn FeasibleStep>>,
+    /// Use `add_child`, changing the children directly requires a call to
+    /// `invalidate_feasibility`.
     pub children: RefCell<Vec<Rc<dyn FeasibleStep>>>,
+    pub review: Review,
+    pub note: Option<String>,
+    /// Position among the siblings from the `number` attribute.
+    pub number: Option<u32>,
+    /// Persistent id from the `uuid` attribute.
+    pub uuid: Option<String>,
+    /// Ids of the requirements from requirements.json the node addresses.
+    pub requirements: Vec<String>,
+    /// Planned or existing controls countering the node.
+    pub defenses: Vec<Defense>,
+    pub assumptions: Vec<Assumption>,
+    /// Deployment profiles the subtree belongs to, empty for all.
+    pub profiles: Vec<String>,
+    /// Custom attributes like `x-ticket=SEC-12`, passed through to the exports.
+    pub attributes: BTreeMap<String, String>,
+    /// The lines the node was parsed from.
+    pub span: Option<SourceSpan>,
+    pub feasibility_cache: FeasibilityCache,
 }
 
 impl AndNode {
@@ -40,6 +169,17 @@ impl AndNode {
             description: title.to_string(),
             parent,
             children: RefCell::new(vec![]),
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
         }
     }
 }
@@ -49,20 +189,43 @@ impl FeasibleStep for AndNode {
         self.id
     }
 
+    fn kind(&self) -> NodeKind {
+        NodeKind::And
+    }
+
     fn feasibility(&self) -> Result<FeasibilityAssessment, TreeError> {
-        if self.children.borrow().is_empty() {
-            return Err(TreeError::AssessmentVectorMismatch);
-        }
+        self.feasibility_cache.get_or_compute(|| {
+            let children = self.children.borrow();
+            cache_feasibility(&children);
+            combine_children(
+                &self.description,
+                children.iter().map(|c| c.feasibility()),
+                |a, b| Ok(a.component_wise_max(&b)),
+            )
+        })
+    }
 
-        let maximum_assessment = self
-            .children
-            .borrow()
-            .iter()
-            .filter_map(|s| s.feasibility().ok())
-            .reduce(|a, b| a.component_wise_max(&b).unwrap())
-            .unwrap();
+    fn feasibility_is_cached(&self) -> bool {
+        self.feasibility_cache.is_cached()
+    }
+
+    fn clear_feasibility_cache(&self) -> bool {
+        self.feasibility_cache.invalidate()
+    }
 
-        Ok(maximum_assessment)
+    fn feasibility_range(
+        &self,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        self.range_from_children(children_ranges(&self.children.borrow()))
+    }
+
+    fn range_from_children(
+        &self,
+        children: Vec<Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>>,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        combine_ranges(&self.description, children, |a, b| {
+            Ok(a.component_wise_max(&b))
+        })
     }
 
     fn title(&self) -> &str {
@@ -71,6 +234,7 @@ impl FeasibleStep for AndNode {
 
     fn add_child(&self, child: &Rc<dyn FeasibleStep>) {
         self.children.borrow_mut().push(child.clone());
+        self.invalidate_feasibility();
     }
 
     fn get_parent(&self) -> Option<Rc<dyn FeasibleStep>> {
@@ -85,6 +249,23 @@ impl FeasibleStep for AndNode {
         render(self, " shape=trapezium")
     }
 
+    fn detection(&self) -> Option<f64> {
+        // every step has to go unnoticed, steps without a rating are assumed
+        // to be undetectable
+        let detections: Vec<Option<f64>> = self
+            .children
+            .borrow()
+            .iter()
+            .map(|c| c.detection())
+            .collect();
+        if detections.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let undetected: f64 = detections.iter().map(|d| 1.0 - d.unwrap_or(0.0)).product();
+        Some(1.0 - undetected)
+    }
+
     fn get_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
         let mut v = Vec::new();
 
@@ -94,6 +275,51 @@ impl FeasibleStep for AndNode {
 
         v
     }
+
+    fn take_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
+        self.clear_feasibility_cache();
+        self.children.take()
+    }
+
+    fn review(&self) -> &Review {
+        &self.review
+    }
+
+    fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    fn number(&self) -> Option<u32> {
+        self.number
+    }
+
+    fn uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    fn requirements(&self) -> &[String] {
+        &self.requirements
+    }
+
+    fn defenses(&self) -> &[Defense] {
+        &self.defenses
+    }
+
+    fn assumptions(&self) -> &[Assumption] {
+        &self.assumptions
+    }
+
+    fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
+    fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    fn span(&self) -> Option<&SourceSpan> {
+        self.span.as_ref()
+    }
 }
 
 pub struct Leaf {
@@ -101,6 +327,27 @@ pub struct Leaf {
     pub description: String,
     pub parent: Option<Rc<dyn FeasibleStep>>,
     pub criteria: FeasibilityAssessment,
+    /// The hardest end of ranges like `Kn=4..6`, `criteria` holds the
+    /// easiest. None if all values are point estimates.
+    pub hardest: Option<FeasibilityAssessment>,
+    pub assessed: Option<NaiveDate>,
+    pub review: Review,
+    pub note: Option<String>,
+    /// Position among the siblings from the `number` attribute.
+    pub number: Option<u32>,
+    /// Persistent id from the `uuid` attribute.
+    pub uuid: Option<String>,
+    /// MITRE ATT&CK technique ids, e.g. `T1078` or `T1078.001`.
+    pub techniques: Vec<String>,
+    pub requirements: Vec<String>,
+    /// Probability between 0 and 1 that the step is detected.
+    pub detection: Option<f64>,
+    pub defenses: Vec<Defense>,
+    pub assumptions: Vec<Assumption>,
+    pub profiles: Vec<String>,
+    pub attributes: BTreeMap<String, String>,
+    /// The lines the node was parsed from.
+    pub span: Option<SourceSpan>,
 }
 
 impl Leaf {
@@ -121,6 +368,20 @@ impl Leaf {
             description: description.to_string(),
             parent,
             criteria: FeasibilityAssessment::new(definition, &assessments).unwrap(),
+            hardest: None,
+            assessed: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            techniques: vec![],
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            detection: None,
         }
     }
 }
@@ -130,8 +391,19 @@ impl FeasibleStep for Leaf {
         self.id
     }
 
+    fn kind(&self) -> NodeKind {
+        NodeKind::Leaf
+    }
+
     fn feasibility(&self) -> Result<FeasibilityAssessment, TreeError> {
-        FeasibilityAssessment::new(&self.criteria.definition, &self.criteria.assessments.0)
+        Ok(self.criteria.clone())
+    }
+
+    fn feasibility_range(
+        &self,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        let hardest = self.hardest.as_ref().unwrap_or(&self.criteria);
+        Ok((self.criteria.clone(), hardest.clone()))
     }
 
     fn title(&self) -> &str {
@@ -157,15 +429,70 @@ impl FeasibleStep for Leaf {
     fn get_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
         Vec::new()
     }
+
+    fn assessed(&self) -> Option<NaiveDate> {
+        self.assessed
+    }
+
+    fn techniques(&self) -> &[String] {
+        &self.techniques
+    }
+
+    fn detection(&self) -> Option<f64> {
+        self.detection
+    }
+
+    fn review(&self) -> &Review {
+        &self.review
+    }
+
+    fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    fn number(&self) -> Option<u32> {
+        self.number
+    }
+
+    fn uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    fn requirements(&self) -> &[String] {
+        &self.requirements
+    }
+
+    fn defenses(&self) -> &[Defense] {
+        &self.defenses
+    }
+
+    fn assumptions(&self) -> &[Assumption] {
+        &self.assumptions
+    }
+
+    fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
+    fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    fn span(&self) -> Option<&SourceSpan> {
+        self.span.as_ref()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FeasibilityAssessment {
     definition: Rc<FeasibilityCriteria>,
-    assessments: FeasibilityVector,
+    assessments: CriterionValues,
 }
 
 impl FeasibilityAssessment {
+    /// Builds the assessment from values in the order of the criteria of
+    /// `definition`, None for unrated criteria. Fails unless there is
+    /// exactly one value per criterion, `from_pairs` matches values by id.
     pub fn new(
         definition: &Rc<FeasibilityCriteria>,
         assessments: &[Option<u32>],
@@ -174,58 +501,183 @@ impl FeasibilityAssessment {
             return Err(TreeError::AssessmentVectorMismatch);
         }
 
+        Ok(Self::from_values(definition, assessments))
+    }
+
+    /// Like `new` for values built from the criteria of `definition`
+    /// itself, which have the right length.
+    pub(crate) fn from_values(
+        definition: &Rc<FeasibilityCriteria>,
+        assessments: &[Option<u32>],
+    ) -> FeasibilityAssessment {
+        debug_assert_eq!(assessments.len(), definition.0.len());
+
+        FeasibilityAssessment {
+            definition: Rc::clone(definition),
+            assessments: CriterionValues(
+                definition
+                    .0
+                    .iter()
+                    .zip(assessments)
+                    .filter_map(|(c, v)| Some((c.id.clone(), (*v)?)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Builds the assessment of a leaf from `criterion id, value` pairs.
+    /// Criteria without a pair stay unrated.
+    pub fn from_pairs(
+        definition: &Rc<FeasibilityCriteria>,
+        pairs: &[(&str, u32)],
+    ) -> Result<FeasibilityAssessment, TreeError> {
+        let mut values = CriterionValues::default();
+        for (id, value) in pairs {
+            let criterion = &definition.0[definition.position(id)?];
+            values.0.insert(criterion.id.clone(), *value);
+        }
+
         Ok(FeasibilityAssessment {
             definition: Rc::clone(definition),
-            assessments: FeasibilityVector(assessments.to_vec()),
+            assessments: values,
         })
     }
 
-    pub fn sum(&self) -> u32 {
-        self.assessments.0.iter().map(|v| v.unwrap_or(0)).sum()
+    /// Fails with the first criterion that has no value.
+    pub fn check_complete(&self) -> Result<(), TreeError> {
+        match self.iter().find(|(_, v)| v.is_none()) {
+            Some((c, _)) => Err(TreeError::MissingAssessment(c.id.clone())),
+            None => Ok(()),
+        }
     }
 
-    pub fn component_wise_max(
-        &self,
-        other: &FeasibilityAssessment,
-    ) -> Result<FeasibilityAssessment, TreeError> {
-        if self.assessments.0.len() != other.assessments.0.len() {
-            return Err(TreeError::AssessmentVectorMismatch);
+    /// The values in the order of the criteria of `definition`, None for
+    /// unrated criteria.
+    pub fn values(&self) -> Vec<Option<u32>> {
+        self.iter().map(|(_, v)| v).collect()
+    }
+
+    /// The value of the criterion with the id, None if it is unrated or
+    /// unknown.
+    pub fn value(&self, criterion_id: &str) -> Option<u32> {
+        let index = self.definition.position(criterion_id).ok()?;
+        self.assessments.get(&self.definition.0[index].id)
+    }
+
+    pub fn definition(&self) -> &Rc<FeasibilityCriteria> {
+        &self.definition
+    }
+
+    pub fn by_id(&self) -> &CriterionValues {
+        &self.assessments
+    }
+
+    /// The criteria with their values, in the order of the definition.
+    pub fn iter(&self) -> impl Iterator<Item = (&FeasiblityCriterion, Option<u32>)> {
+        self.definition
+            .0
+            .iter()
+            .map(|c| (c, self.assessments.get(&c.id)))
+    }
+
+    /// The values the reports show, in the order of
+    /// `FeasibilityCriteria::shown`: those of the derived criteria if there
+    /// are any, else the assessed ones with their unit. None if unrated.
+    pub fn shown_values(&self) -> Vec<Option<String>> {
+        match self.definition.1.is_empty() {
+            true => self
+                .iter()
+                .map(|(c, v)| v.map(|v| c.format_value(v)))
+                .collect(),
+            false => self
+                .definition
+                .1
+                .iter()
+                .map(|d| d.expression.evaluate(&self.values()).map(|v| v.to_string()))
+                .collect(),
         }
+    }
 
+    /// The weighted sum of all assessment values, u32::MAX if it does not
+    /// fit. The parser reports values too large for their weight.
+    pub fn sum(&self) -> u32 {
+        self.iter().fold(0u32, |sum, (c, v)| {
+            sum.saturating_add(c.weight.saturating_mul(c.difficulty(v)))
+        })
+    }
+
+    /// Combines two assessments by taking the harder value of each criterion
+    /// of this assessment. The values of `other` are matched by criterion id,
+    /// so its definition may list the criteria in another order.
+    pub fn component_wise_max(&self, other: &FeasibilityAssessment) -> FeasibilityAssessment {
         let maxima: Vec<Option<u32>> = self
-            .assessments
-            .0
             .iter()
-            .zip(other.assessments.0.iter())
-            .map(|(a, b)| Some(std::cmp::max(a.unwrap_or(0), b.unwrap_or(0))))
+            .map(|(c, a)| {
+                let b = other.assessments.get(&c.id);
+                if c.difficulty(a) >= c.difficulty(b) {
+                    a
+                } else {
+                    b
+                }
+            })
             .collect();
 
-        FeasibilityAssessment::new(&self.definition, &maxima)
+        FeasibilityAssessment::from_values(&self.definition, &maxima)
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct FeasibilityVector(Vec<Option<u32>>);
+/// Prints the values with their criteria and the sum, e.g.
+/// `Kn=3, Eq=5 (sum 8)`. Unrated criteria are shown as `Kn=?`.
+impl Display for FeasibilityAssessment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<String> = self
+            .iter()
+            .map(|(c, v)| match v {
+                Some(v) => format!("{}={}", c.id, c.format_value(v)),
+                None => format!("{}=?", c.id),
+            })
+            .collect();
+
+        write!(f, "{} (sum {})", values.join(", "), self.sum())
+    }
+}
+
+/// The values of an assessment by criterion id. Unrated criteria have no
+/// entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CriterionValues(BTreeMap<String, u32>);
+
+impl CriterionValues {
+    pub fn get(&self, criterion_id: &str) -> Option<u32> {
+        self.0.get(criterion_id).copied()
+    }
+
+    /// The rated criteria with their values, ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.0.iter().map(|(id, v)| (id.as_str(), *v))
+    }
 
-#[derive(Debug)]
-pub struct FeasibilityCriteria(pub Vec<FeasiblityCriterion>);
+    /// The number of rated criteria.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
 
-#[derive(Deserialize, Debug)]
-pub struct FeasiblityCriterion {
-    pub name: String,
-    pub id: String,
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use std::cell::RefCell;
+    use std::collections::BTreeMap;
     use std::rc::Rc;
 
     use crate::model::TreeError;
 
     use super::{
-        generate_id, AndNode, FeasibilityAssessment, FeasibilityCriteria, FeasibleStep,
-        FeasiblityCriterion, Leaf,
+        generate_id, AndNode, FeasibilityAssessment, FeasibilityCache, FeasibilityCriteria,
+        FeasibleStep, FeasiblityCriterion, Leaf, Review, Scale,
     };
     use crate::model::or_node::OrNode;
 
@@ -233,11 +685,9 @@ pub mod tests {
         Rc::new(FeasibilityCriteria(
             names
                 .iter()
-                .map(|n| FeasiblityCriterion {
-                    name: n.to_string(),
-                    id: n.to_string(),
-                })
+                .map(|n| FeasiblityCriterion::new(n, n))
                 .collect(),
+            vec![],
         ))
     }
 
@@ -250,13 +700,27 @@ pub mod tests {
     }
 
     fn build_leaf(criteria: &Rc<FeasibilityCriteria>, assessment: &[u32]) -> Leaf {
-        let feasibility = build_feasibility(&criteria, assessment);
+        let feasibility = build_feasibility(criteria, assessment);
 
         Leaf {
             id: generate_id(),
             description: "Attack step".to_string(),
             parent: None,
             criteria: feasibility,
+            hardest: None,
+            assessed: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            techniques: vec![],
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            detection: None,
         }
     }
 
@@ -265,7 +729,18 @@ pub mod tests {
             id: generate_id(),
             description: "An and-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
             children: RefCell::new(children),
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
         })
     }
 
@@ -274,10 +749,32 @@ pub mod tests {
             id: generate_id(),
             description: "An or-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
             children: RefCell::new(children),
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
         })
     }
 
+    #[test]
+    fn assessments_are_combined_by_criterion_id_regardless_of_order() {
+        let a = build_feasibility(&build_criteria(&["Eq", "Kn"]), &[1, 5]);
+        let b = build_feasibility(&build_criteria(&["Kn", "Eq", "Wo"]), &[2, 3, 9]);
+
+        let combined = a.component_wise_max(&b);
+
+        assert_eq!(combined.values(), vec![Some(3), Some(5)]);
+        assert_eq!(combined.value("Eq"), Some(3));
+    }
+
     #[test]
     fn in_feasibility_assessments_the_vector_must_match_the_definition() {
         let criteria = build_criteria(&["Eq", "Kn"]);
@@ -296,7 +793,27 @@ pub mod tests {
 
         let expected_feasibility = build_feasibility(&criteria, &[1, 2]);
 
-        assert_eq!(result.assessments.0, expected_feasibility.assessments.0);
+        assert_eq!(result.by_id(), expected_feasibility.by_id());
+    }
+
+    #[test]
+    fn assessments_from_pairs_report_unknown_and_missing_criteria() {
+        let criteria = build_criteria(&["Eq", "Kn"]);
+
+        assert_eq!(
+            FeasibilityAssessment::from_pairs(&criteria, &[("Tm", 1)]).unwrap_err(),
+            TreeError::UnknownCriterion("Tm".to_string())
+        );
+
+        let partial = FeasibilityAssessment::from_pairs(&criteria, &[("Kn", 2)]).unwrap();
+        assert_eq!(
+            partial.check_complete(),
+            Err(TreeError::MissingAssessment("Eq".to_string()))
+        );
+
+        let complete =
+            FeasibilityAssessment::from_pairs(&criteria, &[("Kn", 2), ("Eq", 1)]).unwrap();
+        assert_eq!(complete.check_complete(), Ok(()));
     }
 
     #[test]
@@ -305,12 +822,23 @@ pub mod tests {
             id: generate_id(),
             description: "An or node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![]),
         };
 
         assert_eq!(
             node.feasibility().unwrap_err(),
-            TreeError::AssessmentVectorMismatch
+            TreeError::EmptyNode("An or node".to_string())
         );
     }
 
@@ -322,6 +850,17 @@ pub mod tests {
             id: generate_id(),
             description: "An or-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![
                 Rc::new(build_leaf(&criteria, &[0, 50])),
                 Rc::new(build_leaf(&criteria, &[1, 49])),
@@ -345,6 +884,17 @@ pub mod tests {
             id: generate_id(),
             description: "An or-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![
                 Rc::new(build_leaf(&criteria, &[0, 50])),
                 Rc::new(build_leaf(&criteria, &[1, 49])),
@@ -361,12 +911,23 @@ pub mod tests {
             id: generate_id(),
             description: "An and-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![]),
         };
 
         assert_eq!(
             node.feasibility().unwrap_err(),
-            TreeError::AssessmentVectorMismatch
+            TreeError::EmptyNode("An and-node".to_string())
         );
     }
 
@@ -376,6 +937,17 @@ pub mod tests {
             id: generate_id(),
             description: "An and-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![]),
         };
 
@@ -390,6 +962,17 @@ pub mod tests {
             id: generate_id(),
             description: "An and-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![
                 Rc::new(build_leaf(&criteria, &[1, 6, 8])),
                 Rc::new(build_leaf(&criteria, &[2, 4, 9])),
@@ -413,6 +996,17 @@ pub mod tests {
             id: generate_id(),
             description: "An and-node".to_string(),
             parent: None,
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
             children: RefCell::new(vec![
                 Rc::new(build_leaf(&criteria, &[1, 6, 8])),
                 Rc::new(build_leaf(&criteria, &[2, 4, 9])),
@@ -423,6 +1017,39 @@ pub mod tests {
         assert_eq!(node.feasibility_value(), 3 + 6 + 9);
     }
 
+    #[test]
+    fn adding_a_child_invalidates_the_cached_feasibility_of_all_ancestors() {
+        let criteria = build_criteria(&["Eq", "Kn"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, generate_id));
+        let or_node: Rc<dyn FeasibleStep> =
+            Rc::new(OrNode::new("Or", Some(root.clone()), generate_id));
+        root.add_child(&or_node);
+        or_node.add_child(&(Rc::new(build_leaf(&criteria, &[5, 5])) as Rc<dyn FeasibleStep>));
+
+        assert_eq!(root.feasibility_value(), 10);
+
+        or_node.add_child(&(Rc::new(build_leaf(&criteria, &[1, 2])) as Rc<dyn FeasibleStep>));
+
+        assert_eq!(or_node.feasibility_value(), 3);
+        assert_eq!(root.feasibility_value(), 3);
+    }
+
+    #[test]
+    fn the_feasibility_of_an_inner_node_is_computed_once() {
+        let criteria = build_criteria(&["Eq", "Kn"]);
+        let node = AndNode::new("An and-node", None, generate_id);
+        node.add_child(&(Rc::new(build_leaf(&criteria, &[1, 2])) as Rc<dyn FeasibleStep>));
+
+        assert!(!node.feasibility_cache.is_cached());
+        assert_eq!(node.feasibility_value(), 3);
+        assert!(node.feasibility_cache.is_cached());
+
+        node.invalidate_feasibility();
+
+        assert!(!node.feasibility_cache.is_cached());
+    }
+
     #[test]
     fn a_leaf_returns_the_sum_of_all_assessments_as_feasibility_value() {
         let criteria = build_criteria(&["Eq", "Kn"]);
@@ -433,6 +1060,126 @@ pub mod tests {
         assert_eq!(result, 3);
     }
 
+    #[test]
+    fn criteria_weights_are_applied_to_the_feasibility_value() {
+        let mut criteria = FeasibilityCriteria(
+            vec![
+                FeasiblityCriterion::new("Eq", "Equipment"),
+                FeasiblityCriterion::new("Kn", "Knowledge"),
+            ],
+            vec![],
+        );
+        criteria.0[1].weight = 3;
+        let criteria = Rc::new(criteria);
+
+        let leaf = build_leaf(&criteria, &[1, 2]);
+
+        assert_eq!(leaf.feasibility_value(), 1 + 3 * 2);
+    }
+
+    #[test]
+    fn weighted_sums_too_large_saturate() {
+        let mut criteria = FeasibilityCriteria(
+            vec![
+                FeasiblityCriterion::new("Eq", "Equipment"),
+                FeasiblityCriterion::new("Kn", "Knowledge"),
+            ],
+            vec![],
+        );
+        criteria.0[1].weight = 3;
+        let criteria = Rc::new(criteria);
+
+        let leaf = build_leaf(&criteria, &[1, u32::MAX / 2]);
+
+        assert_eq!(leaf.feasibility_value(), u32::MAX);
+    }
+
+    fn build_inverse_criteria() -> Rc<FeasibilityCriteria> {
+        let mut criteria = FeasibilityCriteria(
+            vec![
+                FeasiblityCriterion::new("Kn", "Knowledge"),
+                FeasiblityCriterion::new("Li", "Likelihood"),
+            ],
+            vec![],
+        );
+        criteria.0[1].scale = Scale::HigherIsEasier;
+        criteria.0[1].max = Some(5);
+        Rc::new(criteria)
+    }
+
+    #[test]
+    fn values_on_an_inverse_scale_are_normalized_in_the_sum() {
+        let criteria = build_inverse_criteria();
+        let leaf = build_leaf(&criteria, &[2, 4]);
+
+        assert_eq!(leaf.feasibility_value(), 2 + (5 - 4));
+    }
+
+    #[test]
+    fn assessments_print_their_criteria_and_sum() {
+        let criteria = build_criteria(&["Kn", "Eq", "Wo"]);
+        let assessment = FeasibilityAssessment::new(&criteria, &[Some(3), Some(5), None]).unwrap();
+
+        assert_eq!(assessment.to_string(), "Kn=3, Eq=5, Wo=? (sum 8)");
+        assert_eq!(assessment.value("Eq"), Some(5));
+        assert_eq!(assessment.value("Wo"), None);
+        assert_eq!(assessment.value("Xy"), None);
+    }
+
+    #[test]
+    fn assessments_can_be_built_from_pairs_and_iterated() {
+        let criteria = build_criteria(&["Kn", "Eq"]);
+        let assessment = FeasibilityAssessment::from_pairs(&criteria, &[("Eq", 2)]).unwrap();
+
+        let pairs: Vec<(&str, Option<u32>)> =
+            assessment.iter().map(|(c, v)| (c.id.as_str(), v)).collect();
+        assert_eq!(pairs, vec![("Kn", None), ("Eq", Some(2))]);
+        assert_eq!(
+            assessment.by_id().iter().collect::<Vec<_>>(),
+            vec![("Eq", 2)]
+        );
+        assert_eq!(
+            FeasibilityAssessment::from_pairs(&criteria, &[("Xy", 1)]).err(),
+            Some(TreeError::UnknownCriterion("Xy".to_string()))
+        );
+    }
+
+    #[test]
+    fn an_and_node_takes_the_lower_value_of_an_inverse_scale_criterion() {
+        let criteria = build_inverse_criteria();
+
+        let node = build_and_node(vec![
+            Rc::new(build_leaf(&criteria, &[1, 4])),
+            Rc::new(build_leaf(&criteria, &[3, 2])),
+        ]);
+
+        let expected_assessment = build_feasibility(&criteria, &[3, 2]);
+
+        assert_eq!(
+            node.feasibility().unwrap().assessments.0,
+            expected_assessment.assessments.0
+        );
+        assert_eq!(node.feasibility_value(), 3 + (5 - 2));
+    }
+
+    #[test]
+    fn an_or_node_prefers_the_child_that_is_easiest_on_the_normalized_scale() {
+        let criteria = build_inverse_criteria();
+
+        // raw sums would pick the second child, normalized sums are 5 and 6
+        let node = build_or_node(vec![
+            Rc::new(build_leaf(&criteria, &[4, 4])),
+            Rc::new(build_leaf(&criteria, &[1, 0])),
+        ]);
+
+        let expected_assessment = build_feasibility(&criteria, &[4, 4]);
+
+        assert_eq!(
+            node.feasibility().unwrap().assessments.0,
+            expected_assessment.assessments.0
+        );
+    }
+
     #[test]
     fn the_feasibility_of_a_three_level_tree_is_calculated_correctly() {
         let criteria = build_criteria(&["Eq", "Kn"]);
@@ -459,4 +1206,49 @@ pub mod tests {
 
         assert_eq!(tree.feasibility_value(), 3 + 14);
     }
+
+    fn build_detected_leaf(
+        criteria: &Rc<FeasibilityCriteria>,
+        assessment: &[u32],
+        detection: Option<f64>,
+    ) -> Rc<dyn FeasibleStep> {
+        Rc::new(Leaf {
+            detection,
+            ..build_leaf(criteria, assessment)
+        })
+    }
+
+    #[test]
+    fn an_and_node_is_detected_if_any_of_its_steps_is_detected() {
+        let criteria = build_criteria(&["Eq", "Kn"]);
+
+        let tree = build_and_node(vec![
+            build_detected_leaf(&criteria, &[1, 5], Some(0.5)),
+            build_detected_leaf(&criteria, &[3, 1], Some(0.2)),
+            build_detected_leaf(&criteria, &[3, 1], None),
+        ]);
+
+        assert!((tree.detection().unwrap() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn an_or_node_takes_the_detection_of_its_easiest_child() {
+        let criteria = build_criteria(&["Eq", "Kn"]);
+
+        let tree = build_or_node(vec![
+            build_detected_leaf(&criteria, &[10, 5], Some(0.1)),
+            build_detected_leaf(&criteria, &[3, 1], Some(0.9)),
+        ]);
+
+        assert_eq!(tree.detection(), Some(0.9));
+    }
+
+    #[test]
+    fn nodes_without_rated_steps_have_no_detection() {
+        let criteria = build_criteria(&["Eq", "Kn"]);
+
+        let tree = build_and_node(vec![build_detected_leaf(&criteria, &[1, 5], None)]);
+
+        assert_eq!(tree.detection(), None);
+    }
 }
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, rc::Rc};
+
+use super::feasible_step::{FeasibleStep, NAMESPACE_SEPARATOR};
+
+/// Hierarchical numbers of the nodes like `T1.2.3`, keyed by node id.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NodeNumbers(pub HashMap<u32, String>);
+
+impl NodeNumbers {
+    pub fn get(&self, node_id: u32) -> Option<&str> {
+        self.0.get(&node_id).map(String::as_str)
+    }
+}
+
+/// Numbers the threats `T1`, `T2`, ... in the given order and their nodes
+/// by the position below their parent, e.g. `T1.2.3`. A node with a `number`
+/// attribute keeps that number, the nodes after it continue counting from
+/// there and skip the numbers their siblings already have. Threats of a
+/// namespace are counted separately and numbered after it, e.g. `Door::T1`.
+pub fn number_nodes(roots: &[&Rc<dyn FeasibleStep>]) -> NodeNumbers {
+    let mut numbers = NodeNumbers::default();
+
+    let mut namespaces = vec![];
+    for root in roots {
+        let namespace = root.namespace();
+        if !namespaces.contains(&namespace) {
+            namespaces.push(namespace);
+        }
+    }
+
+    for namespace in namespaces {
+        let threats: Vec<_> = roots
+            .iter()
+            .filter(|r| r.namespace() == namespace)
+            .copied()
+            .collect();
+        let prefix = match &namespace {
+            Some(namespace) => format!("{}{}T", namespace, NAMESPACE_SEPARATOR),
+            None => "T".to_string(),
+        };
+        number_threats(&threats, &prefix, &mut numbers);
+    }
+
+    numbers
+}
+
+/// Like `number_nodes`, but all threats are counted together and the numbers
+/// do not name the namespaces, e.g. for copies shared with a third party.
+pub(crate) fn number_nodes_ignoring_namespaces(roots: &[&Rc<dyn FeasibleStep>]) -> NodeNumbers {
+    let mut numbers = NodeNumbers::default();
+    number_threats(roots, "T", &mut numbers);
+
+    numbers
+}
+
+fn number_threats(threats: &[&Rc<dyn FeasibleStep>], prefix: &str, numbers: &mut NodeNumbers) {
+    for (root, n) in threats.iter().zip(positions(threats)) {
+        number(root, format!("{}{}", prefix, n), numbers);
+    }
+}
+
+fn number(node: &Rc<dyn FeasibleStep>, prefix: String, numbers: &mut NodeNumbers) {
+    let children = node.get_children();
+    for (child, n) in children.iter().zip(positions(&children)) {
+        number(child, format!("{}.{}", prefix, n), numbers);
+    }
+
+    numbers.0.insert(node.id(), prefix);
+}
+
+fn positions<T: AsRef<dyn FeasibleStep>>(siblings: &[T]) -> Vec<u32> {
+    let mut taken: Vec<u32> = siblings
+        .iter()
+        .filter_map(|s| s.as_ref().number())
+        .collect();
+
+    let mut previous = 0;
+    siblings
+        .iter()
+        .map(|s| {
+            let n = s.as_ref().number().unwrap_or_else(|| {
+                let mut n = previous + 1;
+                while taken.contains(&n) {
+                    n += 1;
+                }
+                taken.push(n);
+                n
+            });
+            previous = n;
+            n
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn numbered_titles(texts: &[&str]) -> Vec<(String, String)> {
+        let definition = build_criteria(&["Kn"]);
+        let roots: Vec<_> = texts
+            .iter()
+            .map(|t| {
+                AttackTreeParser::new()
+                    .parse(&mut Cursor::new(*t), &definition)
+                    .unwrap()
+            })
+            .collect();
+        let numbers = number_nodes(&roots.iter().collect::<Vec<_>>());
+
+        let mut result = vec![];
+        let mut stack: Vec<_> = roots.iter().rev().cloned().collect();
+        while let Some(node) = stack.pop() {
+            result.push((
+                numbers.get(node.id()).unwrap().to_string(),
+                node.title().to_string(),
+            ));
+            stack.extend(node.get_children().into_iter().rev());
+        }
+        result
+    }
+
+    #[test]
+    fn numbers_follow_the_structure_of_the_trees() {
+        let result = numbered_titles(&[
+            "Enter house;|\n    Door;&\n        Pick lock; Kn=1\n        Open; Kn=1\n    Window; Kn=2",
+            "Steal car; Kn=3",
+        ]);
+
+        let expected = [
+            ("T1", "Enter house"),
+            ("T1.1", "Door"),
+            ("T1.1.1", "Pick lock"),
+            ("T1.1.2", "Open"),
+            ("T1.2", "Window"),
+            ("T2", "Steal car"),
+        ];
+        assert_eq!(
+            result,
+            expected.map(|(n, t)| (n.to_string(), t.to_string()))
+        );
+    }
+
+    #[test]
+    fn fixed_numbers_survive_the_insertion_of_siblings() {
+        let result = numbered_titles(&[
+            "Root;|\n    A; Kn=1, number=1\n    New; Kn=1\n    B; Kn=1, number=2\n    C; Kn=1",
+            "Other; Kn=1, number=5",
+        ]);
+
+        let expected = [
+            ("T1", "Root"),
+            ("T1.1", "A"),
+            ("T1.3", "New"),
+            ("T1.2", "B"),
+            ("T1.4", "C"),
+            ("T5", "Other"),
+        ];
+        assert_eq!(
+            result,
+            expected.map(|(n, t)| (n.to_string(), t.to_string()))
+        );
+    }
+
+    #[test]
+    fn threats_are_counted_per_namespace() {
+        let result = numbered_titles(&[
+            "Open door;| namespace=Door\n    Pick lock; Kn=1",
+            "Steal car; Kn=3",
+            "Break door;| namespace=Door\n    Kick; Kn=1",
+        ]);
+
+        let expected = [
+            ("Door::T1", "Open door"),
+            ("Door::T1.1", "Pick lock"),
+            ("T1", "Steal car"),
+            ("Door::T2", "Break door"),
+            ("Door::T2.1", "Kick"),
+        ];
+        assert_eq!(
+            result,
+            expected.map(|(n, t)| (n.to_string(), t.to_string()))
+        );
+    }
+}
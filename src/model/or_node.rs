@@ -1,13 +1,37 @@
-use std::{cell::RefCell, rc::Rc};
-
-use super::{render, FeasibilityAssessment, FeasibleStep, TreeError};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
+use super::{
+    combine_children, combine_ranges, render,
+    traversal::{cache_feasibility, children_ranges},
+    Assumption, Defense, FeasibilityAssessment, FeasibilityCache, FeasibleStep, NodeKind, Review,
+    SourceSpan, TreeError,
+};
 
 pub struct OrNode {
     pub id: u32,
     pub description: String,
     pub parent: Option<Rc<dyn FeasibleStep>>,
+    /// Use `add_child`, changing the children directly requires a call to
+    /// `invalidate_feasibility`.
     pub children: RefCell<Vec<Rc<dyn FeasibleStep>>>,
+    pub review: Review,
+    pub note: Option<String>,
+    /// Position among the siblings from the `number` attribute.
+    pub number: Option<u32>,
+    /// Persistent id from the `uuid` attribute.
+    pub uuid: Option<String>,
+    /// Ids of the requirements from requirements.json the node addresses.
+    pub requirements: Vec<String>,
+    /// Planned or existing controls countering the node.
+    pub defenses: Vec<Defense>,
+    pub assumptions: Vec<Assumption>,
+    /// Deployment profiles the subtree belongs to, empty for all.
+    pub profiles: Vec<String>,
+    /// Custom attributes like `x-ticket=SEC-12`, passed through to the exports.
+    pub attributes: BTreeMap<String, String>,
+    /// The lines the node was parsed from.
+    pub span: Option<SourceSpan>,
+    pub feasibility_cache: FeasibilityCache,
 }
 
 impl OrNode {
@@ -20,6 +44,17 @@ impl OrNode {
             description: title.to_string(),
             parent,
             children: RefCell::new(vec![]),
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
         }
     }
 }
@@ -29,19 +64,41 @@ impl FeasibleStep for OrNode {
         self.id
     }
 
+    fn kind(&self) -> NodeKind {
+        NodeKind::Or
+    }
+
     fn feasibility(&self) -> Result<FeasibilityAssessment, TreeError> {
-        if self.children.borrow().is_empty() {
-            return Err(TreeError::AssessmentVectorMismatch);
-        }
+        self.feasibility_cache.get_or_compute(|| {
+            let children = self.children.borrow();
+            cache_feasibility(&children);
+            combine_children(
+                &self.description,
+                children.iter().map(|c| c.feasibility()),
+                easiest,
+            )
+        })
+    }
 
-        let min_feasibility = self
-            .children
-            .borrow()
-            .iter()
-            .map(|s| s.feasibility().unwrap())
-            .min_by_key(|f| f.sum());
+    fn feasibility_is_cached(&self) -> bool {
+        self.feasibility_cache.is_cached()
+    }
+
+    fn clear_feasibility_cache(&self) -> bool {
+        self.feasibility_cache.invalidate()
+    }
+
+    fn feasibility_range(
+        &self,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        self.range_from_children(children_ranges(&self.children.borrow()))
+    }
 
-        Ok(min_feasibility.unwrap())
+    fn range_from_children(
+        &self,
+        children: Vec<Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>>,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        combine_ranges(&self.description, children, easiest)
     }
 
     fn title(&self) -> &str {
@@ -50,6 +107,7 @@ impl FeasibleStep for OrNode {
 
     fn add_child(&self, child: &Rc<dyn FeasibleStep>) {
         self.children.borrow_mut().push(child.clone());
+        self.invalidate_feasibility();
     }
 
     fn get_parent(&self) -> Option<Rc<dyn FeasibleStep>> {
@@ -64,6 +122,17 @@ impl FeasibleStep for OrNode {
         render(self, " shape=invtrapezium")
     }
 
+    fn detection(&self) -> Option<f64> {
+        // the attacker takes the easiest child, the one feasibility picks
+        let children = self.children.borrow();
+        let easiest = children
+            .iter()
+            .filter_map(|c| c.feasibility().ok().map(|f| (f.sum(), c)))
+            .reduce(|a, b| if b.0 < a.0 { b } else { a });
+
+        easiest.and_then(|(_, c)| c.detection())
+    }
+
     fn get_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
         let mut v = Vec::new();
 
@@ -73,4 +142,57 @@ impl FeasibleStep for OrNode {
 
         v
     }
+
+    fn take_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
+        self.clear_feasibility_cache();
+        self.children.take()
+    }
+
+    fn review(&self) -> &Review {
+        &self.review
+    }
+
+    fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    fn number(&self) -> Option<u32> {
+        self.number
+    }
+
+    fn uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    fn requirements(&self) -> &[String] {
+        &self.requirements
+    }
+
+    fn defenses(&self) -> &[Defense] {
+        &self.defenses
+    }
+
+    fn assumptions(&self) -> &[Assumption] {
+        &self.assumptions
+    }
+
+    fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
+    fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    fn span(&self) -> Option<&SourceSpan> {
+        self.span.as_ref()
+    }
+}
+
+/// The easiest child wins, on ties the first one.
+fn easiest(
+    a: FeasibilityAssessment,
+    b: FeasibilityAssessment,
+) -> Result<FeasibilityAssessment, TreeError> {
+    Ok(if b.sum() < a.sum() { b } else { a })
 }
@@ -0,0 +1,232 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{
+    feasible_step::{FeasibleStep, NodeKind},
+    merge::{copy_leaf, new_inner_node},
+    traversal::preorder_with_depth,
+    FeasibilityAssessment,
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OverlayError {
+    #[error("overlay file parser error: {0}")]
+    ParseError(String),
+    #[error("an override of {0} needs either a path or a node position")]
+    MissingTarget(String),
+    #[error("{0}: no node at {1}")]
+    UnknownNode(String, String),
+    #[error("{0}: {1} is not a leaf")]
+    NotALeaf(String, String),
+    #[error("{0}: unknown criterion '{1}'")]
+    UnknownCriterion(String, String),
+    #[error("no tree file '{0}'")]
+    UnknownFile(String),
+}
+
+/// Assessments of an environment, e.g. production, that replace values of
+/// the leaves in the .att files. Read from files like `prod.overlay.json`.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Overlay {
+    pub overrides: Vec<Override>,
+}
+
+/// New values for the criteria of one leaf.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Override {
+    /// Name of the .att file of the leaf.
+    pub file: String,
+    /// Titles from the root down to the leaf.
+    #[serde(default)]
+    pub path: Vec<String>,
+    /// Position of the leaf in depth-first order, as in the `node-N` anchors
+    /// of the tree pages. Used if no path is given.
+    pub node: Option<usize>,
+    /// The values by criterion id or alias.
+    pub values: BTreeMap<String, u32>,
+}
+
+impl Override {
+    fn target(&self) -> String {
+        match self.node {
+            Some(position) if self.path.is_empty() => format!("node {}", position),
+            _ => format!("'{}'", self.path.join(" > ")),
+        }
+    }
+}
+
+impl Overlay {
+    pub fn from_json(json: &str) -> Result<Overlay, OverlayError> {
+        serde_json::from_str(json).map_err(|e| OverlayError::ParseError(e.to_string()))
+    }
+
+    /// Copies the tree of the file `file_name` with the values of its
+    /// overrides. Overrides that cannot be applied are returned as errors.
+    pub fn apply(
+        &self,
+        file_name: &str,
+        root: &Rc<dyn FeasibleStep>,
+    ) -> (Rc<dyn FeasibleStep>, Vec<OverlayError>) {
+        let overrides: Vec<&Override> = self
+            .overrides
+            .iter()
+            .filter(|o| o.file == file_name)
+            .collect();
+        if overrides.is_empty() {
+            return (root.clone(), vec![]);
+        }
+
+        let mut paths: Vec<Vec<String>> = vec![];
+        let mut titles: Vec<String> = vec![];
+        let nodes = preorder_with_depth(root);
+        for (depth, node) in &nodes {
+            titles.truncate(*depth);
+            titles.push(node.title().to_string());
+            paths.push(titles.clone());
+        }
+
+        let mut errors = vec![];
+        let mut values: BTreeMap<usize, Vec<(usize, u32)>> = BTreeMap::new();
+        for o in overrides {
+            let position = match (o.path.is_empty(), o.node) {
+                (false, _) => paths.iter().position(|p| *p == o.path),
+                (true, Some(position)) => (position < nodes.len()).then_some(position),
+                (true, None) => {
+                    errors.push(OverlayError::MissingTarget(file_name.to_string()));
+                    continue;
+                }
+            };
+            let Some(position) = position else {
+                errors.push(OverlayError::UnknownNode(file_name.to_string(), o.target()));
+                continue;
+            };
+            let node = &nodes[position].1;
+            let assessment = match node.feasibility() {
+                Ok(a) if node.kind() == NodeKind::Leaf => a,
+                _ => {
+                    errors.push(OverlayError::NotALeaf(file_name.to_string(), o.target()));
+                    continue;
+                }
+            };
+
+            for (criterion, value) in &o.values {
+                match assessment.definition().lookup(criterion, false) {
+                    Ok(index) => values.entry(position).or_default().push((index, *value)),
+                    Err(_) => errors.push(OverlayError::UnknownCriterion(
+                        file_name.to_string(),
+                        criterion.clone(),
+                    )),
+                }
+            }
+        }
+
+        let copy = copy_overridden(root, None, &mut 0, &values);
+        (copy, errors)
+    }
+
+    /// Errors for the overrides of files that are not among `file_names`.
+    pub fn unknown_files(&self, file_names: &[&str]) -> Vec<OverlayError> {
+        let mut unknown: Vec<&str> = self
+            .overrides
+            .iter()
+            .map(|o| o.file.as_str())
+            .filter(|f| !file_names.contains(f))
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+
+        unknown
+            .into_iter()
+            .map(|f| OverlayError::UnknownFile(f.to_string()))
+            .collect()
+    }
+}
+
+fn copy_overridden(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    position: &mut usize,
+    values: &BTreeMap<usize, Vec<(usize, u32)>>,
+) -> Rc<dyn FeasibleStep> {
+    let overrides = values.get(position).map(Vec::as_slice).unwrap_or_default();
+    *position += 1;
+
+    if node.kind() == NodeKind::Leaf {
+        if let Ok((easiest, hardest)) = node.feasibility_range() {
+            let replace = |a: FeasibilityAssessment| {
+                let mut v = a.values().to_vec();
+                for (index, value) in overrides {
+                    v[*index] = Some(*value);
+                }
+                FeasibilityAssessment::from_values(a.definition(), &v)
+            };
+            return copy_leaf(node, parent, (replace(easiest), replace(hardest)));
+        }
+    }
+
+    let copy = new_inner_node(node, parent);
+    for c in node.get_children() {
+        copy.add_child(&copy_overridden(&c, Some(copy.clone()), position, values));
+    }
+
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn overrides_replace_the_values_of_leaves() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Enter house;|\n    Pick lock; Kn=2..6, Eq=1\n    Break window; Kn=1, Eq=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+        let overlay = Overlay::from_json(
+            r#"{"overrides": [
+                {"file": "house.att", "path": ["Enter house", "Pick lock"], "values": {"Kn": 4}},
+                {"file": "house.att", "node": 2, "values": {"Eq": 9, "Ex": 1}},
+                {"file": "house.att", "path": ["Enter house", "Open door"], "values": {"Kn": 1}},
+                {"file": "house.att", "node": 0, "values": {"Kn": 1}},
+                {"file": "car.att", "node": 1, "values": {"Kn": 1}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let (copy, errors) = overlay.apply("house.att", &root);
+
+        let children = copy.get_children();
+        assert_eq!(children[0].feasibility_value(), 5);
+        assert_eq!(children[0].feasibility_interval(), None);
+        assert_eq!(children[1].feasibility_value(), 10);
+        assert_eq!(copy.feasibility_value(), 5);
+        assert_eq!(root.feasibility_value(), 2);
+        assert_eq!(
+            errors,
+            vec![
+                OverlayError::UnknownCriterion("house.att".to_string(), "Ex".to_string()),
+                OverlayError::UnknownNode(
+                    "house.att".to_string(),
+                    "'Enter house > Open door'".to_string()
+                ),
+                OverlayError::NotALeaf("house.att".to_string(), "node 0".to_string()),
+            ]
+        );
+        assert_eq!(
+            overlay.unknown_files(&["house.att"]),
+            vec![OverlayError::UnknownFile("car.att".to_string())]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::rc::Rc;
+
+use super::{
+    feasible_step::{FeasibleStep, NodeKind},
+    merge::{copy_leaf, new_inner_node},
+};
+
+/// Copies the tree without the subtrees limited to other profiles than
+/// `profile`. Nodes without profiles belong to every profile. Inner nodes
+/// whose children all belong to other profiles are left out as well. Returns
+/// None if the root itself is excluded or the attack cannot be carried out
+/// with the remaining steps.
+pub fn select_profile(root: &Rc<dyn FeasibleStep>, profile: &str) -> Option<Rc<dyn FeasibleStep>> {
+    match copy_selected(root, None, profile) {
+        Selection::Copied(copy) => Some(copy),
+        Selection::Excluded | Selection::Infeasible => None,
+    }
+}
+
+enum Selection {
+    Copied(Rc<dyn FeasibleStep>),
+    /// The node does not belong to the profile.
+    Excluded,
+    /// The node belongs to the profile, but too few of its children do.
+    Infeasible,
+}
+
+fn copy_selected(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    profile: &str,
+) -> Selection {
+    if !node.profiles().is_empty() && !node.profiles().iter().any(|p| p == profile) {
+        return Selection::Excluded;
+    }
+
+    if node.kind() == NodeKind::Leaf {
+        if let Ok(range) = node.feasibility_range() {
+            return Selection::Copied(copy_leaf(node, parent, range));
+        }
+    }
+
+    let children = node.get_children();
+    let copy = new_inner_node(node, parent);
+    let mut selected = 0;
+    let mut infeasible = 0;
+    for c in &children {
+        match copy_selected(c, Some(copy.clone()), profile) {
+            Selection::Copied(child) => {
+                copy.add_child(&child);
+                selected += 1;
+            }
+            Selection::Excluded => {}
+            Selection::Infeasible => infeasible += 1,
+        }
+    }
+
+    if children.is_empty() {
+        return Selection::Copied(copy);
+    }
+    // an AND node needs every remaining step, a voting node its threshold
+    let needed = match node.kind() {
+        NodeKind::And if infeasible > 0 => usize::MAX,
+        NodeKind::Voting => node.threshold().unwrap_or(1),
+        _ => 1,
+    };
+    if selected >= needed {
+        Selection::Copied(copy)
+    } else if selected == 0 && infeasible == 0 {
+        Selection::Excluded
+    } else {
+        Selection::Infeasible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(text: &str) -> Rc<dyn FeasibleStep> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap()
+    }
+
+    #[test]
+    fn subtrees_of_other_profiles_are_left_out() {
+        let tree = parse(
+            r#"
+Take over device;|
+    Use debug port; Kn=1, Eq=1, profile=lab
+    Exploit web interface;& profile=remote-only, profile=cloud
+        Find vulnerability; Kn=5, Eq=2
+        Send exploit; Kn=3, Eq=4
+    Steal credentials; Kn=8, Eq=3"#,
+        );
+
+        let remote = select_profile(&tree, "remote-only").unwrap();
+        let titles: Vec<_> = remote
+            .get_children()
+            .iter()
+            .map(|c| c.title().to_string())
+            .collect();
+        assert_eq!(titles, vec!["Exploit web interface", "Steal credentials"]);
+        assert_eq!(remote.feasibility_value(), 5 + 4);
+
+        let lab = select_profile(&tree, "lab").unwrap();
+        assert_eq!(lab.feasibility_value(), 1 + 1);
+    }
+
+    #[test]
+    fn a_tree_limited_to_another_profile_is_dropped() {
+        let tree = parse("Steal car; Kn=1, Eq=1, profile=fleet");
+
+        assert!(select_profile(&tree, "private").is_none());
+    }
+
+    #[test]
+    fn or_nodes_without_selected_children_are_dropped() {
+        let tree = parse(
+            r#"
+Open door;&
+    Reach door; Kn=1, Eq=1
+    Get in;|
+        Use badge; Kn=2, Eq=2, profile=office
+        Use key; Kn=3, Eq=3, profile=office"#,
+        );
+
+        let home = select_profile(&tree, "home").unwrap();
+        let titles: Vec<_> = home
+            .get_children()
+            .iter()
+            .map(|c| c.title().to_string())
+            .collect();
+        assert_eq!(titles, vec!["Reach door"]);
+        assert_eq!(home.feasibility_value(), 1 + 1);
+    }
+
+    #[test]
+    fn voting_nodes_with_too_few_selected_children_are_infeasible() {
+        let tree = parse(
+            r#"
+Take over device;|
+    Bypass checks;2/3
+        Glitch clock; Kn=1, Eq=1, profile=lab
+        Glitch voltage; Kn=1, Eq=1, profile=lab
+        Skip boot check; Kn=2, Eq=2
+    Steal credentials; Kn=8, Eq=3
+"#,
+        );
+
+        let remote = select_profile(&tree, "remote").unwrap();
+        let titles: Vec<_> = remote
+            .get_children()
+            .iter()
+            .map(|c| c.title().to_string())
+            .collect();
+        assert_eq!(titles, vec!["Steal credentials"]);
+        assert_eq!(remote.feasibility_value(), 8 + 3);
+
+        let lab = select_profile(&tree, "lab").unwrap();
+        assert_eq!(lab.feasibility_value(), 1 + 1);
+
+        let bypass = parse(
+            r#"
+Bypass checks;2/3
+    Glitch clock; Kn=1, Eq=1, profile=lab
+    Glitch voltage; Kn=1, Eq=1, profile=lab
+    Skip boot check; Kn=2, Eq=2"#,
+        );
+        assert!(select_profile(&bypass, "remote").is_none());
+    }
+}
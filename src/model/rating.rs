@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+/// A named band of feasibility values, e.g. `High` for values up to 13.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RatingLevel {
+    pub name: String,
+    /// Highest feasibility value of the level, unset for the last level.
+    pub max: Option<u32>,
+}
+
+/// Names the first level whose maximum is not below `value`. Levels are
+/// expected in ascending order.
+pub fn rate(levels: &[RatingLevel], value: u32) -> Option<&str> {
+    levels
+        .iter()
+        .find(|l| l.max.is_none_or(|m| value <= m))
+        .map(|l| l.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_rated_by_the_first_matching_level() {
+        let levels = vec![
+            RatingLevel {
+                name: "High".to_string(),
+                max: Some(13),
+            },
+            RatingLevel {
+                name: "Medium".to_string(),
+                max: Some(19),
+            },
+            RatingLevel {
+                name: "Low".to_string(),
+                max: None,
+            },
+        ];
+
+        assert_eq!(rate(&levels, 0), Some("High"));
+        assert_eq!(rate(&levels, 13), Some("High"));
+        assert_eq!(rate(&levels, 14), Some("Medium"));
+        assert_eq!(rate(&levels, 40), Some("Low"));
+        assert_eq!(rate(&levels[..1], 40), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use super::{
+    feasible_step::{FeasibleStep, NodeKind},
+    generate_id,
+    numbering::number_nodes_ignoring_namespaces,
+    or_node::OrNode,
+    voting_node::VotingNode,
+    AndNode, Defense, Leaf, NodeNumbers, Review,
+};
+
+/// Copies the trees with the title of every node replaced by its number, see
+/// `number_nodes`, e.g. to share them with a third party. The numbers do not
+/// name the namespaces. The structure, the
+/// assessments, detection ratings and the effects of the defenses are kept.
+/// Notes, review, attributes, requirements, profiles, techniques and
+/// assumptions are dropped, defenses are named after their node.
+pub fn redact_trees(roots: &[&Rc<dyn FeasibleStep>]) -> Vec<Rc<dyn FeasibleStep>> {
+    let numbers = number_nodes_ignoring_namespaces(roots);
+
+    roots
+        .iter()
+        .map(|root| redact_node(root, None, &numbers))
+        .collect()
+}
+
+fn redact_node(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    numbers: &NodeNumbers,
+) -> Rc<dyn FeasibleStep> {
+    let title = numbers.get(node.id()).unwrap_or_default();
+    let defenses: Vec<Defense> = node
+        .defenses()
+        .iter()
+        .enumerate()
+        .map(|(i, d)| Defense {
+            id: generate_id(),
+            description: format!("{} defense {}", title, i + 1),
+            effect: d.effect.clone(),
+            note: None,
+        })
+        .collect();
+
+    let copy: Rc<dyn FeasibleStep> = match node.kind() {
+        NodeKind::Leaf => {
+            let Ok((criteria, hardest)) = node.feasibility_range() else {
+                return Rc::new(AndNode::new(title, parent, generate_id));
+            };
+            return Rc::new(Leaf {
+                id: generate_id(),
+                description: title.to_string(),
+                parent,
+                hardest: (hardest.values() != criteria.values()).then_some(hardest),
+                criteria,
+                assessed: None,
+                review: Review::default(),
+                note: None,
+                number: None,
+                uuid: None,
+                techniques: vec![],
+                detection: node.detection(),
+                requirements: vec![],
+                defenses,
+                assumptions: vec![],
+                profiles: vec![],
+                attributes: BTreeMap::new(),
+                span: None,
+            });
+        }
+        NodeKind::And => Rc::new(AndNode {
+            defenses,
+            ..AndNode::new(title, parent, generate_id)
+        }),
+        NodeKind::Or => Rc::new(OrNode {
+            defenses,
+            ..OrNode::new(title, parent, generate_id)
+        }),
+        NodeKind::Voting => Rc::new(VotingNode {
+            defenses,
+            ..VotingNode::new(title, node.threshold().unwrap_or(1), parent, generate_id)
+        }),
+    };
+
+    for c in node.get_children() {
+        copy.add_child(&redact_node(&c, Some(copy.clone()), numbers));
+    }
+
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        model::tests::build_criteria, parser::AttackTreeParser, render::render_to_att_string,
+    };
+
+    #[test]
+    fn titles_and_details_are_replaced_but_values_are_kept() {
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Enter house;| owner=alice, x-ticket=SEC-1\n    Pick lock; Kn=1..3, Eq=2, attack=T1200\n        > Works on the back door\n        Alarm;! Kn=2\n    Window; Kn=4, Eq=4\n        Nobody is home;~",
+                ),
+                &build_criteria(&["Kn", "Eq"]),
+            )
+            .unwrap();
+
+        let redacted = redact_trees(&[&root]).remove(0);
+
+        assert_eq!(
+            render_to_att_string(&redacted),
+            "T1;|\n    T1.1; Kn=1..3, Eq=2\n        T1.1 defense 1;! Kn=2\n    T1.2; Kn=4, Eq=4"
+        );
+        assert_eq!(redacted.feasibility_value(), root.feasibility_value());
+    }
+
+    #[test]
+    fn namespaces_are_not_named_in_the_numbers() {
+        let definition = build_criteria(&["Kn"]);
+        let roots: Vec<_> = [
+            "Steal card data;| namespace=Payments\n    Skim; Kn=1",
+            "Open door;| namespace=Door\n    Pick lock; Kn=2",
+        ]
+        .iter()
+        .map(|t| {
+            AttackTreeParser::new()
+                .parse(&mut Cursor::new(*t), &definition)
+                .unwrap()
+        })
+        .collect();
+
+        let redacted = redact_trees(&roots.iter().collect::<Vec<_>>());
+
+        let texts: Vec<_> = redacted.iter().map(render_to_att_string).collect();
+        assert_eq!(texts, vec!["T1;|\n    T1.1; Kn=1", "T2;|\n    T2.1; Kn=2"]);
+    }
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+/// A security requirement or control from requirements.json that tree nodes
+/// can reference.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub id: String,
+    pub title: String,
+}
+
+/// Reads the contents of a requirements.json file.
+pub fn read_requirements(json: &str) -> Result<Vec<Requirement>, serde_json::Error> {
+    serde_json::from_str(json)
+}
@@ -0,0 +1,71 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Where a tree stands in the sign-off process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    Draft,
+    Reviewed,
+    Approved,
+}
+
+impl FromStr for ReviewStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(ReviewStatus::Draft),
+            "reviewed" => Ok(ReviewStatus::Reviewed),
+            "approved" => Ok(ReviewStatus::Approved),
+            _ => Err(format!(
+                "unknown status '{}', use draft, reviewed or approved",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for ReviewStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReviewStatus::Draft => write!(f, "draft"),
+            ReviewStatus::Reviewed => write!(f, "reviewed"),
+            ReviewStatus::Approved => write!(f, "approved"),
+        }
+    }
+}
+
+/// Ownership and review state of a tree, set on its root node.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Review {
+    pub owner: Option<String>,
+    pub status: Option<ReviewStatus>,
+    /// Version of the tree, free text like `1.2`.
+    pub version: Option<String>,
+    /// Date of the version.
+    pub date: Option<NaiveDate>,
+    /// Impact level of the threat, one of the configured impact levels.
+    pub impact: Option<String>,
+    /// Namespace of the titles of the tree, e.g. the component it belongs
+    /// to, which keeps them apart from equal titles of other components.
+    pub namespace: Option<String>,
+}
+
+impl Review {
+    /// Whether neither owner nor status is set, version and date are not
+    /// part of the review.
+    pub fn is_empty(&self) -> bool {
+        self.owner.is_none() && self.status.is_none()
+    }
+
+    /// Trees without a status count as drafts.
+    pub fn is_reviewed(&self) -> bool {
+        matches!(
+            self.status,
+            Some(ReviewStatus::Reviewed | ReviewStatus::Approved)
+        )
+    }
+}
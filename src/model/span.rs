@@ -0,0 +1,31 @@
+use std::{fmt::Display, path::Path, rc::Rc};
+
+/// Where a node is written in its .att file, for diagnostics and editors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// Unset when the text was not read from a file.
+    pub file: Option<Rc<Path>>,
+    /// The line of the node, counting from 1.
+    pub first_line: u32,
+    /// The last line of the node's note, defenses and subtree.
+    pub last_line: u32,
+}
+
+impl SourceSpan {
+    pub fn contains(&self, line: u32) -> bool {
+        (self.first_line..=self.last_line).contains(&line)
+    }
+}
+
+impl Display for SourceSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        if self.first_line == self.last_line {
+            write!(f, "{}", self.first_line)
+        } else {
+            write!(f, "{}-{}", self.first_line, self.last_line)
+        }
+    }
+}
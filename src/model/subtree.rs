@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use super::{feasible_step::FeasibleStep, merge::copy_subtree};
+
+/// How the node at the top of a subtree is chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubtreeSelector {
+    /// The node with this title, which must be unique in the tree.
+    Title(String),
+    /// The node at this position in depth-first order, the root is 0. Tree
+    /// pages use the same numbers in their `node-N` anchors.
+    Position(usize),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SubtreeError {
+    #[error("no node is titled '{0}'")]
+    UnknownTitle(String),
+    #[error("{1} nodes are titled '{0}', select one with its position instead")]
+    AmbiguousTitle(String, usize),
+    #[error("there is no node at position {0}, the tree has {1} nodes")]
+    UnknownPosition(usize, usize),
+}
+
+/// Copies the selected node and its descendants into a tree of its own.
+pub fn select_subtree(
+    root: &Rc<dyn FeasibleStep>,
+    selector: &SubtreeSelector,
+) -> Result<Rc<dyn FeasibleStep>, SubtreeError> {
+    let mut nodes = vec![];
+    collect(root, &mut nodes);
+
+    let node = match selector {
+        SubtreeSelector::Title(title) => {
+            let matches: Vec<_> = nodes.iter().filter(|n| n.title() == title).collect();
+            match matches.as_slice() {
+                [node] => *node,
+                [] => return Err(SubtreeError::UnknownTitle(title.clone())),
+                _ => return Err(SubtreeError::AmbiguousTitle(title.clone(), matches.len())),
+            }
+        }
+        SubtreeSelector::Position(position) => nodes
+            .get(*position)
+            .ok_or(SubtreeError::UnknownPosition(*position, nodes.len()))?,
+    };
+
+    Ok(copy_subtree(node, None))
+}
+
+fn collect(node: &Rc<dyn FeasibleStep>, result: &mut Vec<Rc<dyn FeasibleStep>>) {
+    result.push(node.clone());
+    for c in node.get_children() {
+        collect(&c, result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    fn parse(text: &str) -> Rc<dyn FeasibleStep> {
+        AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &build_criteria(&["Kn"]))
+            .unwrap()
+    }
+
+    #[test]
+    fn the_selected_node_becomes_a_root() {
+        let root = parse(
+            "Root;|\n    Door;&\n        Pick lock; Kn=3\n        Open; Kn=1\n    Window; Kn=9",
+        );
+
+        let by_title = select_subtree(&root, &SubtreeSelector::Title("Door".to_string())).unwrap();
+        let by_position = select_subtree(&root, &SubtreeSelector::Position(1)).unwrap();
+
+        for subtree in [by_title, by_position] {
+            assert_eq!(subtree.title(), "Door");
+            assert!(subtree.get_parent().is_none());
+            assert_eq!(subtree.get_children().len(), 2);
+            assert_eq!(subtree.feasibility_value(), 3);
+        }
+    }
+
+    #[test]
+    fn unknown_and_ambiguous_selections_are_rejected() {
+        let root = parse("Root;|\n    Step; Kn=3\n    Step; Kn=1");
+
+        assert_eq!(
+            select_subtree(&root, &SubtreeSelector::Title("Door".to_string())).err(),
+            Some(SubtreeError::UnknownTitle("Door".to_string()))
+        );
+        assert_eq!(
+            select_subtree(&root, &SubtreeSelector::Title("Step".to_string())).err(),
+            Some(SubtreeError::AmbiguousTitle("Step".to_string(), 2))
+        );
+        assert_eq!(
+            select_subtree(&root, &SubtreeSelector::Position(3)).err(),
+            Some(SubtreeError::UnknownPosition(3, 3))
+        );
+    }
+}
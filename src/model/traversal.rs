@@ -0,0 +1,202 @@
+use std::rc::Rc;
+
+use super::{FeasibilityAssessment, FeasibleStep, NodeKind, TreeError};
+
+/// The nodes of the tree in pre-order with their depth, the root has depth
+/// 0. Uses an explicit stack, so the depth of the tree is not limited by the
+/// call stack.
+pub fn preorder_with_depth(root: &Rc<dyn FeasibleStep>) -> Vec<(usize, Rc<dyn FeasibleStep>)> {
+    let mut result = vec![];
+    let mut stack = vec![(0, root.clone())];
+
+    while let Some((depth, node)) = stack.pop() {
+        stack.extend(
+            node.get_children()
+                .into_iter()
+                .rev()
+                .map(|c| (depth + 1, c)),
+        );
+        result.push((depth, node));
+    }
+
+    result
+}
+
+/// The nodes of the tree in pre-order.
+pub fn preorder(root: &Rc<dyn FeasibleStep>) -> Vec<Rc<dyn FeasibleStep>> {
+    preorder_with_depth(root)
+        .into_iter()
+        .map(|(_, node)| node)
+        .collect()
+}
+
+/// The number of levels of the tree, 1 for a single node.
+pub fn depth(root: &Rc<dyn FeasibleStep>) -> usize {
+    preorder_with_depth(root)
+        .iter()
+        .map(|(depth, _)| depth + 1)
+        .max()
+        .unwrap_or(1)
+}
+
+/// Takes the children off every node of the tree. Children hold their parents,
+/// so a tree is only freed after its links are broken like this. The tree is
+/// left without children.
+pub fn release_tree(root: &Rc<dyn FeasibleStep>) {
+    let mut nodes = vec![];
+    let mut stack = vec![root.clone()];
+    while let Some(node) = stack.pop() {
+        stack.extend(node.take_children());
+        nodes.push(node);
+    }
+
+    // children first, a freed child must not free its ancestors recursively
+    while nodes.pop().is_some() {}
+}
+
+enum Visit {
+    Node(Rc<dyn FeasibleStep>),
+    Combine(Rc<dyn FeasibleStep>, usize),
+}
+
+/// Evaluates the subtrees of `children` bottom-up with an explicit stack.
+/// `value` gives the value of a node that needs none of its children, e.g. a
+/// leaf, and `combine` that of any other node from the values of its
+/// children.
+fn fold_children<T, V, C>(children: &[Rc<dyn FeasibleStep>], value: V, combine: C) -> Vec<T>
+where
+    V: Fn(&Rc<dyn FeasibleStep>) -> Option<T>,
+    C: Fn(&Rc<dyn FeasibleStep>, Vec<T>) -> T,
+{
+    let mut results = vec![];
+    let mut stack: Vec<Visit> = children.iter().rev().cloned().map(Visit::Node).collect();
+
+    while let Some(visit) = stack.pop() {
+        match visit {
+            Visit::Node(node) => match value(&node) {
+                Some(v) => results.push(v),
+                None => {
+                    let grandchildren = node.get_children();
+                    stack.push(Visit::Combine(node, grandchildren.len()));
+                    stack.extend(grandchildren.into_iter().rev().map(Visit::Node));
+                }
+            },
+            Visit::Combine(node, count) => {
+                let values = results.split_off(results.len() - count);
+                results.push(combine(&node, values));
+            }
+        }
+    }
+
+    results
+}
+
+/// Fills the feasibility caches below `children` from the leaves up, so that
+/// the feasibility of a node is computed from the cached values of its
+/// direct children instead of a recursion through the whole subtree.
+pub(crate) fn cache_feasibility(children: &[Rc<dyn FeasibleStep>]) {
+    fold_children(
+        children,
+        |node| node.feasibility_is_cached().then_some(()),
+        |node, _| {
+            // the result stays in the cache of the node
+            let _ = node.feasibility();
+        },
+    );
+}
+
+/// The feasibility ranges of the children, see `feasibility_range`.
+pub(crate) fn children_ranges(
+    children: &[Rc<dyn FeasibleStep>],
+) -> Vec<Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>> {
+    fold_children(
+        children,
+        |node| (node.kind() == NodeKind::Leaf).then(|| node.feasibility_range()),
+        |node, ranges| node.range_from_children(ranges),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    /// A chain of AND nodes, deeper than the recursion would allow.
+    fn build_chain(depth: usize) -> Rc<dyn FeasibleStep> {
+        use crate::model::{generate_id, AndNode, Leaf};
+
+        let definition = build_criteria(&["Kn"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Level 0", None, generate_id));
+        let mut node = root.clone();
+        for level in 1..depth {
+            let child: Rc<dyn FeasibleStep> = Rc::new(AndNode::new(
+                &format!("Level {}", level),
+                Some(node.clone()),
+                generate_id,
+            ));
+            node.add_child(&child);
+            node = child;
+        }
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step",
+            Some(node.clone()),
+            &definition,
+            &[3],
+            generate_id,
+        ));
+        node.add_child(&leaf);
+
+        root
+    }
+
+    #[test]
+    fn nodes_are_listed_in_preorder_with_their_depth() {
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Root;&\n    A;|\n        A1; Kn=1\n    B; Kn=2"),
+                &build_criteria(&["Kn"]),
+            )
+            .unwrap();
+
+        let nodes: Vec<(usize, String)> = preorder_with_depth(&root)
+            .iter()
+            .map(|(d, n)| (*d, n.title().to_string()))
+            .collect();
+
+        assert_eq!(
+            nodes,
+            vec![
+                (0, "Root".to_string()),
+                (1, "A".to_string()),
+                (2, "A1".to_string()),
+                (1, "B".to_string())
+            ]
+        );
+        assert_eq!(depth(&root), 3);
+    }
+
+    #[test]
+    fn deep_trees_do_not_overflow_the_stack() {
+        let root = build_chain(100_000);
+
+        assert_eq!(depth(&root), 100_001);
+        assert_eq!(root.feasibility_value(), 3);
+        assert_eq!(root.feasibility_interval(), None);
+    }
+
+    #[test]
+    fn released_trees_are_freed() {
+        let root = build_chain(100_000);
+        let leaf = Rc::downgrade(&preorder(&root).pop().unwrap());
+        let weak_root = Rc::downgrade(&root);
+
+        release_tree(&root);
+        assert!(root.get_children().is_empty());
+        assert!(leaf.upgrade().is_none());
+
+        drop(root);
+        assert!(weak_root.upgrade().is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use std::rc::Rc;
+
+use super::{
+    feasible_step::{FeasibleStep, NodeKind},
+    merge::{copy_leaf, new_inner_node},
+    FeasibilityAssessment,
+};
+
+/// Copies the tree with a point value for every range of a leaf, chosen by
+/// `pick(easiest, hardest)`. Values outside of ranges are kept.
+pub fn resolve_ranges(
+    root: &Rc<dyn FeasibleStep>,
+    pick: &mut dyn FnMut(u32, u32) -> u32,
+) -> Rc<dyn FeasibleStep> {
+    copy_resolved(root, None, pick)
+}
+
+fn copy_resolved(
+    node: &Rc<dyn FeasibleStep>,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    pick: &mut dyn FnMut(u32, u32) -> u32,
+) -> Rc<dyn FeasibleStep> {
+    if node.kind() == NodeKind::Leaf {
+        if let Ok((easiest, hardest)) = node.feasibility_range() {
+            let values: Vec<Option<u32>> = easiest
+                .iter()
+                .map(|(c, e)| match (e, hardest.by_id().get(&c.id)) {
+                    (Some(e), Some(h)) if e != h => Some(pick(e, h)),
+                    (e, _) => e,
+                })
+                .collect();
+            let resolved = FeasibilityAssessment::from_values(easiest.definition(), &values);
+
+            return copy_leaf(node, parent, (resolved.clone(), resolved));
+        }
+    }
+
+    let copy = new_inner_node(node, parent);
+    for c in node.get_children() {
+        copy.add_child(&copy_resolved(&c, Some(copy.clone()), pick));
+    }
+
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn ranges_are_replaced_by_the_picked_value() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Root;|\n    Pick lock; Kn=2..6, Eq=1\n    Break window; Kn=5, Eq=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+
+        let easiest = resolve_ranges(&root, &mut |e, _| e);
+        let hardest = resolve_ranges(&root, &mut |_, h| h);
+
+        assert_eq!(easiest.feasibility_value(), 3);
+        assert_eq!(easiest.feasibility_interval(), None);
+        assert_eq!(hardest.feasibility_value(), 6);
+        assert_eq!(hardest.get_children()[1].feasibility_value(), 6);
+    }
+}
@@ -0,0 +1,345 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use super::{
+    render,
+    traversal::{cache_feasibility, children_ranges},
+    Assumption, Defense, FeasibilityAssessment, FeasibilityCache, FeasibleStep, NodeKind, Review,
+    SourceSpan, TreeError,
+};
+
+/// A k-out-of-n node, the attacker needs any `threshold` of the children.
+pub struct VotingNode {
+    pub id: u32,
+    pub description: String,
+    pub parent: Option<Rc<dyn FeasibleStep>>,
+    /// Number of children needed for the attack, at least 1.
+    pub threshold: usize,
+    /// Use `add_child`, changing the children directly requires a call to
+    /// `invalidate_feasibility`.
+    pub children: RefCell<Vec<Rc<dyn FeasibleStep>>>,
+    pub review: Review,
+    pub note: Option<String>,
+    /// Position among the siblings from the `number` attribute.
+    pub number: Option<u32>,
+    /// Persistent id from the `uuid` attribute.
+    pub uuid: Option<String>,
+    /// Ids of the requirements from requirements.json the node addresses.
+    pub requirements: Vec<String>,
+    /// Planned or existing controls countering the node.
+    pub defenses: Vec<Defense>,
+    pub assumptions: Vec<Assumption>,
+    /// Deployment profiles the subtree belongs to, empty for all.
+    pub profiles: Vec<String>,
+    /// Custom attributes like `x-ticket=SEC-12`, passed through to the exports.
+    pub attributes: BTreeMap<String, String>,
+    /// The lines the node was parsed from.
+    pub span: Option<SourceSpan>,
+    pub feasibility_cache: FeasibilityCache,
+}
+
+impl VotingNode {
+    pub fn new<F>(
+        title: &str,
+        threshold: usize,
+        parent: Option<Rc<dyn FeasibleStep>>,
+        id_gen: F,
+    ) -> VotingNode
+    where
+        F: Fn() -> u32,
+    {
+        VotingNode {
+            id: id_gen(),
+            description: title.to_string(),
+            parent,
+            threshold,
+            children: RefCell::new(vec![]),
+            review: Review::default(),
+            note: None,
+            number: None,
+            uuid: None,
+            requirements: vec![],
+            defenses: vec![],
+            assumptions: vec![],
+            profiles: vec![],
+            attributes: BTreeMap::new(),
+            span: None,
+            feasibility_cache: FeasibilityCache::default(),
+        }
+    }
+
+    /// Positions of the children forming the easiest combination and its
+    /// assessment.
+    fn easiest_combination(&self) -> Result<(Vec<usize>, FeasibilityAssessment), TreeError> {
+        let children = self.children.borrow();
+        cache_feasibility(&children);
+        find_easiest_combination(
+            &self.description,
+            self.threshold,
+            children.iter().map(|c| c.feasibility()).collect(),
+        )
+    }
+}
+
+impl FeasibleStep for VotingNode {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn kind(&self) -> NodeKind {
+        NodeKind::Voting
+    }
+
+    fn threshold(&self) -> Option<usize> {
+        Some(self.threshold)
+    }
+
+    fn feasibility(&self) -> Result<FeasibilityAssessment, TreeError> {
+        self.feasibility_cache
+            .get_or_compute(|| self.easiest_combination().map(|(_, a)| a))
+    }
+
+    fn feasibility_is_cached(&self) -> bool {
+        self.feasibility_cache.is_cached()
+    }
+
+    fn clear_feasibility_cache(&self) -> bool {
+        self.feasibility_cache.invalidate()
+    }
+
+    fn feasibility_range(
+        &self,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        self.range_from_children(children_ranges(&self.children.borrow()))
+    }
+
+    fn range_from_children(
+        &self,
+        ranges: Vec<Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError>>,
+    ) -> Result<(FeasibilityAssessment, FeasibilityAssessment), TreeError> {
+        // like AND and OR, the combination only grows with the children's values
+        let (_, easiest) = find_easiest_combination(
+            &self.description,
+            self.threshold,
+            ranges.iter().map(|r| r.clone().map(|(e, _)| e)).collect(),
+        )?;
+        let (_, hardest) = find_easiest_combination(
+            &self.description,
+            self.threshold,
+            ranges.into_iter().map(|r| r.map(|(_, h)| h)).collect(),
+        )?;
+
+        Ok((easiest, hardest))
+    }
+
+    fn title(&self) -> &str {
+        &self.description
+    }
+
+    fn add_child(&self, child: &Rc<dyn FeasibleStep>) {
+        self.children.borrow_mut().push(child.clone());
+        self.invalidate_feasibility();
+    }
+
+    fn get_parent(&self) -> Option<Rc<dyn FeasibleStep>> {
+        self.parent.clone()
+    }
+
+    fn render(&self) -> String {
+        render(
+            self,
+            &format!(
+                " shape=hexagon xlabel=\"{} of {}\"",
+                self.threshold,
+                self.children.borrow().len()
+            ),
+        )
+    }
+
+    fn detection(&self) -> Option<f64> {
+        // every step of the easiest combination has to go unnoticed
+        let (positions, _) = self.easiest_combination().ok()?;
+        let children = self.children.borrow();
+        let detections: Vec<Option<f64>> =
+            positions.iter().map(|i| children[*i].detection()).collect();
+        if detections.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let undetected: f64 = detections.iter().map(|d| 1.0 - d.unwrap_or(0.0)).product();
+        Some(1.0 - undetected)
+    }
+
+    fn get_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
+        self.children.borrow().clone()
+    }
+
+    fn take_children(&self) -> Vec<Rc<dyn FeasibleStep>> {
+        self.clear_feasibility_cache();
+        self.children.take()
+    }
+
+    fn review(&self) -> &Review {
+        &self.review
+    }
+
+    fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    fn number(&self) -> Option<u32> {
+        self.number
+    }
+
+    fn uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    fn requirements(&self) -> &[String] {
+        &self.requirements
+    }
+
+    fn defenses(&self) -> &[Defense] {
+        &self.defenses
+    }
+
+    fn assumptions(&self) -> &[Assumption] {
+        &self.assumptions
+    }
+
+    fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
+    fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    fn span(&self) -> Option<&SourceSpan> {
+        self.span.as_ref()
+    }
+}
+
+/// Tries every combination of `threshold` children with an assessment and
+/// returns the one with the lowest sum, on ties the first one. The steps of a
+/// combination are combined like the children of an AND node.
+/// The number of ways to choose `k` of `n` children, None if it does not fit
+/// into a u64.
+pub fn combination_count(n: usize, k: usize) -> Option<u64> {
+    if k > n {
+        return Some(0);
+    }
+
+    let k = k.min(n - k) as u64;
+    let n = n as u64;
+    let mut count: u64 = 1;
+    for i in 0..k {
+        // exact, the product of i + 1 consecutive numbers is divisible by (i + 1)!
+        count = count.checked_mul(n - i)? / (i + 1);
+    }
+    Some(count)
+}
+
+/// Tries every combination of `threshold` children, so the number of
+/// combinations has to be limited, see `combination_count`.
+pub(crate) fn find_easiest_combination(
+    title: &str,
+    threshold: usize,
+    children: Vec<Result<FeasibilityAssessment, TreeError>>,
+) -> Result<(Vec<usize>, FeasibilityAssessment), TreeError> {
+    let mut first_error = None;
+    let mut assessed = vec![];
+    for (i, c) in children.into_iter().enumerate() {
+        match c {
+            Ok(a) => assessed.push((i, a)),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if threshold == 0 || assessed.len() < threshold {
+        return Err(first_error
+            .unwrap_or_else(|| TreeError::NotEnoughChildren(title.to_string(), threshold)));
+    }
+
+    let mut best: Option<(Vec<usize>, FeasibilityAssessment)> = None;
+    let mut combination: Vec<usize> = (0..threshold).collect();
+    loop {
+        let mut assessment = assessed[combination[0]].1.clone();
+        for i in &combination[1..] {
+            assessment = assessment.component_wise_max(&assessed[*i].1);
+        }
+        if best
+            .as_ref()
+            .is_none_or(|(_, b)| assessment.sum() < b.sum())
+        {
+            let positions = combination.iter().map(|i| assessed[*i].0).collect();
+            best = Some((positions, assessment));
+        }
+
+        // next combination in lexicographic order
+        let n = assessed.len();
+        let Some(k) = (0..threshold)
+            .rev()
+            .find(|k| combination[*k] < n - threshold + k)
+        else {
+            break;
+        };
+        combination[k] += 1;
+        for j in k + 1..threshold {
+            combination[j] = combination[j - 1] + 1;
+        }
+    }
+
+    Ok(best.expect("there is at least one combination"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{generate_id, tests::build_criteria, Leaf};
+
+    fn voting_node(threshold: usize, children: &[[u32; 2]]) -> Rc<dyn FeasibleStep> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let node: Rc<dyn FeasibleStep> =
+            Rc::new(VotingNode::new("Vote", threshold, None, generate_id));
+        for (i, values) in children.iter().enumerate() {
+            let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+                &format!("Step {}", i),
+                Some(node.clone()),
+                &definition,
+                values,
+                generate_id,
+            ));
+            node.add_child(&leaf);
+        }
+
+        node
+    }
+
+    #[test]
+    fn the_easiest_combination_of_k_children_is_taken() {
+        let node = voting_node(2, &[[1, 9], [5, 1], [2, 8], [4, 2]]);
+
+        // [5, 1] and [4, 2] give 5 + 2, better than any pair with a 9 or 8
+        assert_eq!(node.feasibility_value(), 7);
+        assert_eq!(voting_node(1, &[[1, 9], [5, 1]]).feasibility_value(), 6);
+        assert_eq!(voting_node(2, &[[1, 9], [5, 1]]).feasibility_value(), 14);
+    }
+
+    #[test]
+    fn too_few_children_are_reported() {
+        assert_eq!(
+            voting_node(3, &[[1, 1], [2, 2]]).feasibility().err(),
+            Some(TreeError::NotEnoughChildren("Vote".to_string(), 3))
+        );
+    }
+
+    #[test]
+    fn combinations_are_counted_without_overflow() {
+        assert_eq!(combination_count(5, 2), Some(10));
+        assert_eq!(combination_count(30, 10), Some(30_045_015));
+        assert_eq!(combination_count(3, 4), Some(0));
+        assert_eq!(combination_count(1000, 500), None);
+    }
+}
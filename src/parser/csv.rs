@@ -0,0 +1,434 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use crate::model::{
+    feasible_step::FeasibleStep, generate_id, or_node::OrNode, AndNode, FeasibilityAssessment,
+    FeasibilityCriteria, Leaf, Review,
+};
+
+use super::Diagnostic;
+
+/// Column holding the title of a node.
+pub const TITLE_COLUMN: &str = "title";
+/// Column holding the title of the parent node, empty for root nodes.
+pub const PARENT_COLUMN: &str = "parent";
+/// Column holding `and`, `or` or `leaf`. Empty cells become OR nodes if the
+/// row has children and leaves otherwise.
+pub const TYPE_COLUMN: &str = "type";
+
+#[derive(Clone, Copy, PartialEq)]
+enum RowType {
+    And,
+    Or,
+    Leaf,
+    Inferred,
+}
+
+struct Row {
+    line: u32,
+    title: String,
+    parent: String,
+    row_type: RowType,
+    values: Vec<Option<u32>>,
+}
+
+/// Builds trees from a CSV with one row per node. Parents are referenced by
+/// title, so titles must be unique. All other columns are matched to the
+/// criteria by id or name. Returns the trees in the order of their root rows.
+pub fn import_csv(
+    text: &str,
+    definition: &Rc<FeasibilityCriteria>,
+) -> Result<Vec<Rc<dyn FeasibleStep>>, Vec<Diagnostic>> {
+    let mut diagnostics = vec![];
+
+    let records = read_records(text).map_err(|d| vec![d])?;
+    let Some(((header_line, header), records)) = records.split_first() else {
+        return Err(vec![Diagnostic::new(1, "the file has no header row")]);
+    };
+
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let Some(title_column) = column(TITLE_COLUMN) else {
+        return Err(vec![Diagnostic::new(
+            *header_line,
+            &format!("missing column '{}'", TITLE_COLUMN),
+        )]);
+    };
+    let parent_column = column(PARENT_COLUMN);
+    let type_column = column(TYPE_COLUMN);
+
+    let mut criterion_columns = vec![];
+    for (index, name) in header.iter().enumerate() {
+        let name = name.trim();
+        if [Some(title_column), parent_column, type_column].contains(&Some(index))
+            || name.is_empty()
+        {
+            continue;
+        }
+        match definition.0.iter().position(|c| {
+            c.names()
+                .chain([c.name.as_str()])
+                .any(|n| n.eq_ignore_ascii_case(name))
+        }) {
+            Some(position) => criterion_columns.push((index, position)),
+            None => diagnostics.push(Diagnostic::new(
+                *header_line,
+                &format!("column '{}' is neither a criterion id nor a name", name),
+            )),
+        }
+    }
+
+    let cell = |record: &[String], index: Option<usize>| {
+        index
+            .and_then(|i| record.get(i))
+            .map(|c| c.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut rows: Vec<Row> = vec![];
+    for (line, record) in records {
+        if record.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+
+        let title = cell(record, Some(title_column));
+        if title.is_empty() {
+            diagnostics.push(Diagnostic::new(*line, "the title is empty"));
+            continue;
+        }
+        if rows.iter().any(|r| r.title == title) {
+            diagnostics.push(Diagnostic::new(
+                *line,
+                &format!(
+                    "duplicate title '{}', parents are referenced by their title",
+                    title
+                ),
+            ));
+            continue;
+        }
+
+        let type_cell = cell(record, type_column);
+        let row_type = match type_cell.to_lowercase().as_str() {
+            "" => RowType::Inferred,
+            "and" | "&" => RowType::And,
+            "or" | "|" => RowType::Or,
+            "leaf" => RowType::Leaf,
+            _ => {
+                diagnostics.push(Diagnostic::new(
+                    *line,
+                    &format!(
+                        "invalid type '{}': expected 'and', 'or' or 'leaf'",
+                        type_cell
+                    ),
+                ));
+                RowType::Inferred
+            }
+        };
+
+        let mut values = vec![None; definition.0.len()];
+        for (index, position) in &criterion_columns {
+            let value = cell(record, Some(*index));
+            if value.is_empty() {
+                continue;
+            }
+            match value.parse() {
+                Ok(v) => values[*position] = Some(v),
+                Err(_) => diagnostics.push(Diagnostic::new(
+                    *line,
+                    &format!(
+                        "invalid value '{}' for '{}': expected a non-negative integer",
+                        value, definition.0[*position].id
+                    ),
+                )),
+            }
+        }
+
+        rows.push(Row {
+            line: *line,
+            title,
+            parent: cell(record, parent_column),
+            row_type,
+            values,
+        });
+    }
+
+    let mut children: HashMap<&str, Vec<&Row>> = HashMap::new();
+    for row in &rows {
+        if row.parent.is_empty() {
+            continue;
+        }
+        if rows.iter().any(|r| r.title == row.parent) {
+            children.entry(row.parent.as_str()).or_default().push(row);
+        } else {
+            diagnostics.push(Diagnostic::new(
+                row.line,
+                &format!("unknown parent '{}'", row.parent),
+            ));
+        }
+    }
+
+    let mut visited = vec![];
+    let roots: Vec<Rc<dyn FeasibleStep>> = rows
+        .iter()
+        .filter(|r| r.parent.is_empty())
+        .map(|r| {
+            build_node(
+                r,
+                None,
+                &children,
+                definition,
+                &mut visited,
+                &mut diagnostics,
+            )
+        })
+        .collect();
+
+    // rows whose parents form a cycle are never reached from a root
+    for row in &rows {
+        if !row.parent.is_empty()
+            && !visited.contains(&row.line)
+            && !diagnostics.iter().any(|d| d.line == row.line)
+        {
+            diagnostics.push(Diagnostic::new(
+                row.line,
+                &format!("'{}' is not connected to a root node", row.title),
+            ));
+        }
+    }
+
+    if roots.is_empty() && diagnostics.is_empty() {
+        diagnostics.push(Diagnostic::new(*header_line, "the file contains no nodes"));
+    }
+
+    if diagnostics.is_empty() {
+        Ok(roots)
+    } else {
+        diagnostics.sort_by_key(|d| d.line);
+        Err(diagnostics)
+    }
+}
+
+fn build_node(
+    row: &Row,
+    parent: Option<Rc<dyn FeasibleStep>>,
+    children: &HashMap<&str, Vec<&Row>>,
+    definition: &Rc<FeasibilityCriteria>,
+    visited: &mut Vec<u32>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Rc<dyn FeasibleStep> {
+    visited.push(row.line);
+    let child_rows = children
+        .get(row.title.as_str())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    let assessed = row.values.iter().any(Option::is_some);
+
+    let row_type = match row.row_type {
+        RowType::Inferred if child_rows.is_empty() => RowType::Leaf,
+        RowType::Inferred => RowType::Or,
+        t => t,
+    };
+
+    let node: Rc<dyn FeasibleStep> = match row_type {
+        RowType::And => Rc::new(AndNode::new(&row.title, parent, generate_id)),
+        RowType::Or | RowType::Inferred => Rc::new(OrNode::new(&row.title, parent, generate_id)),
+        RowType::Leaf => {
+            if !child_rows.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    row.line,
+                    &format!("the leaf '{}' cannot have children", row.title),
+                ));
+            }
+            return Rc::new(Leaf {
+                id: generate_id(),
+                description: row.title.clone(),
+                parent,
+                criteria: FeasibilityAssessment::from_values(definition, &row.values),
+                hardest: None,
+                assessed: None,
+                review: Review::default(),
+                note: None,
+                number: None,
+                uuid: None,
+                techniques: vec![],
+                requirements: vec![],
+                detection: None,
+                defenses: vec![],
+                assumptions: vec![],
+                profiles: vec![],
+                attributes: BTreeMap::new(),
+                span: None,
+            });
+        }
+    };
+
+    if assessed {
+        diagnostics.push(Diagnostic::new(
+            row.line,
+            &format!("'{}' has children, only leaves can be assessed", row.title),
+        ));
+    }
+
+    for child_row in child_rows {
+        if visited.contains(&child_row.line) {
+            continue;
+        }
+        let child = build_node(
+            child_row,
+            Some(node.clone()),
+            children,
+            definition,
+            visited,
+            diagnostics,
+        );
+        node.add_child(&child);
+    }
+
+    node
+}
+
+/// Splits `text` into records with the line each starts on. Fields may be
+/// quoted and contain line breaks. The delimiter is ',' unless the header
+/// only contains ';', as spreadsheets write it in some locales.
+fn read_records(text: &str) -> Result<Vec<(u32, Vec<String>)>, Diagnostic> {
+    let header = text.lines().next().unwrap_or_default();
+    let delimiter = if !header.contains(',') && header.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            '\n' if in_quotes => {
+                line += 1;
+                field.push('\n');
+            }
+            '\r' if !in_quotes => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut record)));
+                line += 1;
+                record_line = line;
+            }
+            c if c == delimiter && !in_quotes => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(Diagnostic::new(record_line, "unterminated quoted field"));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{model::tests::build_criteria, render::render_to_att_string};
+
+    use super::*;
+
+    #[test]
+    fn rows_become_trees_referencing_parents_by_title() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let text = "title,parent,type,Kn,Eq\n\
+                    Enter house,,and,,\n\
+                    Get in,Enter house,,,\n\
+                    Pick lock,Get in,,5,3\n\
+                    \"Break window, quietly\",Get in,,1,\n\
+                    Avoid neighbours,Enter house,leaf,2,2\n\
+                    Steal car,,,4,4\n";
+
+        let trees = import_csv(text, &definition).unwrap();
+
+        assert_eq!(
+            render_to_att_string(&trees[0]),
+            "Enter house;&\n    Get in;|\n        Pick lock; Kn=5, Eq=3\n        Break window, quietly; Kn=1\n    Avoid neighbours; Kn=2, Eq=2"
+        );
+        assert_eq!(render_to_att_string(&trees[1]), "Steal car; Kn=4, Eq=4");
+    }
+
+    #[test]
+    fn problems_are_reported_with_their_line() {
+        let definition = build_criteria(&["Kn"]);
+        let text = "Title;Parent;Type;kn;Time\r\n\
+                    Root;;or;;\r\n\
+                    Step;Root;leaf;x;\r\n\
+                    Step;Root;;1;\r\n\
+                    Other;Nowhere;xor;1;\r\n";
+
+        let messages: Vec<String> = import_csv(text, &definition)
+            .err()
+            .unwrap()
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "line 1: column 'Time' is neither a criterion id nor a name",
+                "line 3: invalid value 'x' for 'Kn': expected a non-negative integer",
+                "line 4: duplicate title 'Step', parents are referenced by their title",
+                "line 5: invalid type 'xor': expected 'and', 'or' or 'leaf'",
+                "line 5: unknown parent 'Nowhere'",
+            ]
+        );
+    }
+
+    #[test]
+    fn cycles_are_not_connected_to_a_root() {
+        let definition = build_criteria(&["Kn"]);
+        let text = "title,parent\nRoot,\nA,B\nB,A\n";
+
+        let diagnostics = import_csv(text, &definition).err().unwrap();
+
+        assert_eq!(
+            diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn quoted_fields_may_span_lines() {
+        let records = read_records("a,\"b\nc\",\"d \"\"e\"\"\"\nf,g").unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                (
+                    1,
+                    vec!["a".to_string(), "b\nc".to_string(), "d \"e\"".to_string()]
+                ),
+                (3, vec!["f".to_string(), "g".to_string()]),
+            ]
+        );
+    }
+}
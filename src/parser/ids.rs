@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use super::{
+    tokenizer::{tokenize, NodeType},
+    Diagnostic, UUID_KEY,
+};
+
+/// Adds a `uuid` attribute with an id from `generate` to every node of a
+/// .att file that has none, and returns the text and the number of added
+/// ids. Defenses, assumptions, aliases and expressions get no id. Everything
+/// else, including line endings and ids used twice, is kept as it is; the
+/// parser reports the latter.
+pub fn assign_uuids(
+    text: &str,
+    mut generate: impl FnMut() -> String,
+) -> Result<(String, usize), Diagnostic> {
+    let mut diagnostics = vec![];
+    let lines = tokenize(text, &mut diagnostics);
+    if let Some(d) = diagnostics.into_iter().next() {
+        return Err(d);
+    }
+
+    // separator in front of the new attribute by line number
+    let mut missing: BTreeMap<u32, &str> = BTreeMap::new();
+    for line in &lines {
+        let attributes = match &line.node_type {
+            NodeType::And | NodeType::Or | NodeType::Voting(..) => &line.attributes,
+            NodeType::Leaf(assessments) => assessments,
+            NodeType::Defense(_)
+            | NodeType::Assumption
+            | NodeType::Alias(_)
+            | NodeType::Expression(_) => continue,
+        };
+        if !attributes.iter().any(|a| a.criterion == UUID_KEY) {
+            let separator = if attributes.is_empty() { " " } else { ", " };
+            missing.insert(line.number, separator);
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    for (index, line) in split_lines(text).enumerate() {
+        let content = line.trim_end_matches(['\r', '\n']);
+        match missing.get(&(index as u32 + 1)) {
+            Some(separator) => {
+                result.push_str(content.trim_end());
+                result.push_str(&format!("{}{}={}", separator, UUID_KEY, generate()));
+                result.push_str(&line[content.len()..]);
+            }
+            None => result.push_str(line),
+        }
+    }
+
+    Ok((result, missing.len()))
+}
+
+/// The lines with their endings, which may be `\n`, `\r\n` or `\r` as in
+/// `tokenize`.
+fn split_lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = match rest.find(['\r', '\n']) {
+            Some(i) if rest[i..].starts_with("\r\n") => i + 2,
+            Some(i) => i + 1,
+            None => rest.len(),
+        };
+        let (line, remainder) = rest.split_at(end);
+        rest = remainder;
+        Some(line)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter() -> impl FnMut() -> String {
+        let mut next = 0;
+        move || {
+            next += 1;
+            format!("id{}", next)
+        }
+    }
+
+    #[test]
+    fn nodes_without_a_uuid_get_one() {
+        let text = "Root;&\r\n    Get in;| owner=Bob\r\n        > a note\r\n        Pick lock; Kn=1\r\n        Lock; ! Kn=2\r\n        Break in;  \r\n    &creds Get key; Kn=1, uuid=0a\r\n    *creds";
+
+        let (assigned, count) = assign_uuids(text, counter()).unwrap();
+
+        assert_eq!(count, 4);
+        assert_eq!(
+            assigned,
+            "Root;& uuid=id1\r\n    Get in;| owner=Bob, uuid=id2\r\n        > a note\r\n        Pick lock; Kn=1, uuid=id3\r\n        Lock; ! Kn=2\r\n        Break in; uuid=id4\r\n    &creds Get key; Kn=1, uuid=0a\r\n    *creds"
+        );
+    }
+
+    #[test]
+    fn files_with_syntax_errors_are_left_alone() {
+        let error = assign_uuids("Root;&\n    Pick lock", counter()).unwrap_err();
+
+        assert_eq!(error.line, 2);
+    }
+}
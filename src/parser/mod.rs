@@ -1,247 +1,1236 @@
-use std::{collections::HashMap, io::BufRead, rc::Rc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    io::BufRead,
+    path::Path,
+    rc::Rc,
+};
 
 use crate::model::*;
 
-use feasible_step::FeasibleStep;
+use chrono::NaiveDate;
+use feasible_step::{FeasibleStep, NodeKind, NAMESPACE_SEPARATOR};
+use merge::copy_alias;
 use or_node::OrNode;
 use thiserror::Error;
+use tokenizer::{Assessment, Expression, Line, NodeType};
+use voting_node::{combination_count, VotingNode};
+
+mod csv;
+mod ids;
+mod rename;
+mod tokenizer;
+
+pub use csv::{import_csv, PARENT_COLUMN, TITLE_COLUMN, TYPE_COLUMN};
+pub use ids::assign_uuids;
+pub use rename::{check_title, rename_title, RenameError};
+
+pub use tokenizer::{quote_title, NOTE_MARKER};
+
+/// Leaf attribute holding the date of the last review of the assessment.
+pub const ASSESSED_KEY: &str = "assessed";
+/// Leaf attribute holding the probability that the step is detected.
+pub const DETECTION_KEY: &str = "detection";
+/// Leaf attribute referencing a MITRE ATT&CK technique, may be repeated.
+pub const ATTACK_KEY: &str = "attack";
+/// Attribute referencing a requirement from requirements.json, may be
+/// repeated and set on any node.
+pub const REQUIREMENT_KEY: &str = "requirement";
+/// Attribute limiting a subtree to a deployment profile, may be repeated and
+/// set on any node.
+pub const PROFILE_KEY: &str = "profile";
+/// Prefix of custom attributes, which are stored without interpretation,
+/// e.g. `x-ticket=SEC-12`.
+pub const CUSTOM_ATTRIBUTE_PREFIX: &str = "x-";
+/// Attribute fixing the position of a node in its hierarchical number, so
+/// that inserting a sibling above does not renumber it.
+pub const NUMBER_KEY: &str = "number";
+/// Attribute holding a persistent id of the node, e.g. for external systems.
+pub const UUID_KEY: &str = "uuid";
+/// Root node attribute naming the person responsible for the tree.
+pub const OWNER_KEY: &str = "owner";
+/// Root node attribute holding the review status of the tree.
+pub const STATUS_KEY: &str = "status";
+/// Root node attribute holding the version of the tree, e.g. `1.2`.
+pub const VERSION_KEY: &str = "version";
+/// Root node attribute holding the date of the version, e.g. `2024-05-01`.
+pub const DATE_KEY: &str = "date";
+/// Root node attribute holding the impact level of the threat, e.g. `Severe`.
+pub const IMPACT_KEY: &str = "impact";
+/// Root node attribute holding the namespace of the titles of the tree, e.g.
+/// `Door`.
+pub const NAMESPACE_KEY: &str = "namespace";
+/// Levels a tree may have unless `ParserOptions::max_depth` says otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+/// Lines of nodes, defenses and assumptions a file may have unless
+/// `ParserOptions::max_nodes` says otherwise.
+pub const DEFAULT_MAX_NODES: usize = 100_000;
+/// Combinations of children a voting node may have, each of them is compared
+/// to find the easiest.
+pub const MAX_VOTING_COMBINATIONS: u64 = 100_000;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum TreeFileError {
-    #[error("File read error")]
-    FileReadError,
-    #[error("Syntax error")]
+    #[error("File read error: {0}")]
+    FileReadError(String),
+    #[error("Syntax error in line {0}")]
     SyntaxError(u32),
+    #[error("No attack tree found")]
+    EmptyTree,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: u32,
+    pub message: String,
+    /// The model error behind the diagnostic, if any.
+    pub error: Option<TreeError>,
+}
+
+impl Diagnostic {
+    pub fn new(line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            line,
+            message: message.to_string(),
+            error: None,
+        }
+    }
+
+    pub fn from_error(line: u32, error: TreeError) -> Diagnostic {
+        Diagnostic {
+            line,
+            message: error.to_string(),
+            error: Some(error),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
 }
 
-enum ParserState {
-    DeterminingIndentationLevel,
-    InTitle,
-    DeterminingNodeType,
-    InAssessmentName,
-    InAssessmentValue,
-    SkipToLineEnd,
+/// Checks on top of the syntax that teams can opt into. The default is
+/// lenient and enables none of them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// Indentation must be a multiple of this number of spaces.
+    pub indentation_width: Option<u32>,
+    /// Every leaf must rate all criteria.
+    pub require_assessments: bool,
+    /// AND and OR nodes must have children.
+    pub forbid_empty_nodes: bool,
+    /// Match criteria ids and aliases in assessments regardless of case.
+    pub ignore_criteria_case: bool,
+    /// Most levels of a tree, `DEFAULT_MAX_DEPTH` if unset.
+    pub max_depth: Option<usize>,
+    /// Most nodes of a file, `DEFAULT_MAX_NODES` if unset.
+    pub max_nodes: Option<usize>,
+}
+
+impl ParserOptions {
+    pub fn strict() -> ParserOptions {
+        ParserOptions {
+            indentation_width: Some(4),
+            require_assessments: true,
+            forbid_empty_nodes: true,
+            ignore_criteria_case: false,
+            max_depth: None,
+            max_nodes: None,
+        }
+    }
 }
 
 pub struct AttackTreeParser {
-    state: ParserState,
-    title: String,
-    assessment_value: String,
-    assessment_title: String,
-    parsed_assessments: HashMap<String, u32>,
-    indentation_counter: u32,
-    previous_indentation: u32,
-    current_indentation: u32,
-    current_node: Option<Rc<dyn FeasibleStep>>,
-    last_added_node: Option<Rc<dyn FeasibleStep>>,
+    lines: Vec<Line>,
+    position: usize,
+    diagnostics: Vec<Diagnostic>,
+    options: ParserOptions,
+    /// Nodes named with `&name` so far, repeated by `*name`.
+    anchors: HashMap<String, Rc<dyn FeasibleStep>>,
+    /// The uuids read so far with the lines they are on.
+    uuids: HashMap<String, u32>,
+    /// The file named in the spans of the nodes.
+    file: Option<Rc<Path>>,
+}
+
+/// A node read by `parse_node`.
+enum Parsed {
+    /// A node without children to parse, with the index of its line.
+    Done(Rc<dyn FeasibleStep>, usize),
+    /// An inner node whose children follow.
+    Open(OpenNode),
+}
+
+/// An inner node whose children `parse_subtree` is reading.
+struct OpenNode {
+    node: Rc<dyn FeasibleStep>,
+    line_index: usize,
+    indentation: u32,
+    /// The indentation of the first child, expected of the others.
+    child_indentation: u32,
+    /// The line of the child being parsed.
+    child_line: u32,
+    /// The numbers of the children so far.
+    numbers: Vec<u32>,
+}
+
+impl Default for AttackTreeParser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AttackTreeParser {
     pub fn new() -> AttackTreeParser {
         AttackTreeParser {
-            state: ParserState::DeterminingIndentationLevel,
-            title: String::new(),
-            assessment_value: String::new(),
-            assessment_title: String::new(),
-            parsed_assessments: HashMap::new(),
-            indentation_counter: 0,
-            previous_indentation: 0,
-            current_indentation: 0,
-            current_node: None,
-            last_added_node: None,
+            lines: vec![],
+            position: 0,
+            diagnostics: vec![],
+            options: ParserOptions::default(),
+            anchors: HashMap::new(),
+            uuids: HashMap::new(),
+            file: None,
         }
     }
 
+    /// Names the file of the following calls to `parse` in the spans of the
+    /// nodes.
+    pub fn set_file(&mut self, file: &Path) {
+        self.file = Some(Rc::from(file));
+    }
+
+    /// All problems found by the last call to `parse`, in order of appearance.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     pub fn parse(
         &mut self,
         buf_read: &mut dyn BufRead,
         definition: &Rc<FeasibilityCriteria>,
     ) -> Result<Rc<dyn FeasibleStep>, TreeFileError> {
-        let mut text = String::new();
-        if buf_read.read_to_string(&mut text).is_err() {
-            return Err(TreeFileError::FileReadError);
+        self.parse_with_options(buf_read, definition, &ParserOptions::default())
+    }
+
+    pub fn parse_with_options(
+        &mut self,
+        buf_read: &mut dyn BufRead,
+        definition: &Rc<FeasibilityCriteria>,
+        options: &ParserOptions,
+    ) -> Result<Rc<dyn FeasibleStep>, TreeFileError> {
+        let text = read_text(buf_read)?;
+
+        self.diagnostics.clear();
+        self.lines = tokenizer::tokenize(&text, &mut self.diagnostics);
+        self.position = 0;
+        self.options = options.clone();
+        self.anchors.clear();
+        self.uuids.clear();
+
+        let max_nodes = self.options.max_nodes.unwrap_or(DEFAULT_MAX_NODES);
+        if let Some(line) = self.lines.get(max_nodes) {
+            let line_number = line.number;
+            self.diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!("the file has more than the limit of {} nodes", max_nodes),
+            ));
+            return Err(TreeFileError::SyntaxError(line_number));
         }
 
-        for c in text.chars() {
-            match self.state {
-                ParserState::InTitle => {
-                    if c == ';' {
-                        self.set_state(ParserState::DeterminingNodeType);
-                    } else {
-                        self.title.push(c);
-                    }
+        if let Some(width) = self.options.indentation_width.filter(|w| *w > 0) {
+            for line in &self.lines {
+                if line.indentation % width != 0 {
+                    self.diagnostics.push(Diagnostic::new(
+                        line.number,
+                        &format!(
+                            "indentation of {} spaces is not a multiple of {}",
+                            line.indentation, width
+                        ),
+                    ));
                 }
-                ParserState::DeterminingNodeType => {
-                    if c == '&' {
-                        self.add_node(Rc::new(AndNode::new(
-                            &self.title,
-                            self.current_node.clone(),
-                            generate_id,
-                        )));
-                        self.state = ParserState::SkipToLineEnd;
-                        self.set_state(ParserState::SkipToLineEnd);
-                    } else if c == '|' {
-                        self.add_node(Rc::new(OrNode::new(
-                            &self.title,
-                            self.current_node.clone(),
-                            generate_id,
-                        )));
-                        self.set_state(ParserState::SkipToLineEnd);
-                    } else if c != ' ' {
-                        self.set_state(ParserState::InAssessmentName);
-                        self.assessment_title.push(c);
-                    }
+            }
+        }
+
+        let root = self.parse_subtree(None, definition);
+
+        // everything after the root's subtree would be a second root node
+        while let Some(line) = self.lines.get(self.position) {
+            self.diagnostics.push(Diagnostic::new(
+                line.number,
+                "a file must contain exactly one root node",
+            ));
+            self.parse_subtree(None, definition);
+        }
+
+        self.diagnostics.sort_by_key(|d| d.line);
+
+        if let Some(first) = self.diagnostics.first() {
+            return Err(TreeFileError::SyntaxError(first.line));
+        }
+
+        root.ok_or(TreeFileError::EmptyTree)
+    }
+
+    /// Parses the node at `position` and everything indented below it. The
+    /// levels are kept on an explicit stack, so the depth of the tree is not
+    /// limited by the call stack.
+    fn parse_subtree(
+        &mut self,
+        parent: Option<Rc<dyn FeasibleStep>>,
+        definition: &Rc<FeasibilityCriteria>,
+    ) -> Option<Rc<dyn FeasibleStep>> {
+        let mut open: Vec<OpenNode> = vec![];
+        let mut parsed = self.parse_within_depth_limit(parent, 0, definition);
+
+        loop {
+            let mut finished = match parsed {
+                Some(Parsed::Open(node)) => {
+                    open.push(node);
+                    None
                 }
-                ParserState::SkipToLineEnd => {
-                    if c == '\n' {
-                        self.set_state(ParserState::DeterminingIndentationLevel);
-                    }
+                Some(Parsed::Done(node, line_index)) => {
+                    self.add_anchor(line_index, &node);
+                    Some(node)
                 }
-                ParserState::DeterminingIndentationLevel => {
-                    if c == ' ' {
-                        self.indentation_counter += 1;
-                    } else if c == '\n' {
-                        self.set_state(ParserState::DeterminingIndentationLevel);
-                    } else {
-                        self.previous_indentation = self.current_indentation;
-                        self.current_indentation = self.indentation_counter;
-                        self.update_current_node();
+                None => None,
+            };
 
-                        self.set_state(ParserState::InTitle);
-                        self.title.push(c);
+            // adds finished nodes to their parents until one has another child
+            parsed = loop {
+                let Some(top) = open.last_mut() else {
+                    return finished;
+                };
+                if let Some(child) = finished.take() {
+                    if let Some(number) = child.number() {
+                        if top.numbers.contains(&number) {
+                            self.diagnostics.push(Diagnostic::new(
+                                top.child_line,
+                                &format!("number {} is already used by a sibling", number),
+                            ));
+                        }
+                        top.numbers.push(number);
                     }
+                    top.node.add_child(&child);
                 }
-                ParserState::InAssessmentName => {
-                    if c == '=' {
-                        self.set_state(ParserState::InAssessmentValue);
-                    } else {
-                        self.assessment_title.push(c);
+
+                match self.lines.get(self.position) {
+                    Some(next) if next.indentation > top.indentation => {
+                        if next.indentation != top.child_indentation {
+                            self.diagnostics.push(Diagnostic::new(
+                                next.number,
+                                &format!(
+                                    "inconsistent indentation: expected {} spaces but found {}",
+                                    top.child_indentation, next.indentation
+                                ),
+                            ));
+                        }
+                        top.child_line = next.number;
+                        let parent = top.node.clone();
+                        let depth = open.len();
+                        break self.parse_within_depth_limit(Some(parent), depth, definition);
                     }
-                }
-                ParserState::InAssessmentValue => {
-                    if c == ',' {
-                        self.commit_assessment()?;
-                        self.set_state(ParserState::InAssessmentName);
-                    } else if c == '\n' {
-                        self.commit_assessment()?;
-                        self.add_node(self.build_leaf(&definition));
-                        self.set_state(ParserState::DeterminingIndentationLevel);
-                    } else {
-                        self.assessment_value.push(c);
+                    _ => {
+                        let Some(top) = open.pop() else {
+                            unreachable!("the stack was checked above")
+                        };
+                        self.check_vote_count(top.line_index, &top.node);
+                        self.add_anchor(top.line_index, &top.node);
+                        finished = Some(top.node);
                     }
                 }
-            }
+            };
         }
+    }
 
-        // handle leafs at end of file
-        if let ParserState::InAssessmentValue = self.state {
-            self.commit_assessment()?;
-            self.add_node(self.build_leaf(&definition));
+    /// Parses the node at `position` at the level `depth` below the node
+    /// `parse_subtree` started with, or skips it beyond the depth limit.
+    fn parse_within_depth_limit(
+        &mut self,
+        parent: Option<Rc<dyn FeasibleStep>>,
+        depth: usize,
+        definition: &Rc<FeasibilityCriteria>,
+    ) -> Option<Parsed> {
+        let max_depth = self.options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        if depth >= max_depth {
+            let line = &self.lines[self.position];
+            self.diagnostics.push(Diagnostic::new(
+                line.number,
+                &format!("the tree is deeper than the limit of {} levels", max_depth),
+            ));
+            let indentation = line.indentation;
+            self.position += 1;
+            while self
+                .lines
+                .get(self.position)
+                .is_some_and(|l| l.indentation > indentation)
+            {
+                self.position += 1;
+            }
+            return None;
         }
 
-        // set self.current_node to the tree's root node
-        // ToDo: just safe the root node in an extra variable
-        loop {
-            if let Some(n) = &self.current_node {
-                if let Some(parent) = n.get_parent() {
-                    self.current_node.replace(parent.clone());
-                } else {
-                    break;
-                }
+        self.parse_node(parent, definition)
+    }
+
+    /// Remembers a node named with `&name` once its subtree is complete, so
+    /// an alias cannot repeat the node it is part of.
+    fn add_anchor(&mut self, line_index: usize, node: &Rc<dyn FeasibleStep>) {
+        let line = &self.lines[line_index];
+        if let Some(anchor) = &line.anchor {
+            if self.anchors.contains_key(anchor) {
+                self.diagnostics.push(Diagnostic::new(
+                    line.number,
+                    &format!("anchor '{}' is defined twice", anchor),
+                ));
             } else {
-                break;
+                self.anchors.insert(anchor.clone(), node.clone());
             }
         }
-
-        Ok(self.current_node.as_ref().unwrap().clone())
     }
 
-    fn set_state(&mut self, state: ParserState) {
-        self.state = state;
+    /// Parses the node at `position` with its defenses and assumptions. The
+    /// children of an inner node are left to `parse_subtree`.
+    fn parse_node(
+        &mut self,
+        parent: Option<Rc<dyn FeasibleStep>>,
+        definition: &Rc<FeasibilityCriteria>,
+    ) -> Option<Parsed> {
+        let line_index = self.position;
+        let line = self.lines.get(line_index)?;
+        self.position += 1;
+
+        if let NodeType::Alias(name) = &line.node_type {
+            let indentation = line.indentation;
+            let copy = match self.anchors.get(name) {
+                Some(node) => Some(copy_alias(node, parent)),
+                None => {
+                    self.diagnostics.push(Diagnostic::new(
+                        line.number,
+                        &format!("unknown anchor '{}', define it above the alias", name),
+                    ));
+                    None
+                }
+            };
+            self.skip_children(indentation, "an alias cannot have children or defenses");
+            return copy.map(|c| Parsed::Done(c, line_index));
+        }
+
+        if matches!(line.node_type, NodeType::Defense(_)) {
+            self.diagnostics.push(Diagnostic::new(
+                line.number,
+                "a defense must directly follow the node it counters",
+            ));
+            let indentation = line.indentation;
+            self.skip_children(indentation, "a defense cannot have children");
+            return None;
+        }
+
+        if matches!(line.node_type, NodeType::Assumption) {
+            self.diagnostics.push(Diagnostic::new(
+                line.number,
+                "an assumption must directly follow the node it belongs to",
+            ));
+            let indentation = line.indentation;
+            self.skip_children(indentation, "an assumption cannot have children");
+            return None;
+        }
+
+        let (defenses, assumptions) = self.parse_defenses(line_index, definition);
+        let line = &self.lines[line_index];
+
+        let indentation = line.indentation;
+        let is_root = parent.is_none();
+        let mut review = Review::default();
+        let mut requirements = vec![];
+        let mut profiles = vec![];
+        let mut attributes = BTreeMap::new();
+        let mut number = None;
+        let mut uuid = None;
+        for a in &line.attributes {
+            if read_custom_attribute(a, line.number, &mut attributes, &mut self.diagnostics) {
+                continue;
+            }
+            if a.criterion == NUMBER_KEY {
+                number = read_number(a, line.number, &mut self.diagnostics);
+            } else if a.criterion == UUID_KEY {
+                uuid = read_uuid(a, line.number, &mut self.uuids, &mut self.diagnostics);
+            } else if a.criterion == REQUIREMENT_KEY {
+                requirements.push(a.value.clone());
+            } else if a.criterion == PROFILE_KEY {
+                profiles.push(a.value.clone());
+            } else if !read_review_attribute(
+                a,
+                line.number,
+                is_root,
+                &mut review,
+                &mut self.diagnostics,
+            ) {
+                self.diagnostics.push(Diagnostic::new(
+                    line.number,
+                    &format!("unknown attribute '{}'", a.criterion),
+                ));
+            }
+        }
 
-        match self.state {
-            ParserState::DeterminingIndentationLevel => {
-                self.indentation_counter = 0;
+        let note = build_note(line);
+        let node: Rc<dyn FeasibleStep> = match &line.node_type {
+            NodeType::And | NodeType::Expression(Expression::And(_)) => Rc::new(AndNode {
+                review,
+                note,
+                number,
+                uuid,
+                requirements,
+                defenses,
+                assumptions,
+                profiles,
+                attributes,
+                span: Some(self.span(line_index)),
+                ..AndNode::new(&line.title, parent, generate_id)
+            }),
+            NodeType::Or | NodeType::Expression(_) => Rc::new(OrNode {
+                review,
+                note,
+                number,
+                uuid,
+                requirements,
+                defenses,
+                assumptions,
+                profiles,
+                attributes,
+                span: Some(self.span(line_index)),
+                ..OrNode::new(&line.title, parent, generate_id)
+            }),
+            NodeType::Voting(k, n) => {
+                if *k == 0 || k > n {
+                    self.diagnostics.push(Diagnostic::new(
+                        line.number,
+                        &format!("cannot require {} of {} children", k, n),
+                    ));
+                } else if combination_count(*n, *k).is_none_or(|c| c > MAX_VOTING_COMBINATIONS) {
+                    self.diagnostics.push(Diagnostic::new(
+                        line.number,
+                        &format!(
+                            "{} of {} children are more than the limit of {} combinations to compare",
+                            k, n, MAX_VOTING_COMBINATIONS
+                        ),
+                    ));
+                }
+                Rc::new(VotingNode {
+                    review,
+                    note,
+                    number,
+                    uuid,
+                    requirements,
+                    defenses,
+                    assumptions,
+                    profiles,
+                    attributes,
+                    span: Some(self.span(line_index)),
+                    ..VotingNode::new(&line.title, *k, parent, generate_id)
+                })
+            }
+            NodeType::Defense(_) | NodeType::Assumption | NodeType::Alias(_) => {
+                unreachable!("defense, assumption and alias lines are handled above")
             }
-            ParserState::InTitle => {
-                self.title.clear();
+            NodeType::Leaf(_) => {
+                let leaf = self.build_leaf(line_index, parent, defenses, assumptions, definition);
+                self.skip_children(
+                    indentation,
+                    "a leaf cannot have children, mark its parent with '&' or '|'",
+                );
+                return Some(Parsed::Done(leaf, line_index));
             }
-            ParserState::DeterminingNodeType => {}
-            ParserState::InAssessmentName => {
-                self.assessment_title.clear();
+        };
+
+        if let NodeType::Expression(Expression::And(steps) | Expression::Or(steps)) =
+            &line.node_type
+        {
+            let steps = steps.clone();
+            let line_number = line.number;
+            let span = self.span(line_index);
+            for step in &steps {
+                let child = build_step(step, &node, definition, &span);
+                node.add_child(&child);
             }
-            ParserState::InAssessmentValue => {
-                self.assessment_value.clear();
+            // the steps are unrated leaves
+            if self.options.require_assessments {
+                for step in traversal::preorder(&node) {
+                    if step.kind() != NodeKind::Leaf {
+                        continue;
+                    }
+                    for c in &definition.0 {
+                        self.diagnostics.push(Diagnostic::from_error(
+                            line_number,
+                            TreeError::MissingAssessment(c.id.clone()),
+                        ));
+                    }
+                }
             }
-            ParserState::SkipToLineEnd => {}
+            self.skip_children(
+                indentation,
+                "a node written as an expression cannot have children, expand it first",
+            );
+            return Some(Parsed::Done(node, line_index));
         }
+
+        let child_indentation = match self.lines.get(self.position) {
+            Some(next) if next.indentation > indentation => next.indentation,
+            _ => {
+                if self.options.forbid_empty_nodes {
+                    let line = &self.lines[self.position - 1];
+                    self.diagnostics.push(Diagnostic::from_error(
+                        line.number,
+                        TreeError::EmptyNode(line.title.clone()),
+                    ));
+                }
+                self.check_vote_count(line_index, &node);
+                return Some(Parsed::Done(node, line_index));
+            }
+        };
+
+        Some(Parsed::Open(OpenNode {
+            node,
+            line_index,
+            indentation,
+            child_indentation,
+            child_line: 0,
+            numbers: vec![],
+        }))
     }
 
-    fn update_current_node(&mut self) {
-        if self.current_node.is_none() {
-            return;
+    /// The lines of the node at `line_index`, up to the last line indented
+    /// below it.
+    fn span(&self, line_index: usize) -> SourceSpan {
+        let line = &self.lines[line_index];
+        let last_line = self.lines[line_index + 1..]
+            .iter()
+            .take_while(|l| l.indentation > line.indentation)
+            .last()
+            .unwrap_or(line)
+            .last_number;
+
+        SourceSpan {
+            file: self.file.clone(),
+            first_line: line.number,
+            last_line,
         }
+    }
 
-        if self.current_indentation > self.previous_indentation {
-            self.current_node
-                .replace(self.last_added_node.as_ref().unwrap().clone());
+    /// Compares the children of a voting node to the `n` of its `k/n`.
+    fn check_vote_count(&mut self, line_index: usize, node: &Rc<dyn FeasibleStep>) {
+        let line = &self.lines[line_index];
+        if let NodeType::Voting(_, n) = line.node_type {
+            let count = node.get_children().len();
+            if count != n {
+                self.diagnostics.push(Diagnostic::new(
+                    line.number,
+                    &format!("voting node expects {} children but has {}", n, count),
+                ));
+            }
         }
-        if self.current_indentation < self.previous_indentation {
-            self.current_node
-                .replace(self.current_node.as_ref().unwrap().get_parent().unwrap());
+    }
+
+    fn skip_children(&mut self, indentation: u32, message: &str) {
+        while let Some(next) = self.lines.get(self.position) {
+            if next.indentation <= indentation {
+                break;
+            }
+
+            self.diagnostics.push(Diagnostic::new(next.number, message));
+            self.position += 1;
         }
     }
 
-    fn add_node(&mut self, node: Rc<dyn FeasibleStep>) {
-        if self.current_node.is_none() {
-            self.current_node = Some(node.clone());
-        } else {
-            self.current_node.as_ref().unwrap().add_child(&node);
+    /// Reads the defense and assumption lines directly below the node at
+    /// `line_index`.
+    fn parse_defenses(
+        &mut self,
+        line_index: usize,
+        definition: &Rc<FeasibilityCriteria>,
+    ) -> (Vec<Defense>, Vec<Assumption>) {
+        let indentation = self.lines[line_index].indentation;
+        let mut defenses = vec![];
+        let mut assumptions = vec![];
+
+        while let Some(line) = self.lines.get(self.position) {
+            if line.indentation <= indentation {
+                break;
+            }
+            if matches!(line.node_type, NodeType::Assumption) {
+                assumptions.push(Assumption {
+                    id: generate_id(),
+                    description: line.title.clone(),
+                    note: build_note(line),
+                });
+
+                let assumption_indentation = line.indentation;
+                self.position += 1;
+                self.skip_children(assumption_indentation, "an assumption cannot have children");
+                continue;
+            }
+            let NodeType::Defense(attributes) = &line.node_type else {
+                break;
+            };
+
+            let mut values: Vec<Option<u32>> = vec![None; definition.0.len()];
+            for a in attributes {
+                let position = definition.lookup(&a.criterion, self.options.ignore_criteria_case);
+                let unit = criterion_unit(definition, &position);
+                if let Some(value) =
+                    read_criterion_value(a, unit, line.number, &mut self.diagnostics)
+                {
+                    match position {
+                        Ok(index) => {
+                            if values[index].replace(value).is_some() {
+                                self.diagnostics
+                                    .push(assessed_twice(&a.criterion, line.number));
+                            }
+                        }
+                        Err(e) => self
+                            .diagnostics
+                            .push(Diagnostic::from_error(line.number, e)),
+                    }
+                }
+            }
+
+            defenses.push(Defense {
+                id: generate_id(),
+                description: line.title.clone(),
+                effect: FeasibilityAssessment::from_values(definition, &values),
+                note: build_note(line),
+            });
+
+            let defense_indentation = line.indentation;
+            self.position += 1;
+            self.skip_children(defense_indentation, "a defense cannot have children");
         }
 
-        self.last_added_node.replace(node.clone());
+        (defenses, assumptions)
     }
 
-    fn build_leaf(&self, definition: &Rc<FeasibilityCriteria>) -> Rc<dyn FeasibleStep> {
-        let assessment_values: Vec<Option<u32>> = definition
-            .0
-            .iter()
-            .map(|c| &c.id)
-            .map(|n| self.parsed_assessments.get(n))
-            .map(|v| match v {
-                Some(v) => Some(*v),
-                None => None,
-            })
-            .collect();
+    fn build_leaf(
+        &mut self,
+        line_index: usize,
+        parent: Option<Rc<dyn FeasibleStep>>,
+        defenses: Vec<Defense>,
+        assumptions: Vec<Assumption>,
+        definition: &Rc<FeasibilityCriteria>,
+    ) -> Rc<dyn FeasibleStep> {
+        let line = &self.lines[line_index];
+        let mut assessment_values: Vec<Option<u32>> = vec![None; definition.0.len()];
+        let mut hardest_values = assessment_values.clone();
+        let mut assessed = None;
+        let mut techniques = vec![];
+        let mut requirements = vec![];
+        let mut profiles = vec![];
+        let mut detection = None;
+        let mut review = Review::default();
+        let mut attributes = BTreeMap::new();
+        let mut number = None;
+        let mut uuid = None;
+        let is_root = parent.is_none();
+
+        if let NodeType::Leaf(assessments) = &line.node_type {
+            for a in assessments {
+                if read_custom_attribute(a, line.number, &mut attributes, &mut self.diagnostics) {
+                    continue;
+                }
+                if read_review_attribute(
+                    a,
+                    line.number,
+                    is_root,
+                    &mut review,
+                    &mut self.diagnostics,
+                ) {
+                    continue;
+                }
+
+                if a.criterion == NUMBER_KEY {
+                    number = read_number(a, line.number, &mut self.diagnostics);
+                    continue;
+                }
+
+                if a.criterion == UUID_KEY {
+                    uuid = read_uuid(a, line.number, &mut self.uuids, &mut self.diagnostics);
+                    continue;
+                }
+
+                if a.criterion == ASSESSED_KEY {
+                    match NaiveDate::parse_from_str(&a.value, "%Y-%m-%d") {
+                        Ok(date) => assessed = Some(date),
+                        Err(_) => self.diagnostics.push(Diagnostic::new(
+                            line.number,
+                            &format!(
+                                "invalid date '{}' for '{}': expected YYYY-MM-DD",
+                                a.value, ASSESSED_KEY
+                            ),
+                        )),
+                    }
+                    continue;
+                }
+
+                if a.criterion == REQUIREMENT_KEY {
+                    requirements.push(a.value.clone());
+                    continue;
+                }
+
+                if a.criterion == PROFILE_KEY {
+                    profiles.push(a.value.clone());
+                    continue;
+                }
+
+                if a.criterion == DETECTION_KEY {
+                    match a.value.parse::<f64>() {
+                        Ok(p) if (0.0..=1.0).contains(&p) => detection = Some(p),
+                        _ => self.diagnostics.push(Diagnostic::new(
+                            line.number,
+                            &format!(
+                                "invalid value '{}' for '{}': expected a probability between 0 and 1",
+                                a.value, DETECTION_KEY
+                            ),
+                        )),
+                    }
+                    continue;
+                }
+
+                if a.criterion == ATTACK_KEY {
+                    if is_technique_id(&a.value) {
+                        techniques.push(a.value.clone());
+                    } else {
+                        self.diagnostics.push(Diagnostic::new(
+                            line.number,
+                            &format!(
+                                "invalid ATT&CK technique '{}': expected e.g. T1078 or T1078.001",
+                                a.value
+                            ),
+                        ));
+                    }
+                    continue;
+                }
+
+                let position = definition.lookup(&a.criterion, self.options.ignore_criteria_case);
+                let unit = criterion_unit(definition, &position);
+                let Some((low, high)) =
+                    read_criterion_range(a, unit, line.number, &mut self.diagnostics)
+                else {
+                    continue;
+                };
+
+                match position {
+                    Ok(index) => {
+                        let criterion = &definition.0[index];
+                        let (easiest, hardest) = if criterion.difficulty(Some(low))
+                            <= criterion.difficulty(Some(high))
+                        {
+                            (low, high)
+                        } else {
+                            (high, low)
+                        };
+                        if criterion
+                            .weight
+                            .checked_mul(criterion.difficulty(Some(hardest)))
+                            .is_none()
+                        {
+                            self.diagnostics.push(Diagnostic::new(
+                                line.number,
+                                &format!(
+                                    "value of '{}' is too large for its weight {}",
+                                    a.criterion, criterion.weight
+                                ),
+                            ));
+                            continue;
+                        }
+                        if assessment_values[index].replace(easiest).is_some() {
+                            self.diagnostics
+                                .push(assessed_twice(&a.criterion, line.number));
+                        }
+                        hardest_values[index] = Some(hardest);
+                    }
+                    Err(e) => self
+                        .diagnostics
+                        .push(Diagnostic::from_error(line.number, e)),
+                }
+            }
+        }
+
+        if self.options.require_assessments {
+            for (c, v) in definition.0.iter().zip(&assessment_values) {
+                if v.is_none() {
+                    self.diagnostics.push(Diagnostic::from_error(
+                        line.number,
+                        TreeError::MissingAssessment(c.id.clone()),
+                    ));
+                }
+            }
+        }
 
         Rc::new(Leaf {
             id: generate_id(),
-            description: self.title.clone(),
-            parent: self.current_node.clone(),
-            criteria: FeasibilityAssessment::new(&definition, &assessment_values).unwrap(),
+            description: line.title.clone(),
+            parent,
+            criteria: FeasibilityAssessment::from_values(definition, &assessment_values),
+            hardest: (hardest_values != assessment_values)
+                .then(|| FeasibilityAssessment::from_values(definition, &hardest_values)),
+            assessed,
+            review,
+            note: build_note(line),
+            number,
+            uuid,
+            techniques,
+            requirements,
+            detection,
+            defenses,
+            assumptions,
+            profiles,
+            attributes,
+            span: Some(self.span(line_index)),
         })
     }
+}
 
-    fn commit_assessment(&mut self) -> Result<(), TreeFileError> {
-        let value: u32 = match self.assessment_value.parse() {
-            Ok(v) => v,
-            Err(_) => {
-                return Err(TreeFileError::SyntaxError(1));
-            }
-        };
+/// The node for a step of an inline expression, unrated if it is a leaf.
+fn build_step(
+    step: &Expression,
+    parent: &Rc<dyn FeasibleStep>,
+    definition: &Rc<FeasibilityCriteria>,
+    span: &SourceSpan,
+) -> Rc<dyn FeasibleStep> {
+    let parent = Some(parent.clone());
+    let (node, steps): (Rc<dyn FeasibleStep>, _) = match step {
+        Expression::Step(title) => {
+            return Rc::new(Leaf {
+                id: generate_id(),
+                description: title.clone(),
+                parent,
+                criteria: FeasibilityAssessment::from_values(
+                    definition,
+                    &vec![None; definition.0.len()],
+                ),
+                hardest: None,
+                assessed: None,
+                review: Review::default(),
+                note: None,
+                number: None,
+                uuid: None,
+                techniques: vec![],
+                requirements: vec![],
+                detection: None,
+                defenses: vec![],
+                assumptions: vec![],
+                profiles: vec![],
+                attributes: BTreeMap::new(),
+                span: Some(span.clone()),
+            })
+        }
+        Expression::And(steps) => (
+            Rc::new(AndNode {
+                span: Some(span.clone()),
+                ..AndNode::new(&step.title(), parent, generate_id)
+            }),
+            steps,
+        ),
+        Expression::Or(steps) => (
+            Rc::new(OrNode {
+                span: Some(span.clone()),
+                ..OrNode::new(&step.title(), parent, generate_id)
+            }),
+            steps,
+        ),
+    };
+
+    for step in steps {
+        let child = build_step(step, &node, definition, span);
+        node.add_child(&child);
+    }
+
+    node
+}
+
+/// Reads the whole input, which must be UTF-8 text. Binary files, e.g. an
+/// export that ended up with the .att extension, are told apart by invalid
+/// UTF-8 or NUL bytes.
+fn read_text(buf_read: &mut dyn BufRead) -> Result<String, TreeFileError> {
+    let mut bytes = vec![];
+    buf_read
+        .read_to_end(&mut bytes)
+        .map_err(|e| TreeFileError::FileReadError(e.to_string()))?;
+
+    let line_of =
+        |bytes: &[u8], offset: usize| bytes[..offset].iter().filter(|b| **b == b'\n').count() + 1;
+
+    let text = String::from_utf8(bytes).map_err(|e| {
+        TreeFileError::FileReadError(format!(
+            "invalid UTF-8 in line {}, is this a binary file?",
+            line_of(e.as_bytes(), e.utf8_error().valid_up_to())
+        ))
+    })?;
+
+    match text.find('\0') {
+        Some(offset) => Err(TreeFileError::FileReadError(format!(
+            "NUL byte in line {}, is this a binary file?",
+            line_of(text.as_bytes(), offset)
+        ))),
+        None => Ok(text),
+    }
+}
+
+/// Checks for the `T1234` or `T1234.567` format of ATT&CK technique ids.
+fn is_technique_id(id: &str) -> bool {
+    let all_digits = |s: &str, n: usize| s.len() == n && s.bytes().all(|b| b.is_ascii_digit());
+
+    match id.strip_prefix('T').map(|rest| rest.split_once('.')) {
+        Some(Some((technique, sub_technique))) => {
+            all_digits(technique, 4) && all_digits(sub_technique, 3)
+        }
+        Some(None) => all_digits(&id[1..], 4),
+        None => false,
+    }
+}
+
+fn read_criterion_value(
+    attribute: &Assessment,
+    unit: Option<&str>,
+    line_number: u32,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<u32> {
+    match without_unit(&attribute.value, unit).parse() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!(
+                    "invalid value '{}' for '{}': expected a non-negative integer{}",
+                    attribute.value,
+                    attribute.criterion,
+                    unit_hint(unit)
+                ),
+            ));
+            None
+        }
+    }
+}
+
+fn criterion_unit<'a>(
+    definition: &'a FeasibilityCriteria,
+    position: &Result<usize, TreeError>,
+) -> Option<&'a str> {
+    definition.0[*position.as_ref().ok()?].unit.as_deref()
+}
+
+/// The value without the unit of its criterion, which is optional.
+fn without_unit<'a>(value: &'a str, unit: Option<&str>) -> &'a str {
+    let value = value.trim();
+    match unit {
+        Some(unit) => value.strip_suffix(unit).unwrap_or(value).trim_end(),
+        None => value,
+    }
+}
+
+fn unit_hint(unit: Option<&str>) -> String {
+    match unit {
+        Some(unit) => format!(", optionally followed by '{}'", unit),
+        None => String::new(),
+    }
+}
+
+/// Reads the value of the `number` attribute, numbers start at 1.
+fn read_number(
+    attribute: &Assessment,
+    line_number: u32,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<u32> {
+    match attribute.value.parse() {
+        Ok(n) if n > 0 => Some(n),
+        _ => {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!(
+                    "invalid value '{}' for '{}': expected a positive integer",
+                    attribute.value, NUMBER_KEY
+                ),
+            ));
+            None
+        }
+    }
+}
+
+/// Reads the value of the `uuid` attribute, which must be written as
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` with hexadecimal digits and must
+/// not be used by another node of the file.
+fn read_uuid(
+    attribute: &Assessment,
+    line_number: u32,
+    uuids: &mut HashMap<String, u32>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<String> {
+    if is_uuid(&attribute.value) {
+        let uuid = attribute.value.to_ascii_lowercase();
+        if let Some(first) = uuids.insert(uuid.clone(), line_number) {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!("uuid '{}' is already used on line {}", uuid, first),
+            ));
+        }
+        return Some(uuid);
+    }
+
+    diagnostics.push(Diagnostic::new(
+        line_number,
+        &format!(
+            "invalid value '{}' for '{}': expected a UUID like 123e4567-e89b-12d3-a456-426614174000",
+            attribute.value, UUID_KEY
+        ),
+    ));
+    None
+}
+
+fn is_uuid(text: &str) -> bool {
+    let groups: Vec<&str> = text.split('-').collect();
+    groups.iter().map(|g| g.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|g| g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Stores an attribute with the custom prefix. Returns false if the
+/// attribute is something else.
+fn read_custom_attribute(
+    attribute: &Assessment,
+    line_number: u32,
+    attributes: &mut BTreeMap<String, String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> bool {
+    if !attribute.criterion.starts_with(CUSTOM_ATTRIBUTE_PREFIX) {
+        return false;
+    }
 
-        self.parsed_assessments
-            .insert(self.assessment_title.trim().to_string(), value);
-        self.assessment_value.clear();
-        self.assessment_title.clear();
+    if attributes
+        .insert(attribute.criterion.clone(), attribute.value.clone())
+        .is_some()
+    {
+        diagnostics.push(Diagnostic::new(
+            line_number,
+            &format!("attribute '{}' is set twice", attribute.criterion),
+        ));
+    }
+    true
+}
+
+/// A later value of a criterion would silently replace the earlier one.
+fn assessed_twice(criterion: &str, line_number: u32) -> Diagnostic {
+    Diagnostic::new(
+        line_number,
+        &format!("criterion '{}' is assessed twice", criterion),
+    )
+}
+
+/// Reads a value or a range like `4..6` of a leaf's criterion.
+fn read_criterion_range(
+    attribute: &Assessment,
+    unit: Option<&str>,
+    line_number: u32,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<(u32, u32)> {
+    let Some((low, high)) = attribute.value.split_once("..") else {
+        return read_criterion_value(attribute, unit, line_number, diagnostics).map(|v| (v, v));
+    };
+
+    match (
+        without_unit(low, unit).parse(),
+        without_unit(high, unit).parse(),
+    ) {
+        (Ok(low), Ok(high)) if low <= high => Some((low, high)),
+        _ => {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!(
+                    "invalid range '{}' for '{}': expected e.g. 4..6{}",
+                    attribute.value,
+                    attribute.criterion,
+                    unit_hint(unit)
+                ),
+            ));
+            None
+        }
+    }
+}
+
+fn build_note(line: &Line) -> Option<String> {
+    if line.note.is_empty() {
+        None
+    } else {
+        Some(line.note.join("\n"))
+    }
+}
+
+/// Applies an `owner` or `status` attribute to `review`. Returns false if the
+/// attribute is something else.
+fn read_review_attribute(
+    attribute: &Assessment,
+    line_number: u32,
+    is_root: bool,
+    review: &mut Review,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> bool {
+    if ![
+        OWNER_KEY,
+        STATUS_KEY,
+        VERSION_KEY,
+        DATE_KEY,
+        IMPACT_KEY,
+        NAMESPACE_KEY,
+    ]
+    .contains(&attribute.criterion.as_str())
+    {
+        return false;
+    }
+
+    if !is_root {
+        diagnostics.push(Diagnostic::new(
+            line_number,
+            &format!("'{}' can only be set on the root node", attribute.criterion),
+        ));
+        return true;
+    }
 
-        Ok(())
+    match attribute.criterion.as_str() {
+        OWNER_KEY => review.owner = Some(attribute.value.clone()),
+        VERSION_KEY => review.version = Some(attribute.value.clone()),
+        IMPACT_KEY => review.impact = Some(attribute.value.clone()),
+        NAMESPACE_KEY => match attribute.value.contains(NAMESPACE_SEPARATOR)
+            || attribute.value.contains(char::is_whitespace)
+        {
+            true => diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!(
+                    "invalid namespace '{}', use a single word without '{}'",
+                    attribute.value, NAMESPACE_SEPARATOR
+                ),
+            )),
+            false => review.namespace = Some(attribute.value.clone()),
+        },
+        DATE_KEY => match NaiveDate::parse_from_str(&attribute.value, "%Y-%m-%d") {
+            Ok(date) => review.date = Some(date),
+            Err(_) => diagnostics.push(Diagnostic::new(
+                line_number,
+                &format!("invalid date '{}', expected YYYY-MM-DD", attribute.value),
+            )),
+        },
+        _ => match attribute.value.parse() {
+            Ok(status) => review.status = Some(status),
+            Err(e) => diagnostics.push(Diagnostic::new(line_number, &e)),
+        },
     }
+
+    true
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::feasible_step::NodeKind;
     use crate::model::tests::*;
     use std::io;
 
@@ -308,7 +1297,7 @@ Enter house;|
         let result = parser.parse(&mut file_stub, &definition).unwrap();
 
         assert_eq!(result.title(), "Enter house");
-        assert_eq!(result.feasibility_value(), 6 + 0);
+        assert_eq!(result.feasibility_value(), 6);
     }
 
     #[test]
@@ -338,4 +1327,1213 @@ Enter house;&
 
         assert_eq!(result.feasibility_value(), 4 + 3);
     }
+
+    #[test]
+    fn parsing_continues_after_an_error_and_reports_all_diagnostics() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            r#"Enter house;&
+    Observe when people are away; Kn=x, Eq=1
+    Break into the house;|
+        Step 3; Kn=0, Eq=2
+          Step 4; Kn=4, Eq=0
+    Pick lock Kn=5"#,
+        );
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+        assert_eq!(
+            parser
+                .diagnostics()
+                .iter()
+                .map(|d| d.line)
+                .collect::<Vec<_>>(),
+            vec![2, 5, 6]
+        );
+    }
+
+    #[test]
+    fn a_voting_node_takes_the_easiest_k_children() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            r#"
+Enter house;2/3
+    Trick people; Kn=6, Eq=0
+    Pick lock; Kn=5, Eq=3
+    Break window; Kn=1, Eq=2"#,
+        );
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        assert_eq!(result.kind(), feasible_step::NodeKind::Voting);
+        assert_eq!(result.threshold(), Some(2));
+        assert_eq!(result.feasibility_value(), 8);
+    }
+
+    #[test]
+    fn voting_nodes_must_match_their_children() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new(
+                "Root;&\n    A;3/2\n        B; Kn=1, Eq=1\n    C;1/2\n        D; Kn=1, Eq=1",
+            ),
+            &definition,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(2, "cannot require 3 of 2 children"),
+                Diagnostic::new(2, "voting node expects 2 children but has 1"),
+                Diagnostic::new(4, "voting node expects 2 children but has 1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn voting_nodes_with_too_many_combinations_are_rejected() {
+        let definition = build_criteria(&["Kn"]);
+        let children = |n: usize| -> String {
+            (0..n)
+                .map(|i| format!("\n    Step {}; Kn={}", i, i))
+                .collect()
+        };
+        let mut parser = AttackTreeParser::new();
+
+        // 20 of 30 are 30045015 combinations
+        let result = parser.parse(
+            &mut io::Cursor::new(format!("Root;20/30{}", children(30))),
+            &definition,
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[Diagnostic::new(
+                1,
+                "20 of 30 children are more than the limit of 100000 combinations to compare"
+            )]
+        );
+
+        // 10 of 20 are 184756, 3 of 80 are 82160
+        assert!(parser
+            .parse(
+                &mut io::Cursor::new(format!("Root;10/20{}", children(20))),
+                &definition
+            )
+            .is_err());
+        let root = parser
+            .parse(
+                &mut io::Cursor::new(format!("Root;3/80{}", children(80))),
+                &definition,
+            )
+            .unwrap();
+        assert_eq!(root.feasibility_value(), 2);
+    }
+
+    #[test]
+    fn inline_expressions_are_expanded_into_nodes() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let result = AttackTreeParser::new()
+            .parse(
+                &mut io::Cursor::new(
+                    "Enter house; (Pick lock | Break window) & Disable alarm\n    > Workshop draft\n    Alarm;! Kn=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+
+        let titles: Vec<(String, NodeKind)> = traversal::preorder(&result)
+            .iter()
+            .map(|n| (n.title().to_string(), n.kind()))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("Enter house".to_string(), NodeKind::And),
+                ("Pick lock or Break window".to_string(), NodeKind::Or),
+                ("Pick lock".to_string(), NodeKind::Leaf),
+                ("Break window".to_string(), NodeKind::Leaf),
+                ("Disable alarm".to_string(), NodeKind::Leaf),
+            ]
+        );
+        assert_eq!(result.note(), Some("Workshop draft"));
+        assert_eq!(result.defenses().len(), 1);
+
+        let mut parser = AttackTreeParser::new();
+        assert!(parser
+            .parse(
+                &mut io::Cursor::new("Root; A & B\n    Child; Kn=1"),
+                &definition
+            )
+            .is_err());
+        assert_eq!(parser.diagnostics()[0].line, 2);
+    }
+
+    #[test]
+    fn steps_of_expressions_need_assessments_if_required() {
+        let definition = build_criteria(&["Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse_with_options(
+            &mut io::Cursor::new("Enter house; (Pick lock | Break window) & Disable alarm"),
+            &definition,
+            &ParserOptions {
+                require_assessments: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        let missing = Diagnostic::from_error(1, TreeError::MissingAssessment("Kn".to_string()));
+        assert_eq!(
+            parser.diagnostics(),
+            &[missing.clone(), missing.clone(), missing]
+        );
+    }
+
+    #[test]
+    fn aliases_repeat_the_anchored_node() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let result = AttackTreeParser::new()
+            .parse(
+                &mut io::Cursor::new(
+                    "Root;|\n    Door;&\n        &creds Obtain credentials; Kn=4, Eq=2\n        Open door; Kn=1, Eq=1\n    Server;&\n        *creds\n        Log in; Kn=2, Eq=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+
+        let server = &result.get_children()[1];
+        let alias = &server.get_children()[0];
+        assert_eq!(alias.title(), "Obtain credentials");
+        assert_eq!(alias.feasibility_value(), 6);
+        assert!(Rc::ptr_eq(&alias.get_parent().unwrap(), server));
+        assert_eq!(server.feasibility_value(), 6);
+    }
+
+    #[test]
+    fn unknown_and_duplicate_anchors_are_reported() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new(
+                "Root;|\n    *later\n    &later A; Kn=1, Eq=1\n    &later B; Kn=1, Eq=1\n    *later\n        C; Kn=1, Eq=1",
+            ),
+            &definition,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(2, "unknown anchor 'later', define it above the alias"),
+                Diagnostic::new(4, "anchor 'later' is defined twice"),
+                Diagnostic::new(6, "an alias cannot have children or defenses"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_second_root_node_is_reported() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new("Root; Kn=1, Eq=1\nOther root; Kn=1, Eq=1");
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+    }
+
+    #[test]
+    fn a_file_with_windows_line_endings_can_be_parsed() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "\u{feff}Break into house;&\r\n    Observe when people are away; Kn=6, Eq=1\r\n    Pick lock; Kn=5, Eq=3\r\n",
+        );
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition).unwrap();
+
+        assert_eq!(result.title(), "Break into house");
+        assert_eq!(
+            result.get_children()[0].title(),
+            "Observe when people are away"
+        );
+        assert_eq!(result.feasibility_value(), 6 + 3);
+    }
+
+    #[test]
+    fn the_assessment_date_of_a_leaf_is_read() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new("Pick lock; Kn=5, Eq=3, assessed=2023-06-01");
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition).unwrap();
+
+        assert_eq!(result.assessed(), NaiveDate::from_ymd_opt(2023, 6, 1));
+        assert_eq!(result.feasibility_value(), 5 + 3);
+    }
+
+    #[test]
+    fn an_invalid_assessment_date_is_reported() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new("Pick lock; Kn=5, Eq=3, assessed=June 2023");
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+    }
+
+    #[test]
+    fn version_and_date_of_a_tree_are_read() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser
+            .parse(
+                &mut io::Cursor::new(
+                    "Enter house;| version=1.2, date=2024-05-01\n    Pick lock; Kn=5, Eq=3",
+                ),
+                &definition,
+            )
+            .unwrap();
+
+        assert_eq!(result.review().version.as_deref(), Some("1.2"));
+        assert_eq!(result.review().date, NaiveDate::from_ymd_opt(2024, 5, 1));
+        assert!(result.review().is_empty());
+
+        let result = parser.parse(
+            &mut io::Cursor::new("Enter house;| date=May 2024\n    Pick lock; Kn=5, Eq=3"),
+            &definition,
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        assert_eq!(
+            parser.diagnostics()[0].to_string(),
+            "line 1: invalid date 'May 2024', expected YYYY-MM-DD"
+        );
+    }
+
+    #[test]
+    fn the_impact_is_set_on_the_root() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser
+            .parse(
+                &mut io::Cursor::new("Enter house;| impact=Severe\n    Pick lock; Kn=5, Eq=3"),
+                &definition,
+            )
+            .unwrap();
+        assert_eq!(result.review().impact.as_deref(), Some("Severe"));
+
+        let result = parser.parse(
+            &mut io::Cursor::new("Enter house;|\n    Pick lock; Kn=5, Eq=3, impact=Major"),
+            &definition,
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+        assert_eq!(
+            parser.diagnostics()[0].to_string(),
+            "line 2: 'impact' can only be set on the root node"
+        );
+    }
+
+    #[test]
+    fn owner_and_status_of_a_tree_are_read() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Enter house;| owner=Alice, status=reviewed\n    Pick lock; Kn=5, Eq=3",
+        );
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition).unwrap();
+
+        assert_eq!(
+            result.review(),
+            &Review {
+                owner: Some("Alice".to_string()),
+                status: Some(ReviewStatus::Reviewed),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn the_namespace_of_a_tree_qualifies_the_titles_of_its_nodes() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser
+            .parse(
+                &mut io::Cursor::new("Enter house;| namespace=Door\n    Pick lock; Kn=5, Eq=3"),
+                &definition,
+            )
+            .unwrap();
+
+        assert_eq!(result.review().namespace.as_deref(), Some("Door"));
+        assert_eq!(
+            result.get_children()[0].qualified_title(),
+            "Door::Pick lock"
+        );
+
+        let invalid = parser.parse(
+            &mut io::Cursor::new("Enter house;| namespace=Front::Door\n    Pick lock; Kn=5"),
+            &definition,
+        );
+        assert_eq!(invalid.err(), Some(TreeFileError::SyntaxError(1)));
+    }
+
+    #[test]
+    fn review_attributes_below_the_root_and_unknown_statuses_are_reported() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Enter house;| status=done\n    Pick lock; Kn=5, Eq=3, owner=Bob\n    Break in;& color=red\n        Smash; Kn=1",
+        );
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        assert_eq!(
+            parser
+                .diagnostics()
+                .iter()
+                .map(|d| d.line)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn notes_are_stored_on_their_node() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Enter house;|\n    > Only houses without alarm.\n    Pick lock; Kn=5, Eq=3\n        > Standard cylinder lock.\n        > Takes a few minutes.",
+        );
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition).unwrap();
+
+        assert_eq!(result.note(), Some("Only houses without alarm."));
+        assert_eq!(
+            result.get_children()[0].note(),
+            Some("Standard cylinder lock.\nTakes a few minutes.")
+        );
+    }
+
+    #[test]
+    fn nodes_know_the_lines_they_were_parsed_from() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Enter house;|\n    Pick lock; Kn=5, Eq=3\n        > Standard cylinder lock.\n\n    Break window; Kn=1, Eq=1\n        Bars; ! Eq=2",
+        );
+
+        let mut parser = AttackTreeParser::new();
+        parser.set_file(Path::new("house.att"));
+
+        let result = parser.parse(&mut file_stub, &definition).unwrap();
+
+        let span = |node: &Rc<dyn FeasibleStep>| node.span().unwrap().to_string();
+        let children = result.get_children();
+        assert_eq!(span(&result), "house.att:1-6");
+        assert_eq!(span(&children[0]), "house.att:2-3");
+        assert_eq!(span(&children[1]), "house.att:5-6");
+        assert!(children[1].span().unwrap().contains(6));
+    }
+
+    #[test]
+    fn strict_options_enforce_indentation_assessments_and_children() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+        let text = "Enter house;&\n  Pick lock; Kn=5\n  Break in;|";
+
+        let mut parser = AttackTreeParser::new();
+
+        assert!(parser
+            .parse(&mut io::Cursor::new(text), &definition)
+            .is_ok());
+
+        let result = parser.parse_with_options(
+            &mut io::Cursor::new(text),
+            &definition,
+            &ParserOptions::strict(),
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+        assert_eq!(
+            parser
+                .diagnostics()
+                .iter()
+                .map(|d| d.message.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "indentation of 2 spaces is not a multiple of 4",
+                "missing assessment for 'Eq'",
+                "indentation of 2 spaces is not a multiple of 4",
+                "'Break in' has no children",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_criteria_are_reported_with_their_model_error() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new("Pick lock; Kn=5, Eq=3, Tm=2");
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        assert_eq!(
+            parser.diagnostics()[0].error,
+            Some(TreeError::UnknownCriterion("Tm".to_string()))
+        );
+    }
+
+    #[test]
+    fn leaves_reference_attack_techniques() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub =
+            io::Cursor::new("Use stolen credentials; Kn=1, Eq=0, attack=T1078, attack=T1110.003");
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition).unwrap();
+
+        assert_eq!(result.techniques(), ["T1078", "T1110.003"]);
+    }
+
+    #[test]
+    fn detection_probabilities_are_aggregated() {
+        let definition = build_criteria(&["Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Root;&\n    Phish; Kn=1, detection=0.5\n    Exfiltrate;|\n        Upload; Kn=2, detection=0.2\n        Print; Kn=3",
+        );
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        // the upload is chosen over printing, 1 - 0.5 * 0.8
+        assert_eq!(result.detection(), Some(0.6));
+    }
+
+    #[test]
+    fn detection_probabilities_must_be_between_0_and_1() {
+        let definition = build_criteria(&["Kn"]);
+
+        for value in ["1.5", "-0.1", "often"] {
+            let mut file_stub = io::Cursor::new(format!("Phish; Kn=1, detection={}", value));
+
+            let result = AttackTreeParser::new().parse(&mut file_stub, &definition);
+
+            assert_eq!(
+                result.err(),
+                Some(TreeFileError::SyntaxError(1)),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn custom_attributes_are_kept_on_any_node() {
+        let definition = build_criteria(&["Kn"]);
+
+        let mut file_stub =
+            io::Cursor::new("Root;& x-ticket=SEC-12\n    Step; Kn=1, x-asset=ECU, x-cvss=7.5");
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        assert_eq!(result.attributes()["x-ticket"], "SEC-12");
+        let leaf = &result.get_children()[0];
+        assert_eq!(
+            leaf.attributes().keys().collect::<Vec<_>>(),
+            vec!["x-asset", "x-cvss"]
+        );
+    }
+
+    #[test]
+    fn criteria_must_not_be_assessed_twice() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new("Root;&\n    Step; Kn=3, Eq=1, Kn=5\n        Lock;! Eq=2, Eq=4"),
+            &definition,
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(2, "criterion 'Kn' is assessed twice"),
+                Diagnostic::new(3, "criterion 'Eq' is assessed twice"),
+            ]
+        );
+    }
+
+    #[test]
+    fn values_overflowing_with_their_weight_are_reported() {
+        let mut criteria = FeasibilityCriteria(
+            vec![
+                FeasiblityCriterion::new("Kn", "Kn"),
+                FeasiblityCriterion::new("Eq", "Eq"),
+            ],
+            vec![],
+        );
+        criteria.0[1].weight = 4;
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new("Root; Kn=4000000000, Eq=2000000000"),
+            &Rc::new(criteria),
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[Diagnostic::new(
+                1,
+                "value of 'Eq' is too large for its weight 4"
+            )]
+        );
+    }
+
+    #[test]
+    fn assessments_may_use_aliases_and_optionally_ignore_case() {
+        let definition = Rc::new(FeasibilityCriteria(
+            vec![
+                FeasiblityCriterion {
+                    aliases: vec!["Knowledge".to_string()],
+                    ..FeasiblityCriterion::new("Kn", "Knowledge")
+                },
+                FeasiblityCriterion::new("Eq", "Equipment"),
+            ],
+            vec![],
+        ));
+        let text = "Root;&\n    Step; Knowledge=3, eq=1\n    Other; Kn=2, Eq=4";
+        let mut parser = AttackTreeParser::new();
+
+        assert!(parser
+            .parse(&mut io::Cursor::new(text), &definition)
+            .is_err());
+        assert_eq!(parser.diagnostics()[0].message, "unknown criterion 'eq'");
+
+        let result = parser
+            .parse_with_options(
+                &mut io::Cursor::new(text),
+                &definition,
+                &ParserOptions {
+                    ignore_criteria_case: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let step = &result.get_children()[0];
+        assert_eq!(step.feasibility().unwrap().values(), vec![Some(3), Some(1)]);
+    }
+
+    /// A chain of AND nodes with a leaf at level `depth`.
+    fn build_chain_text(depth: usize) -> String {
+        let mut text = String::new();
+        for level in 0..depth - 1 {
+            text.push_str(&format!("{}Level {};&\n", " ".repeat(level), level));
+        }
+        text.push_str(&format!("{}Step; Kn=1", " ".repeat(depth - 1)));
+        text
+    }
+
+    #[test]
+    fn trees_at_the_depth_limit_do_not_overflow_the_stack() {
+        let definition = build_criteria(&["Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let root = parser
+            .parse(
+                &mut io::Cursor::new(build_chain_text(DEFAULT_MAX_DEPTH)),
+                &definition,
+            )
+            .unwrap();
+        assert_eq!(root.feasibility_value(), 1);
+
+        let result = parser.parse(
+            &mut io::Cursor::new(build_chain_text(DEFAULT_MAX_DEPTH + 1)),
+            &definition,
+        );
+        assert_eq!(
+            result.err(),
+            Some(TreeFileError::SyntaxError(DEFAULT_MAX_DEPTH as u32 + 1))
+        );
+        assert_eq!(
+            parser.diagnostics()[0].message,
+            format!(
+                "the tree is deeper than the limit of {} levels",
+                DEFAULT_MAX_DEPTH
+            )
+        );
+    }
+
+    #[test]
+    fn trees_beyond_the_size_limits_are_rejected() {
+        let definition = build_criteria(&["Kn"]);
+        let text = "Root;&\n    A;&\n        A1; Kn=1\n    B; Kn=2";
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse_with_options(
+            &mut io::Cursor::new(text),
+            &definition,
+            &ParserOptions {
+                max_depth: Some(2),
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(3)));
+        assert_eq!(
+            parser.diagnostics()[0].message,
+            "the tree is deeper than the limit of 2 levels"
+        );
+
+        let result = parser.parse_with_options(
+            &mut io::Cursor::new(text),
+            &definition,
+            &ParserOptions {
+                max_nodes: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(4)));
+        assert_eq!(
+            parser.diagnostics()[0].message,
+            "the file has more than the limit of 3 nodes"
+        );
+    }
+
+    #[test]
+    fn binary_input_is_reported_with_its_line() {
+        let definition = build_criteria(&["Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new(b"Root;&\n    Step; Kn=1\n\xff\xfe".to_vec()),
+            &definition,
+        );
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "File read error: invalid UTF-8 in line 3, is this a binary file?"
+        );
+
+        let result = parser.parse(
+            &mut io::Cursor::new(b"PK\x03\x04\0\0".to_vec()),
+            &definition,
+        );
+        assert_eq!(
+            result.err(),
+            Some(TreeFileError::FileReadError(
+                "NUL byte in line 1, is this a binary file?".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn custom_attributes_must_be_unique() {
+        let definition = build_criteria(&["Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new("Step; Kn=1, x-asset=ECU, x-asset=Gateway"),
+            &definition,
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+        assert_eq!(
+            parser.diagnostics()[0].message,
+            "attribute 'x-asset' is set twice"
+        );
+    }
+
+    #[test]
+    fn ranges_propagate_to_a_feasibility_interval() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Root;&\n    Enter;|\n        Pick lock; Kn=1..5, Eq=2\n        Break door; Kn=3, Eq=2\n    Escape; Kn=0, Eq=1..4",
+        );
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        // the door is the easier way in once the lock turns out to be hard
+        assert_eq!(result.feasibility_value(), 3);
+        assert_eq!(result.feasibility_interval(), Some((3, 7)));
+        assert_eq!(
+            result.get_children()[1].feasibility_interval(),
+            Some((1, 4))
+        );
+    }
+
+    #[test]
+    fn values_may_be_followed_by_the_unit_of_their_criterion() {
+        let definition = Rc::new(FeasibilityCriteria(
+            vec![
+                FeasiblityCriterion {
+                    unit: Some("d".to_string()),
+                    ..FeasiblityCriterion::new("ET", "Elapsed time")
+                },
+                FeasiblityCriterion::new("Kn", "Knowledge"),
+            ],
+            vec![],
+        ));
+
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut io::Cursor::new("Root;|\n    A; ET=30d, Kn=1\n    B; ET=10 d..40d, Kn=2"),
+                &definition,
+            )
+            .unwrap();
+        assert_eq!(
+            root.get_children()[0].feasibility().unwrap().value("ET"),
+            Some(30)
+        );
+        assert_eq!(
+            root.get_children()[1]
+                .feasibility_range()
+                .unwrap()
+                .1
+                .value("ET"),
+            Some(40)
+        );
+        assert!(root.get_children()[0].render().contains("ET=30d, Kn=1"));
+
+        let mut parser = AttackTreeParser::new();
+        let result = parser.parse(
+            &mut io::Cursor::new("Root;|\n    A; ET=30h, Kn=1d"),
+            &definition,
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(
+                    2,
+                    "invalid value '30h' for 'ET': expected a non-negative integer, optionally followed by 'd'"
+                ),
+                Diagnostic::new(
+                    2,
+                    "invalid value '1d' for 'Kn': expected a non-negative integer"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_easiest_end_of_a_range_depends_on_the_scale() {
+        let definition = Rc::new(FeasibilityCriteria(
+            vec![FeasiblityCriterion {
+                scale: Scale::HigherIsEasier,
+                max: Some(10),
+                ..FeasiblityCriterion::new("Wo", "Window of opportunity")
+            }],
+            vec![],
+        ));
+
+        let mut file_stub = io::Cursor::new("Wait; Wo=2..4");
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        assert_eq!(result.feasibility().unwrap().values(), [Some(4)]);
+        assert_eq!(result.feasibility_interval(), Some((6, 8)));
+    }
+
+    #[test]
+    fn malformed_ranges_are_reported() {
+        let definition = build_criteria(&["Kn"]);
+
+        for value in ["6..4", "4..x", "..4"] {
+            let mut file_stub = io::Cursor::new(format!("Phish; Kn={}", value));
+
+            let result = AttackTreeParser::new().parse(&mut file_stub, &definition);
+
+            assert_eq!(
+                result.err(),
+                Some(TreeFileError::SyntaxError(1)),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_attack_techniques_are_reported() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        for id in ["1078", "T107", "T1078.1", "T1078.", "Tabcd"] {
+            let mut file_stub = io::Cursor::new(format!("Login; Kn=1, Eq=0, attack={}", id));
+
+            let mut parser = AttackTreeParser::new();
+
+            let result = parser.parse(&mut file_stub, &definition);
+
+            assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)), "{}", id);
+            assert_eq!(
+                parser.diagnostics()[0].message,
+                format!(
+                    "invalid ATT&CK technique '{}': expected e.g. T1078 or T1078.001",
+                    id
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn an_empty_file_is_reported() {
+        let definition = build_criteria(&["Eq", "Kn"]);
+
+        let mut file_stub = io::Cursor::new("\n   \n");
+
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::EmptyTree));
+    }
+
+    mod round_trip {
+        use super::*;
+        use crate::render::render_to_att_string;
+        use proptest::prelude::*;
+
+        #[derive(Debug, Clone)]
+        enum TreeSpec {
+            And(String, Vec<TreeSpec>),
+            Or(String, Vec<TreeSpec>),
+            Leaf(String, u32, u32),
+        }
+
+        fn title() -> impl Strategy<Value = String> {
+            "[A-Za-z][A-Za-z0-9 ]{0,15}[A-Za-z0-9]"
+        }
+
+        fn tree_spec() -> impl Strategy<Value = TreeSpec> {
+            let leaf =
+                (title(), 0..100u32, 0..100u32).prop_map(|(t, a, b)| TreeSpec::Leaf(t, a, b));
+
+            leaf.prop_recursive(4, 32, 4, |inner| {
+                prop_oneof![
+                    (title(), prop::collection::vec(inner.clone(), 1..4))
+                        .prop_map(|(t, c)| TreeSpec::And(t, c)),
+                    (title(), prop::collection::vec(inner, 1..4))
+                        .prop_map(|(t, c)| TreeSpec::Or(t, c)),
+                ]
+            })
+        }
+
+        fn build_tree(
+            spec: &TreeSpec,
+            parent: Option<Rc<dyn FeasibleStep>>,
+            definition: &Rc<FeasibilityCriteria>,
+        ) -> Rc<dyn FeasibleStep> {
+            let (node, children): (Rc<dyn FeasibleStep>, _) = match spec {
+                TreeSpec::And(t, c) => (Rc::new(AndNode::new(t, parent, generate_id)), c),
+                TreeSpec::Or(t, c) => (Rc::new(OrNode::new(t, parent, generate_id)), c),
+                TreeSpec::Leaf(t, a, b) => {
+                    return Rc::new(Leaf::new(t, parent, definition, &[*a, *b], generate_id))
+                }
+            };
+
+            for c in children {
+                node.add_child(&build_tree(c, Some(node.clone()), definition));
+            }
+
+            node
+        }
+
+        fn assert_same_tree(expected: &Rc<dyn FeasibleStep>, actual: &Rc<dyn FeasibleStep>) {
+            assert_eq!(expected.kind(), actual.kind());
+            assert_eq!(expected.title(), actual.title());
+            assert_eq!(
+                expected.feasibility().unwrap().values(),
+                actual.feasibility().unwrap().values()
+            );
+
+            let expected_children = expected.get_children();
+            let actual_children = actual.get_children();
+            assert_eq!(expected_children.len(), actual_children.len());
+
+            for (e, a) in expected_children.iter().zip(actual_children.iter()) {
+                assert_same_tree(e, a);
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn a_written_tree_parses_back_to_the_same_tree(spec in tree_spec()) {
+                let definition = build_criteria(&["Eq", "Kn"]);
+                let tree = build_tree(&spec, None, &definition);
+
+                let text = render_to_att_string(&tree);
+
+                let mut parser = AttackTreeParser::new();
+                let parsed = parser.parse(&mut io::Cursor::new(text), &definition).unwrap();
+
+                assert_same_tree(&tree, &parsed);
+            }
+
+            #[test]
+            fn arbitrary_input_does_not_panic(text in "[ a-zA-Z0-9;&|=,.>\n]{0,200}") {
+                let definition = build_criteria(&["Eq", "Kn"]);
+
+                let mut parser = AttackTreeParser::new();
+
+                if let Ok(root) = parser.parse(&mut io::Cursor::new(text), &definition) {
+                    root.feasibility_value();
+                    root.render();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn defenses_are_attached_to_the_node_above_them() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let mut file_stub = io::Cursor::new(
+            r#"
+Enter house;|
+    Alarm system;! Kn=2
+        > Installed in 2023
+    Pick lock; Kn=5, Eq=3
+        Security lock;! Eq=4, Kn=1"#,
+        );
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        assert_eq!(result.get_children().len(), 1);
+        assert_eq!(result.defenses()[0].description, "Alarm system");
+        assert_eq!(
+            result.defenses()[0].note.as_deref(),
+            Some("Installed in 2023")
+        );
+        assert_eq!(
+            result.get_children()[0].defenses()[0].describe_effect(),
+            "Kn+1, Eq+4"
+        );
+        // defenses do not change the values without defenses
+        assert_eq!(result.feasibility_value(), 5 + 3);
+        assert_eq!(apply_defenses(&result).feasibility_value(), 8 + 7);
+    }
+
+    #[test]
+    fn defenses_must_directly_follow_the_countered_node() {
+        let definition = build_criteria(&["Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Root;&
+    Step; Kn=1
+    Alarm;! Kn=2
+        Child; Kn=1",
+        );
+
+        let mut parser = AttackTreeParser::new();
+        let result = parser.parse(&mut file_stub, &definition);
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(3)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(3, "a defense must directly follow the node it counters"),
+                Diagnostic::new(4, "a defense cannot have children"),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_of_nodes_are_read_and_must_differ_between_siblings() {
+        let definition = build_criteria(&["Kn"]);
+
+        let mut parser = AttackTreeParser::new();
+        let result = parser.parse(
+            &mut io::Cursor::new(
+                "Root;| number=2\n    A; Kn=1, number=1\n    B; Kn=1, number=1\n    C; Kn=1, number=0",
+            ),
+            &definition,
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(3)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(3, "number 1 is already used by a sibling"),
+                Diagnostic::new(
+                    4,
+                    "invalid value '0' for 'number': expected a positive integer"
+                ),
+            ]
+        );
+
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut io::Cursor::new("Root;| number=2\n    A; Kn=1, number=3"),
+                &definition,
+            )
+            .unwrap();
+        assert_eq!(root.number(), Some(2));
+        assert_eq!(root.get_children()[0].number(), Some(3));
+    }
+
+    #[test]
+    fn uuids_of_nodes_are_read_in_lower_case() {
+        let definition = build_criteria(&["Kn"]);
+
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut io::Cursor::new(
+                    "Root;| uuid=123E4567-E89B-12D3-A456-426614174000\n    A; Kn=1, uuid=0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a\n    B; Kn=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+        assert_eq!(root.uuid(), Some("123e4567-e89b-12d3-a456-426614174000"));
+        assert_eq!(
+            root.get_children()[0].uuid(),
+            Some("0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a")
+        );
+        assert_eq!(root.get_children()[1].uuid(), None);
+
+        let mut parser = AttackTreeParser::new();
+        let result = parser.parse(&mut io::Cursor::new("A; Kn=1, uuid=1234"), &definition);
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(1)));
+    }
+
+    #[test]
+    fn uuids_used_twice_are_reported() {
+        let definition = build_criteria(&["Kn"]);
+        let mut parser = AttackTreeParser::new();
+
+        let result = parser.parse(
+            &mut io::Cursor::new(
+                "Root;| uuid=0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a\n    A; Kn=1, uuid=0E2C9A4B-54F4-4BD8-9FB4-E3E51B0E4F6A",
+            ),
+            &definition,
+        );
+
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(2)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[Diagnostic::new(
+                2,
+                "uuid '0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a' is already used on line 1"
+            )]
+        );
+    }
+
+    #[test]
+    fn alias_copies_have_no_uuids() {
+        let definition = build_criteria(&["Kn"]);
+
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut io::Cursor::new(
+                    "Root;|\n    &creds Obtain credentials;| uuid=0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a\n        Phish; Kn=2, uuid=123e4567-e89b-12d3-a456-426614174000\n    Server;&\n        *creds",
+                ),
+                &definition,
+            )
+            .unwrap();
+
+        let copy = &root.get_children()[1].get_children()[0];
+        assert_eq!(copy.title(), "Obtain credentials");
+        assert_eq!(copy.uuid(), None);
+        assert_eq!(copy.get_children()[0].uuid(), None);
+        assert_eq!(
+            root.get_children()[0].uuid(),
+            Some("0e2c9a4b-54f4-4bd8-9fb4-e3e51b0e4f6a")
+        );
+    }
+
+    #[test]
+    fn assumptions_belong_to_the_node_above_them() {
+        let definition = build_criteria(&["Kn"]);
+
+        let mut file_stub = io::Cursor::new(
+            "Root;&
+    No insider;~
+    Step; Kn=1
+        Alarm;! Kn=2
+        Lock is standard;~
+            > DIN 18252
+    Other; Kn=3",
+        );
+
+        let result = AttackTreeParser::new()
+            .parse(&mut file_stub, &definition)
+            .unwrap();
+
+        assert_eq!(result.assumptions()[0].description, "No insider");
+        let step = &result.get_children()[0];
+        assert_eq!(step.defenses().len(), 1);
+        assert_eq!(step.assumptions()[0].description, "Lock is standard");
+        assert_eq!(step.assumptions()[0].note.as_deref(), Some("DIN 18252"));
+        // assumptions do not change the feasibility
+        assert_eq!(result.feasibility_value(), 3);
+
+        let mut parser = AttackTreeParser::new();
+        let result = parser.parse(
+            &mut io::Cursor::new(
+                "Root;&
+    Step; Kn=1
+    No insider;~ Kn=1",
+            ),
+            &definition,
+        );
+        assert_eq!(result.err(), Some(TreeFileError::SyntaxError(3)));
+        assert_eq!(
+            parser.diagnostics(),
+            &[
+                Diagnostic::new(3, "an assumption cannot have assessments"),
+                Diagnostic::new(
+                    3,
+                    "an assumption must directly follow the node it belongs to"
+                ),
+            ]
+        );
+    }
 }
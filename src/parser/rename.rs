@@ -0,0 +1,134 @@
+use thiserror::Error;
+
+use super::tokenizer::{quote_title, split_title, ALIAS_MARKER, ANCHOR_MARKER, NOTE_MARKER};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RenameError {
+    #[error("a title must not be empty")]
+    EmptyTitle,
+    #[error("a title must not contain line breaks")]
+    InvalidCharacter,
+    #[error(
+        "a title must not start with '{}', '{}' or '{}'",
+        NOTE_MARKER,
+        ANCHOR_MARKER,
+        ALIAS_MARKER
+    )]
+    Marker,
+}
+
+/// Fails if `title` cannot be written as the title of a node.
+pub fn check_title(title: &str) -> Result<(), RenameError> {
+    if title.trim().is_empty() {
+        Err(RenameError::EmptyTitle)
+    } else if title.contains(['\n', '\r']) {
+        Err(RenameError::InvalidCharacter)
+    } else if title.trim_start().starts_with(NOTE_MARKER)
+        || title
+            .trim_start()
+            .starts_with([ANCHOR_MARKER, ALIAS_MARKER])
+    {
+        Err(RenameError::Marker)
+    } else {
+        Ok(())
+    }
+}
+
+/// Replaces the title of every node titled `old` in the text of a .att file
+/// and returns the text and the number of renamed nodes. Indentation,
+/// assessments, notes and line endings are kept as they are.
+pub fn rename_title(text: &str, old: &str, new: &str) -> Result<(String, usize), RenameError> {
+    check_title(new)?;
+
+    let old = old.trim();
+    let new = new.trim();
+    let mut count = 0;
+    let mut renamed = String::with_capacity(text.len());
+
+    for line in text.split_inclusive('\n') {
+        let mut content = line.trim_start_matches([' ', '\u{feff}']);
+        // an anchor like `&creds ` stays in front of the title
+        if let Some((_, title)) = content
+            .strip_prefix(ANCHOR_MARKER)
+            .and_then(|c| c.split_once(' '))
+        {
+            content = title.trim_start();
+        }
+        let prefix = &line[..line.len() - content.len()];
+
+        match split_title(content) {
+            Ok((title, rest)) if !content.starts_with(NOTE_MARKER) && title == old => {
+                // the title as written, with quotes and the spaces before ';'
+                let written = &content[..content.len() - rest.len() - 1];
+                renamed.push_str(prefix);
+                renamed.push_str(&quote_title(new));
+                renamed.push_str(&written[written.trim_end().len()..]);
+                renamed.push(';');
+                renamed.push_str(rest);
+                count += 1;
+            }
+            _ => renamed.push_str(line),
+        }
+    }
+
+    Ok((renamed, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_matching_node_titles_are_replaced() {
+        let text = "Enter house;&\r\n    Pick lock ; Kn=1\r\n        > Pick lock; see above\r\n    Pick lock and door;|\r\n";
+
+        let (renamed, count) = rename_title(text, "Pick lock", "Open lock").unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(
+            renamed,
+            "Enter house;&\r\n    Open lock ; Kn=1\r\n        > Pick lock; see above\r\n    Pick lock and door;|\r\n"
+        );
+    }
+
+    #[test]
+    fn anchors_are_kept_in_front_of_the_title() {
+        let (renamed, count) = rename_title(
+            "Root;&\n    &creds  Get key; Kn=1\n    *creds\n",
+            "Get key",
+            "Steal key",
+        )
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(renamed, "Root;&\n    &creds  Steal key; Kn=1\n    *creds\n");
+    }
+
+    #[test]
+    fn titles_with_semicolons_are_matched_and_written_quoted() {
+        let text = "\"A; B\" ;&\n    C; Kn=1\n";
+
+        let (renamed, count) = rename_title(text, "A; B", "A and B").unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(renamed, "A and B ;&\n    C; Kn=1\n");
+
+        let (renamed, _) = rename_title(text, "C", "C; D").unwrap();
+        assert_eq!(renamed, "\"A; B\" ;&\n    \"C; D\"; Kn=1\n");
+    }
+
+    #[test]
+    fn titles_that_cannot_be_parsed_back_are_rejected() {
+        assert_eq!(
+            rename_title("A; Kn=1", "A", "B\nKn=2"),
+            Err(RenameError::InvalidCharacter)
+        );
+        assert_eq!(
+            rename_title("A; Kn=1", "A", " "),
+            Err(RenameError::EmptyTitle)
+        );
+        assert_eq!(
+            rename_title("A; Kn=1", "A", "> B"),
+            Err(RenameError::Marker)
+        );
+    }
+}
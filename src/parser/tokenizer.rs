@@ -0,0 +1,655 @@
+use std::{borrow::Cow, iter::Peekable};
+
+use super::Diagnostic;
+
+/// Lines starting with this marker hold the note of the node above them.
+pub const NOTE_MARKER: &str = ">";
+/// Prefix of an anchor name before a node title, e.g. `&creds Get key; Kn=1`.
+pub const ANCHOR_MARKER: char = '&';
+/// Prefix of a line repeating the anchored node, e.g. `*creds`.
+pub const ALIAS_MARKER: char = '*';
+/// Delimits a title that contains ';', e.g. `"Bypass auth; use default creds";&`.
+/// A quote inside the title is written twice.
+pub const QUOTE: char = '"';
+
+#[derive(Debug, PartialEq)]
+pub enum NodeType {
+    And,
+    Or,
+    /// `k/n`, any k of the n children.
+    Voting(usize, usize),
+    Leaf(Vec<Assessment>),
+    /// A control countering the node above it, with the values it adds.
+    Defense(Vec<Assessment>),
+    /// A premise of the analysis of the node above it.
+    Assumption,
+    /// A copy of the node with the anchor name.
+    Alias(String),
+    /// An AND or OR node written on one line, e.g.
+    /// `Enter house; (Pick lock | Break window) & Disable alarm`.
+    Expression(Expression),
+}
+
+/// The steps of an inline expression. '&' binds stronger than '|'.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Step(String),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+}
+
+impl Expression {
+    /// The title of the node of a group, e.g. `Pick lock or Break window`.
+    pub fn title(&self) -> String {
+        let (steps, operator) = match self {
+            Expression::Step(title) => return title.clone(),
+            Expression::And(steps) => (steps, " and "),
+            Expression::Or(steps) => (steps, " or "),
+        };
+        let titles: Vec<String> = steps
+            .iter()
+            .map(|s| match s {
+                Expression::Step(title) => title.clone(),
+                _ => format!("({})", s.title()),
+            })
+            .collect();
+
+        titles.join(operator)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Assessment {
+    pub criterion: String,
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Line {
+    pub number: u32,
+    pub indentation: u32,
+    pub title: String,
+    /// Name the node can be repeated with by an alias.
+    pub anchor: Option<String>,
+    pub node_type: NodeType,
+    /// `key=value` pairs following the '&' or '|' of inner nodes.
+    pub attributes: Vec<Assessment>,
+    /// Lines of the note block below the node.
+    pub note: Vec<String>,
+    /// Number of the last line of the node and its note.
+    pub last_number: u32,
+}
+
+pub fn tokenize(text: &str, diagnostics: &mut Vec<Diagnostic>) -> Vec<Line> {
+    let mut lines: Vec<Line> = vec![];
+
+    for (i, l) in normalize(text).lines().enumerate() {
+        if l.trim().is_empty() {
+            continue;
+        }
+
+        let number = i as u32 + 1;
+        let content = l.trim_start_matches(' ');
+        match content.strip_prefix(NOTE_MARKER) {
+            Some(note) => {
+                let indentation = (l.len() - content.len()) as u32;
+                match lines.last_mut() {
+                    Some(node) if indentation > node.indentation => {
+                        // a single space after the marker is part of the syntax
+                        let note = note.strip_prefix(' ').unwrap_or(note);
+                        node.note.push(note.trim_end().to_string());
+                        node.last_number = number;
+                    }
+                    _ => diagnostics.push(Diagnostic::new(
+                        number,
+                        "a note must be indented below the node it belongs to",
+                    )),
+                }
+            }
+            None => lines.push(tokenize_line(number, l, diagnostics)),
+        }
+    }
+
+    lines
+}
+
+/// Removes a leading byte order mark and converts Windows (`\r\n`) and
+/// classic Mac (`\r`) line endings to `\n`.
+fn normalize(text: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+fn tokenize_line(number: u32, text: &str, diagnostics: &mut Vec<Diagnostic>) -> Line {
+    let content = text.trim_start_matches(' ');
+    let indentation = (text.len() - content.len()) as u32;
+
+    if let Some(name) = content.strip_prefix(ALIAS_MARKER) {
+        let name = name.trim_end();
+        if !is_anchor_name(name) {
+            diagnostics.push(Diagnostic::new(
+                number,
+                &format!("invalid alias '{}{}'", ALIAS_MARKER, name),
+            ));
+        }
+
+        return Line {
+            number,
+            indentation,
+            title: name.to_string(),
+            anchor: None,
+            node_type: NodeType::Alias(name.to_string()),
+            attributes: vec![],
+            note: vec![],
+            last_number: number,
+        };
+    }
+
+    let (anchor, content) = match content.strip_prefix(ANCHOR_MARKER) {
+        Some(rest) => {
+            let (name, title) = rest.split_once(' ').unwrap_or((rest, ""));
+            if !is_anchor_name(name) {
+                diagnostics.push(Diagnostic::new(
+                    number,
+                    &format!("invalid anchor '{}{}'", ANCHOR_MARKER, name),
+                ));
+            }
+            (Some(name.to_string()), title.trim_start())
+        }
+        None => (None, content),
+    };
+
+    let (title, rest) = match split_title(content) {
+        Ok(split) => split,
+        Err(message) => {
+            diagnostics.push(Diagnostic::new(number, message));
+
+            return Line {
+                number,
+                indentation,
+                title: content.trim_end().to_string(),
+                anchor,
+                node_type: NodeType::Leaf(vec![]),
+                attributes: vec![],
+                note: vec![],
+                last_number: number,
+            };
+        }
+    };
+
+    let rest = rest.trim();
+    let (node_type, attributes) = if is_expression(rest) {
+        match parse_expression(rest) {
+            Ok(expression @ (Expression::And(_) | Expression::Or(_))) => {
+                (NodeType::Expression(expression), vec![])
+            }
+            Ok(Expression::Step(_)) => {
+                diagnostics.push(Diagnostic::new(
+                    number,
+                    "an expression needs '&' or '|' between its steps",
+                ));
+                (NodeType::Leaf(vec![]), vec![])
+            }
+            Err(message) => {
+                diagnostics.push(Diagnostic::new(number, message));
+                (NodeType::Leaf(vec![]), vec![])
+            }
+        }
+    } else if let Some(attributes) = rest.strip_prefix('&') {
+        (
+            NodeType::And,
+            tokenize_assessments(number, attributes.trim(), diagnostics),
+        )
+    } else if let Some(attributes) = rest.strip_prefix('|') {
+        (
+            NodeType::Or,
+            tokenize_assessments(number, attributes.trim(), diagnostics),
+        )
+    } else if let Some((votes, attributes)) = split_votes(rest) {
+        match votes {
+            Some((k, n)) => (
+                NodeType::Voting(k, n),
+                tokenize_assessments(number, attributes, diagnostics),
+            ),
+            None => {
+                diagnostics.push(Diagnostic::new(
+                    number,
+                    "invalid voting node, expected e.g. '2/3' for any 2 of 3 children",
+                ));
+                (NodeType::Voting(1, 1), vec![])
+            }
+        }
+    } else if let Some(extra) = rest.strip_prefix('~') {
+        if !extra.trim().is_empty() {
+            diagnostics.push(Diagnostic::new(
+                number,
+                "an assumption cannot have assessments",
+            ));
+        }
+        (NodeType::Assumption, vec![])
+    } else if let Some(effect) = rest.strip_prefix('!') {
+        (
+            NodeType::Defense(tokenize_assessments(number, effect.trim(), diagnostics)),
+            vec![],
+        )
+    } else {
+        (
+            NodeType::Leaf(tokenize_assessments(number, rest, diagnostics)),
+            vec![],
+        )
+    };
+
+    Line {
+        number,
+        indentation,
+        title,
+        anchor,
+        node_type,
+        attributes,
+        note: vec![],
+        last_number: number,
+    }
+}
+
+/// Splits a line at the ';' after the title into the title, without quotes,
+/// and the rest.
+pub(crate) fn split_title(content: &str) -> Result<(String, &str), &'static str> {
+    let Some(quoted) = content.strip_prefix(QUOTE) else {
+        return match content.split_once(';') {
+            Some((title, rest)) => Ok((title.trim_end().to_string(), rest)),
+            None => Err("expected ';' after the node title"),
+        };
+    };
+
+    let mut title = String::new();
+    let mut chars = quoted.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != QUOTE {
+            title.push(c);
+        } else if chars.next_if(|(_, c)| *c == QUOTE).is_some() {
+            title.push(QUOTE);
+        } else {
+            return match quoted[i + 1..].trim_start().strip_prefix(';') {
+                Some(rest) => Ok((title, rest)),
+                None => Err("expected ';' after the quoted title"),
+            };
+        }
+    }
+
+    Err("missing '\"' at the end of the quoted title")
+}
+
+/// The title as written in a .att file, quoted if it contains ';' or
+/// starts with a quote.
+pub fn quote_title(title: &str) -> Cow<'_, str> {
+    if title.contains(';') || title.starts_with(QUOTE) {
+        Cow::Owned(format!(
+            "{}{}{}",
+            QUOTE,
+            title.replace(QUOTE, "\"\""),
+            QUOTE
+        ))
+    } else {
+        Cow::Borrowed(title)
+    }
+}
+
+/// An inline expression has steps instead of assessments: it has no '=' and
+/// combines them with '&', '|' or parentheses.
+fn is_expression(rest: &str) -> bool {
+    !rest.contains('=')
+        && (rest.starts_with('(') || (!rest.starts_with(['&', '|']) && rest.contains(['&', '|'])))
+}
+
+/// Parses `(Pick lock | Break window) & Disable alarm`. `A & B & C` is one
+/// AND node, every pair of parentheses another node.
+fn parse_expression(text: &str) -> Result<Expression, &'static str> {
+    let mut tokens = text
+        .split_inclusive(['(', ')', '&', '|'])
+        .flat_map(|t| {
+            // separate the operator at the end from the title in front of it
+            let (title, operator) =
+                t.split_at(t.len() - t.ends_with(['(', ')', '&', '|']) as usize);
+            [title.trim(), operator]
+        })
+        .filter(|t| !t.is_empty())
+        .peekable();
+
+    let expression = parse_or(&mut tokens)?;
+    match tokens.next() {
+        None => Ok(expression),
+        Some(")") => Err("unexpected ')' in the expression"),
+        Some(_) => Err("expected '&' or '|' between the steps of the expression"),
+    }
+}
+
+fn parse_or<'a, I: Iterator<Item = &'a str>>(
+    tokens: &mut Peekable<I>,
+) -> Result<Expression, &'static str> {
+    let mut operands = vec![parse_and(tokens)?];
+    while tokens.next_if_eq(&"|").is_some() {
+        operands.push(parse_and(tokens)?);
+    }
+
+    Ok(combine(operands, Expression::Or))
+}
+
+fn parse_and<'a, I: Iterator<Item = &'a str>>(
+    tokens: &mut Peekable<I>,
+) -> Result<Expression, &'static str> {
+    let mut operands = vec![parse_operand(tokens)?];
+    while tokens.next_if_eq(&"&").is_some() {
+        operands.push(parse_operand(tokens)?);
+    }
+
+    Ok(combine(operands, Expression::And))
+}
+
+fn parse_operand<'a, I: Iterator<Item = &'a str>>(
+    tokens: &mut Peekable<I>,
+) -> Result<Expression, &'static str> {
+    match tokens.next() {
+        Some("(") => {
+            let expression = parse_or(tokens)?;
+            match tokens.next() {
+                Some(")") => Ok(expression),
+                _ => Err("missing ')' in the expression"),
+            }
+        }
+        Some(")" | "&" | "|") | None => Err("missing a step in the expression"),
+        Some(title) => Ok(Expression::Step(title.to_string())),
+    }
+}
+
+/// A single operand stays as it is.
+fn combine(
+    mut operands: Vec<Expression>,
+    operator: fn(Vec<Expression>) -> Expression,
+) -> Expression {
+    match operands.len() {
+        1 => operands.remove(0),
+        _ => operator(operands),
+    }
+}
+
+/// Letters, digits, '-' and '_'.
+fn is_anchor_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Splits `2/3 owner=x` into the vote counts and the attributes if the text
+/// starts with a digit. The counts are None if they are malformed.
+fn split_votes(text: &str) -> Option<(Option<(usize, usize)>, &str)> {
+    if !text.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (votes, attributes) = text.split_once(' ').unwrap_or((text, ""));
+    let counts = votes
+        .split_once('/')
+        .and_then(|(k, n)| Some((k.parse().ok()?, n.parse().ok()?)));
+
+    Some((counts, attributes.trim()))
+}
+
+fn tokenize_assessments(
+    number: u32,
+    text: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Assessment> {
+    if text.is_empty() {
+        return vec![];
+    }
+
+    let mut assessments = vec![];
+
+    for item in text.split(',') {
+        match item.split_once('=') {
+            Some((criterion, value)) if !criterion.trim().is_empty() => {
+                assessments.push(Assessment {
+                    criterion: criterion.trim().to_string(),
+                    value: value.trim().to_string(),
+                });
+            }
+            _ => diagnostics.push(Diagnostic::new(
+                number,
+                &format!("expected 'criterion=value' but found '{}'", item.trim()),
+            )),
+        }
+    }
+
+    assessments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_split_into_indentation_title_and_node_type() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize("Root;&\n\n    Step; Kn=5, Eq = 3\n", &mut diagnostics);
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            lines,
+            vec![
+                Line {
+                    number: 1,
+                    indentation: 0,
+                    title: "Root".to_string(),
+                    anchor: None,
+                    node_type: NodeType::And,
+                    attributes: vec![],
+                    note: vec![],
+                    last_number: 1,
+                },
+                Line {
+                    number: 3,
+                    indentation: 4,
+                    title: "Step".to_string(),
+                    anchor: None,
+                    node_type: NodeType::Leaf(vec![
+                        Assessment {
+                            criterion: "Kn".to_string(),
+                            value: "5".to_string(),
+                        },
+                        Assessment {
+                            criterion: "Eq".to_string(),
+                            value: "3".to_string(),
+                        },
+                    ]),
+                    attributes: vec![],
+                    note: vec![],
+                    last_number: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn anchors_are_split_from_the_title_and_aliases_are_nodes() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize(
+            "Root;&\n    &creds Get key; Kn=1\n    *creds\n    &a.b X; Kn=1",
+            &mut diagnostics,
+        );
+
+        assert_eq!(lines[1].title, "Get key");
+        assert_eq!(lines[1].anchor.as_deref(), Some("creds"));
+        assert_eq!(lines[2].node_type, NodeType::Alias("creds".to_string()));
+        assert_eq!(lines[2].indentation, 4);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::new(4, "invalid anchor '&a.b'")]
+        );
+    }
+
+    #[test]
+    fn voting_nodes_have_two_counts_and_attributes() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize("Root;2/3 x-asset=door\nOther;2of3", &mut diagnostics);
+
+        assert_eq!(lines[0].node_type, NodeType::Voting(2, 3));
+        assert_eq!(
+            lines[0].attributes,
+            vec![Assessment {
+                criterion: "x-asset".to_string(),
+                value: "door".to_string(),
+            }]
+        );
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::new(
+                2,
+                "invalid voting node, expected e.g. '2/3' for any 2 of 3 children"
+            )]
+        );
+    }
+
+    #[test]
+    fn byte_order_mark_and_carriage_returns_are_ignored() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize(
+            "\u{feff}Root;|\r\n    A; Kn=1\r\n    B; Kn=2\r    C; Kn=3\r\n",
+            &mut diagnostics,
+        );
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            lines
+                .iter()
+                .map(|l| (l.number, l.title.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "Root"), (2, "A"), (3, "B"), (4, "C")]
+        );
+        assert_eq!(lines[0].node_type, NodeType::Or);
+        assert_eq!(
+            lines[3].node_type,
+            NodeType::Leaf(vec![Assessment {
+                criterion: "Kn".to_string(),
+                value: "3".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn malformed_lines_produce_a_diagnostic_and_are_still_tokenized() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize("Root\n  Step; Kn5, Eq=1", &mut diagnostics);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].title, "Root");
+        assert_eq!(
+            diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn attributes_of_inner_nodes_follow_the_node_type() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize("Root;& owner=Alice, status=draft", &mut diagnostics);
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(lines[0].node_type, NodeType::And);
+        assert_eq!(
+            lines[0].attributes,
+            vec![
+                Assessment {
+                    criterion: "owner".to_string(),
+                    value: "Alice".to_string(),
+                },
+                Assessment {
+                    criterion: "status".to_string(),
+                    value: "draft".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_titles_may_contain_semicolons() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize(
+            "&auth \"Bypass auth; use default creds\" ;&\n    \"Say \"\"hi\"\"\"; Kn=1\n    \"Open; Kn=1\n    \"Closed\" Kn=1",
+            &mut diagnostics,
+        );
+
+        assert_eq!(lines[0].title, "Bypass auth; use default creds");
+        assert_eq!(lines[0].anchor, Some("auth".to_string()));
+        assert_eq!(lines[0].node_type, NodeType::And);
+        assert_eq!(lines[1].title, "Say \"hi\"");
+        assert_eq!(
+            diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn inline_expressions_bind_and_stronger_than_or() {
+        let step = |t: &str| Expression::Step(t.to_string());
+
+        assert_eq!(
+            parse_expression("A | B & C | (D | E)"),
+            Ok(Expression::Or(vec![
+                step("A"),
+                Expression::And(vec![step("B"), step("C")]),
+                Expression::Or(vec![step("D"), step("E")]),
+            ]))
+        );
+        assert!(parse_expression("(A | B").is_err());
+        assert!(parse_expression("A | B)").is_err());
+        assert!(parse_expression("A & | B").is_err());
+        assert!(parse_expression("(A) B").is_err());
+    }
+
+    #[test]
+    fn lines_with_steps_instead_of_assessments_are_expressions() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize(
+            "Root; (A | B) & C\n    Step; x-note=A & B\n    Other;& owner=Al",
+            &mut diagnostics,
+        );
+
+        assert!(diagnostics.is_empty());
+        assert!(matches!(
+            lines[0].node_type,
+            NodeType::Expression(Expression::And(_))
+        ));
+        assert!(matches!(lines[1].node_type, NodeType::Leaf(_)));
+        assert_eq!(lines[2].node_type, NodeType::And);
+    }
+
+    #[test]
+    fn note_lines_are_attached_to_the_node_above() {
+        let mut diagnostics = vec![];
+
+        let lines = tokenize(
+            "Root;&\n    > Assumes physical access.\n    >\n    >   Indented\n    Step; Kn=1\n> misplaced",
+            &mut diagnostics,
+        );
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0].note,
+            vec!["Assumes physical access.", "", "  Indented"]
+        );
+        assert_eq!(
+            diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+            vec![6]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+/// A built-in criteria definition with the rating levels of its standard.
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Contents of criteria.json.
+    pub criteria: &'static str,
+    /// Contents of att.toml with the rating levels.
+    pub config: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "iso21434",
+        description: "ISO/SAE 21434 attack potential (Annex G)",
+        criteria: ISO_21434_CRITERIA,
+        config: ISO_21434_CONFIG,
+    },
+    Preset {
+        name: "common-criteria",
+        description: "Common Criteria attack potential (CEM, ISO/IEC 18045)",
+        criteria: COMMON_CRITERIA_CRITERIA,
+        config: COMMON_CRITERIA_CONFIG,
+    },
+];
+
+pub fn find_preset(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|p| p.name == name)
+}
+
+const ISO_21434_CRITERIA: &str = r#"[
+  {
+    "id": "ET",
+    "name": "Elapsed time",
+    "max": 19,
+    "guidance": {"0": "up to 1 day", "1": "up to 1 week", "4": "up to 1 month", "17": "up to 6 months", "19": "more than 6 months"}
+  },
+  {
+    "id": "SE",
+    "name": "Specialist expertise",
+    "max": 8,
+    "guidance": {"0": "Layman", "3": "Proficient", "6": "Expert", "8": "Multiple experts"}
+  },
+  {
+    "id": "KoI",
+    "name": "Knowledge of the item or component",
+    "max": 11,
+    "guidance": {"0": "Public", "3": "Restricted", "7": "Confidential", "11": "Strictly confidential"}
+  },
+  {
+    "id": "WoO",
+    "name": "Window of opportunity",
+    "max": 10,
+    "guidance": {"0": "Unlimited", "1": "Easy", "4": "Moderate", "10": "Difficult"}
+  },
+  {
+    "id": "Eq",
+    "name": "Equipment",
+    "max": 9,
+    "guidance": {"0": "Standard", "4": "Specialized", "7": "Bespoke", "9": "Multiple bespoke"}
+  }
+]
+"#;
+
+const ISO_21434_CONFIG: &str = r#"# Attack feasibility rating of ISO/SAE 21434, Annex G
+[[ratings]]
+name = "High"
+max = 9
+
+[[ratings]]
+name = "Medium"
+max = 13
+
+[[ratings]]
+name = "Low"
+max = 19
+
+[[ratings]]
+name = "Very low"
+"#;
+
+const COMMON_CRITERIA_CRITERIA: &str = r#"[
+  {
+    "id": "ET",
+    "name": "Elapsed time",
+    "max": 19,
+    "guidance": {"0": "up to 1 day", "1": "up to 1 week", "2": "up to 2 weeks", "4": "up to 1 month", "7": "up to 2 months", "10": "up to 3 months", "13": "up to 4 months", "15": "up to 5 months", "17": "up to 6 months", "19": "more than 6 months"}
+  },
+  {
+    "id": "Ex",
+    "name": "Expertise",
+    "max": 8,
+    "guidance": {"0": "Layman", "3": "Proficient", "6": "Expert", "8": "Multiple experts"}
+  },
+  {
+    "id": "KoT",
+    "name": "Knowledge of the TOE",
+    "max": 11,
+    "guidance": {"0": "Public", "3": "Restricted", "7": "Sensitive", "11": "Critical"}
+  },
+  {
+    "id": "WoO",
+    "name": "Window of opportunity",
+    "max": 10,
+    "guidance": {"0": "Unnecessary or unlimited", "1": "Easy", "4": "Moderate", "10": "Difficult"}
+  },
+  {
+    "id": "Eq",
+    "name": "Equipment",
+    "max": 9,
+    "guidance": {"0": "Standard", "4": "Specialised", "7": "Bespoke", "9": "Multiple bespoke"}
+  }
+]
+"#;
+
+const COMMON_CRITERIA_CONFIG: &str = r#"# Attack potential of the Common Criteria evaluation methodology (CEM, B.4),
+# named by the attack potential the TOE resists
+[[ratings]]
+name = "No rating"
+max = 9
+
+[[ratings]]
+name = "Basic"
+max = 13
+
+[[ratings]]
+name = "Enhanced-Basic"
+max = 19
+
+[[ratings]]
+name = "Moderate"
+max = 24
+
+[[ratings]]
+name = "High"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, model::FeasibilityCriteria};
+
+    #[test]
+    fn all_presets_are_valid_criteria_and_configurations() {
+        for p in PRESETS {
+            let criteria = FeasibilityCriteria::from_json(p.criteria).unwrap();
+            let config = Config::from_toml(p.config).unwrap();
+
+            assert_eq!(criteria.0.len(), 5, "{}", p.name);
+            assert_eq!(criteria.max_feasibility_value(), Some(57), "{}", p.name);
+            assert!(config.ratings.last().unwrap().max.is_none(), "{}", p.name);
+        }
+
+        assert!(find_preset("iso21434").is_some());
+        assert!(find_preset("iso-21434").is_none());
+    }
+}
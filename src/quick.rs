@@ -0,0 +1,66 @@
+use std::{
+    io::{self, ErrorKind},
+    path::Path,
+    process::Command,
+};
+
+/// Criteria of `att quick` when no criteria file is given.
+pub const QUICK_CRITERIA: &str = r#"[
+  {"id": "Kn", "name": "Knowledge"},
+  {"id": "Eq", "name": "Equipment"}
+]"#;
+
+/// Commands printing the clipboard as text, tried in order.
+const PASTE_COMMANDS: &[&[&str]] = if cfg!(target_os = "macos") {
+    &[&["pbpaste"]]
+} else if cfg!(windows) {
+    &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]
+} else {
+    &[
+        &["wl-paste", "--no-newline"],
+        &["xclip", "-selection", "clipboard", "-out"],
+        &["xsel", "--clipboard", "--output"],
+    ]
+};
+
+/// The text on the clipboard, read with the paste command of the platform.
+pub fn read_clipboard() -> io::Result<String> {
+    for command in PASTE_COMMANDS {
+        match Command::new(command[0]).args(&command[1..]).output() {
+            Ok(output) if output.status.success() => {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Ok(_) => continue,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(io::Error::new(
+        ErrorKind::NotFound,
+        "no clipboard tool found, pipe the tree to stdin instead",
+    ))
+}
+
+/// Opens the file with the default application of the platform.
+pub fn open_with_viewer(file: &Path) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        // the empty title keeps `start` from taking a quoted path as one
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    let status = command.arg(file).status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!(
+            "the viewer exited with {}",
+            status
+        ))),
+    }
+}
@@ -0,0 +1,342 @@
+use std::rc::Rc;
+
+use crate::{
+    model::feasible_step::{FeasibleStep, NodeKind},
+    parser::{
+        quote_title, ASSESSED_KEY, ATTACK_KEY, DATE_KEY, DETECTION_KEY, IMPACT_KEY, NAMESPACE_KEY,
+        NOTE_MARKER, NUMBER_KEY, OWNER_KEY, PROFILE_KEY, REQUIREMENT_KEY, STATUS_KEY, UUID_KEY,
+        VERSION_KEY,
+    },
+};
+
+const INDENTATION: &str = "    ";
+
+pub fn render_to_att_string(root_node: &Rc<dyn FeasibleStep>) -> String {
+    let mut lines = vec![];
+    render_node(root_node, 0, &mut lines);
+
+    lines.join("\n")
+}
+
+fn render_node(node: &Rc<dyn FeasibleStep>, depth: usize, lines: &mut Vec<String>) {
+    let indentation = INDENTATION.repeat(depth);
+    let title = quote_title(node.title());
+
+    let mut attributes = vec![];
+    if let Some(owner) = &node.review().owner {
+        attributes.push(format!("{}={}", OWNER_KEY, owner));
+    }
+    if let Some(status) = node.review().status {
+        attributes.push(format!("{}={}", STATUS_KEY, status));
+    }
+    if let Some(version) = &node.review().version {
+        attributes.push(format!("{}={}", VERSION_KEY, version));
+    }
+    if let Some(date) = node.review().date {
+        attributes.push(format!("{}={}", DATE_KEY, date.format("%Y-%m-%d")));
+    }
+    if let Some(impact) = &node.review().impact {
+        attributes.push(format!("{}={}", IMPACT_KEY, impact));
+    }
+    if let Some(namespace) = &node.review().namespace {
+        attributes.push(format!("{}={}", NAMESPACE_KEY, namespace));
+    }
+    if let Some(number) = node.number() {
+        attributes.push(format!("{}={}", NUMBER_KEY, number));
+    }
+    if let Some(uuid) = node.uuid() {
+        attributes.push(format!("{}={}", UUID_KEY, uuid));
+    }
+    for r in node.requirements() {
+        attributes.push(format!("{}={}", REQUIREMENT_KEY, r));
+    }
+    for p in node.profiles() {
+        attributes.push(format!("{}={}", PROFILE_KEY, p));
+    }
+    for (key, value) in node.attributes() {
+        attributes.push(format!("{}={}", key, value));
+    }
+
+    match node.kind() {
+        NodeKind::And | NodeKind::Or | NodeKind::Voting => {
+            let node_type = match node.kind() {
+                NodeKind::And => "&".to_string(),
+                NodeKind::Or => "|".to_string(),
+                _ => format!(
+                    "{}/{}",
+                    node.threshold().unwrap_or(1),
+                    node.get_children().len()
+                ),
+            };
+            if attributes.is_empty() {
+                lines.push(format!("{}{};{}", indentation, title, node_type));
+            } else {
+                lines.push(format!(
+                    "{}{};{} {}",
+                    indentation,
+                    title,
+                    node_type,
+                    attributes.join(", ")
+                ));
+            }
+        }
+        NodeKind::Leaf => {
+            let mut assessments = match node.feasibility_range() {
+                Ok((easiest, hardest)) => easiest
+                    .iter()
+                    .filter_map(|(c, a)| match (a, hardest.by_id().get(&c.id)) {
+                        (Some(a), Some(b)) if a != b => Some(format!(
+                            "{}={}..{}",
+                            c.id,
+                            c.format_value(a.min(b)),
+                            c.format_value(a.max(b))
+                        )),
+                        (v, _) => v.map(|v| format!("{}={}", c.id, c.format_value(v))),
+                    })
+                    .collect::<Vec<_>>(),
+                Err(_) => vec![],
+            };
+
+            if let Some(date) = node.assessed() {
+                assessments.push(format!("{}={}", ASSESSED_KEY, date.format("%Y-%m-%d")));
+            }
+            if let Some(d) = node.detection() {
+                assessments.push(format!("{}={}", DETECTION_KEY, d));
+            }
+            for t in node.techniques() {
+                assessments.push(format!("{}={}", ATTACK_KEY, t));
+            }
+            assessments.append(&mut attributes);
+
+            lines.push(format!(
+                "{}{}; {}",
+                indentation,
+                title,
+                assessments.join(", ")
+            ));
+        }
+    }
+
+    if let Some(note) = node.note() {
+        render_note(note, depth + 1, lines);
+    }
+
+    for d in node.defenses() {
+        let effect: Vec<String> = d
+            .effect
+            .iter()
+            .filter_map(|(c, v)| v.map(|v| format!("{}={}", c.id, c.format_value(v))))
+            .collect();
+        lines.push(format!(
+            "{}{};! {}",
+            INDENTATION.repeat(depth + 1),
+            quote_title(&d.description),
+            effect.join(", ")
+        ));
+        if let Some(note) = &d.note {
+            render_note(note, depth + 2, lines);
+        }
+    }
+
+    for a in node.assumptions() {
+        lines.push(format!(
+            "{}{};~",
+            INDENTATION.repeat(depth + 1),
+            quote_title(&a.description)
+        ));
+        if let Some(note) = &a.note {
+            render_note(note, depth + 2, lines);
+        }
+    }
+
+    for c in node.get_children() {
+        render_node(&c, depth + 1, lines);
+    }
+}
+
+fn render_note(note: &str, depth: usize, lines: &mut Vec<String>) {
+    let note_indentation = INDENTATION.repeat(depth);
+    for l in note.lines() {
+        if l.is_empty() {
+            lines.push(format!("{}{}", note_indentation, NOTE_MARKER));
+        } else {
+            lines.push(format!("{}{} {}", note_indentation, NOTE_MARKER, l));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::model::{
+        feasible_step::FeasibleStep, or_node::OrNode, tests::build_criteria, AndNode, Leaf, Review,
+        ReviewStatus,
+    };
+
+    use super::render_to_att_string;
+    use crate::parser::AttackTreeParser;
+    use std::io::Cursor;
+
+    #[test]
+    fn a_tree_is_written_in_att_syntax() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[15, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        assert_eq!(
+            render_to_att_string(&root),
+            "Root;&\n    Step 1; Kn=15, Eq=5"
+        );
+    }
+
+    #[test]
+    fn owner_and_status_are_written_after_the_node_type() {
+        let root: Rc<dyn FeasibleStep> = Rc::new(OrNode {
+            review: Review {
+                owner: Some("Alice".to_string()),
+                status: Some(ReviewStatus::Approved),
+                namespace: Some("Door".to_string()),
+                ..Default::default()
+            },
+            ..OrNode::new("Root", None, || 1)
+        });
+
+        assert_eq!(
+            render_to_att_string(&root),
+            "Root;| owner=Alice, status=approved, namespace=Door"
+        );
+    }
+
+    #[test]
+    fn attack_techniques_are_written_after_the_assessment() {
+        let definition = build_criteria(&["Kn"]);
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            techniques: vec!["T1078".to_string(), "T1110.003".to_string()],
+            ..Leaf::new("Login", None, &definition, &[1], || 1)
+        });
+
+        assert_eq!(
+            render_to_att_string(&leaf),
+            "Login; Kn=1, attack=T1078, attack=T1110.003"
+        );
+    }
+
+    #[test]
+    fn requirements_are_written_as_attributes() {
+        let definition = build_criteria(&["Kn"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode {
+            requirements: vec!["SR-1".to_string()],
+            ..AndNode::new("Root", None, || 1)
+        });
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            requirements: vec!["SR-2".to_string()],
+            ..Leaf::new("Step", Some(root.clone()), &definition, &[1], || 2)
+        });
+        root.add_child(&leaf);
+
+        assert_eq!(
+            render_to_att_string(&root),
+            "Root;& requirement=SR-1\n    Step; Kn=1, requirement=SR-2"
+        );
+    }
+
+    #[test]
+    fn notes_are_written_below_their_node() {
+        let definition = build_criteria(&["Kn"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode {
+            note: Some("First\n\nSecond".to_string()),
+            ..AndNode::new("Root", None, || 1)
+        });
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step",
+            Some(root.clone()),
+            &definition,
+            &[1],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        assert_eq!(
+            render_to_att_string(&root),
+            "Root;&\n    > First\n    >\n    > Second\n    Step; Kn=1"
+        );
+    }
+
+    #[test]
+    fn custom_attributes_are_written_back() {
+        let text = "Root;& owner=Alice, x-ticket=SEC-12\n    Step; Kn=1, x-asset=ECU, x-cvss=7.5";
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        assert_eq!(render_to_att_string(&root), text);
+    }
+
+    #[test]
+    fn version_date_and_impact_are_written_back() {
+        let text =
+            "Root;& owner=Alice, version=1.2, date=2024-05-01, impact=Severe\n    Step; Kn=1";
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        assert_eq!(render_to_att_string(&root), text);
+    }
+
+    #[test]
+    fn voting_nodes_are_written_back() {
+        let text = "Root;2/3 x-asset=door\n    A; Kn=1\n    B; Kn=2\n    C; Kn=3";
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        assert_eq!(render_to_att_string(&root), text);
+    }
+
+    #[test]
+    fn ranges_are_written_back() {
+        let text = "Root;&\n    Step; Kn=2..4, Eq=1";
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        assert_eq!(render_to_att_string(&root), text);
+    }
+
+    #[test]
+    fn defenses_are_written_below_the_countered_node() {
+        let text =
+            "Root;&\n    Alarm;! Kn=2\n        > Monitored\n    Step; Kn=1\n        Lock;! Kn=1";
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        assert_eq!(render_to_att_string(&root), text);
+    }
+
+    #[test]
+    fn titles_with_semicolons_are_quoted() {
+        let text = "\"Bypass auth; use \"\"admin\"\"\";|\n    \"Guess; brute force\"; Kn=1\n    Read docs; Kn=2";
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        assert_eq!(root.title(), "Bypass auth; use \"admin\"");
+        assert_eq!(render_to_att_string(&root), text);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::model::feasible_step::FeasibleStep;
+
+use super::{render_to_dot_string_with_options, run_graphviz, RenderError, RenderOptions};
+
+/// Name of the renderer used unless another one is selected.
+pub const DEFAULT_RENDERER: &str = "graphviz";
+
+/// The rendered image of a tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    /// Extension of the file the image is written to, e.g. `png`.
+    pub extension: String,
+    pub contents: Vec<u8>,
+}
+
+/// A backend turning a tree into an image, selected by the name it is
+/// registered with in `Renderers`.
+pub trait Renderer {
+    /// Extension of the files the renderer produces with these options.
+    fn extension(&self, options: &RenderOptions) -> String;
+
+    fn render(
+        &self,
+        tree: &Rc<dyn FeasibleStep>,
+        options: &RenderOptions,
+    ) -> Result<Artifact, RenderError>;
+}
+
+/// Runs Graphviz on the DOT source, in the format of the options.
+pub struct GraphvizRenderer;
+
+impl Renderer for GraphvizRenderer {
+    fn extension(&self, options: &RenderOptions) -> String {
+        // formats like "svg:cairo" select a Graphviz renderer, the extension
+        // is the part before it
+        let format = options.format();
+        format.split(':').next().unwrap_or(format).to_string()
+    }
+
+    fn render(
+        &self,
+        tree: &Rc<dyn FeasibleStep>,
+        options: &RenderOptions,
+    ) -> Result<Artifact, RenderError> {
+        Ok(Artifact {
+            extension: self.extension(options),
+            contents: run_graphviz(tree, options, options.format(), &[])?,
+        })
+    }
+}
+
+/// Writes the DOT source itself, e.g. where Graphviz is not installed.
+pub struct DotRenderer;
+
+impl Renderer for DotRenderer {
+    fn extension(&self, _options: &RenderOptions) -> String {
+        "dot".to_string()
+    }
+
+    fn render(
+        &self,
+        tree: &Rc<dyn FeasibleStep>,
+        options: &RenderOptions,
+    ) -> Result<Artifact, RenderError> {
+        Ok(Artifact {
+            extension: self.extension(options),
+            contents: render_to_dot_string_with_options(tree, options)?.into_bytes(),
+        })
+    }
+}
+
+/// The renderers by name. `default` has the built-in `graphviz` and `dot`,
+/// others can be added with `register`.
+pub struct Renderers(BTreeMap<String, Box<dyn Renderer>>);
+
+impl Default for Renderers {
+    fn default() -> Self {
+        let mut renderers = Renderers(BTreeMap::new());
+        renderers.register(DEFAULT_RENDERER, Box::new(GraphvizRenderer));
+        renderers.register("dot", Box::new(DotRenderer));
+        renderers
+    }
+}
+
+impl Renderers {
+    /// Adds the renderer, replacing one with the same name.
+    pub fn register(&mut self, name: &str, renderer: Box<dyn Renderer>) {
+        self.0.insert(name.to_string(), renderer);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Renderer> {
+        self.0.get(name).map(|r| r.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    struct TitleRenderer;
+
+    impl Renderer for TitleRenderer {
+        fn extension(&self, _options: &RenderOptions) -> String {
+            "txt".to_string()
+        }
+
+        fn render(
+            &self,
+            tree: &Rc<dyn FeasibleStep>,
+            options: &RenderOptions,
+        ) -> Result<Artifact, RenderError> {
+            Ok(Artifact {
+                extension: self.extension(options),
+                contents: tree.title().as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn renderers_are_selected_by_name() {
+        let tree = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Enter house; Kn=1"),
+                &build_criteria(&["Kn"]),
+            )
+            .unwrap();
+        let mut renderers = Renderers::default();
+        renderers.register("title", Box::new(TitleRenderer));
+
+        assert_eq!(renderers.names(), vec!["dot", "graphviz", "title"]);
+
+        let options = RenderOptions::default();
+        let artifact = renderers.get("title").unwrap().render(&tree, &options);
+        assert_eq!(artifact.unwrap().contents, b"Enter house");
+
+        let dot = renderers
+            .get("dot")
+            .unwrap()
+            .render(&tree, &options)
+            .unwrap();
+        assert_eq!(dot.extension, "dot");
+        assert!(String::from_utf8(dot.contents)
+            .unwrap()
+            .starts_with("digraph"));
+
+        let options = RenderOptions {
+            format: Some("svg:cairo".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            renderers.get("graphviz").unwrap().extension(&options),
+            "svg"
+        );
+        assert!(renderers.get("tikz").is_none());
+    }
+}
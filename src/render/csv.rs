@@ -0,0 +1,193 @@
+use std::rc::Rc;
+
+use crate::{
+    analysis::{metrics::RunMetrics, simulation::Simulation},
+    model::{feasible_step::FeasibleStep, FeasibilityCriteria},
+};
+
+use super::{flatten, SIMULATION_PERCENTILES};
+
+/// Renders one row per node of all trees, including each node's aggregated
+/// feasibility vector.
+pub fn render_to_csv(
+    attack_trees: Vec<&Rc<dyn FeasibleStep>>,
+    definition: &FeasibilityCriteria,
+) -> String {
+    let mut header = vec![
+        "tree".to_string(),
+        "id".to_string(),
+        "uuid".to_string(),
+        "parent_id".to_string(),
+        "type".to_string(),
+        "title".to_string(),
+        "feasibility_value".to_string(),
+    ];
+    header.extend(definition.0.iter().map(|c| c.id.clone()));
+
+    let mut rows = vec![to_csv_row(&header)];
+
+    for root_node in attack_trees {
+        let mut nodes = vec![];
+        flatten(root_node, &mut nodes);
+
+        for node in nodes {
+            let mut row = vec![
+                root_node.title().to_string(),
+                node.id().to_string(),
+                node.uuid().unwrap_or_default().to_string(),
+                node.get_parent()
+                    .map(|p| p.id().to_string())
+                    .unwrap_or_default(),
+                node.kind().to_string(),
+                node.title().to_string(),
+                node.feasibility_value().to_string(),
+            ];
+
+            match node.feasibility() {
+                Ok(a) => row.extend(
+                    definition
+                        .0
+                        .iter()
+                        .map(|c| a.value(&c.id).map(|v| v.to_string()).unwrap_or_default()),
+                ),
+                Err(_) => row.extend(definition.0.iter().map(|_| String::new())),
+            }
+
+            rows.push(to_csv_row(&row));
+        }
+    }
+
+    rows.join("\n") + "\n"
+}
+
+/// Renders one row per simulated threat with the percentiles of the sampled
+/// feasibility values.
+pub fn render_simulation_to_csv(simulations: &[Simulation]) -> String {
+    let mut header = vec![
+        "tree".to_string(),
+        "file".to_string(),
+        "samples".to_string(),
+        "min".to_string(),
+    ];
+    header.extend(SIMULATION_PERCENTILES.iter().map(|p| format!("p{}", p)));
+    header.extend(["max".to_string(), "mean".to_string()]);
+
+    let mut rows = vec![to_csv_row(&header)];
+    for s in simulations {
+        let mut row = vec![
+            s.threat.clone(),
+            s.file
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            s.samples.len().to_string(),
+            s.percentile(0).to_string(),
+        ];
+        row.extend(
+            SIMULATION_PERCENTILES
+                .iter()
+                .map(|p| s.percentile(*p).to_string()),
+        );
+        row.extend([s.percentile(100).to_string(), format!("{:.2}", s.mean())]);
+        rows.push(to_csv_row(&row));
+    }
+
+    rows.join("\n") + "\n"
+}
+
+/// Renders the metrics of a run as a CSV row, preceded by the header for a
+/// new file.
+pub fn render_metrics_to_csv(metrics: &RunMetrics, with_header: bool) -> String {
+    let mut rows = vec![];
+    if with_header {
+        rows.push(to_csv_row(&[
+            "timestamp".to_string(),
+            "threat_count".to_string(),
+            "average_feasibility".to_string(),
+            "high_risk_count".to_string(),
+        ]));
+    }
+    rows.push(to_csv_row(&[
+        metrics.timestamp.clone(),
+        metrics.threat_count.to_string(),
+        format!("{:.2}", metrics.average_feasibility),
+        metrics
+            .high_risk_count
+            .map(|c| c.to_string())
+            .unwrap_or_default(),
+    ]));
+
+    rows.join("\n") + "\n"
+}
+
+pub(super) fn to_csv_row(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|f| escape(f))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::model::{feasible_step::FeasibleStep, tests::build_criteria, AndNode, Leaf};
+
+    use super::{render_metrics_to_csv, render_to_csv};
+    use crate::analysis::metrics::RunMetrics;
+
+    #[test]
+    fn every_node_is_exported_as_a_row() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root, main", None, || 1));
+        for (id, title, values) in [(2, "Step 1", [1, 5]), (3, "Step \"2\"", [3, 1])] {
+            let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+                title,
+                Some(root.clone()),
+                &definition,
+                &values,
+                || id,
+            ));
+            root.add_child(&leaf);
+        }
+
+        let result = render_to_csv(vec![&root], &definition);
+
+        let expected = r#"tree,id,uuid,parent_id,type,title,feasibility_value,Kn,Eq
+"Root, main",1,,,AND,"Root, main",8,3,5
+"Root, main",2,,1,Leaf,Step 1,6,1,5
+"Root, main",3,,1,Leaf,"Step ""2""",4,3,1
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn metrics_rows_have_a_header_only_in_new_files() {
+        let metrics = RunMetrics {
+            timestamp: "2024-06-01T12:00:00+02:00".to_string(),
+            threat_count: 3,
+            average_feasibility: 17.0 / 3.0,
+            high_risk_count: None,
+        };
+
+        assert_eq!(
+            render_metrics_to_csv(&metrics, true),
+            "timestamp,threat_count,average_feasibility,high_risk_count\n2024-06-01T12:00:00+02:00,3,5.67,\n"
+        );
+        assert_eq!(
+            render_metrics_to_csv(&metrics, false),
+            "2024-06-01T12:00:00+02:00,3,5.67,\n"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    analysis::baseline::{ChangeSet, ChangeStatus},
+    model::{feasible_step::FeasibleStep, traversal::preorder_with_depth},
+};
+
+use super::{escape_dot_string, render_to_dot_string_with_options, RenderError, RenderOptions};
+
+// fill colors with black text, readable with all themes
+const ADDED_COLOR: &str = "palegreen";
+const CHANGED_COLOR: &str = "khaki";
+const REMOVED_COLOR: &str = "lightcoral";
+
+/// Renders the tree with the changes since a baseline filled in: added nodes
+/// green, re-rated ones yellow and the `removed` ones, given by their title
+/// paths from the root, red below their former parents.
+pub fn render_diff_to_dot_string(
+    root_node: &Rc<dyn FeasibleStep>,
+    changes: &ChangeSet,
+    removed: &[&[String]],
+    options: &RenderOptions,
+) -> Result<String, RenderError> {
+    let mut dot = render_to_dot_string_with_options(root_node, options)?;
+
+    let mut lines = vec![];
+    let mut ids: HashMap<Vec<String>, String> = HashMap::new();
+    let mut path = vec![];
+    for (depth, node) in preorder_with_depth(root_node) {
+        path.truncate(depth);
+        path.push(node.title().to_string());
+        ids.insert(path.clone(), node.id().to_string());
+
+        let color = match changes.status(node.id()) {
+            Some(ChangeStatus::New) => ADDED_COLOR,
+            Some(ChangeStatus::Changed) => CHANGED_COLOR,
+            None => continue,
+        };
+        lines.push(format!(
+            "{} [style=filled fillcolor={} fontcolor=black]",
+            node.id(),
+            color
+        ));
+    }
+
+    for (i, path) in removed.iter().enumerate() {
+        let Some((title, parent_path)) = path.split_last() else {
+            continue;
+        };
+        let id = format!("removed{}", i);
+        lines.push(format!(
+            r#"{} [label="{}" style="filled,dashed" fillcolor={} fontcolor=black]"#,
+            id,
+            escape_dot_string(title),
+            REMOVED_COLOR
+        ));
+        if let Some(parent) = ids.get(parent_path) {
+            lines.push(format!("{} -> {} [style=dashed];", parent, id));
+        }
+        ids.insert(path.to_vec(), id);
+    }
+
+    if let Some(end) = dot.rfind('}') {
+        dot.insert_str(end, &format!("\n{}\n", lines.join("\n")));
+    }
+
+    Ok(dot)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::PathBuf};
+
+    use super::*;
+    use crate::{
+        analysis::baseline::Baseline, model::tests::build_criteria, parser::AttackTreeParser,
+    };
+
+    fn parse(text: &str) -> Vec<(PathBuf, Rc<dyn FeasibleStep>)> {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(&mut Cursor::new(text), &definition)
+            .unwrap();
+
+        vec![(PathBuf::from("tree.att"), root)]
+    }
+
+    #[test]
+    fn added_rerated_and_removed_nodes_are_filled() {
+        let baseline =
+            Baseline::from_trees(&parse("Root;|\n    A; Kn=1\n    B;&\n        C; Kn=2"));
+        let trees = parse("Root;|\n    A; Kn=3\n    D; Kn=1");
+        let (file, root) = &trees[0];
+        let id = |title: &str| {
+            preorder_with_depth(root)
+                .into_iter()
+                .find(|(_, n)| n.title() == title)
+                .unwrap()
+                .1
+                .id()
+        };
+
+        let dot = render_diff_to_dot_string(
+            root,
+            &baseline.compare(&trees),
+            &baseline.removed_nodes(file, root),
+            &RenderOptions::default(),
+        )
+        .unwrap();
+
+        assert!(dot.contains(&format!(
+            "{} [style=filled fillcolor=khaki fontcolor=black]",
+            id("A")
+        )));
+        assert!(dot.contains(&format!(
+            "{} [style=filled fillcolor=palegreen fontcolor=black]",
+            id("D")
+        )));
+        assert!(dot.contains(&format!("{} -> removed0 [style=dashed];", id("Root"))));
+        assert!(dot.contains("removed1 [label=\"C\" style=\"filled,dashed\" fillcolor=lightcoral"));
+        assert!(dot.contains("removed0 -> removed1 [style=dashed];"));
+        assert!(dot.ends_with("}"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::rc::Rc;
+
+use crate::model::{feasible_step::FeasibleStep, FeasibilityCriteria};
+
+use super::flatten;
+
+/// Renders all trees as one directed GraphML graph for tools like Gephi or
+/// yEd. Nodes carry their type, rating and aggregated feasibility vector.
+pub fn render_to_graphml(
+    attack_trees: Vec<&Rc<dyn FeasibleStep>>,
+    definition: &FeasibilityCriteria,
+) -> String {
+    let mut result = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="uuid" for="node" attr.name="uuid" attr.type="string"/>
+  <key id="tree" for="node" attr.name="tree" attr.type="string"/>
+  <key id="type" for="node" attr.name="type" attr.type="string"/>
+  <key id="feasibility_value" for="node" attr.name="feasibility_value" attr.type="int"/>
+"#,
+    );
+
+    for c in &definition.0 {
+        result.push_str(&format!(
+            "  <key id=\"c_{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"int\"/>\n",
+            escape(&c.id)
+        ));
+    }
+
+    result.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+
+    let mut edges = vec![];
+
+    for root_node in attack_trees {
+        let mut nodes = vec![];
+        flatten(root_node, &mut nodes);
+
+        for node in nodes {
+            result.push_str(&format!("    <node id=\"n{}\">\n", node.id()));
+            push_data(&mut result, "label", node.title());
+            if let Some(uuid) = node.uuid() {
+                push_data(&mut result, "uuid", uuid);
+            }
+            push_data(&mut result, "tree", root_node.title());
+            push_data(&mut result, "type", &node.kind().to_string());
+
+            if let Ok(a) = node.feasibility() {
+                push_data(&mut result, "feasibility_value", &a.sum().to_string());
+                for c in &definition.0 {
+                    if let Some(v) = a.value(&c.id) {
+                        push_data(&mut result, &format!("c_{}", c.id), &v.to_string());
+                    }
+                }
+            }
+
+            result.push_str("    </node>\n");
+
+            if let Some(parent) = node.get_parent() {
+                edges.push(format!(
+                    "    <edge source=\"n{}\" target=\"n{}\"/>\n",
+                    parent.id(),
+                    node.id()
+                ));
+            }
+        }
+    }
+
+    result.push_str(&edges.concat());
+    result.push_str("  </graph>\n</graphml>\n");
+
+    result
+}
+
+fn push_data(result: &mut String, key: &str, value: &str) {
+    result.push_str(&format!(
+        "      <data key=\"{}\">{}</data>\n",
+        escape(key),
+        escape(value)
+    ));
+}
+
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::model::{feasible_step::FeasibleStep, tests::build_criteria, AndNode, Leaf};
+
+    use super::render_to_graphml;
+
+    #[test]
+    fn nodes_carry_their_attributes_and_edges_point_to_the_children() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root & co", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step <1>",
+            Some(root.clone()),
+            &definition,
+            &[1, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let result = render_to_graphml(vec![&root], &definition);
+
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="uuid" for="node" attr.name="uuid" attr.type="string"/>
+  <key id="tree" for="node" attr.name="tree" attr.type="string"/>
+  <key id="type" for="node" attr.name="type" attr.type="string"/>
+  <key id="feasibility_value" for="node" attr.name="feasibility_value" attr.type="int"/>
+  <key id="c_Kn" for="node" attr.name="Kn" attr.type="int"/>
+  <key id="c_Eq" for="node" attr.name="Eq" attr.type="int"/>
+  <graph id="G" edgedefault="directed">
+    <node id="n1">
+      <data key="label">Root &amp; co</data>
+      <data key="tree">Root &amp; co</data>
+      <data key="type">AND</data>
+      <data key="feasibility_value">6</data>
+      <data key="c_Kn">1</data>
+      <data key="c_Eq">5</data>
+    </node>
+    <node id="n2">
+      <data key="label">Step &lt;1&gt;</data>
+      <data key="tree">Root &amp; co</data>
+      <data key="type">Leaf</data>
+      <data key="feasibility_value">6</data>
+      <data key="c_Kn">1</data>
+      <data key="c_Eq">5</data>
+    </node>
+    <edge source="n1" target="n2"/>
+  </graph>
+</graphml>
+"#;
+
+        assert_eq!(result, expected);
+    }
+}
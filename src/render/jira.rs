@@ -0,0 +1,195 @@
+use std::{collections::BTreeSet, rc::Rc};
+
+use serde_json::json;
+
+use crate::model::{feasible_step::FeasibleStep, rate, RatingLevel};
+
+use super::{csv::to_csv_row, flatten, RenderError};
+
+/// Label added to every issue, to find the imported threats in Jira.
+pub const JIRA_LABEL: &str = "attack-tree";
+
+/// One Jira issue per threat, i.e. per tree.
+#[derive(Debug, PartialEq)]
+pub struct JiraIssue {
+    pub summary: String,
+    /// Jira wiki markup.
+    pub description: String,
+    pub labels: Vec<String>,
+    pub feasibility: u32,
+    /// Name of the rating band of the feasibility, if ratings are configured.
+    pub rating: Option<String>,
+}
+
+/// Builds the issues of all trees. `image_url` is the location of a tree's
+/// image as the Jira users can reach it.
+pub fn build_jira_issues(
+    attack_trees: Vec<(String, &Rc<dyn FeasibleStep>)>,
+    ratings: &[RatingLevel],
+) -> Vec<JiraIssue> {
+    attack_trees
+        .into_iter()
+        .map(|(image_url, root)| {
+            let feasibility = root.feasibility_value();
+
+            let mut description = vec![format!("*Feasibility:* {}", feasibility)];
+            if let Ok(a) = root.feasibility() {
+                let values: Vec<String> = a
+                    .iter()
+                    .map(|(c, v)| format!("{}={}", c.id, c.format_value(v.unwrap_or(0))))
+                    .collect();
+                description[0].push_str(&format!(" ({})", values.join(", ")));
+            }
+            if let Some(owner) = &root.review().owner {
+                description.push(format!("*Owner:* {}", owner));
+            }
+            if let Some(note) = root.note() {
+                description.push(note.to_string());
+            }
+            description.push(format!("!{}!", image_url));
+
+            let mut nodes = vec![];
+            flatten(root, &mut nodes);
+            let techniques: BTreeSet<&String> = nodes.iter().flat_map(|n| n.techniques()).collect();
+
+            let mut labels = vec![JIRA_LABEL.to_string()];
+            labels.extend(techniques.into_iter().cloned());
+
+            JiraIssue {
+                summary: root.title().to_string(),
+                description: description.join("\n\n"),
+                labels,
+                feasibility,
+                rating: rate(ratings, feasibility).map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+/// Renders the issues for the Jira CSV importer. Labels are repeated
+/// columns, which the importer joins into one field.
+pub fn render_jira_csv(issues: &[JiraIssue], issue_type: &str) -> String {
+    let label_columns = issues.iter().map(|i| i.labels.len()).max().unwrap_or(0);
+
+    let mut header = vec![
+        "Summary".to_string(),
+        "Issue Type".to_string(),
+        "Description".to_string(),
+        "Feasibility".to_string(),
+        "Risk Rating".to_string(),
+    ];
+    header.extend((0..label_columns).map(|_| "Labels".to_string()));
+
+    let mut rows = vec![to_csv_row(&header)];
+    for issue in issues {
+        let mut row = vec![
+            issue.summary.clone(),
+            issue_type.to_string(),
+            issue.description.clone(),
+            issue.feasibility.to_string(),
+            issue.rating.clone().unwrap_or_default(),
+        ];
+        row.extend((0..label_columns).map(|i| issue.labels.get(i).cloned().unwrap_or_default()));
+        rows.push(to_csv_row(&row));
+    }
+
+    rows.join("\n") + "\n"
+}
+
+/// Renders the issues in the format of the Jira JSON importer.
+pub fn render_jira_json(issues: &[JiraIssue], issue_type: &str) -> Result<String, RenderError> {
+    let issues: Vec<_> = issues
+        .iter()
+        .map(|i| {
+            let mut custom_fields = vec![json!({
+                "fieldName": "Feasibility",
+                "fieldType": "com.atlassian.jira.plugin.system.customfieldtypes:float",
+                "value": i.feasibility.to_string(),
+            })];
+            if let Some(rating) = &i.rating {
+                custom_fields.push(json!({
+                    "fieldName": "Risk Rating",
+                    "fieldType": "com.atlassian.jira.plugin.system.customfieldtypes:textfield",
+                    "value": rating,
+                }));
+            }
+
+            json!({
+                "summary": i.summary,
+                "issueType": issue_type,
+                "description": i.description,
+                "labels": i.labels,
+                "customFieldValues": custom_fields,
+            })
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(
+        &json!({ "projects": [{ "issues": issues }] }),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::model::{
+        feasible_step::FeasibleStep, tests::build_criteria, AndNode, Leaf, RatingLevel,
+    };
+
+    use super::*;
+
+    fn build_tree() -> Rc<dyn FeasibleStep> {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode {
+            note: Some("Seen in the wild".to_string()),
+            ..AndNode::new("Enter house", None, || 1)
+        });
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            techniques: vec!["T1200".to_string()],
+            ..Leaf::new("Pick lock", Some(root.clone()), &definition, &[5, 3], || 2)
+        });
+        root.add_child(&leaf);
+
+        root
+    }
+
+    #[test]
+    fn every_tree_becomes_an_issue() {
+        let root = build_tree();
+        let ratings = vec![RatingLevel {
+            name: "High".to_string(),
+            max: None,
+        }];
+
+        let issues = build_jira_issues(
+            vec![("https://example.com/images/house.png".to_string(), &root)],
+            &ratings,
+        );
+
+        assert_eq!(
+            issues,
+            vec![JiraIssue {
+                summary: "Enter house".to_string(),
+                description: "*Feasibility:* 8 (Kn=5, Eq=3)\n\nSeen in the wild\n\n!https://example.com/images/house.png!".to_string(),
+                labels: vec!["attack-tree".to_string(), "T1200".to_string()],
+                feasibility: 8,
+                rating: Some("High".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn labels_are_written_to_repeated_columns() {
+        let root = build_tree();
+        let issues = build_jira_issues(vec![("house.png".to_string(), &root)], &[]);
+
+        let csv = render_jira_csv(&issues, "Task");
+
+        assert_eq!(
+            csv.lines().next(),
+            Some("Summary,Issue Type,Description,Feasibility,Risk Rating,Labels,Labels")
+        );
+        assert!(csv.ends_with(",8,,attack-tree,T1200\n"));
+    }
+}
@@ -0,0 +1,161 @@
+use std::{path::PathBuf, rc::Rc};
+
+use serde_json::{json, Map, Value};
+
+use crate::model::feasible_step::FeasibleStep;
+
+use super::RenderError;
+
+pub fn render_to_json(
+    attack_trees: Vec<(PathBuf, &Rc<dyn FeasibleStep>)>,
+) -> Result<String, RenderError> {
+    let trees: Vec<Value> = attack_trees
+        .iter()
+        .map(|(image_path, root_node)| {
+            json!({
+                "image": image_path.to_str().unwrap_or(""),
+                "root": node_to_json(root_node),
+            })
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&trees)?)
+}
+
+/// The node with its subtree as in threats.json.
+pub fn node_to_json(node: &Rc<dyn FeasibleStep>) -> Value {
+    let feasibility = match node.feasibility() {
+        Ok(a) => Value::Object(
+            a.iter()
+                .map(|(c, v)| (c.id.clone(), json!(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Err(_) => Value::Null,
+    };
+
+    let mut value = json!({
+        "id": node.id(),
+        "title": node.title(),
+        "type": node.kind(),
+        "feasibility_value": node.feasibility_value(),
+        "feasibility": feasibility,
+        "children": node.get_children().iter().map(node_to_json).collect::<Vec<_>>(),
+    });
+
+    if let Some(threshold) = node.threshold() {
+        value["threshold"] = json!(threshold);
+    }
+
+    if let Some(namespace) = &node.review().namespace {
+        value["namespace"] = json!(namespace);
+    }
+
+    if let Some((lowest, highest)) = node.feasibility_interval() {
+        value["feasibility_range"] = json!([lowest, highest]);
+    }
+
+    if let Some(uuid) = node.uuid() {
+        value["uuid"] = json!(uuid);
+    }
+
+    if let Some(note) = node.note() {
+        value["note"] = json!(note);
+    }
+
+    if let Some(d) = node.detection() {
+        value["detection"] = json!(d);
+    }
+
+    if !node.techniques().is_empty() {
+        value["attack"] = json!(node.techniques());
+    }
+
+    if !node.requirements().is_empty() {
+        value["requirements"] = json!(node.requirements());
+    }
+
+    if !node.profiles().is_empty() {
+        value["profiles"] = json!(node.profiles());
+    }
+
+    if !node.attributes().is_empty() {
+        value["attributes"] = json!(node.attributes());
+    }
+
+    if !node.defenses().is_empty() {
+        value["defenses"] = node
+            .defenses()
+            .iter()
+            .map(|d| {
+                json!({
+                    "id": d.id,
+                    "title": d.description,
+                    "effect": d
+                        .effect
+                        .iter()
+                        .filter_map(|(c, v)| v.map(|v| (c.id.clone(), json!(v))))
+                        .collect::<Map<String, Value>>(),
+                })
+            })
+            .collect();
+    }
+
+    if !node.assumptions().is_empty() {
+        value["assumptions"] = node
+            .assumptions()
+            .iter()
+            .map(|a| json!({ "id": a.id, "title": a.description }))
+            .collect();
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, rc::Rc};
+
+    use serde_json::{json, Value};
+
+    use crate::model::{feasible_step::FeasibleStep, or_node::OrNode, tests::build_criteria, Leaf};
+
+    use super::render_to_json;
+
+    #[test]
+    fn every_node_is_exported_with_its_feasibility_vector() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(OrNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[15, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let result = render_to_json(vec![(PathBuf::from("images/root.png"), &root)]).unwrap();
+
+        let expected = json!([{
+            "image": "images/root.png",
+            "root": {
+                "id": 1,
+                "title": "Root",
+                "type": "or",
+                "feasibility_value": 20,
+                "feasibility": {"Kn": 15, "Eq": 5},
+                "children": [{
+                    "id": 2,
+                    "title": "Step 1",
+                    "type": "leaf",
+                    "feasibility_value": 20,
+                    "feasibility": {"Kn": 15, "Eq": 5},
+                    "children": [],
+                }],
+            },
+        }]);
+
+        assert_eq!(serde_json::from_str::<Value>(&result).unwrap(), expected);
+    }
+}
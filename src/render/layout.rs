@@ -0,0 +1,285 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use serde::Deserialize;
+
+use super::theme::Theme;
+use crate::parser::CUSTOM_ATTRIBUTE_PREFIX;
+
+/// The node types icons can be set for, see `NodeKind`.
+const NODE_TYPES: [&str; 4] = ["and", "or", "voting", "leaf"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum RankDir {
+    TB,
+    LR,
+    BT,
+    RL,
+}
+
+impl FromStr for RankDir {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "TB" => Ok(RankDir::TB),
+            "LR" => Ok(RankDir::LR),
+            "BT" => Ok(RankDir::BT),
+            "RL" => Ok(RankDir::RL),
+            _ => Err(format!(
+                "unknown rank direction '{}', use TB, LR, BT or RL",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for RankDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Splines {
+    Spline,
+    Line,
+    Polyline,
+    Ortho,
+    Curved,
+    None,
+}
+
+impl FromStr for Splines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "spline" => Ok(Splines::Spline),
+            "line" => Ok(Splines::Line),
+            "polyline" => Ok(Splines::Polyline),
+            "ortho" => Ok(Splines::Ortho),
+            "curved" => Ok(Splines::Curved),
+            "none" => Ok(Splines::None),
+            _ => Err(format!(
+                "unknown spline style '{}', use spline, line, polyline, ortho, curved or none",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Splines {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// The Graphviz program used to lay out the graph.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    #[default]
+    Dot,
+    Neato,
+    Twopi,
+    Circo,
+    Fdp,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(Engine::Dot),
+            "neato" => Ok(Engine::Neato),
+            "twopi" => Ok(Engine::Twopi),
+            "circo" => Ok(Engine::Circo),
+            "fdp" => Ok(Engine::Fdp),
+            _ => Err(format!(
+                "unknown layout engine '{}', use dot, neato, twopi, circo or fdp",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Resolution Graphviz renders bitmap images with if no `dpi` is given.
+const DEFAULT_DPI: u32 = 96;
+
+/// Graphviz graph attributes controlling the layout of rendered trees. Unset
+/// values keep the Graphviz defaults.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutOptions {
+    pub rankdir: Option<RankDir>,
+    pub ranksep: Option<f32>,
+    pub nodesep: Option<f32>,
+    pub splines: Option<Splines>,
+    pub dpi: Option<u32>,
+    /// Largest width of the images in pixels at `dpi`, 96 if not set. Wider
+    /// graphs are scaled down.
+    pub max_width: Option<u32>,
+    /// Adds a legend of the node shapes, criteria and ratings to the graph.
+    pub legend: Option<bool>,
+    /// Adds the title, version, date, owner and status of the tree above
+    /// the graph.
+    pub banner: Option<bool>,
+    pub theme: Option<Theme>,
+    /// Icons or emoji put in front of the labels, keyed by node type (`and`,
+    /// `or`, `voting` or `leaf`) or by custom attribute (`x-vector=remote`).
+    pub icons: Option<BTreeMap<String, String>>,
+    /// Draws a box around each subtree of an inner node at this depth, 1
+    /// for the children of the root.
+    pub clusters: Option<usize>,
+    /// Labels the edges with the feasibility value of the child and
+    /// highlights the easiest child of each OR node.
+    pub edge_labels: Option<bool>,
+}
+
+impl LayoutOptions {
+    /// Combines two sets of options, values set in `overrides` win.
+    pub fn merge(&self, overrides: &LayoutOptions) -> LayoutOptions {
+        LayoutOptions {
+            rankdir: overrides.rankdir.or(self.rankdir),
+            ranksep: overrides.ranksep.or(self.ranksep),
+            nodesep: overrides.nodesep.or(self.nodesep),
+            splines: overrides.splines.or(self.splines),
+            dpi: overrides.dpi.or(self.dpi),
+            max_width: overrides.max_width.or(self.max_width),
+            legend: overrides.legend.or(self.legend),
+            banner: overrides.banner.or(self.banner),
+            theme: overrides.theme.or(self.theme),
+            icons: overrides.icons.clone().or_else(|| self.icons.clone()),
+            clusters: overrides.clusters.or(self.clusters),
+            edge_labels: overrides.edge_labels.or(self.edge_labels),
+        }
+    }
+
+    /// Keys of `icons` that can never match a node.
+    pub fn unknown_icon_keys(&self) -> Vec<&str> {
+        let Some(icons) = &self.icons else {
+            return vec![];
+        };
+
+        icons
+            .keys()
+            .filter(|k| match k.split_once('=') {
+                Some((attribute, _)) => !attribute.starts_with(CUSTOM_ATTRIBUTE_PREFIX),
+                None => !NODE_TYPES.contains(&k.as_str()),
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub(crate) fn to_dot_attributes(&self) -> Option<String> {
+        let mut attributes = vec![];
+
+        if let Some(v) = self.rankdir {
+            attributes.push(format!("rankdir={}", v));
+        }
+        if let Some(v) = self.ranksep {
+            attributes.push(format!("ranksep={}", v));
+        }
+        if let Some(v) = self.nodesep {
+            attributes.push(format!("nodesep={}", v));
+        }
+        if let Some(v) = self.splines {
+            attributes.push(format!("splines={}", v));
+        }
+        if let Some(v) = self.dpi {
+            attributes.push(format!("dpi={}", v));
+        }
+        if let Some(v) = self.max_width {
+            // Graphviz takes the size in inches, the height is not limited
+            let inches = v as f32 / self.dpi.unwrap_or(DEFAULT_DPI) as f32;
+            attributes.push(format!("size=\"{:.2},1000\"", inches));
+        }
+
+        if attributes.is_empty() {
+            None
+        } else {
+            Some(format!("graph [{}]", attributes.join(" ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_read_from_a_style_file() {
+        let options: LayoutOptions =
+            serde_json::from_str(r#"{"rankdir": "LR", "splines": "ortho", "dpi": 300}"#).unwrap();
+
+        assert_eq!(
+            options.to_dot_attributes(),
+            Some("graph [rankdir=LR splines=ortho dpi=300]".to_string())
+        );
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let file = LayoutOptions {
+            rankdir: Some(RankDir::LR),
+            ranksep: Some(0.5),
+            ..Default::default()
+        };
+        let cli = LayoutOptions {
+            rankdir: Some(RankDir::BT),
+            ..Default::default()
+        };
+
+        let merged = file.merge(&cli);
+
+        assert_eq!(merged.rankdir, Some(RankDir::BT));
+        assert_eq!(merged.ranksep, Some(0.5));
+    }
+
+    #[test]
+    fn engines_are_parsed_case_insensitively() {
+        assert_eq!("Neato".parse::<Engine>(), Ok(Engine::Neato));
+        assert!("graphviz".parse::<Engine>().is_err());
+    }
+
+    #[test]
+    fn icons_are_set_for_node_types_and_custom_attributes() {
+        let options: LayoutOptions = serde_json::from_str(
+            r#"{"icons": {"leaf": "*", "x-vector=remote": "R", "x": "?", "owner=me": "!"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(options.unknown_icon_keys(), vec!["owner=me", "x"]);
+    }
+
+    #[test]
+    fn the_max_width_is_converted_to_inches_at_the_resolution() {
+        let options = LayoutOptions {
+            dpi: Some(192),
+            max_width: Some(960),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.to_dot_attributes(),
+            Some("graph [dpi=192 size=\"5.00,1000\"]".to_string())
+        );
+        assert_eq!(
+            options.merge(&LayoutOptions::default()).max_width,
+            Some(960)
+        );
+    }
+
+    #[test]
+    fn without_options_no_attributes_are_emitted() {
+        assert_eq!(LayoutOptions::default().to_dot_attributes(), None);
+    }
+}
@@ -0,0 +1,147 @@
+use std::rc::Rc;
+
+use crate::model::feasible_step::{FeasibleStep, NodeKind};
+
+use super::{escape_dot_string, RenderOptions};
+
+/// A cluster explaining the shapes of the node types used in `nodes`, the
+/// criteria of the assessments, the rating levels and the change badges.
+pub(super) fn render_legend(nodes: &[Rc<dyn FeasibleStep>], options: &RenderOptions) -> String {
+    let mut entries = vec![];
+    let palette = options.layout.theme.unwrap_or_default().palette();
+
+    let uses = |kind: NodeKind| nodes.iter().any(|n| n.kind() == kind);
+    if uses(NodeKind::And) {
+        entries.push(r#"legend_and [label="AND\nall children" shape=trapezium]"#.to_string());
+    }
+    if uses(NodeKind::Or) {
+        entries.push(r#"legend_or [label="OR\neasiest child" shape=invtrapezium]"#.to_string());
+    }
+    if uses(NodeKind::Voting) {
+        entries.push(
+            r#"legend_voting [label="k of n\neasiest k children" shape=hexagon]"#.to_string(),
+        );
+    }
+    if uses(NodeKind::Leaf) {
+        entries.push(r#"legend_leaf [label="Attack step"]"#.to_string());
+    }
+    if nodes.iter().any(|n| !n.defenses().is_empty()) {
+        entries.push(format!(
+            r#"legend_defense [label="Defense" shape=box style="rounded,dashed" color={}]"#,
+            palette.defense
+        ));
+    }
+    if nodes.iter().any(|n| !n.assumptions().is_empty()) {
+        entries.push(format!(
+            r#"legend_assumption [label="Assumption" shape=note style=dashed color={0} fontcolor={0}]"#,
+            palette.assumption
+        ));
+    }
+
+    if let Some(assessment) = nodes.first().and_then(|n| n.feasibility().ok()) {
+        let criteria: Vec<String> = assessment
+            .definition()
+            .shown()
+            .iter()
+            .map(|(id, name)| format!("{}: {}", id, name))
+            .collect();
+        entries.push(plain_text("legend_criteria", &criteria));
+    }
+
+    if let Some(icons) = &options.layout.icons {
+        let lines: Vec<String> = icons
+            .iter()
+            .map(|(key, icon)| format!("{} {}", icon, key))
+            .collect();
+        entries.push(plain_text("legend_icons", &lines));
+    }
+
+    if !options.ratings.is_empty() {
+        let mut lowest = 0;
+        let levels: Vec<String> = options
+            .ratings
+            .iter()
+            .map(|l| {
+                let range = match l.max {
+                    Some(max) => format!("{}..{}", lowest, max),
+                    None => format!("{} and above", lowest),
+                };
+                lowest = l.max.map_or(lowest, |m| m + 1);
+                format!("{}: {}", l.name, range)
+            })
+            .collect();
+        entries.push(plain_text("legend_ratings", &levels));
+    }
+
+    if options.changes.is_some() {
+        entries.push(format!(
+            r#"legend_new [label="Added since\nthe baseline" xlabel="NEW" color={} penwidth=2]"#,
+            palette.new
+        ));
+        entries.push(format!(
+            r#"legend_changed [label="Modified since\nthe baseline" xlabel="CHANGED" color={} penwidth=2]"#,
+            palette.changed
+        ));
+    }
+
+    format!(
+        "subgraph cluster_legend {{\nlabel=\"Legend\"\nstyle=dashed\n{}\n}}",
+        entries.join("\n")
+    )
+}
+
+/// A node without border listing `lines` left-aligned.
+fn plain_text(name: &str, lines: &[String]) -> String {
+    let label: String = lines
+        .iter()
+        .map(|l| format!("{}\\l", escape_dot_string(l)))
+        .collect();
+
+    format!(r#"{} [label="{}" shape=plaintext]"#, name, label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{tests::build_criteria, AndNode, Leaf, RatingLevel};
+
+    #[test]
+    fn the_legend_lists_the_used_shapes_criteria_and_ratings() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step",
+            Some(root.clone()),
+            &definition,
+            &[1, 2],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let options = RenderOptions {
+            ratings: vec![
+                RatingLevel {
+                    name: "High".to_string(),
+                    max: Some(13),
+                },
+                RatingLevel {
+                    name: "Low".to_string(),
+                    max: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_legend(&[root, leaf], &options),
+            r#"subgraph cluster_legend {
+label="Legend"
+style=dashed
+legend_and [label="AND\nall children" shape=trapezium]
+legend_leaf [label="Attack step"]
+legend_criteria [label="Kn: Kn\lEq: Eq\l" shape=plaintext]
+legend_ratings [label="High: 0..13\lLow: 14 and above\l" shape=plaintext]
+}"#
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use markdown_table_formatter::format_tables;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::{
     io::Write,
@@ -8,7 +8,59 @@ use std::{
 };
 use thiserror::Error;
 
-use crate::model::feasible_step::FeasibleStep;
+use crate::{
+    analysis::{
+        baseline::{ChangeSet, ChangeStatus},
+        grouping::group_threats,
+    },
+    model::{
+        feasible_step::{escape_dot_string, format_probability, FeasibleStep, NodeKind},
+        rate,
+        traversal::{preorder, preorder_with_depth},
+        FeasibilityCriteria, NodeNumbers, RatingLevel,
+    },
+};
+
+mod att_file;
+mod backend;
+mod csv;
+mod diff;
+mod graphml;
+mod jira;
+mod json;
+mod layout;
+mod legend;
+mod otm;
+mod projects;
+mod report;
+mod risk_matrix;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod template;
+mod theme;
+
+pub use att_file::render_to_att_string;
+pub use backend::{Artifact, DotRenderer, GraphvizRenderer, Renderer, Renderers, DEFAULT_RENDERER};
+pub use csv::{render_metrics_to_csv, render_simulation_to_csv, render_to_csv};
+pub use diff::render_diff_to_dot_string;
+pub use graphml::render_to_graphml;
+pub use jira::{build_jira_issues, render_jira_csv, render_jira_json, JiraIssue};
+pub use json::{node_to_json, render_to_json};
+pub use layout::{Engine, LayoutOptions, RankDir, Splines};
+pub use otm::render_to_otm;
+pub use projects::{render_projects_to_markdown, ProjectSummary};
+pub use report::{
+    add_changelog_entry, render_assumptions_to_markdown, render_attribute_summaries_to_markdown,
+    render_changelog_entry, render_coverage_matrix_to_markdown, render_duplicates_to_markdown,
+    render_rating_method_to_markdown, render_residual_feasibility_to_markdown,
+    render_simulation_to_markdown, render_stale_assessments_to_markdown,
+    render_techniques_to_markdown, render_weakest_leaves_to_markdown, SIMULATION_PERCENTILES,
+};
+pub use risk_matrix::{RiskMatrix, DEFAULT_IMPACT_LEVELS};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{render_to_sqlite, SQLITE_SCHEMA};
+pub use template::{render_report_with_template, render_tree_with_template};
+pub use theme::Theme;
 
 #[derive(Error, Debug)]
 pub enum RenderError {
@@ -16,93 +68,996 @@ pub enum RenderError {
     PathError,
     #[error("File write error")]
     FileWriteError(#[from] io::Error),
+    #[error("JSON serialization error")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Graphviz '{0}' not found, install Graphviz and make sure it is on the PATH")]
+    GraphvizNotFound(String),
+    #[error("Template error: {0}")]
+    TemplateError(String),
+    #[error("Graphviz '{0}' failed: {1}")]
+    GraphvizFailed(String, String),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+}
+
+#[derive(Debug, Default)]
+pub struct RenderOptions {
+    /// Nodes added or modified since the baseline, highlighted when set.
+    pub changes: Option<ChangeSet>,
+    pub layout: LayoutOptions,
+    pub engine: Engine,
+    /// Graphviz output format (`-T`), e.g. png, svg or pdf.
+    pub format: Option<String>,
+    /// Rating bands of the feasibility values, adds a rating column to the
+    /// overview when set.
+    pub ratings: Vec<RatingLevel>,
+    /// Page the nodes of the graph link to. Tree pages get an anchor for
+    /// every node when set.
+    pub node_links: Option<String>,
+    /// Numbers shown in front of the titles in the reports when set.
+    pub numbers: Option<NodeNumbers>,
+    /// Name of the backend rendering the images, see `Renderers`.
+    pub renderer: Option<String>,
+    /// Merges threats with the same `threat_key` into one row of the
+    /// overview, which shows the easiest of them and lists their files.
+    pub group_threats: bool,
+    /// Directory of the tree pages relative to the overview. When set, the
+    /// overview links every threat to its page and the pages link back.
+    pub tree_pages: Option<PathBuf>,
+    /// Adds a table per OR node to the tree pages comparing its children.
+    pub compare_or: bool,
+}
+
+impl RenderOptions {
+    pub fn format(&self) -> &str {
+        self.format.as_deref().unwrap_or(DEFAULT_FORMAT)
+    }
+
+    pub fn renderer(&self) -> &str {
+        self.renderer.as_deref().unwrap_or(DEFAULT_RENDERER)
+    }
+
+    /// Whether the output format keeps the links of the nodes.
+    pub fn supports_links(&self) -> bool {
+        self.format().starts_with("svg")
+    }
 }
 
-pub fn render_to_png(
+/// The anchor of a node on its tree page, from the position of the node in
+/// depth-first order. Unlike node ids it stays the same when the tree is
+/// parsed again.
+pub fn node_anchor(position: usize) -> String {
+    format!("node-{}", position)
+}
+
+/// The overview of all threats, next to the directory of the tree pages.
+pub const OVERVIEW_FILE: &str = "threats.md";
+
+/// Output format passed to Graphviz when none is configured.
+pub const DEFAULT_FORMAT: &str = "png";
+
+/// Checks whether the Graphviz program of `engine` can be run.
+pub fn graphviz_available(engine: Engine) -> bool {
+    Command::new(engine.to_string())
+        .arg("-V")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// Writes the DOT source of a tree without running Graphviz.
+pub fn render_to_dot_file(
     root_node: &Rc<dyn FeasibleStep>,
-    file_path: &PathBuf,
+    file_path: &Path,
+    options: &RenderOptions,
+) -> Result<(), RenderError> {
+    let dot_file_content = render_to_dot_string_with_options(root_node, options)?;
+    write_atomically(file_path, dot_file_content.as_bytes())?;
+
+    Ok(())
+}
+
+pub fn render_to_image(
+    root_node: &Rc<dyn FeasibleStep>,
+    file_path: &Path,
+    options: &RenderOptions,
 ) -> Result<(), RenderError> {
-    let dot_file_content = render_to_dot_string(root_node).expect("render to dot-file error");
     let file_path = match file_path.to_str() {
         Some(f) => f,
         None => return Err(RenderError::PathError),
     };
 
-    let mut child = Command::new("dot")
-        .args(["-Tpng", "-o", file_path])
-        .stdin(Stdio::piped())
-        .spawn()?;
+    run_graphviz(root_node, options, options.format(), &["-o", file_path])?;
+
+    Ok(())
+}
+
+/// Number of graphs passed to one Graphviz process, keeps the command line
+/// below the length limit of Windows.
+const GRAPHVIZ_BATCH_SIZE: usize = 100;
+
+/// Renders the DOT sources to the image paths with one Graphviz process per
+/// batch, starting a process per tree is slow on Windows. The graphs Graphviz
+/// could not render are returned with their error.
+pub fn render_dot_sources_to_images(
+    sources: &[(String, PathBuf)],
+    options: &RenderOptions,
+) -> Result<Vec<(PathBuf, RenderError)>, RenderError> {
+    let batch_dir = std::env::temp_dir().join(format!("att-{}", std::process::id()));
+    std::fs::create_dir_all(&batch_dir)?;
+
+    let result = render_batches(sources, options, &batch_dir);
+    let _ = std::fs::remove_dir_all(&batch_dir);
+
+    result
+}
 
-    let child_stdin = child.stdin.as_mut().unwrap();
-    child_stdin.write_all(dot_file_content.as_bytes())?;
+fn render_batches(
+    sources: &[(String, PathBuf)],
+    options: &RenderOptions,
+    batch_dir: &Path,
+) -> Result<Vec<(PathBuf, RenderError)>, RenderError> {
+    let engine = options.engine.to_string();
+    let format = options.format();
+    let extension = format.split(':').next().unwrap_or(format);
+    let mut failed = vec![];
+
+    for (batch_index, batch) in sources.chunks(GRAPHVIZ_BATCH_SIZE).enumerate() {
+        // the file names show up in the messages of Graphviz
+        let mut dot_paths = vec![];
+        for (i, (source, image_path)) in batch.iter().enumerate() {
+            let stem = image_path.file_stem().unwrap_or_default().to_string_lossy();
+            let dot_path = batch_dir.join(format!(
+                "{}-{}.gv",
+                batch_index * GRAPHVIZ_BATCH_SIZE + i,
+                stem
+            ));
+            std::fs::write(&dot_path, source)?;
+            dot_paths.push(dot_path);
+        }
+
+        // -O writes each graph next to its source with the format appended
+        let output = match Command::new(&engine)
+            .arg(format!("-T{}", format))
+            .arg("-O")
+            .args(&dot_paths)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+        {
+            Ok(o) => o,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(RenderError::GraphvizNotFound(engine))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let messages = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+        for (dot_path, (_, image_path)) in dot_paths.iter().zip(batch) {
+            let mut rendered = dot_path.clone().into_os_string();
+            rendered.push(".");
+            rendered.push(extension);
+            let rendered = PathBuf::from(rendered);
+
+            if !rendered.exists() {
+                // Graphviz names the file in its messages, keep the ones about this graph
+                let name = dot_path.file_name().unwrap_or_default().to_string_lossy();
+                let own: Vec<&str> = messages.lines().filter(|l| l.contains(&*name)).collect();
+                let message = if own.is_empty() {
+                    messages.clone()
+                } else {
+                    own.join("\n")
+                };
+                failed.push((
+                    image_path.clone(),
+                    RenderError::GraphvizFailed(engine.clone(), message),
+                ));
+            } else if let Err(e) = move_file(&rendered, image_path) {
+                failed.push((image_path.clone(), e.into()));
+            }
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Renames the file and copies it where renaming fails, e.g. from the
+/// temporary directory to another drive.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_err() {
+        write_atomically(to, &std::fs::read(from)?)?;
+        std::fs::remove_file(from)?;
+    }
 
     Ok(())
 }
 
-fn render_to_dot_string(root_node: &Rc<dyn FeasibleStep>) -> Result<String, RenderError> {
+/// Writes the file through a temporary file next to it that replaces it in
+/// one step, a crash never leaves a half-written file behind.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = path.with_file_name(format!(".{}.tmp", name));
+
+    let result =
+        std::fs::write(&temporary, contents).and_then(|_| std::fs::rename(&temporary, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+
+    result
+}
+
+/// Renders the tree to an SVG document without writing a file.
+pub fn render_to_svg(
+    root_node: &Rc<dyn FeasibleStep>,
+    options: &RenderOptions,
+) -> Result<String, RenderError> {
+    let svg = run_graphviz(root_node, options, "svg", &[])?;
+
+    Ok(String::from_utf8_lossy(&svg).into_owned())
+}
+
+/// Runs Graphviz on the graph of `root_node` and returns its standard output.
+fn run_graphviz(
+    root_node: &Rc<dyn FeasibleStep>,
+    options: &RenderOptions,
+    format: &str,
+    args: &[&str],
+) -> Result<Vec<u8>, RenderError> {
+    let dot_file_content = render_to_dot_string_with_options(root_node, options)?;
+
+    let engine = options.engine.to_string();
+    let mut child = match Command::new(&engine)
+        .arg(format!("-T{}", format))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(RenderError::GraphvizNotFound(engine))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(mut child_stdin) = child.stdin.take() {
+        child_stdin.write_all(dot_file_content.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(RenderError::GraphvizFailed(
+            engine,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+pub fn render_to_dot_string(root_node: &Rc<dyn FeasibleStep>) -> Result<String, RenderError> {
+    render_to_dot_string_with_options(root_node, &RenderOptions::default())
+}
+
+pub fn render_to_dot_string_with_options(
+    root_node: &Rc<dyn FeasibleStep>,
+    options: &RenderOptions,
+) -> Result<String, RenderError> {
     let mut flat_nodes_list: Vec<Rc<dyn FeasibleStep>> = Vec::new();
     flatten(root_node, &mut flat_nodes_list);
 
     let mut labels_texts: Vec<String> = Vec::new();
     let mut edges_texts: Vec<String> = Vec::new();
 
-    for node in flat_nodes_list {
-        labels_texts.push(format!(r#"{} [{}]"#, node.id(), node.render()));
+    let palette = options.layout.theme.unwrap_or_default().palette();
+    for (position, node) in flat_nodes_list.iter().enumerate() {
+        let badge = match change_status(options, node.id()) {
+            Some(ChangeStatus::New) => {
+                format!(r#" xlabel="NEW" color={} penwidth=2"#, palette.new)
+            }
+            Some(ChangeStatus::Changed) => {
+                format!(r#" xlabel="CHANGED" color={} penwidth=2"#, palette.changed)
+            }
+            None => String::new(),
+        };
+
+        let mut tooltip_lines: Vec<String> = node.note().map(str::to_string).into_iter().collect();
+        tooltip_lines.extend(
+            node.attributes()
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v)),
+        );
+        let tooltip = if tooltip_lines.is_empty() {
+            String::new()
+        } else {
+            format!(
+                r#" tooltip="{}""#,
+                escape_dot_string(&tooltip_lines.join("\n"))
+            )
+        };
+
+        let link = match &options.node_links {
+            Some(page) => format!(
+                r#" URL="{}#{}" target="_top""#,
+                escape_dot_string(page),
+                node_anchor(position)
+            ),
+            None => String::new(),
+        };
+
+        let icons = node_icons(options, node);
+        let label = if icons.is_empty() {
+            node.render()
+        } else {
+            node.render().replacen(
+                r#"label=""#,
+                &format!(r#"label="{} "#, escape_dot_string(&icons)),
+                1,
+            )
+        };
+
+        labels_texts.push(format!(
+            r#"{} [{}{}{}{}]"#,
+            node.id(),
+            label,
+            tooltip,
+            link,
+            badge
+        ));
 
         if let Some(parent) = node.get_parent() {
-            edges_texts.push(format!("{} -> {};", parent.id(), node.id()));
+            let label = match options.layout.edge_labels {
+                Some(true) if easiest_child(&parent) == Some(node.id()) => {
+                    format!(
+                        r#" [label="{} (easiest)" penwidth=2]"#,
+                        node.feasibility_value()
+                    )
+                }
+                Some(true) => format!(r#" [label="{}"]"#, node.feasibility_value()),
+                _ => String::new(),
+            };
+            edges_texts.push(format!("{} -> {}{};", parent.id(), node.id(), label));
         }
+
+        for d in node.defenses() {
+            labels_texts.push(format!(
+                r#"{} [label="{}\n{}" shape=box style="rounded,dashed" color={}]"#,
+                d.id,
+                escape_dot_string(&d.description),
+                d.describe_effect(),
+                palette.defense
+            ));
+            edges_texts.push(format!(
+                r#"{} -> {} [style=dashed arrowhead=tee color={}];"#,
+                node.id(),
+                d.id,
+                palette.defense
+            ));
+        }
+
+        for a in node.assumptions() {
+            labels_texts.push(format!(
+                r#"{} [label="{}" shape=note style=dashed color={2} fontcolor={2}]"#,
+                a.id,
+                escape_dot_string(&a.description),
+                palette.assumption
+            ));
+            edges_texts.push(format!(
+                r#"{} -> {} [style=dotted arrowhead=none color={}];"#,
+                node.id(),
+                a.id,
+                palette.assumption
+            ));
+        }
+    }
+
+    let mut graph_attributes = match options.layout.to_dot_attributes() {
+        Some(a) => format!("{}\n", a),
+        None => String::new(),
+    };
+    if let Some(a) = options.layout.theme.unwrap_or_default().to_dot_attributes() {
+        graph_attributes.push_str(&format!("{}\n", a));
+    }
+    if options.layout.banner == Some(true) {
+        graph_attributes.push_str(&format!(
+            "graph [label=\"{}\" labelloc=t fontsize=20]\n",
+            escape_dot_string(&banner_text(root_node))
+        ));
     }
 
+    let clusters = match options.layout.clusters {
+        Some(depth) => render_clusters(root_node, depth)
+            .into_iter()
+            .map(|c| format!("{}\n\n", c))
+            .collect(),
+        None => String::new(),
+    };
+
+    let legend = if options.layout.legend == Some(true) {
+        format!("{}\n\n", legend::render_legend(&flat_nodes_list, options))
+    } else {
+        String::new()
+    };
+
     let dot_content = format!(
         r#"digraph G {{
 
-node [shape=box]
+{}node [shape=box]
 
 {}
 
 {}
 
-}}"#,
+{}{}}}"#,
+        graph_attributes,
         labels_texts.join("\n"),
-        edges_texts.join("\n")
+        edges_texts.join("\n"),
+        clusters,
+        legend
     );
 
     Ok(dot_content.to_string())
 }
 
+/// The id of the child deciding the value of an OR node, the first one of
+/// the lowest value as in the computation.
+fn easiest_child(node: &Rc<dyn FeasibleStep>) -> Option<u32> {
+    if node.kind() != NodeKind::Or || node.feasibility().is_err() {
+        return None;
+    }
+
+    // children without a value do not take part, as in the computation
+    node.get_children()
+        .iter()
+        .filter_map(|c| c.feasibility().ok().map(|f| (f.sum(), c.id())))
+        .min_by_key(|(value, _)| *value)
+        .map(|(_, id)| id)
+}
+
+/// A cluster for the subtree of each inner node at `depth`, labeled with
+/// its title. Defenses and assumptions stay with their nodes.
+fn render_clusters(root_node: &Rc<dyn FeasibleStep>, depth: usize) -> Vec<String> {
+    preorder_with_depth(root_node)
+        .into_iter()
+        .filter(|(d, node)| *d == depth && !node.get_children().is_empty())
+        .map(|(_, subtree)| {
+            let ids: Vec<String> = preorder(&subtree)
+                .iter()
+                .flat_map(|node| {
+                    let mut ids = vec![node.id()];
+                    ids.extend(node.defenses().iter().map(|d| d.id));
+                    ids.extend(node.assumptions().iter().map(|a| a.id));
+                    ids
+                })
+                .map(|id| id.to_string())
+                .collect();
+            format!(
+                "subgraph cluster_{} {{\nlabel=\"{}\"\nstyle=dashed\n{};\n}}",
+                subtree.id(),
+                escape_dot_string(subtree.title()),
+                ids.join("; ")
+            )
+        })
+        .collect()
+}
+
+/// The icons of the node from the `icons` style, the one of its type first.
+fn node_icons(options: &RenderOptions, node: &Rc<dyn FeasibleStep>) -> String {
+    let Some(icons) = &options.layout.icons else {
+        return String::new();
+    };
+
+    let kind = node.kind().to_string().to_lowercase();
+    let mut result: Vec<&str> = icons.get(&kind).map(String::as_str).into_iter().collect();
+    for (key, value) in node.attributes() {
+        if let Some(icon) = icons.get(&format!("{}={}", key, value)) {
+            result.push(icon);
+        }
+    }
+
+    result.concat()
+}
+
+/// The title of the tree followed by a line with its version, date, owner
+/// and status, as far as they are set.
+fn banner_text(root_node: &Rc<dyn FeasibleStep>) -> String {
+    let review = root_node.review();
+    let mut details = vec![];
+    if let Some(version) = &review.version {
+        details.push(format!("Version {}", version));
+    }
+    if let Some(date) = review.date {
+        details.push(date.format("%Y-%m-%d").to_string());
+    }
+    if let Some(owner) = &review.owner {
+        details.push(format!("Owner: {}", owner));
+    }
+    if let Some(status) = review.status {
+        details.push(status.to_string());
+    }
+
+    if details.is_empty() {
+        root_node.title().to_string()
+    } else {
+        format!("{}\n{}", root_node.title(), details.join(" | "))
+    }
+}
+
+fn change_status(options: &RenderOptions, node_id: u32) -> Option<ChangeStatus> {
+    options.changes.as_ref().and_then(|c| c.status(node_id))
+}
+
+/// The title of the node, after its number if the nodes are numbered.
+fn numbered_title(options: &RenderOptions, node: &Rc<dyn FeasibleStep>) -> String {
+    match options.numbers.as_ref().and_then(|n| n.get(node.id())) {
+        Some(number) => format!("{} {}", number, node.title()),
+        None => node.title().to_string(),
+    }
+}
+
 fn flatten(node: &Rc<dyn FeasibleStep>, result: &mut Vec<Rc<dyn FeasibleStep>>) {
-    result.push(node.clone());
+    result.extend(preorder(node));
+}
+
+/// The path as the target of a markdown link, with the characters that end
+/// the target or are not allowed in URLs percent-encoded, e.g. the space of
+/// `door lock.md`.
+fn link_target(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
 
-    for c in node.get_children() {
-        flatten(&c, result);
+    let mut target = String::new();
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                target.push(b as char)
+            }
+            _ => target.push_str(&format!("%{:02X}", b)),
+        }
     }
+
+    target
+}
+
+fn flatten_with_depth(
+    node: &Rc<dyn FeasibleStep>,
+    depth: usize,
+    result: &mut Vec<(usize, Rc<dyn FeasibleStep>)>,
+) {
+    result.extend(
+        preorder_with_depth(node)
+            .into_iter()
+            .map(|(d, n)| (depth + d, n)),
+    );
 }
 
 pub fn render_to_markdown_table(attack_trees: Vec<(PathBuf, &Rc<dyn FeasibleStep>)>) -> String {
-    let mut result = "| Threat Scenario | Feasbility | Impact | Risk |\n".to_string();
-    result.push_str("|--|--|--|--|\n");
+    render_to_markdown_table_with_options(attack_trees, &RenderOptions::default())
+}
 
-    for (image_path, root_node) in attack_trees {
+pub fn render_to_markdown_table_with_options(
+    attack_trees: Vec<(PathBuf, &Rc<dyn FeasibleStep>)>,
+    options: &RenderOptions,
+) -> String {
+    let mut result = "| Threat Scenario | Feasbility |".to_string();
+    let mut separator = "|--|--|".to_string();
+    let range_column = attack_trees
+        .iter()
+        .any(|(_, r)| r.feasibility_interval().is_some());
+    if range_column {
+        result.push_str(" Range |");
+        separator.push_str("--|");
+    }
+    if !options.ratings.is_empty() {
+        result.push_str(" Rating |");
+        separator.push_str("--|");
+    }
+    result.push_str(" Impact | Risk |");
+    separator.push_str("--|--|");
+    let detection_column = attack_trees.iter().any(|(_, r)| r.detection().is_some());
+    if detection_column {
+        result.push_str(" Detection |");
+        separator.push_str("--|");
+    }
+    let review_columns = attack_trees.iter().any(|(_, r)| !r.review().is_empty());
+    if review_columns {
+        result.push_str(" Owner | Status |");
+        separator.push_str("--|--|");
+    }
+    if options.changes.is_some() {
+        result.push_str(" Changes |");
+        separator.push_str("--|");
+    }
+    if options.group_threats {
+        result.push_str(" Sources |");
+        separator.push_str("--|");
+    }
+    result.push_str(&format!("\n{}\n", separator));
+
+    let link = |image_path: &Path| {
+        let link = match &options.tree_pages {
+            Some(pages) => pages.join(
+                image_path
+                    .with_extension("md")
+                    .file_name()
+                    .unwrap_or_default(),
+            ),
+            None => image_path.to_path_buf(),
+        };
+        link_target(&link)
+    };
+    let groups = match options.group_threats {
+        true => group_threats(&attack_trees.iter().map(|(_, r)| *r).collect::<Vec<_>>()),
+        false => (0..attack_trees.len()).map(|i| vec![i]).collect(),
+    };
+
+    for group in groups {
+        // a group is shown with its easiest threat
+        let Some(&easiest) = group
+            .iter()
+            .min_by_key(|i| attack_trees[**i].1.feasibility_value())
+        else {
+            continue;
+        };
+        let (image_path, root_node) = &attack_trees[easiest];
+        let value = root_node.feasibility_value();
+        // numbers start with the namespace already
+        let title = match options.numbers {
+            Some(_) => numbered_title(options, root_node),
+            None => root_node.qualified_title(),
+        };
+        result.push_str(&format!(
+            "| [{}]({}) | {} |",
+            title,
+            link(image_path),
+            value
+        ));
+        if range_column {
+            result.push_str(&format!(" {} |", format_interval(root_node)));
+        }
+        if !options.ratings.is_empty() {
+            result.push_str(&format!(
+                " {} |",
+                rate(&options.ratings, value).unwrap_or("")
+            ));
+        }
         result.push_str(&format!(
-            "| [{}]({}) | {} | | |\n",
-            root_node.title(),
-            image_path.to_str().unwrap_or(""),
-            root_node.feasibility_value()
+            " {} | |",
+            root_node.review().impact.as_deref().unwrap_or("")
         ));
+
+        if detection_column {
+            result.push_str(&format!(
+                " {} |",
+                root_node
+                    .detection()
+                    .map(format_probability)
+                    .unwrap_or_default()
+            ));
+        }
+
+        if review_columns {
+            let review = root_node.review();
+            result.push_str(&format!(
+                " {} | {} |",
+                review.owner.as_deref().unwrap_or(""),
+                review.status.map(|s| s.to_string()).unwrap_or_default()
+            ));
+        }
+
+        if let Some(changes) = &options.changes {
+            result.push_str(&format!(" {} |", summarize_changes(root_node, changes)));
+        }
+
+        if options.group_threats {
+            let sources: Vec<String> = group
+                .iter()
+                .map(|i| {
+                    let image_path = &attack_trees[*i].0;
+                    format!(
+                        "[{}]({})",
+                        image_path
+                            .with_extension("att")
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy(),
+                        link(image_path)
+                    )
+                })
+                .collect();
+            result.push_str(&format!(" {} |", sources.join("<br>")));
+        }
+
+        result.push('\n');
     }
 
     format_tables(result)
 }
 
+/// `lowest..highest` if ranges of leaves below make a difference.
+fn format_interval(node: &Rc<dyn FeasibleStep>) -> String {
+    node.feasibility_interval()
+        .map(|(lowest, highest)| format!("{}..{}", lowest, highest))
+        .unwrap_or_default()
+}
+
+fn summarize_changes(root_node: &Rc<dyn FeasibleStep>, changes: &ChangeSet) -> String {
+    let mut nodes = vec![];
+    flatten(root_node, &mut nodes);
+
+    let count = |status| {
+        nodes
+            .iter()
+            .filter(|n| changes.status(n.id()) == Some(status))
+            .count()
+    };
+
+    let summary: Vec<String> = [ChangeStatus::New, ChangeStatus::Changed]
+        .into_iter()
+        .map(|s| (count(s), s))
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, s)| format!("{} {}", n, s))
+        .collect();
+
+    summary.join(", ")
+}
+
+/// Renders a page for a single tree with its image and a table listing the
+/// aggregated feasibility of every node.
+pub fn render_tree_to_markdown(
+    image_path: &Path,
+    root_node: &Rc<dyn FeasibleStep>,
+    definition: &FeasibilityCriteria,
+    options: &RenderOptions,
+) -> String {
+    let mut result = format!("# {}\n\n", numbered_title(options, root_node));
+    if let Some(pages) = &options.tree_pages {
+        let overview: PathBuf = pages.components().map(|_| "..").collect();
+        result.push_str(&format!(
+            "[Back to the overview]({})\n\n",
+            overview
+                .join(OVERVIEW_FILE)
+                .to_string_lossy()
+                .replace('\\', "/")
+        ));
+    }
+    result.push_str(&format!(
+        "![{}]({})\n\n",
+        root_node.title(),
+        link_target(image_path)
+    ));
+
+    let mut nodes = vec![];
+    flatten_with_depth(root_node, 0, &mut nodes);
+
+    let criteria_ids: Vec<&str> = definition.shown().iter().map(|(id, _)| *id).collect();
+    let range_column = nodes
+        .iter()
+        .any(|(_, n)| n.feasibility_interval().is_some());
+    let detection_column = nodes.iter().any(|(_, n)| n.detection().is_some());
+    let attributes_column = nodes.iter().any(|(_, n)| !n.attributes().is_empty());
+    let change_column = options.changes.is_some();
+    result.push_str(&format!(
+        "| Node | Type | Feasibility |{} {} |{}{}{}\n",
+        if range_column { " Range |" } else { "" },
+        criteria_ids.join(" | "),
+        if detection_column { " Detection |" } else { "" },
+        if attributes_column {
+            " Attributes |"
+        } else {
+            ""
+        },
+        if change_column { " Change |" } else { "" }
+    ));
+    result.push_str(&format!(
+        "|--|--|--|{}{}{}{}{}\n",
+        if range_column { "--|" } else { "" },
+        "--|".repeat(criteria_ids.len()),
+        if detection_column { "--|" } else { "" },
+        if attributes_column { "--|" } else { "" },
+        if change_column { "--|" } else { "" }
+    ));
+
+    for (position, (depth, node)) in nodes.iter().enumerate() {
+        let (value, values): (u32, Vec<String>) = match node.feasibility() {
+            Ok(a) => (
+                a.sum(),
+                a.shown_values()
+                    .into_iter()
+                    .map(Option::unwrap_or_default)
+                    .collect(),
+            ),
+            Err(_) => (0, criteria_ids.iter().map(|_| String::new()).collect()),
+        };
+
+        let anchor = match options.node_links {
+            Some(_) => format!(r#"<a id="{}"></a>"#, node_anchor(position)),
+            None => String::new(),
+        };
+
+        let range = if range_column {
+            format!(" {} |", format_interval(node))
+        } else {
+            String::new()
+        };
+
+        result.push_str(&format!(
+            "| {}{}{} | {} | {} |{} {} |",
+            anchor,
+            "&nbsp;&nbsp;".repeat(*depth),
+            numbered_title(options, node),
+            node.kind(),
+            value,
+            range,
+            values.join(" | ")
+        ));
+
+        if detection_column {
+            result.push_str(&format!(
+                " {} |",
+                node.detection().map(format_probability).unwrap_or_default()
+            ));
+        }
+
+        if attributes_column {
+            let attributes: Vec<String> = node
+                .attributes()
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            result.push_str(&format!(" {} |", attributes.join(", ")));
+        }
+
+        if change_column {
+            let status = change_status(options, node.id());
+            result.push_str(&format!(
+                " {} |",
+                status.map(|s| s.to_string()).unwrap_or_default()
+            ));
+        }
+
+        result.push('\n');
+    }
+
+    let defenses: Vec<String> = nodes
+        .iter()
+        .flat_map(|(_, n)| {
+            n.defenses().iter().map(move |d| {
+                format!(
+                    "| {} | {} | {} |\n",
+                    d.description,
+                    n.title(),
+                    d.describe_effect()
+                )
+            })
+        })
+        .collect();
+    if !defenses.is_empty() {
+        result.push_str("\n## Defenses\n\n| Defense | Counters | Effect |\n|--|--|--|\n");
+        result.push_str(&defenses.concat());
+    }
+
+    let assumptions: Vec<String> = nodes
+        .iter()
+        .flat_map(|(_, n)| {
+            n.assumptions().iter().map(move |a| {
+                format!(
+                    "| {} | {} | {} |\n",
+                    a.description,
+                    n.title(),
+                    a.note.as_deref().unwrap_or_default().replace('\n', "<br>")
+                )
+            })
+        })
+        .collect();
+    if !assumptions.is_empty() {
+        result.push_str("\n## Assumptions\n\n| Assumption | Node | Note |\n|--|--|--|\n");
+        result.push_str(&assumptions.concat());
+    }
+
+    if options.compare_or {
+        result.push_str(&render_decision_points(&nodes, &criteria_ids, options));
+    }
+
+    let mut result = format_tables(result);
+
+    let notes: Vec<String> = nodes_with_notes(root_node)
+        .iter()
+        .map(|n| format!("**{}**\n\n{}\n", n.title(), n.note().unwrap_or_default()))
+        .collect();
+    if !notes.is_empty() {
+        result.push_str("\n## Notes\n\n");
+        result.push_str(&notes.join("\n"));
+    }
+
+    result
+}
+
+/// A table per OR node with the values of its children side by side, the
+/// easiest one marked.
+fn render_decision_points(
+    nodes: &[(usize, Rc<dyn FeasibleStep>)],
+    criteria_ids: &[&str],
+    options: &RenderOptions,
+) -> String {
+    let mut result = String::new();
+    for (_, node) in nodes.iter().filter(|(_, n)| n.kind() == NodeKind::Or) {
+        let easiest = easiest_child(node);
+        result.push_str(&format!(
+            "\n### {}\n\n| Option | Feasibility | {} |\n|--|--|{}\n",
+            numbered_title(options, node),
+            criteria_ids.join(" | "),
+            "--|".repeat(criteria_ids.len())
+        ));
+        for child in node.get_children() {
+            let (value, values): (String, Vec<String>) = match child.feasibility() {
+                Ok(a) => (
+                    a.sum().to_string(),
+                    a.shown_values()
+                        .into_iter()
+                        .map(Option::unwrap_or_default)
+                        .collect(),
+                ),
+                Err(_) => (
+                    String::new(),
+                    criteria_ids.iter().map(|_| String::new()).collect(),
+                ),
+            };
+            result.push_str(&format!(
+                "| {}{} | {} | {} |\n",
+                numbered_title(options, &child),
+                if easiest == Some(child.id()) {
+                    " (easiest)"
+                } else {
+                    ""
+                },
+                value,
+                values.join(" | ")
+            ));
+        }
+    }
+
+    match result.is_empty() {
+        true => result,
+        false => format!("\n## Decision points\n{}", result),
+    }
+}
+
+fn nodes_with_notes(root_node: &Rc<dyn FeasibleStep>) -> Vec<Rc<dyn FeasibleStep>> {
+    let mut nodes = vec![];
+    flatten(root_node, &mut nodes);
+    nodes.retain(|n| n.note().is_some());
+
+    nodes
+}
+
 #[cfg(test)]
 mod tests {
     use crate::model::feasible_step::FeasibleStep;
+    use crate::model::traversal::preorder;
+    use crate::parser::AttackTreeParser;
+    use std::io::Cursor;
+    use std::path::Path;
     use std::rc::Rc;
 
-    use crate::model::{tests::build_criteria, AndNode, Leaf, or_node::OrNode};
+    use crate::model::{
+        number_nodes, or_node::OrNode, tests::build_criteria, AndNode, FeasibilityAssessment,
+        FeasibilityCriteria, Leaf, RatingLevel, Review, ReviewStatus,
+    };
 
-    use super::render_to_dot_string;
+    use super::{
+        render_to_dot_string, render_to_dot_string_with_options, render_to_markdown_table,
+        render_to_markdown_table_with_options, render_tree_to_markdown, write_atomically,
+        LayoutOptions, RankDir, RenderOptions,
+    };
+    use crate::analysis::baseline::{ChangeSet, ChangeStatus};
+    use std::collections::{BTreeMap, HashMap};
+    use std::path::PathBuf;
 
     #[test]
     fn a_single_leaf_can_be_rendered() {
@@ -125,6 +1080,27 @@ node [shape=box]
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn quotes_in_titles_are_escaped() {
+        let definition = build_criteria(&["Kn"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new(r#"Root "x""#, None, || 1));
+        assert_eq!(root.render(), r#"label="Root \"x\"" shape=trapezium"#);
+
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            r#"Open "the" door"#,
+            Some(root.clone()),
+            &definition,
+            &[3],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let result = render_to_dot_string(&root).unwrap();
+
+        assert!(result.contains(r#"1 [label="Root \"x\"\n3\nKn=3" shape=trapezium]"#));
+        assert!(result.contains(r#"2 [label="Open \"the\" door\n3\nKn=3"]"#));
+    }
+
     #[test]
     fn an_and_node_with_a_single_leaf_can_be_rendered() {
         let definition = build_criteria(&["Kn", "Eq"]);
@@ -255,4 +1231,663 @@ node [shape=box]
 }"#;
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn a_tree_page_lists_the_feasibility_of_every_node() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[15, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let result = render_tree_to_markdown(
+            Path::new("../images/root.png"),
+            &root,
+            &definition,
+            &RenderOptions::default(),
+        );
+
+        let expected = r#"# Root
+
+![Root](../images/root.png)
+
+| Node               | Type | Feasibility | Kn  | Eq  |
+| ------------------ | ---- | ----------- | --- | --- |
+| Root               | AND  | 20          | 15  | 5   |
+| &nbsp;&nbsp;Step 1 | Leaf | 20          | 15  | 5   |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn the_overview_and_the_tree_pages_link_to_each_other() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Enter house;|\n    Nobody is home;~\n    Pick lock; Kn=2"),
+                &definition,
+            )
+            .unwrap();
+        let options = RenderOptions {
+            tree_pages: Some(PathBuf::from("trees")),
+            ..Default::default()
+        };
+
+        let overview = render_to_markdown_table_with_options(
+            vec![(PathBuf::from("images/house.png"), &root)],
+            &options,
+        );
+        assert!(overview.contains("| [Enter house](trees/house.md) |"));
+
+        let page = render_tree_to_markdown(
+            Path::new("../images/house.png"),
+            &root,
+            &definition,
+            &options,
+        );
+        assert!(page.starts_with(
+            "# Enter house\n\n[Back to the overview](../threats.md)\n\n![Enter house](../images/house.png)\n"
+        ));
+        assert!(page.contains("## Assumptions\n\n| Assumption     | Node        | Note |\n"));
+        assert!(page.contains("| Nobody is home | Enter house |      |\n"));
+    }
+
+    #[test]
+    fn spaces_in_file_names_are_encoded_in_links() {
+        let definition = build_criteria(&["Kn"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(Leaf::new("Root", None, &definition, &[1], || 1));
+        let options = RenderOptions {
+            tree_pages: Some(PathBuf::from("trees")),
+            ..Default::default()
+        };
+
+        let overview = render_to_markdown_table_with_options(
+            vec![(PathBuf::from("images/door lock.png"), &root)],
+            &options,
+        );
+        let page = render_tree_to_markdown(
+            Path::new("../images/door lock.png"),
+            &root,
+            &definition,
+            &options,
+        );
+
+        assert!(overview.contains("| [Root](trees/door%20lock.md) |"));
+        assert!(page.contains("![Root](../images/door%20lock.png)"));
+    }
+
+    #[test]
+    fn numbered_nodes_show_their_number_before_the_title() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[15, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+        let options = RenderOptions {
+            numbers: Some(number_nodes(&[&root])),
+            ..Default::default()
+        };
+
+        let page = render_tree_to_markdown(Path::new("root.png"), &root, &definition, &options);
+        assert!(page.starts_with("# T1 Root\n"));
+        assert!(page.contains("| &nbsp;&nbsp;T1.1 Step 1 | Leaf |"));
+
+        let overview = render_to_markdown_table_with_options(
+            vec![(PathBuf::from("root.png"), &root)],
+            &options,
+        );
+        assert!(overview.contains("| [T1 Root](root.png) |"));
+    }
+
+    #[test]
+    fn detection_probabilities_are_shown_in_the_graph_and_the_tree_page() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            detection: Some(0.25),
+            ..Leaf::new("Step 1", Some(root.clone()), &definition, &[15, 5], || 2)
+        });
+        root.add_child(&leaf);
+
+        let dot = render_to_dot_string(&root).unwrap();
+        assert!(dot.contains("D=25%"));
+
+        let result = render_tree_to_markdown(
+            Path::new("../images/root.png"),
+            &root,
+            &definition,
+            &RenderOptions::default(),
+        );
+
+        let expected = r#"# Root
+
+![Root](../images/root.png)
+
+| Node               | Type | Feasibility | Kn  | Eq  | Detection |
+| ------------------ | ---- | ----------- | --- | --- | --------- |
+| Root               | AND  | 20          | 15  | 5   | 25%       |
+| &nbsp;&nbsp;Step 1 | Leaf | 20          | 15  | 5   | 25%       |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn only_derived_values_are_shown_if_there_are_any() {
+        let definition = Rc::new(
+            FeasibilityCriteria::from_json(
+                r#"[{"id": "Kn", "name": "Knowledge"}, {"id": "Eq", "name": "Equipment"},
+                    {"id": "AP", "name": "Attack potential", "expression": "Kn + Eq"}]"#,
+            )
+            .unwrap(),
+        );
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Root;&\n    Step 1; Kn=3, Eq=1\n    Step 2; Kn=1, Eq=4"),
+                &definition,
+            )
+            .unwrap();
+
+        let dot = render_to_dot_string(&root).unwrap();
+        assert!(dot.contains(r#"label="Root\n7\nAP=7""#));
+        assert!(dot.contains(r#"label="Step 1\n4\nAP=4""#));
+
+        let result = render_tree_to_markdown(
+            Path::new("../images/root.png"),
+            &root,
+            &definition,
+            &RenderOptions::default(),
+        );
+        assert!(result.contains("| Node               | Type | Feasibility | AP  |"));
+        assert!(result.contains("| &nbsp;&nbsp;Step 2 | Leaf | 5           | 5   |"));
+    }
+
+    #[test]
+    fn the_children_of_or_nodes_are_compared_on_the_tree_page() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Root;&\n    Get in;|\n        Pick lock; Kn=3, Eq=2\n        Break window; Kn=1, Eq=2\n    Steal; Kn=1, Eq=1",
+                ),
+                &definition,
+            )
+            .unwrap();
+        let options = RenderOptions {
+            compare_or: true,
+            ..Default::default()
+        };
+
+        let page = render_tree_to_markdown(Path::new("root.png"), &root, &definition, &options);
+
+        let expected = r#"
+## Decision points
+
+### Get in
+
+| Option                 | Feasibility | Kn  | Eq  |
+| ---------------------- | ----------- | --- | --- |
+| Pick lock              | 5           | 3   | 2   |
+| Break window (easiest) | 3           | 1   | 2   |
+"#;
+        assert!(page.ends_with(expected), "{}", page);
+    }
+
+    #[test]
+    fn custom_attributes_are_shown_in_tooltips_and_the_tree_page() {
+        let definition = build_criteria(&["Kn"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode {
+            note: Some("Remote only".to_string()),
+            attributes: BTreeMap::from([("x-ticket".to_string(), "SEC-12".to_string())]),
+            ..AndNode::new("Root", None, || 1)
+        });
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[4],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let dot = render_to_dot_string(&root).unwrap();
+        assert!(dot.contains(r#"tooltip="Remote only\nx-ticket=SEC-12""#));
+
+        let result = render_tree_to_markdown(
+            Path::new("../images/root.png"),
+            &root,
+            &definition,
+            &RenderOptions::default(),
+        );
+
+        assert!(
+            result.contains("| Node               | Type | Feasibility | Kn  | Attributes      |")
+        );
+        assert!(
+            result.contains("| Root               | AND  | 4           | 4   | x-ticket=SEC-12 |")
+        );
+    }
+
+    #[test]
+    fn feasibility_intervals_are_shown_in_the_graph_and_the_tree_page() {
+        let definition = build_criteria(&["Kn"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            hardest: Some(FeasibilityAssessment::new(&definition, &[Some(6)]).unwrap()),
+            ..Leaf::new("Step 1", Some(root.clone()), &definition, &[4], || 2)
+        });
+        root.add_child(&leaf);
+
+        let dot = render_to_dot_string(&root).unwrap();
+        assert!(dot.contains(r#"label="Root\n4..6\nKn=4""#));
+
+        let result = render_tree_to_markdown(
+            Path::new("../images/root.png"),
+            &root,
+            &definition,
+            &RenderOptions::default(),
+        );
+
+        let expected = r#"# Root
+
+![Root](../images/root.png)
+
+| Node               | Type | Feasibility | Range | Kn  |
+| ------------------ | ---- | ----------- | ----- | --- |
+| Root               | AND  | 4           | 4..6  | 4   |
+| &nbsp;&nbsp;Step 1 | Leaf | 4           | 4..6  | 4   |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn nodes_link_to_their_row_on_the_tree_page() {
+        let definition = build_criteria(&["Kn"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(OrNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[3],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let options = RenderOptions {
+            node_links: Some("../trees/root.md".to_string()),
+            ..RenderOptions::default()
+        };
+
+        let dot = render_to_dot_string_with_options(&root, &options).unwrap();
+        assert!(dot.contains(
+            r#"2 [label="Step 1\n3\nKn=3" URL="../trees/root.md#node-1" target="_top"]"#
+        ));
+
+        let page = render_tree_to_markdown(Path::new("root.svg"), &root, &definition, &options);
+        assert!(page.contains(r#"| <a id="node-1"></a>&nbsp;&nbsp;Step 1 |"#));
+    }
+
+    #[test]
+    fn icons_of_the_type_and_the_attributes_prefix_the_labels() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            attributes: BTreeMap::from([("x-vector".to_string(), "remote".to_string())]),
+            ..Leaf::new("Step 1", Some(root.clone()), &definition, &[15, 5], || 2)
+        });
+        root.add_child(&leaf);
+
+        let options = RenderOptions {
+            layout: LayoutOptions {
+                icons: Some(BTreeMap::from([
+                    ("leaf".to_string(), "👣".to_string()),
+                    ("x-vector=remote".to_string(), "🌐".to_string()),
+                ])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = render_to_dot_string_with_options(&root, &options).unwrap();
+
+        assert!(result.contains(r#"1 [label="Root\n20"#));
+        assert!(result.contains(r#"2 [label="👣🌐 Step 1\n20"#));
+    }
+
+    #[test]
+    fn changed_nodes_are_marked_in_the_graph_and_the_overview() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[15, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        let options = RenderOptions {
+            changes: Some(ChangeSet(HashMap::from([
+                (1, ChangeStatus::Changed),
+                (2, ChangeStatus::New),
+            ]))),
+            ..Default::default()
+        };
+
+        let dot = render_to_dot_string_with_options(&root, &options).unwrap();
+        assert!(dot.contains(
+            r#"1 [label="Root\n20\nKn=15, Eq=5" shape=trapezium xlabel="CHANGED" color=orange penwidth=2]"#
+        ));
+        assert!(dot.contains(
+            r#"2 [label="Step 1\n20\nKn=15, Eq=5" xlabel="NEW" color=green penwidth=2]"#
+        ));
+
+        let table = render_to_markdown_table_with_options(
+            vec![(PathBuf::from("images/root.png"), &root)],
+            &options,
+        );
+        let expected = r#"| Threat Scenario         | Feasbility | Impact | Risk | Changes          |
+| ----------------------- | ---------- | ------ | ---- | ---------------- |
+| [Root](images/root.png) | 20         |        |      | 1 new, 1 changed |
+"#;
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn threats_with_similar_titles_are_grouped_in_the_overview() {
+        let definition = build_criteria(&["Kn"]);
+        let leaf = |title: &str, value: u32| -> Rc<dyn FeasibleStep> {
+            Rc::new(Leaf::new(title, None, &definition, &[value], || 1))
+        };
+        let (can, flash, ethernet) = (
+            leaf("Spoof messages (CAN)", 5),
+            leaf("Flash firmware", 3),
+            leaf("Spoof messages (Ethernet)", 2),
+        );
+
+        let options = RenderOptions {
+            group_threats: true,
+            tree_pages: Some(PathBuf::from("trees")),
+            ..Default::default()
+        };
+        let table = render_to_markdown_table_with_options(
+            vec![
+                (PathBuf::from("images/can.png"), &can),
+                (PathBuf::from("images/flash.png"), &flash),
+                (PathBuf::from("images/eth.png"), &ethernet),
+            ],
+            &options,
+        );
+
+        let expected = r#"| Threat Scenario                           | Feasbility | Impact | Risk | Sources                                            |
+| ----------------------------------------- | ---------- | ------ | ---- | -------------------------------------------------- |
+| [Spoof messages (Ethernet)](trees/eth.md) | 2          |        |      | [can.att](trees/can.md)<br>[eth.att](trees/eth.md) |
+| [Flash firmware](trees/flash.md)          | 3          |        |      | [flash.att](trees/flash.md)                        |
+"#;
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn subtrees_of_inner_nodes_are_clustered() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Root;&\n    Get in;|\n        Pick lock; Kn=1\n        Break \"window\"; Kn=2\n    Walk away; Kn=3",
+                ),
+                &definition,
+            )
+            .unwrap();
+        let options = |clusters| RenderOptions {
+            layout: LayoutOptions {
+                clusters,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let get_in = &root.get_children()[0];
+        let ids: Vec<String> = preorder(get_in)
+            .iter()
+            .map(|n| n.id().to_string())
+            .collect();
+
+        let result = render_to_dot_string_with_options(&root, &options(Some(1))).unwrap();
+        assert!(result.contains(&format!(
+            "subgraph cluster_{} {{\nlabel=\"Get in\"\nstyle=dashed\n{};\n}}",
+            get_in.id(),
+            ids.join("; ")
+        )));
+        assert_eq!(result.matches("subgraph cluster_").count(), 1);
+
+        let result = render_to_dot_string_with_options(&root, &options(None)).unwrap();
+        assert!(!result.contains("subgraph"));
+    }
+
+    #[test]
+    fn edges_show_the_values_of_the_children_and_the_easiest_of_an_or_node() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Root;|\n    A; Kn=3\n    B; Kn=2\n    C; Kn=2"),
+                &definition,
+            )
+            .unwrap();
+        let [a, b, c] = [0, 1, 2].map(|i| root.get_children()[i].id());
+        let options = RenderOptions {
+            layout: LayoutOptions {
+                edge_labels: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = render_to_dot_string_with_options(&root, &options).unwrap();
+
+        let edge = |id: u32, label: &str| format!("{} -> {} [{}];", root.id(), id, label);
+        assert!(result.contains(&edge(a, r#"label="3""#)));
+        assert!(result.contains(&edge(b, r#"label="2 (easiest)" penwidth=2"#)));
+        assert!(result.contains(&edge(c, r#"label="2""#)));
+    }
+
+    #[test]
+    fn children_without_a_value_are_not_the_easiest() {
+        let definition = build_criteria(&["Kn"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Root;|\n    Todo;&\n    Break window; Kn=2"),
+                &definition,
+            )
+            .unwrap();
+        let [todo, window] = [0, 1].map(|i| root.get_children()[i].id());
+        let options = RenderOptions {
+            layout: LayoutOptions {
+                edge_labels: Some(true),
+                ..Default::default()
+            },
+            compare_or: true,
+            ..Default::default()
+        };
+
+        let dot = render_to_dot_string_with_options(&root, &options).unwrap();
+        let edge = |id: u32, label: &str| format!("{} -> {} [{}];", root.id(), id, label);
+        assert!(dot.contains(&edge(todo, r#"label="0""#)));
+        assert!(dot.contains(&edge(window, r#"label="2 (easiest)" penwidth=2"#)));
+
+        let page = render_tree_to_markdown(Path::new("root.png"), &root, &definition, &options);
+        assert!(page.contains("Break window (easiest)"));
+        assert!(!page.contains("Todo (easiest)"));
+    }
+
+    #[test]
+    fn the_banner_shows_title_and_metadata_of_the_tree() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            review: Review {
+                owner: Some("Alice".to_string()),
+                version: Some("1.2".to_string()),
+                date: chrono::NaiveDate::from_ymd_opt(2024, 5, 1),
+                ..Default::default()
+            },
+            ..Leaf::new("Steal \"car\"", None, &definition, &[15, 5], || 1)
+        });
+
+        let options = RenderOptions {
+            layout: LayoutOptions {
+                banner: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = render_to_dot_string_with_options(&leaf, &options).unwrap();
+
+        assert!(result.starts_with(
+            "digraph G {\n\ngraph [label=\"Steal \\\"car\\\"\\nVersion 1.2 | 2024-05-01 | Owner: Alice\" labelloc=t fontsize=20]\nnode [shape=box]\n"
+        ));
+    }
+
+    #[test]
+    fn layout_options_are_added_as_graph_attributes() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let leaf: Rc<dyn FeasibleStep> =
+            Rc::new(Leaf::new("Step 1", None, &definition, &[15, 5], || 1));
+
+        let options = RenderOptions {
+            layout: LayoutOptions {
+                rankdir: Some(RankDir::LR),
+                dpi: Some(300),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = render_to_dot_string_with_options(&leaf, &options).unwrap();
+
+        assert!(result.starts_with("digraph G {\n\ngraph [rankdir=LR dpi=300]\nnode [shape=box]\n"));
+    }
+
+    #[test]
+    fn owner_and_status_of_trees_are_listed_in_the_overview() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            review: Review {
+                owner: Some("Alice".to_string()),
+                status: Some(ReviewStatus::Reviewed),
+                ..Default::default()
+            },
+            ..Leaf::new("Root", None, &definition, &[15, 5], || 1)
+        });
+
+        let table = render_to_markdown_table(vec![(PathBuf::from("images/root.png"), &root)]);
+
+        let expected = r#"| Threat Scenario         | Feasbility | Impact | Risk | Owner | Status   |
+| ----------------------- | ---------- | ------ | ---- | ----- | -------- |
+| [Root](images/root.png) | 20         |        |      | Alice | reviewed |
+"#;
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn threats_are_listed_with_their_namespace_in_the_overview() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            review: Review {
+                namespace: Some("Door".to_string()),
+                ..Default::default()
+            },
+            ..Leaf::new("Root", None, &definition, &[15, 5], || 1)
+        });
+
+        let table = render_to_markdown_table(vec![(PathBuf::from("images/root.png"), &root)]);
+
+        assert!(table.contains("| [Door::Root](images/root.png) | 20 "));
+    }
+
+    #[test]
+    fn configured_ratings_add_a_rating_column_to_the_overview() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root: Rc<dyn FeasibleStep> =
+            Rc::new(Leaf::new("Root", None, &definition, &[15, 5], || 1));
+        let options = RenderOptions {
+            ratings: vec![
+                RatingLevel {
+                    name: "High".to_string(),
+                    max: Some(13),
+                },
+                RatingLevel {
+                    name: "Low".to_string(),
+                    max: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let table = render_to_markdown_table_with_options(
+            vec![(PathBuf::from("images/root.png"), &root)],
+            &options,
+        );
+
+        let expected = r#"| Threat Scenario         | Feasbility | Rating | Impact | Risk |
+| ----------------------- | ---------- | ------ | ------ | ---- |
+| [Root](images/root.png) | 20         | Low    |        |      |
+"#;
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn notes_become_tooltips_and_a_section_of_the_tree_page() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf {
+            note: Some("Needs a \"bump\" key.\nTakes minutes.".to_string()),
+            ..Leaf::new("Pick lock", None, &definition, &[5, 3], || 1)
+        });
+
+        let dot = render_to_dot_string(&leaf).unwrap();
+        assert!(dot.contains(
+            r#"1 [label="Pick lock\n8\nKn=5, Eq=3" tooltip="Needs a \"bump\" key.\nTakes minutes."]"#
+        ));
+
+        let page = render_tree_to_markdown(
+            Path::new("root.png"),
+            &leaf,
+            &definition,
+            &RenderOptions::default(),
+        );
+        assert!(page
+            .ends_with("\n## Notes\n\n**Pick lock**\n\nNeeds a \"bump\" key.\nTakes minutes.\n"));
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_file_and_leave_no_temporary_file() {
+        let dir = std::env::temp_dir().join(format!("att-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("threats.md");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomically(&path, b"new").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,185 @@
+use std::{path::PathBuf, rc::Rc};
+
+use serde_json::{json, Map, Value};
+
+use crate::model::{
+    feasible_step::{FeasibleStep, NodeKind},
+    FeasibilityCriteria,
+};
+
+use super::{flatten, RenderError};
+
+/// Version of the Open Threat Model schema written by `render_to_otm`.
+pub const OTM_VERSION: &str = "0.2.0";
+
+/// Renders the trees as Open Threat Model document. Every tree becomes a
+/// threat with its leaves as attack steps, defenses become mitigations. Ids
+/// are derived from the file names and node positions, so they stay the same
+/// between runs.
+pub fn render_to_otm(
+    project_name: &str,
+    attack_trees: Vec<(PathBuf, &Rc<dyn FeasibleStep>)>,
+    definition: &FeasibilityCriteria,
+) -> Result<String, RenderError> {
+    let mut threats = vec![];
+    let mut mitigations = vec![];
+
+    for (file, root) in attack_trees {
+        let threat_id = file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("threat")
+            .to_string();
+
+        let mut nodes = vec![];
+        flatten(root, &mut nodes);
+
+        let mut attack_steps = vec![];
+        let mut mitigation_ids = vec![];
+        for (position, node) in nodes.iter().enumerate() {
+            let node_id = match node.uuid() {
+                Some(uuid) => uuid.to_string(),
+                None => format!("{}:{}", threat_id, position),
+            };
+
+            if node.kind() == NodeKind::Leaf {
+                let mut step = json!({
+                    "id": node_id,
+                    "name": node.title(),
+                    "feasibility": feasibility_object(node),
+                });
+                if !node.techniques().is_empty() {
+                    step["attack"] = json!(node.techniques());
+                }
+                if !node.attributes().is_empty() {
+                    step["attributes"] = json!(node.attributes());
+                }
+                attack_steps.push(step);
+            }
+
+            for (index, d) in node.defenses().iter().enumerate() {
+                let mitigation_id = format!("{}:{}", node_id, index);
+                let mut mitigation = json!({
+                    "name": d.description,
+                    "id": mitigation_id,
+                    "riskReduction": 0,
+                    "attributes": {
+                        "threat": threat_id,
+                        "counters": node.title(),
+                        "effect": d.describe_effect(),
+                    },
+                });
+                if let Some(note) = &d.note {
+                    mitigation["description"] = json!(note);
+                }
+                mitigations.push(mitigation);
+                mitigation_ids.push(mitigation_id);
+            }
+        }
+
+        let value = root.feasibility_value();
+        let mut threat = json!({
+            "name": root.title(),
+            "id": threat_id,
+            "categories": ["attack-tree"],
+            "attributes": {
+                "feasibilityValue": value,
+                "feasibility": feasibility_object(root),
+                "attackSteps": attack_steps,
+                "mitigations": mitigation_ids,
+            },
+        });
+        if let Some(note) = root.note() {
+            threat["description"] = json!(note);
+        }
+        // harder attacks are less likely, impact is not part of the trees
+        if let Some(normalized) = definition.normalize(value) {
+            threat["risk"] = json!({
+                "likelihood": ((1.0 - normalized.min(1.0)) * 100.0).round(),
+                "likelihoodComment": format!("Feasibility value {}", value),
+                "impact": 100,
+                "impactComment": "Not rated in the attack tree, assumed to be the highest",
+            });
+        }
+        threats.push(threat);
+    }
+
+    let document = json!({
+        "otmVersion": OTM_VERSION,
+        "project": {
+            "name": project_name,
+            "id": project_name.to_lowercase().replace(' ', "-"),
+        },
+        "representations": [],
+        "trustZones": [],
+        "components": [],
+        "dataflows": [],
+        "threats": threats,
+        "mitigations": mitigations,
+    });
+
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+fn feasibility_object(node: &Rc<dyn FeasibleStep>) -> Value {
+    match node.feasibility() {
+        Ok(a) => Value::Object(
+            a.iter()
+                .map(|(c, v)| (c.id.clone(), json!(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Err(_) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::PathBuf, rc::Rc};
+
+    use serde_json::Value;
+
+    use crate::{
+        model::{FeasibilityCriteria, FeasiblityCriterion},
+        parser::AttackTreeParser,
+    };
+
+    use super::render_to_otm;
+
+    #[test]
+    fn trees_become_threats_with_attack_steps() {
+        let definition = Rc::new(FeasibilityCriteria(
+            vec![FeasiblityCriterion {
+                max: Some(10),
+                ..FeasiblityCriterion::new("Kn", "Knowledge")
+            }],
+            vec![],
+        ));
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new("Enter house;|\n    Pick lock; Kn=2, attack=T1200\n        Security lock;! Kn=3\n    Break window; Kn=4"),
+                &definition,
+            )
+            .unwrap();
+
+        let otm = render_to_otm(
+            "Smart Home",
+            vec![(PathBuf::from("house.att"), &root)],
+            &definition,
+        )
+        .unwrap();
+        let otm: Value = serde_json::from_str(&otm).unwrap();
+
+        assert_eq!(otm["project"]["id"], "smart-home");
+        let threat = &otm["threats"][0];
+        assert_eq!(threat["id"], "house");
+        assert_eq!(threat["risk"]["likelihood"], 80.0);
+        assert_eq!(threat["attributes"]["attackSteps"][0]["id"], "house:1");
+        assert_eq!(threat["attributes"]["attackSteps"][0]["attack"][0], "T1200");
+        assert_eq!(
+            threat["attributes"]["attackSteps"][1]["name"],
+            "Break window"
+        );
+        assert_eq!(otm["mitigations"][0]["id"], "house:1:0");
+        assert_eq!(threat["attributes"]["mitigations"][0], "house:1:0");
+    }
+}
@@ -0,0 +1,149 @@
+use std::{path::PathBuf, rc::Rc};
+
+use markdown_table_formatter::format_tables;
+
+use crate::model::{feasible_step::FeasibleStep, FeasibilityCriteria};
+
+/// The trees of one model directory for the combined report.
+pub struct ProjectSummary<'a> {
+    pub name: String,
+    /// Link to the project's own report.
+    pub report: PathBuf,
+    pub definition: &'a FeasibilityCriteria,
+    /// Link to each tree's page with its root node.
+    pub trees: Vec<(PathBuf, &'a Rc<dyn FeasibleStep>)>,
+}
+
+/// Renders a report with one section per project and, where the criteria
+/// allow it, a ranking of all threats by normalized feasibility value.
+pub fn render_projects_to_markdown(projects: &[ProjectSummary]) -> String {
+    let mut result = "# Threat overview\n".to_string();
+    let mut ranking = vec![];
+
+    for p in projects {
+        result.push_str(&format!(
+            "\n## {}\n\n[Report]({})\n\n",
+            p.name,
+            p.report.to_str().unwrap_or("")
+        ));
+
+        let mut table = "| Threat Scenario | Feasbility | Normalized |\n|--|--|--|\n".to_string();
+        for (page, root) in &p.trees {
+            let value = root.feasibility_value();
+            let normalized = p.definition.normalize(value);
+            let link = format!("[{}]({})", root.title(), page.to_str().unwrap_or(""));
+
+            table.push_str(&format!(
+                "| {} | {} | {} |\n",
+                link,
+                value,
+                normalized.map(format_percent).unwrap_or_default()
+            ));
+
+            if let Some(n) = normalized {
+                ranking.push((n, link, &p.name));
+            }
+        }
+        result.push_str(&format_tables(table));
+    }
+
+    if !ranking.is_empty() {
+        // the most feasible threats first
+        ranking.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut table = "| Threat Scenario | Project | Normalized |\n|--|--|--|\n".to_string();
+        for (n, link, project) in ranking {
+            table.push_str(&format!(
+                "| {} | {} | {} |\n",
+                link,
+                project,
+                format_percent(n)
+            ));
+        }
+
+        result.push_str("\n## All threats\n\n");
+        result.push_str(
+            "Feasibility values relative to the hardest possible rating of each project's criteria.\n\n",
+        );
+        result.push_str(&format_tables(table));
+    }
+
+    result
+}
+
+fn format_percent(value: f64) -> String {
+    format!("{:.0}%", value * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::model::{
+        feasible_step::FeasibleStep, tests::build_criteria, FeasibilityCriteria,
+        FeasiblityCriterion, Leaf,
+    };
+
+    use super::*;
+
+    #[test]
+    fn projects_get_a_section_and_comparable_threats_are_ranked() {
+        let unbounded = build_criteria(&["Kn"]);
+        let mut knowledge = FeasiblityCriterion::new("Kn", "Knowledge");
+        knowledge.max = Some(10);
+        let bounded = Rc::new(FeasibilityCriteria(vec![knowledge], vec![]));
+
+        let a: Rc<dyn FeasibleStep> = Rc::new(Leaf::new("Open door", None, &unbounded, &[3], || 1));
+        let b: Rc<dyn FeasibleStep> = Rc::new(Leaf::new("Hack ECU", None, &bounded, &[4], || 2));
+        let c: Rc<dyn FeasibleStep> = Rc::new(Leaf::new("Jam radio", None, &bounded, &[1], || 3));
+
+        let result = render_projects_to_markdown(&[
+            ProjectSummary {
+                name: "body".to_string(),
+                report: PathBuf::from("body/threats.md"),
+                definition: &unbounded,
+                trees: vec![(PathBuf::from("body/trees/door.md"), &a)],
+            },
+            ProjectSummary {
+                name: "ecu".to_string(),
+                report: PathBuf::from("ecu/threats.md"),
+                definition: &bounded,
+                trees: vec![
+                    (PathBuf::from("ecu/trees/hack.md"), &b),
+                    (PathBuf::from("ecu/trees/jam.md"), &c),
+                ],
+            },
+        ]);
+
+        let expected = r#"# Threat overview
+
+## body
+
+[Report](body/threats.md)
+
+| Threat Scenario                 | Feasbility | Normalized |
+| ------------------------------- | ---------- | ---------- |
+| [Open door](body/trees/door.md) | 3          |            |
+
+## ecu
+
+[Report](ecu/threats.md)
+
+| Threat Scenario               | Feasbility | Normalized |
+| ----------------------------- | ---------- | ---------- |
+| [Hack ECU](ecu/trees/hack.md) | 4          | 40%        |
+| [Jam radio](ecu/trees/jam.md) | 1          | 10%        |
+
+## All threats
+
+Feasibility values relative to the hardest possible rating of each project's criteria.
+
+| Threat Scenario               | Project | Normalized |
+| ----------------------------- | ------- | ---------- |
+| [Jam radio](ecu/trees/jam.md) | ecu     | 10%        |
+| [Hack ECU](ecu/trees/hack.md) | ecu     | 40%        |
+"#;
+
+        assert_eq!(result, expected);
+    }
+}
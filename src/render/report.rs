@@ -0,0 +1,793 @@
+use markdown_table_formatter::format_tables;
+
+use crate::{
+    analysis::{
+        assets::AttributeSummary, assumptions::AssumptionReference, baseline::ThreatChange,
+        coverage::CoverageMatrix, defenses::ResidualFeasibility, duplicates::DuplicateSubtree,
+        simulation::Simulation, staleness::StaleAssessment, techniques::TechniqueReferences,
+        weakest::TreeWeakestLeaves, NodeLocation,
+    },
+    model::{rate, FeasibilityCriteria, RatingLevel, Scale},
+    parser::CUSTOM_ATTRIBUTE_PREFIX,
+};
+
+fn format_location(location: &NodeLocation) -> String {
+    let file_name = location
+        .file
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or("");
+
+    format!("{}: {}", file_name, location.path.join(" > "))
+}
+
+/// Renders a report section listing subtrees that could be factored out.
+pub fn render_duplicates_to_markdown(duplicates: &[DuplicateSubtree]) -> String {
+    let mut result = "## Duplicate subtrees\n\n".to_string();
+    result.push_str(
+        "The following subtrees occur more than once. Consider factoring them into a shared tree.\n\n",
+    );
+    result.push_str("| Subtree | Nodes | Locations |\n");
+    result.push_str("|--|--|--|\n");
+
+    for d in duplicates {
+        result.push_str(&format!(
+            "| {} | {} | {} |\n",
+            d.title,
+            d.node_count,
+            d.locations
+                .iter()
+                .map(format_location)
+                .collect::<Vec<_>>()
+                .join("<br>")
+        ));
+    }
+
+    format_tables(result)
+}
+
+/// Renders a report section listing assessments due for review.
+pub fn render_stale_assessments_to_markdown(
+    stale: &[StaleAssessment],
+    max_age_days: i64,
+) -> String {
+    let mut result = "## Stale assessments\n\n".to_string();
+    result.push_str(&format!(
+        "The following leaves were assessed more than {} days ago and should be reviewed.\n\n",
+        max_age_days
+    ));
+    result.push_str("| Attack step | Assessed | Age (days) |\n");
+    result.push_str("|--|--|--|\n");
+
+    for s in stale {
+        result.push_str(&format!(
+            "| {} | {} | {} |\n",
+            format_location(&s.location),
+            s.assessed.format("%Y-%m-%d"),
+            s.age_days
+        ));
+    }
+
+    format_tables(result)
+}
+
+/// Renders a report section naming the leaves each threat's feasibility
+/// value is computed from.
+pub fn render_weakest_leaves_to_markdown(trees: &[TreeWeakestLeaves]) -> String {
+    let mut result = "## Weakest leaves\n\n".to_string();
+    result.push_str(
+        "The attack steps that determine the feasibility of each threat. Making them harder raises the value.\n\n",
+    );
+    result.push_str("| Threat | Feasibility | Attack step | Criteria |\n");
+    result.push_str("|--|--|--|--|\n");
+
+    for t in trees {
+        for (i, l) in t.leaves.iter().enumerate() {
+            // the threat is named once for all its leaves
+            let (threat, value) = if i == 0 {
+                (t.threat.clone(), t.feasibility_value.to_string())
+            } else {
+                (String::new(), String::new())
+            };
+            // the path below the root, unless the root is the only step
+            let step = match l.location.path.split_first() {
+                Some((_, below)) if !below.is_empty() => below.join(" > "),
+                _ => l.location.path.join(" > "),
+            };
+            result.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                threat,
+                value,
+                step,
+                l.criteria.join(", ")
+            ));
+        }
+    }
+
+    format_tables(result)
+}
+
+/// Renders a report section listing the assumptions the analysis relies on.
+pub fn render_assumptions_to_markdown(assumptions: &[AssumptionReference]) -> String {
+    let mut result = "## Assumptions\n\n".to_string();
+    result.push_str(
+        "The analysis relies on the following assumptions. The feasibility values are only valid while they hold.\n\n",
+    );
+    result.push_str("| Assumption | Node | Note |\n");
+    result.push_str("|--|--|--|\n");
+
+    for a in assumptions {
+        result.push_str(&format!(
+            "| {} | {} | {} |\n",
+            a.description,
+            format_location(&a.location),
+            a.note.as_deref().unwrap_or("").replace('\n', "<br>")
+        ));
+    }
+
+    format_tables(result)
+}
+
+/// Renders a report section with the easiest attack per value of a custom
+/// attribute like `x-asset`, see `aggregate_by_attribute`.
+pub fn render_attribute_summaries_to_markdown(
+    key: &str,
+    summaries: &[AttributeSummary],
+    ratings: &[RatingLevel],
+) -> String {
+    let name = key.strip_prefix(CUSTOM_ATTRIBUTE_PREFIX).unwrap_or(key);
+    let mut capitalized = name.chars();
+    let column: String = capitalized
+        .next()
+        .map(|c| c.to_uppercase().chain(capitalized).collect())
+        .unwrap_or_default();
+
+    let mut result = format!("## Feasibility by {}\n\n", name);
+    result.push_str(&format!(
+        "The easiest attack on each {} across all trees.\n\n",
+        name
+    ));
+    result.push_str(&format!("| {} | Feasibility |", column));
+    if !ratings.is_empty() {
+        result.push_str(" Rating |");
+    }
+    result.push_str(" Easiest attack | Trees |\n");
+    result.push_str(if ratings.is_empty() {
+        "|--|--|--|--|\n"
+    } else {
+        "|--|--|--|--|--|\n"
+    });
+
+    for s in summaries {
+        result.push_str(&format!("| {} | {} |", s.value, s.feasibility));
+        if !ratings.is_empty() {
+            result.push_str(&format!(
+                " {} |",
+                rate(ratings, s.feasibility).unwrap_or("")
+            ));
+        }
+        result.push_str(&format!(
+            " {} | {} |\n",
+            format_location(&s.easiest),
+            s.trees
+        ));
+    }
+
+    format_tables(result)
+}
+
+/// Renders a report section comparing the feasibility of threats without and
+/// with their defenses.
+pub fn render_residual_feasibility_to_markdown(residuals: &[ResidualFeasibility]) -> String {
+    let mut result = "## Residual feasibility\n\n".to_string();
+    result.push_str("Feasibility values of the threats once the defenses are in place.\n\n");
+    result.push_str("| Threat | Without defenses | With defenses | Defenses |\n");
+    result.push_str("|--|--|--|--|\n");
+
+    for r in residuals {
+        result.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            r.threat, r.without_defenses, r.with_defenses, r.defenses
+        ));
+    }
+
+    format_tables(result)
+}
+
+/// Percentiles of the feasibility samples shown in the simulation report
+/// and export.
+pub const SIMULATION_PERCENTILES: [u32; 5] = [5, 25, 50, 75, 95];
+
+const HISTOGRAM_BUCKETS: u32 = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Renders a report section with the distribution of the sampled feasibility
+/// of every threat with ranges.
+pub fn render_simulation_to_markdown(simulations: &[Simulation]) -> String {
+    let mut result = "## Feasibility distribution\n\n".to_string();
+    let samples = simulations.first().map(|s| s.samples.len()).unwrap_or(0);
+    result.push_str(&format!(
+        "Feasibility values of {} samples of the ranges in the assessments.\n\n",
+        samples
+    ));
+
+    let percentiles: Vec<String> = SIMULATION_PERCENTILES
+        .iter()
+        .map(|p| format!("P{}", p))
+        .collect();
+    result.push_str(&format!(
+        "| Threat | Min | {} | Max | Mean |\n",
+        percentiles.join(" | ")
+    ));
+    result.push_str(&format!(
+        "|--|--|{}--|--|\n",
+        "--|".repeat(percentiles.len())
+    ));
+
+    for s in simulations {
+        let values: Vec<String> = SIMULATION_PERCENTILES
+            .iter()
+            .map(|p| s.percentile(*p).to_string())
+            .collect();
+        result.push_str(&format!(
+            "| {} | {} | {} | {} | {:.1} |\n",
+            s.threat,
+            s.percentile(0),
+            values.join(" | "),
+            s.percentile(100),
+            s.mean()
+        ));
+    }
+    let mut result = format_tables(result);
+
+    for s in simulations {
+        let histogram = s.histogram(HISTOGRAM_BUCKETS);
+        let largest = histogram.iter().map(|b| b.count).max().unwrap_or(1).max(1);
+
+        result.push_str(&format!("\n### {}\n\n```\n", s.threat));
+        for b in &histogram {
+            let bar = (b.count * HISTOGRAM_WIDTH).div_ceil(largest);
+            result.push_str(&format!(
+                "{:>4}-{:<4} {} {}\n",
+                b.from,
+                b.to,
+                "#".repeat(bar),
+                b.count
+            ));
+        }
+        result.push_str("```\n");
+    }
+
+    result
+}
+
+/// Renders a report section cross-referencing ATT&CK techniques and the
+/// leaves that use them.
+pub fn render_techniques_to_markdown(techniques: &[TechniqueReferences]) -> String {
+    let mut result = "## ATT&CK techniques
+
+"
+    .to_string();
+    result.push_str(
+        "| Technique | Attack steps |
+",
+    );
+    result.push_str(
+        "|--|--|
+",
+    );
+
+    for t in techniques {
+        result.push_str(&format!(
+            "| [{}](https://attack.mitre.org/techniques/{}/) | {} |\n",
+            t.technique,
+            t.technique.replace('.', "/"),
+            t.locations
+                .iter()
+                .map(format_location)
+                .collect::<Vec<_>>()
+                .join("<br>")
+        ));
+    }
+
+    format_tables(result)
+}
+
+/// Renders a matrix tracing every requirement to the threats addressing it,
+/// followed by the requirements no threat addresses.
+pub fn render_coverage_matrix_to_markdown(matrix: &CoverageMatrix) -> String {
+    let mut result = "## Requirements coverage\n\n".to_string();
+    result.push_str(&format!(
+        "| Requirement | {} |\n",
+        matrix.threats.join(" | ")
+    ));
+    result.push_str(&format!("|--|{}\n", "--|".repeat(matrix.threats.len())));
+
+    for row in &matrix.rows {
+        let cells: Vec<&str> = row
+            .covered
+            .iter()
+            .map(|c| if *c { "x" } else { "" })
+            .collect();
+        result.push_str(&format!(
+            "| {}: {} | {} |\n",
+            row.requirement.id,
+            row.requirement.title,
+            cells.join(" | ")
+        ));
+    }
+
+    let mut result = format_tables(result);
+
+    let uncovered: Vec<&str> = matrix
+        .rows
+        .iter()
+        .filter(|r| !r.is_covered())
+        .map(|r| r.requirement.id.as_str())
+        .collect();
+    if !uncovered.is_empty() {
+        result.push_str(&format!(
+            "\nNot addressed by any threat: {}\n",
+            uncovered.join(", ")
+        ));
+    }
+
+    result
+}
+
+/// Renders an appendix explaining the feasibility criteria and the meaning of
+/// their values.
+pub fn render_rating_method_to_markdown(definition: &FeasibilityCriteria) -> String {
+    let mut result = "## Rating method\n\n".to_string();
+    result.push_str(
+        "The feasibility of a leaf is the weighted sum of its ratings. AND nodes take the hardest rating of their children per criterion, OR nodes the easiest child.\n",
+    );
+
+    for c in &definition.0 {
+        result.push_str(&format!("\n### {} ({})\n\n", c.name, c.id));
+
+        if let Some(description) = &c.description {
+            result.push_str(&format!("{}\n\n", description));
+        }
+
+        let scale = match c.scale {
+            Scale::HigherIsHarder => "higher values are harder",
+            Scale::HigherIsEasier => "higher values are easier",
+        };
+        match &c.unit {
+            Some(unit) => result.push_str(&format!(
+                "Weight: {}, {}, values in {}.\n",
+                c.weight, scale, unit
+            )),
+            None => result.push_str(&format!("Weight: {}, {}.\n", c.weight, scale)),
+        }
+
+        if !c.guidance.is_empty() {
+            let mut table = "\n| Value | Meaning |\n|--|--|\n".to_string();
+            for (value, meaning) in &c.guidance {
+                table.push_str(&format!("| {} | {} |\n", c.format_value(*value), meaning));
+            }
+            result.push_str(&format_tables(table));
+        }
+    }
+
+    for d in &definition.1 {
+        result.push_str(&format!("\n### {} ({})\n\n", d.name, d.id));
+        if let Some(description) = &d.description {
+            result.push_str(&format!("{}\n\n", description));
+        }
+        result.push_str(&format!(
+            "Derived from the values above as `{}`.\n",
+            d.formula
+        ));
+    }
+
+    result
+}
+
+/// Title of the changelog, the entries follow it with the newest first.
+const CHANGELOG_TITLE: &str = "# Threat model changelog\n";
+
+/// Renders the changes of one run as a changelog entry headed by
+/// `timestamp`. Ratings are added to the values when configured.
+pub fn render_changelog_entry(
+    timestamp: &str,
+    changes: &[ThreatChange],
+    ratings: &[RatingLevel],
+) -> String {
+    let with_rating = |value: u32| match rate(ratings, value) {
+        Some(rating) => format!("{} ({})", value, rating),
+        None => value.to_string(),
+    };
+    let mut added = String::new();
+    let mut removed = String::new();
+    let mut rerated = String::new();
+
+    for change in changes {
+        match change {
+            ThreatChange::Added {
+                file,
+                threat,
+                value,
+            } => added.push_str(&format!(
+                "- {} ({}), feasibility {}\n",
+                threat,
+                file,
+                with_rating(*value)
+            )),
+            ThreatChange::Removed { file, threat } => {
+                removed.push_str(&format!("- {} ({})\n", threat, file))
+            }
+            ThreatChange::Rerated {
+                file,
+                threat,
+                before,
+                after,
+            } => rerated.push_str(&format!(
+                "- {} ({}), feasibility {} -> {}\n",
+                threat,
+                file,
+                with_rating(*before),
+                with_rating(*after)
+            )),
+        }
+    }
+
+    let mut result = format!("## {}\n", timestamp);
+    for (heading, list) in [
+        ("Added", added),
+        ("Removed", removed),
+        ("Re-rated", rerated),
+    ] {
+        if !list.is_empty() {
+            result.push_str(&format!("\n### {}\n\n{}", heading, list));
+        }
+    }
+
+    result
+}
+
+/// Adds the entry on top of the existing changelog, below its title.
+pub fn add_changelog_entry(changelog: &str, entry: &str) -> String {
+    let earlier = changelog
+        .strip_prefix(CHANGELOG_TITLE)
+        .unwrap_or(changelog)
+        .trim_start();
+
+    match earlier.is_empty() {
+        true => format!("{}\n{}", CHANGELOG_TITLE, entry),
+        false => format!("{}\n{}\n{}", CHANGELOG_TITLE, entry, earlier),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{
+        analysis::{coverage::RequirementCoverage, weakest::WeakestLeaf},
+        model::{FeasiblityCriterion, Requirement},
+    };
+
+    #[test]
+    fn changelog_entries_are_added_on_top() {
+        let ratings = vec![RatingLevel {
+            name: "High".to_string(),
+            max: Some(13),
+        }];
+        let changes = vec![
+            ThreatChange::Rerated {
+                file: "house.att".to_string(),
+                threat: "Enter house".to_string(),
+                before: 15,
+                after: 8,
+            },
+            ThreatChange::Removed {
+                file: "car.att".to_string(),
+                threat: "Steal car".to_string(),
+            },
+        ];
+
+        let entry = render_changelog_entry("2024-05-01 10:00", &changes, &ratings);
+        assert_eq!(
+            entry,
+            "## 2024-05-01 10:00\n\n### Removed\n\n- Steal car (car.att)\n\n### Re-rated\n\n- Enter house (house.att), feasibility 15 -> 8 (High)\n"
+        );
+
+        let changelog = add_changelog_entry("", "## First\n");
+        assert_eq!(changelog, "# Threat model changelog\n\n## First\n");
+        assert_eq!(
+            add_changelog_entry(&changelog, "## Second\n"),
+            "# Threat model changelog\n\n## Second\n\n## First\n"
+        );
+    }
+
+    #[test]
+    fn residual_feasibility_lists_both_values() {
+        let residuals = vec![ResidualFeasibility {
+            file: PathBuf::from("house.att"),
+            threat: "Enter house".to_string(),
+            without_defenses: 8,
+            with_defenses: 15,
+            defenses: 2,
+        }];
+
+        let result = render_residual_feasibility_to_markdown(&residuals);
+
+        assert!(result.starts_with("## Residual feasibility\n"));
+        assert!(result.contains("| Enter house | 8                | 15            | 2        |"));
+    }
+
+    #[test]
+    fn simulations_list_percentiles_and_a_histogram() {
+        let simulations = vec![Simulation {
+            file: PathBuf::from("house.att"),
+            threat: "Enter house".to_string(),
+            samples: vec![3, 3, 4, 6],
+        }];
+
+        let result = render_simulation_to_markdown(&simulations);
+
+        assert!(
+            result.starts_with("## Feasibility distribution\n\nFeasibility values of 4 samples")
+        );
+        assert!(result.contains("| Enter house | 3   | 3   | 3   | 3   | 4   | 6   | 6   | 4.0  |"));
+        assert!(result.ends_with(
+            "### Enter house\n\n```\n   3-3    ######################################## 2\n   4-4    #################### 1\n   5-5     0\n   6-6    #################### 1\n```\n"
+        ));
+    }
+
+    #[test]
+    fn duplicates_are_listed_with_their_locations() {
+        let duplicates = vec![DuplicateSubtree {
+            title: "Get key".to_string(),
+            node_count: 3,
+            locations: vec![
+                NodeLocation {
+                    file: PathBuf::from("model/a.att"),
+                    path: vec!["A".to_string(), "Get key".to_string()],
+                },
+                NodeLocation {
+                    file: PathBuf::from("model/b.att"),
+                    path: vec!["B".to_string(), "Get key".to_string()],
+                },
+            ],
+        }];
+
+        let result = render_duplicates_to_markdown(&duplicates);
+
+        let expected = r#"## Duplicate subtrees
+
+The following subtrees occur more than once. Consider factoring them into a shared tree.
+
+| Subtree | Nodes | Locations                                |
+| ------- | ----- | ---------------------------------------- |
+| Get key | 3     | a.att: A > Get key<br>b.att: B > Get key |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn attribute_summaries_name_the_easiest_attack() {
+        let summaries = vec![AttributeSummary {
+            value: "ECU".to_string(),
+            feasibility: 3,
+            easiest: NodeLocation {
+                file: PathBuf::from("model/spoof.att"),
+                path: vec!["Spoof".to_string(), "Unlock".to_string()],
+            },
+            trees: 2,
+        }];
+        let ratings = vec![RatingLevel {
+            name: "High".to_string(),
+            max: None,
+        }];
+
+        let result = render_attribute_summaries_to_markdown("x-asset", &summaries, &ratings);
+
+        let expected = r#"## Feasibility by asset
+
+The easiest attack on each asset across all trees.
+
+| Asset | Feasibility | Rating | Easiest attack            | Trees |
+| ----- | ----------- | ------ | ------------------------- | ----- |
+| ECU   | 3           | High   | spoof.att: Spoof > Unlock | 2     |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn assumptions_are_listed_with_their_node() {
+        let assumptions = vec![AssumptionReference {
+            description: "No insider".to_string(),
+            note: Some("Staff is vetted".to_string()),
+            location: NodeLocation {
+                file: PathBuf::from("model/a.att"),
+                path: vec!["A".to_string(), "Door".to_string()],
+            },
+        }];
+
+        let result = render_assumptions_to_markdown(&assumptions);
+
+        let expected = r#"## Assumptions
+
+The analysis relies on the following assumptions. The feasibility values are only valid while they hold.
+
+| Assumption | Node            | Note            |
+| ---------- | --------------- | --------------- |
+| No insider | a.att: A > Door | Staff is vetted |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn weakest_leaves_are_listed_below_their_threat() {
+        let leaf = |path: &[&str], criteria: &[&str]| WeakestLeaf {
+            location: NodeLocation {
+                file: PathBuf::from("a.att"),
+                path: path.iter().map(|p| p.to_string()).collect(),
+            },
+            criteria: criteria.iter().map(|c| c.to_string()).collect(),
+        };
+        let trees = vec![
+            TreeWeakestLeaves {
+                file: PathBuf::from("a.att"),
+                threat: "Enter house".to_string(),
+                feasibility_value: 6,
+                leaves: vec![
+                    leaf(&["Enter house", "Door", "Pick lock"], &["Kn"]),
+                    leaf(&["Enter house", "Door", "Open"], &["Eq"]),
+                ],
+            },
+            TreeWeakestLeaves {
+                file: PathBuf::from("b.att"),
+                threat: "Steal car".to_string(),
+                feasibility_value: 3,
+                leaves: vec![leaf(&["Steal car"], &["Kn", "Eq"])],
+            },
+        ];
+
+        let result = render_weakest_leaves_to_markdown(&trees);
+
+        let expected = r#"## Weakest leaves
+
+The attack steps that determine the feasibility of each threat. Making them harder raises the value.
+
+| Threat      | Feasibility | Attack step      | Criteria |
+| ----------- | ----------- | ---------------- | -------- |
+| Enter house | 6           | Door > Pick lock | Kn       |
+|             |             | Door > Open      | Eq       |
+| Steal car   | 3           | Steal car        | Kn, Eq   |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn stale_assessments_are_listed_with_their_age() {
+        let stale = vec![StaleAssessment {
+            location: NodeLocation {
+                file: PathBuf::from("model/a.att"),
+                path: vec!["A".to_string(), "Pick lock".to_string()],
+            },
+            assessed: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            age_days: 517,
+        }];
+
+        let result = render_stale_assessments_to_markdown(&stale, 365);
+
+        let expected = r#"## Stale assessments
+
+The following leaves were assessed more than 365 days ago and should be reviewed.
+
+| Attack step          | Assessed   | Age (days) |
+| -------------------- | ---------- | ---------- |
+| a.att: A > Pick lock | 2023-01-01 | 517        |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn techniques_link_to_attack_and_list_their_leaves() {
+        let techniques = vec![TechniqueReferences {
+            technique: "T1110.003".to_string(),
+            locations: vec![
+                NodeLocation {
+                    file: PathBuf::from("model/a.att"),
+                    path: vec!["A".to_string(), "Spray".to_string()],
+                },
+                NodeLocation {
+                    file: PathBuf::from("model/b.att"),
+                    path: vec!["B".to_string(), "Spray".to_string()],
+                },
+            ],
+        }];
+
+        let result = render_techniques_to_markdown(&techniques);
+
+        let expected = r#"## ATT&CK techniques
+
+| Technique                                                   | Attack steps                         |
+| ----------------------------------------------------------- | ------------------------------------ |
+| [T1110.003](https://attack.mitre.org/techniques/T1110/003/) | a.att: A > Spray<br>b.att: B > Spray |
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn the_coverage_matrix_marks_covered_cells_and_lists_gaps() {
+        let requirement = |id: &str, title: &str| Requirement {
+            id: id.to_string(),
+            title: title.to_string(),
+        };
+        let matrix = CoverageMatrix {
+            threats: vec!["Steal data".to_string(), "Sabotage".to_string()],
+            rows: vec![
+                RequirementCoverage {
+                    requirement: requirement("SR-1", "Encrypt"),
+                    covered: vec![true, false],
+                },
+                RequirementCoverage {
+                    requirement: requirement("SR-2", "Log"),
+                    covered: vec![false, false],
+                },
+            ],
+        };
+
+        let result = render_coverage_matrix_to_markdown(&matrix);
+
+        let expected = r#"## Requirements coverage
+
+| Requirement   | Steal data | Sabotage |
+| ------------- | ---------- | -------- |
+| SR-1: Encrypt | x          |          |
+| SR-2: Log     |            |          |
+
+Not addressed by any threat: SR-2
+"#;
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn the_rating_method_explains_each_criterion() {
+        let mut knowledge = FeasiblityCriterion::new("Kn", "Knowledge");
+        knowledge.description = Some("Expertise needed for the step.".to_string());
+        knowledge.guidance = BTreeMap::from([(0, "Layman".to_string()), (3, "Expert".to_string())]);
+        let mut equipment = FeasiblityCriterion::new("Eq", "Equipment");
+        equipment.weight = 2;
+        let definition = FeasibilityCriteria(vec![knowledge, equipment], vec![]);
+
+        let result = render_rating_method_to_markdown(&definition);
+
+        let expected = r#"## Rating method
+
+The feasibility of a leaf is the weighted sum of its ratings. AND nodes take the hardest rating of their children per criterion, OR nodes the easiest child.
+
+### Knowledge (Kn)
+
+Expertise needed for the step.
+
+Weight: 1, higher values are harder.
+
+| Value | Meaning |
+| ----- | ------- |
+| 0     | Layman  |
+| 3     | Expert  |
+
+### Equipment (Eq)
+
+Weight: 2, higher values are harder.
+"#;
+
+        assert_eq!(result, expected);
+    }
+}
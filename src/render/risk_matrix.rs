@@ -0,0 +1,142 @@
+use crate::model::{rate, RatingLevel};
+
+use super::graphml::escape;
+
+/// Impact levels of ISO/SAE 21434, used unless others are configured.
+pub const DEFAULT_IMPACT_LEVELS: [&str; 4] = ["Negligible", "Moderate", "Major", "Severe"];
+
+/// The threats in a grid of feasibility rating and impact level.
+#[derive(Debug, PartialEq)]
+pub struct RiskMatrix {
+    /// Rating levels, the most feasible first.
+    ratings: Vec<RatingLevel>,
+    /// Impact levels, the lowest first.
+    impacts: Vec<String>,
+    /// Threat ids by impact level and rating.
+    cells: Vec<Vec<Vec<String>>>,
+}
+
+impl RiskMatrix {
+    pub fn new(ratings: &[RatingLevel], impacts: &[String]) -> Self {
+        RiskMatrix {
+            ratings: ratings.to_vec(),
+            impacts: impacts.to_vec(),
+            cells: vec![vec![vec![]; ratings.len()]; impacts.len()],
+        }
+    }
+
+    /// Plots the threat in the cell of its rating and impact. The impact is
+    /// compared ignoring case. Values without a rating are left out.
+    pub fn add(&mut self, id: &str, feasibility: u32, impact: &str) -> Result<(), String> {
+        let Some(row) = self
+            .impacts
+            .iter()
+            .position(|i| i.eq_ignore_ascii_case(impact))
+        else {
+            return Err(format!(
+                "unknown impact '{}', use one of: {}",
+                impact,
+                self.impacts.join(", ")
+            ));
+        };
+        let column = rate(&self.ratings, feasibility)
+            .and_then(|name| self.ratings.iter().position(|l| l.name == name));
+        if let Some(column) = column {
+            self.cells[row][column].push(id.to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().flatten().all(|ids| ids.is_empty())
+    }
+
+    /// A Graphviz graph with the matrix as HTML table, the highest impact on
+    /// top and the most feasible rating on the right. Cells are colored by
+    /// the sum of both levels.
+    pub fn to_dot(&self) -> String {
+        let max_score = (self.ratings.len() + self.impacts.len()).saturating_sub(2);
+        let mut rows = vec![];
+
+        for (row, impact) in self.impacts.iter().enumerate().rev() {
+            let mut cells = format!("<td><b>{}</b></td>", escape(impact));
+            for (column, ids) in self.cells[row].iter().enumerate().rev() {
+                let score = row + self.ratings.len() - 1 - column;
+                cells.push_str(&format!(
+                    r#"<td bgcolor="{}">{}</td>"#,
+                    risk_color(score, max_score),
+                    escape(&ids.join(", "))
+                ));
+            }
+            rows.push(format!("<tr>{}</tr>", cells));
+        }
+
+        let mut header = "<td>Impact / Feasibility</td>".to_string();
+        for level in self.ratings.iter().rev() {
+            header.push_str(&format!("<td><b>{}</b></td>", escape(&level.name)));
+        }
+        rows.push(format!("<tr>{}</tr>", header));
+
+        format!(
+            "digraph {{\nmatrix [shape=plaintext label=<<table border=\"0\" cellborder=\"1\" cellspacing=\"0\" cellpadding=\"8\">\n{}\n</table>>]\n}}\n",
+            rows.join("\n")
+        )
+    }
+}
+
+fn risk_color(score: usize, max_score: usize) -> &'static str {
+    if 3 * score < max_score {
+        "palegreen"
+    } else if 3 * score < 2 * max_score {
+        "khaki"
+    } else {
+        "lightcoral"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratings() -> Vec<RatingLevel> {
+        vec![
+            RatingLevel {
+                name: "High".to_string(),
+                max: Some(13),
+            },
+            RatingLevel {
+                name: "Low".to_string(),
+                max: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn threats_are_plotted_by_rating_and_impact() {
+        let impacts: Vec<String> = DEFAULT_IMPACT_LEVELS.map(String::from).to_vec();
+        let mut matrix = RiskMatrix::new(&ratings(), &impacts);
+        assert!(matrix.is_empty());
+
+        matrix.add("T1", 10, "Severe").unwrap();
+        matrix.add("T2", 20, "severe").unwrap();
+        matrix.add("T3", 5, "Severe").unwrap();
+        matrix.add("T4", 25, "Negligible").unwrap();
+        assert_eq!(
+            matrix.add("T5", 1, "Fatal"),
+            Err(
+                "unknown impact 'Fatal', use one of: Negligible, Moderate, Major, Severe"
+                    .to_string()
+            )
+        );
+
+        let dot = matrix.to_dot();
+        assert!(dot.contains(
+            r#"<tr><td><b>Severe</b></td><td bgcolor="lightcoral">T2</td><td bgcolor="lightcoral">T1, T3</td></tr>"#
+        ));
+        assert!(dot.contains(
+            r#"<tr><td><b>Negligible</b></td><td bgcolor="palegreen">T4</td><td bgcolor="palegreen"></td></tr>"#
+        ));
+        assert!(dot.contains("<td><b>Low</b></td><td><b>High</b></td></tr>"));
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rusqlite::{params, Connection, Transaction};
+
+use crate::model::{
+    feasible_step::{FeasibleStep, NodeKind},
+    traversal::preorder_with_depth,
+    FeasibilityCriteria,
+};
+
+use super::RenderError;
+
+/// The tables written by `render_to_sqlite`.
+pub const SQLITE_SCHEMA: &str = "
+-- The criteria of criteria.json, in their order.
+CREATE TABLE criteria (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL
+);
+
+-- One row per .att file.
+CREATE TABLE trees (
+    id INTEGER PRIMARY KEY,
+    file TEXT NOT NULL,
+    title TEXT NOT NULL,
+    root_node_id INTEGER NOT NULL
+);
+
+-- Every node of every tree, parent_id is NULL for the roots. The
+-- feasibility columns are NULL if the value cannot be computed, the range
+-- columns if all assessments are point estimates.
+CREATE TABLE nodes (
+    id INTEGER PRIMARY KEY,
+    tree_id INTEGER NOT NULL REFERENCES trees(id),
+    parent_id INTEGER REFERENCES nodes(id),
+    position INTEGER NOT NULL,
+    depth INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    type TEXT NOT NULL,
+    threshold INTEGER,
+    uuid TEXT,
+    feasibility_value INTEGER,
+    feasibility_min INTEGER,
+    feasibility_max INTEGER,
+    detection REAL,
+    note TEXT
+);
+
+-- The values assessed on the leaves. hardest is the upper end of a range
+-- like Kn=4..6 and equals value for point estimates.
+CREATE TABLE assessments (
+    node_id INTEGER NOT NULL REFERENCES nodes(id),
+    criterion_id TEXT NOT NULL REFERENCES criteria(id),
+    value INTEGER NOT NULL,
+    hardest INTEGER NOT NULL,
+    PRIMARY KEY (node_id, criterion_id)
+);
+
+-- The value of every criterion of every node, aggregated from the leaves.
+CREATE TABLE feasibility (
+    node_id INTEGER NOT NULL REFERENCES nodes(id),
+    criterion_id TEXT NOT NULL REFERENCES criteria(id),
+    value INTEGER NOT NULL,
+    PRIMARY KEY (node_id, criterion_id)
+);
+
+-- Custom attributes like x-asset=ECU.
+CREATE TABLE attributes (
+    node_id INTEGER NOT NULL REFERENCES nodes(id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (node_id, key)
+);
+";
+
+/// Writes the trees with their nodes, assessments and computed values to a
+/// new SQLite database at `path`, replacing an existing file.
+pub fn render_to_sqlite(
+    attack_trees: &[(PathBuf, Rc<dyn FeasibleStep>)],
+    definition: &FeasibilityCriteria,
+    path: &Path,
+) -> Result<(), RenderError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SQLITE_SCHEMA)?;
+
+    for (position, c) in definition.0.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO criteria (id, name, position) VALUES (?1, ?2, ?3)",
+            params![c.id, c.name, position],
+        )?;
+    }
+
+    for (tree_id, (file, root)) in attack_trees.iter().enumerate() {
+        transaction.execute(
+            "INSERT INTO trees (id, file, title, root_node_id) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                tree_id + 1,
+                file.to_string_lossy().replace('\\', "/"),
+                root.title(),
+                root.id()
+            ],
+        )?;
+        insert_nodes(&transaction, tree_id + 1, root)?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+fn insert_nodes(
+    transaction: &Transaction,
+    tree_id: usize,
+    root: &Rc<dyn FeasibleStep>,
+) -> Result<(), RenderError> {
+    for (depth, node) in preorder_with_depth(root) {
+        let parent = node.get_parent().filter(|_| depth > 0);
+        let position = parent
+            .as_ref()
+            .and_then(|p| p.get_children().iter().position(|c| c.id() == node.id()))
+            .unwrap_or(0);
+        let feasibility = node.feasibility().ok();
+        let (min, max) = node.feasibility_interval().unzip();
+
+        transaction.execute(
+            "INSERT INTO nodes (id, tree_id, parent_id, position, depth, title, type, threshold,
+                uuid, feasibility_value, feasibility_min, feasibility_max, detection, note)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                node.id(),
+                tree_id,
+                parent.map(|p| p.id()),
+                position,
+                depth,
+                node.title(),
+                node.kind().to_string(),
+                node.threshold(),
+                node.uuid(),
+                feasibility.as_ref().map(|a| a.sum()),
+                min,
+                max,
+                node.detection(),
+                node.note(),
+            ],
+        )?;
+
+        if node.kind() == NodeKind::Leaf {
+            if let Ok((easiest, hardest)) = node.feasibility_range() {
+                for (c, value) in easiest.iter() {
+                    let Some(value) = value else { continue };
+                    transaction.execute(
+                        "INSERT INTO assessments (node_id, criterion_id, value, hardest)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        params![
+                            node.id(),
+                            c.id,
+                            value,
+                            hardest.by_id().get(&c.id).unwrap_or(value)
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        for (c, value) in feasibility.iter().flat_map(|a| a.iter()) {
+            let Some(value) = value else { continue };
+            transaction.execute(
+                "INSERT INTO feasibility (node_id, criterion_id, value) VALUES (?1, ?2, ?3)",
+                params![node.id(), c.id, value],
+            )?;
+        }
+
+        for (key, value) in node.attributes() {
+            transaction.execute(
+                "INSERT INTO attributes (node_id, key, value) VALUES (?1, ?2, ?3)",
+                params![node.id(), key, value],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{model::tests::build_criteria, parser::AttackTreeParser};
+
+    #[test]
+    fn nodes_assessments_and_computed_values_can_be_queried() {
+        let definition = build_criteria(&["Kn", "Eq"]);
+        let root = AttackTreeParser::new()
+            .parse(
+                &mut Cursor::new(
+                    "Enter house;|\n    Pick lock; Kn=2..4, Eq=3, x-asset=Door\n    Break window; Kn=1, Eq=5",
+                ),
+                &definition,
+            )
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("att-sqlite-{}.db", std::process::id()));
+
+        render_to_sqlite(&[(PathBuf::from("house.att"), root)], &definition, &path).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let query = |sql: &str| -> Vec<String> {
+            let mut statement = connection.prepare(sql).unwrap();
+            let rows = statement
+                .query_map([], |row| row.get::<_, String>(0))
+                .unwrap();
+            rows.map(Result::unwrap).collect()
+        };
+        assert_eq!(
+            query("SELECT file || ':' || title FROM trees"),
+            vec!["house.att:Enter house"]
+        );
+        assert_eq!(
+            query(
+                "SELECT n.title || ':' || n.depth || ':' || n.feasibility_value FROM nodes n
+                 ORDER BY n.depth, n.position"
+            ),
+            vec!["Enter house:0:5", "Pick lock:1:5", "Break window:1:6"]
+        );
+        assert_eq!(
+            query(
+                "SELECT criterion_id || '=' || value || '..' || hardest FROM assessments a
+                 JOIN nodes n ON n.id = a.node_id WHERE n.title = 'Pick lock' ORDER BY 1"
+            ),
+            vec!["Eq=3..3", "Kn=2..4"]
+        );
+        assert_eq!(
+            query(
+                "SELECT criterion_id || '=' || value FROM feasibility f
+                 JOIN trees t ON t.root_node_id = f.node_id ORDER BY 1"
+            ),
+            vec!["Eq=3", "Kn=2"]
+        );
+        assert_eq!(
+            query("SELECT key || '=' || value FROM attributes"),
+            vec!["x-asset=Door"]
+        );
+
+        drop(connection);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use serde_json::{json, Value};
+use tera::{Context, Tera};
+
+use crate::model::{feasible_step::FeasibleStep, FeasibilityCriteria};
+
+use super::{flatten_with_depth, json::node_to_json, RenderError};
+
+/// Renders the overview of all trees with a user supplied Tera template.
+///
+/// The context contains `criteria`, a list of `{id, name, description,
+/// guidance}`, and `trees`, a
+/// list of `{image, owner, status, version, date, root}` where `root` is the node structure
+/// also used in threats.json.
+pub fn render_report_with_template(
+    template: &str,
+    attack_trees: Vec<(PathBuf, &Rc<dyn FeasibleStep>)>,
+    definition: &FeasibilityCriteria,
+) -> Result<String, RenderError> {
+    let trees: Vec<Value> = attack_trees
+        .iter()
+        .map(|(image_path, root_node)| tree_to_json(image_path, root_node))
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("criteria", &criteria_to_json(definition));
+    context.insert("trees", &trees);
+
+    render(template, &context)
+}
+
+/// Renders the page of a single tree with a user supplied Tera template.
+///
+/// The context contains `criteria`, `tree` as in the overview template and
+/// `nodes`, all nodes of the tree in depth-first order with their `depth`.
+pub fn render_tree_with_template(
+    template: &str,
+    image_path: &Path,
+    root_node: &Rc<dyn FeasibleStep>,
+    definition: &FeasibilityCriteria,
+) -> Result<String, RenderError> {
+    let mut nodes = vec![];
+    flatten_with_depth(root_node, 0, &mut nodes);
+
+    let nodes: Vec<Value> = nodes
+        .iter()
+        .map(|(depth, node)| {
+            let mut value = node_to_json(node);
+            value["depth"] = json!(depth);
+            value
+        })
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("criteria", &criteria_to_json(definition));
+    context.insert("tree", &tree_to_json(image_path, root_node));
+    context.insert("nodes", &nodes);
+
+    render(template, &context)
+}
+
+fn render(template: &str, context: &Context) -> Result<String, RenderError> {
+    Tera::one_off(template, context, false).map_err(|e| {
+        // tera puts the actual cause, e.g. the parser error, into the source chain
+        let mut message = e.to_string();
+        let mut source = e.source();
+        while let Some(s) = source {
+            message.push_str(&format!(": {}", s));
+            source = s.source();
+        }
+        RenderError::TemplateError(message)
+    })
+}
+
+fn tree_to_json(image_path: &Path, root_node: &Rc<dyn FeasibleStep>) -> Value {
+    let review = root_node.review();
+
+    json!({
+        "image": image_path.to_str().unwrap_or(""),
+        "owner": review.owner,
+        "status": review.status,
+        "version": review.version,
+        "date": review.date,
+        "root": node_to_json(root_node),
+    })
+}
+
+fn criteria_to_json(definition: &FeasibilityCriteria) -> Value {
+    definition
+        .0
+        .iter()
+        .map(|c| {
+            json!({
+                "id": c.id,
+                "name": c.name,
+                "description": c.description,
+                "guidance": c.guidance,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{or_node::OrNode, tests::build_criteria, Leaf};
+
+    fn build_tree() -> (Rc<FeasibilityCriteria>, Rc<dyn FeasibleStep>) {
+        let definition = build_criteria(&["Kn", "Eq"]);
+
+        let root: Rc<dyn FeasibleStep> = Rc::new(OrNode::new("Root", None, || 1));
+        let leaf: Rc<dyn FeasibleStep> = Rc::new(Leaf::new(
+            "Step 1",
+            Some(root.clone()),
+            &definition,
+            &[15, 5],
+            || 2,
+        ));
+        root.add_child(&leaf);
+
+        (definition, root)
+    }
+
+    #[test]
+    fn the_report_template_can_access_trees_and_criteria() {
+        let (definition, root) = build_tree();
+        let template = "{% for c in criteria %}{{ c.id }} {% endfor %}\n\
+            {% for t in trees %}[{{ t.root.title }}]({{ t.image }}): {{ t.root.feasibility_value }} (Kn={{ t.root.feasibility.Kn }})\n{% endfor %}";
+
+        let result = render_report_with_template(
+            template,
+            vec![(PathBuf::from("images/root.png"), &root)],
+            &definition,
+        )
+        .unwrap();
+
+        assert_eq!(result, "Kn Eq \n[Root](images/root.png): 20 (Kn=15)\n");
+    }
+
+    #[test]
+    fn the_tree_template_lists_all_nodes_with_their_depth() {
+        let (definition, root) = build_tree();
+        let template = "# {{ tree.root.title }}\n{% for n in nodes %}{{ n.depth }} {{ n.type }} {{ n.title }}\n{% endfor %}";
+
+        let result =
+            render_tree_with_template(template, Path::new("root.png"), &root, &definition).unwrap();
+
+        assert_eq!(result, "# Root\n0 or Root\n1 leaf Step 1\n");
+    }
+
+    #[test]
+    fn template_errors_are_reported() {
+        let (definition, root) = build_tree();
+
+        let result = render_tree_with_template(
+            "{% for n in nodes %}",
+            Path::new("root.png"),
+            &root,
+            &definition,
+        );
+
+        assert!(matches!(result, Err(RenderError::TemplateError(_))));
+    }
+}
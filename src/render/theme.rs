@@ -0,0 +1,122 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::Deserialize;
+
+/// Color scheme of the rendered graphs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Black on white, the Graphviz defaults.
+    #[default]
+    Light,
+    /// Light text and lines on a dark background, e.g. for slides.
+    Dark,
+    /// Shades of gray only, for black and white printers.
+    Print,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            "print" => Ok(Theme::Print),
+            _ => Err(format!("unknown theme '{}', use light, dark or print", s)),
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// The colors a theme gives the parts of a graph, as X11 color names which
+/// need no quotes in DOT.
+pub(crate) struct Palette {
+    /// Background, node fill, text and line color, None keeps the Graphviz
+    /// defaults.
+    pub base: Option<BaseColors>,
+    pub defense: &'static str,
+    pub assumption: &'static str,
+    pub new: &'static str,
+    pub changed: &'static str,
+}
+
+pub(crate) struct BaseColors {
+    pub background: &'static str,
+    pub fill: &'static str,
+    pub text: &'static str,
+    pub line: &'static str,
+}
+
+impl Theme {
+    pub(crate) fn palette(self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                base: None,
+                defense: "darkgreen",
+                assumption: "gray40",
+                new: "green",
+                changed: "orange",
+            },
+            Theme::Dark => Palette {
+                base: Some(BaseColors {
+                    background: "gray12",
+                    fill: "gray18",
+                    text: "gray88",
+                    line: "gray69",
+                }),
+                defense: "palegreen3",
+                assumption: "gray62",
+                new: "green3",
+                changed: "orange",
+            },
+            Theme::Print => Palette {
+                base: Some(BaseColors {
+                    background: "white",
+                    fill: "white",
+                    text: "black",
+                    line: "black",
+                }),
+                defense: "gray20",
+                assumption: "gray50",
+                new: "black",
+                changed: "gray40",
+            },
+        }
+    }
+
+    /// Default attributes of the graph, its nodes and edges, None for the
+    /// light theme.
+    pub(crate) fn to_dot_attributes(self) -> Option<String> {
+        let base = self.palette().base?;
+
+        Some(format!(
+            "graph [bgcolor={bg} color={line} fontcolor={text}]\nnode [style=filled fillcolor={fill} color={line} fontcolor={text}]\nedge [color={line} fontcolor={text}]",
+            bg = base.background,
+            fill = base.fill,
+            text = base.text,
+            line = base.line
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_are_parsed_and_only_non_default_themes_set_colors() {
+        assert_eq!("Dark".parse::<Theme>(), Ok(Theme::Dark));
+        assert!("neon".parse::<Theme>().is_err());
+
+        assert_eq!(Theme::Light.to_dot_attributes(), None);
+        let dark = Theme::Dark.to_dot_attributes().unwrap();
+        assert!(dark.starts_with("graph [bgcolor=gray12 "));
+        assert!(dark.contains("node [style=filled fillcolor=gray18 "));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use att::{
+    analysis::criteria_check::invalidated_by,
+    config::Config,
+    model::{feasible_step::FeasibleStep, FeasibilityCriteria},
+    render::{
+        graphviz_available, node_to_json, render_to_markdown_table_with_options, render_to_svg,
+        render_tree_to_markdown, RenderError,
+    },
+    summarize,
+};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    load_directory, read_att_files, render_directory, render_options, to_image_path,
+    tree_page_path, Cli, Failures, Progress, CRITERIA_FILE,
+};
+
+const IMAGES_DIR: &str = "images";
+const TREES_DIR: &str = "trees";
+/// Prefix of the JSON endpoints served with `--api`.
+const API_PREFIX: &str = "/api/";
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; }";
+
+/// Serves the overview, the tree pages and the images of a directory. Every
+/// request parses the .att files again, so edits show up on reload. With
+/// `api`, the trees are also served as JSON below `/api/`.
+pub fn serve(directory_name: &str, port: u16, api: bool, cli: &Cli, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+
+    let address = format!("127.0.0.1:{}", port);
+    let server = match Server::http(&address) {
+        Ok(s) => s,
+        Err(e) => {
+            failures.record(Path::new(directory_name), e);
+            return failures;
+        }
+    };
+    let mut criteria = CriteriaWatch::new(directory_name);
+    if let Err(e) = criteria.check(config) {
+        failures.record(&criteria.path, e);
+        return failures;
+    }
+    println!("Serving {} on http://{}/", directory_name, address);
+
+    for request in server.incoming_requests() {
+        let url = request
+            .url()
+            .split(['?', '#'])
+            .next()
+            .unwrap_or("/")
+            .to_string();
+
+        if !is_local_request(&request, port) {
+            let response = error_page(403, &"only requests from the served pages are answered");
+            if let Err(e) = request.respond(response) {
+                eprintln!("{}: {}", url, e);
+            }
+            continue;
+        }
+
+        let response = match criteria.check(config) {
+            Err(e) => {
+                let message = format!("{}: {}", criteria.path.display(), e);
+                eprintln!("{}", message);
+                match url.starts_with(API_PREFIX) && api {
+                    true => json_error(500, &message),
+                    false => error_page(500, &message),
+                }
+            }
+            Ok(()) => match url.strip_prefix(API_PREFIX) {
+                Some(path) if api => respond_api(
+                    request.method(),
+                    &percent_decode(path),
+                    directory_name,
+                    cli,
+                    config,
+                ),
+                _ => {
+                    // problems in the files are printed, the pages show what could be parsed
+                    let (definition, attack_trees) = load_directory(
+                        directory_name,
+                        config,
+                        &mut Progress::hidden(),
+                        &mut Failures::default(),
+                    );
+                    respond(
+                        &percent_decode(&url),
+                        &definition,
+                        &attack_trees,
+                        cli,
+                        config,
+                    )
+                }
+            },
+        };
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("{}: {}", url, e);
+        }
+    }
+
+    failures
+}
+
+/// Whether the request is addressed to the server by a local name and, if
+/// sent by a web page, comes from one of its pages. Other web pages could
+/// otherwise use the server through the browser of the user, e.g. render
+/// the directory or read the trees after rebinding their DNS name.
+fn is_local_request(request: &Request, port: u16) -> bool {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str())
+    };
+    let is_local = |host: &str| {
+        ["127.0.0.1", "localhost"]
+            .iter()
+            .any(|name| host == format!("{}:{}", name, port))
+    };
+
+    header("Host").is_some_and(is_local)
+        && header("Origin").is_none_or(|o| o.strip_prefix("http://").is_some_and(is_local))
+}
+
+/// The criteria.json of the served directory. The trees are parsed again on
+/// every request, so a change of the criteria applies right away, but an
+/// invalid file must not stop the server and the leaves it invalidates are
+/// reported once.
+struct CriteriaWatch {
+    directory: PathBuf,
+    path: PathBuf,
+    /// The contents of the last valid file.
+    valid: Option<(String, Rc<FeasibilityCriteria>)>,
+}
+
+impl CriteriaWatch {
+    fn new(directory_name: &str) -> CriteriaWatch {
+        let directory = PathBuf::from(directory_name);
+        CriteriaWatch {
+            path: directory.join(CRITERIA_FILE),
+            directory,
+            valid: None,
+        }
+    }
+
+    /// Fails if criteria.json cannot be used. If it changed since the last
+    /// call, prints the problems of the trees caused by the change.
+    fn check(&mut self, config: &Config) -> Result<(), String> {
+        let contents = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        if self.valid.as_ref().is_some_and(|(c, _)| *c == contents) {
+            return Ok(());
+        }
+        let definition =
+            Rc::new(FeasibilityCriteria::from_json(&contents).map_err(|e| e.to_string())?);
+
+        if let Some((_, old)) = self.valid.replace((contents, definition.clone())) {
+            println!("{} changed, checking the trees", self.path.display());
+            let files = read_att_files(&self.directory, config, &mut Failures::default());
+            let invalidated =
+                invalidated_by(&files, &old, &definition, &config.parser.to_options());
+            for (file, d) in &invalidated {
+                eprintln!("{}:{}: {}", file.display(), d.line, d.message);
+            }
+            match invalidated.len() {
+                0 => println!("All trees are valid with the new criteria."),
+                count => println!("{} problem(s) caused by the new criteria.", count),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+type Page = Response<std::io::Cursor<Vec<u8>>>;
+
+fn respond(
+    url: &str,
+    definition: &Rc<FeasibilityCriteria>,
+    attack_trees: &[(std::path::PathBuf, Rc<dyn FeasibleStep>)],
+    cli: &Cli,
+    config: &Config,
+) -> Page {
+    let mut options = render_options(cli, config);
+
+    options.tree_pages = Some(PathBuf::from(TREES_DIR));
+
+    if url == "/" || url == "/threats.md" {
+        let root_nodes = attack_trees
+            .iter()
+            .map(|(f, r)| (to_image_path(Path::new(IMAGES_DIR), f, "svg"), r))
+            .collect();
+        let markdown = render_to_markdown_table_with_options(root_nodes, &options);
+
+        return html_page("Threats", &markdown);
+    }
+
+    let Some((directory, name)) = url.trim_start_matches('/').split_once('/') else {
+        return not_found(url);
+    };
+
+    let tree = attack_trees.iter().find(|(f, _)| match directory {
+        IMAGES_DIR => to_image_path(Path::new(""), f, "svg") == Path::new(name),
+        TREES_DIR => tree_page_path(Path::new(""), f) == Path::new(name),
+        _ => false,
+    });
+    let Some((file, root)) = tree else {
+        return not_found(url);
+    };
+
+    options.node_links = Some(
+        tree_page_path(&Path::new("..").join(TREES_DIR), file)
+            .to_string_lossy()
+            .replace('\\', "/"),
+    );
+
+    if directory == TREES_DIR {
+        let image_path = to_image_path(&Path::new("..").join(IMAGES_DIR), file, "svg");
+        let markdown = render_tree_to_markdown(&image_path, root, definition, &options);
+        return html_page(root.title(), &markdown);
+    }
+
+    if !graphviz_available(options.engine) {
+        return error_page(
+            503,
+            &RenderError::GraphvizNotFound(options.engine.to_string()),
+        );
+    }
+
+    match render_to_svg(root, &options) {
+        Ok(svg) => Response::from_string(svg).with_header(content_type("image/svg+xml")),
+        Err(e) => error_page(500, &e),
+    }
+}
+
+/// The JSON endpoints:
+///
+/// - `GET trees`: the file, title and feasibility value of every tree
+/// - `GET trees/<file>`: the tree as in threats.json
+/// - `GET trees/<file>/feasibility`: the feasibility of the root
+/// - `POST render`: renders the directory like `att <directory>`
+///
+/// Files are named relative to the directory, e.g. `doors/lock.att`.
+fn respond_api(
+    method: &Method,
+    path: &str,
+    directory_name: &str,
+    cli: &Cli,
+    config: &Config,
+) -> Page {
+    if path == "render" {
+        if *method != Method::Post {
+            return json_error(405, "use POST to render the directory");
+        }
+        let mut failures = Failures::default();
+        render_directory(
+            directory_name,
+            render_options(cli, config),
+            &cli.report,
+            config,
+            &mut failures,
+        );
+        let status = if failures.count() == 0 { 200 } else { 500 };
+        return json_response(status, &json!({ "failures": failures.messages }));
+    }
+
+    if *method != Method::Get {
+        return json_error(405, "only GET is supported");
+    }
+
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut Failures::default(),
+    );
+    let name = |f: &Path| {
+        f.strip_prefix(directory_name)
+            .unwrap_or(f)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+
+    if path == "trees" {
+        let mut trees: Vec<_> = attack_trees.iter().collect();
+        trees.sort_by_key(|(f, _)| name(f));
+        let trees: Vec<Value> = trees
+            .iter()
+            .map(|(f, r)| {
+                json!({
+                    "file": name(f),
+                    "title": r.title(),
+                    "feasibility_value": r.feasibility_value(),
+                })
+            })
+            .collect();
+        return json_response(200, &Value::Array(trees));
+    }
+
+    let Some(tree) = path.strip_prefix("trees/") else {
+        return json_error(404, &format!("{}{} not found", API_PREFIX, path));
+    };
+    let (file, feasibility) = match tree.strip_suffix("/feasibility") {
+        Some(file) => (file, true),
+        None => (tree, false),
+    };
+    let Some((_, root)) = attack_trees.iter().find(|(f, _)| name(f) == file) else {
+        return json_error(404, &format!("no tree in {}", file));
+    };
+
+    match feasibility {
+        false => json_response(200, &json!({ "file": file, "root": node_to_json(root) })),
+        true => match summarize(root) {
+            Ok(summary) => json_response(200, &json!(summary)),
+            Err(e) => json_error(422, &e.to_string()),
+        },
+    }
+}
+
+fn json_response(status: u16, value: &Value) -> Page {
+    Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+fn json_error(status: u16, message: &str) -> Page {
+    json_response(status, &json!({ "error": message }))
+}
+
+/// Decodes the `%XX` escapes of a URL path, e.g. of `door%20lock.md`.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|h| bytes[i] == b'%' && h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match escaped {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether inline HTML is one of the tags the reports are written with,
+/// other HTML comes from titles or notes and is shown as text.
+fn is_report_html(html: &str) -> bool {
+    html == "<br>"
+        || html == "</a>"
+        || html
+            .strip_prefix(r#"<a id="node-"#)
+            .and_then(|rest| rest.strip_suffix(r#"">"#))
+            .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Converts markdown to a complete HTML page. Images become objects so the
+/// links in SVGs can be followed.
+fn html_page(title: &str, markdown: &str) -> Page {
+    let events = Parser::new_ext(markdown, Options::ENABLE_TABLES).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) if !is_report_html(&html) => Event::Text(html),
+        Event::Start(Tag::Image { dest_url, .. }) => Event::Html(CowStr::from(format!(
+            r#"<object data="{}" type="image/svg+xml">"#,
+            dest_url
+        ))),
+        Event::End(TagEnd::Image) => Event::Html(CowStr::from("</object>")),
+        e => e,
+    });
+
+    let mut body = String::new();
+    html::push_html(&mut body, events);
+
+    Response::from_string(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        STYLE,
+        body
+    ))
+    .with_header(content_type("text/html; charset=utf-8"))
+}
+
+fn not_found(url: &str) -> Page {
+    Response::from_string(format!("{} not found", url)).with_status_code(404)
+}
+
+fn error_page(status: u16, error: &dyn std::fmt::Display) -> Page {
+    Response::from_string(error.to_string()).with_status_code(status)
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("the header is valid ASCII")
+}
@@ -0,0 +1,439 @@
+use std::{collections::HashSet, io, path::Path, rc::Rc};
+
+use att::{
+    config::Config,
+    model::{
+        feasible_step::{format_probability, FeasibleStep, NodeKind},
+        rate,
+    },
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{load_directory, AttackTrees, Failures, Progress};
+
+const HELP: &str =
+    " ↑/↓ move  → expand  ← collapse  space toggle  e expand all  c collapse all  r reload  q quit";
+
+/// A node shown in the tree list.
+struct Row {
+    /// Index of the tree and pre-order position of the node in it, which stay
+    /// the same when the files are parsed again.
+    key: (usize, usize),
+    depth: usize,
+    node: Rc<dyn FeasibleStep>,
+}
+
+/// The trees of a directory with the expanded nodes and the selection.
+struct Browser {
+    attack_trees: AttackTrees,
+    expanded: HashSet<(usize, usize)>,
+    rows: Vec<Row>,
+    selected: usize,
+}
+
+impl Browser {
+    /// Starts with the root nodes expanded.
+    fn new(attack_trees: AttackTrees) -> Browser {
+        let expanded = (0..attack_trees.len()).map(|t| (t, 0)).collect();
+        let mut browser = Browser {
+            attack_trees,
+            expanded,
+            rows: vec![],
+            selected: 0,
+        };
+        browser.update_rows();
+        browser
+    }
+
+    fn reload(&mut self, attack_trees: AttackTrees) {
+        let key = self.rows.get(self.selected).map(|r| r.key);
+        self.attack_trees = attack_trees;
+        self.update_rows();
+        self.select_key(key);
+    }
+
+    fn update_rows(&mut self) {
+        self.rows.clear();
+        for (tree, (_, root)) in self.attack_trees.iter().enumerate() {
+            let mut position = 0;
+            add_rows(
+                root,
+                tree,
+                0,
+                &mut position,
+                true,
+                &self.expanded,
+                &mut self.rows,
+            );
+        }
+        self.selected = self.selected.min(self.rows.len().saturating_sub(1));
+    }
+
+    fn select_key(&mut self, key: Option<(usize, usize)>) {
+        if let Some(index) = self.rows.iter().position(|r| Some(r.key) == key) {
+            self.selected = index;
+        }
+    }
+
+    fn selected_row(&self) -> Option<&Row> {
+        self.rows.get(self.selected)
+    }
+
+    fn move_by(&mut self, offset: isize) {
+        let last = self.rows.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + offset).clamp(0, last) as usize;
+    }
+
+    fn expand(&mut self) {
+        if let Some(row) = self.selected_row() {
+            if !row.node.get_children().is_empty() {
+                self.expanded.insert(row.key);
+                self.update_rows();
+            }
+        }
+    }
+
+    /// Collapses the selected node, or selects its parent if there is
+    /// nothing to collapse.
+    fn collapse(&mut self) {
+        let Some(row) = self.selected_row() else {
+            return;
+        };
+        let (key, depth) = (row.key, row.depth);
+
+        if self.expanded.remove(&key) {
+            self.update_rows();
+        } else if let Some(parent) = self.rows[..self.selected]
+            .iter()
+            .rposition(|r| r.depth < depth)
+        {
+            self.selected = parent;
+        }
+    }
+
+    fn toggle(&mut self) {
+        match self.selected_row() {
+            Some(row) if self.expanded.contains(&row.key) => self.collapse(),
+            _ => self.expand(),
+        }
+    }
+
+    fn expand_all(&mut self) {
+        let key = self.selected_row().map(|r| r.key);
+        for (tree, (_, root)) in self.attack_trees.iter().enumerate() {
+            let mut position = 0;
+            expand_subtree(root, tree, &mut position, &mut self.expanded);
+        }
+        self.update_rows();
+        self.select_key(key);
+    }
+
+    fn collapse_all(&mut self) {
+        let tree = self.selected_row().map(|r| r.key.0);
+        self.expanded.clear();
+        self.update_rows();
+        self.select_key(tree.map(|t| (t, 0)));
+    }
+}
+
+fn add_rows(
+    node: &Rc<dyn FeasibleStep>,
+    tree: usize,
+    depth: usize,
+    position: &mut usize,
+    visible: bool,
+    expanded: &HashSet<(usize, usize)>,
+    rows: &mut Vec<Row>,
+) {
+    let key = (tree, *position);
+    *position += 1;
+
+    if visible {
+        rows.push(Row {
+            key,
+            depth,
+            node: node.clone(),
+        });
+    }
+
+    // positions of hidden nodes are still counted to keep the keys stable
+    let children_visible = visible && expanded.contains(&key);
+    for c in node.get_children() {
+        add_rows(
+            &c,
+            tree,
+            depth + 1,
+            position,
+            children_visible,
+            expanded,
+            rows,
+        );
+    }
+}
+
+fn expand_subtree(
+    node: &Rc<dyn FeasibleStep>,
+    tree: usize,
+    position: &mut usize,
+    expanded: &mut HashSet<(usize, usize)>,
+) {
+    let children = node.get_children();
+    if !children.is_empty() {
+        expanded.insert((tree, *position));
+    }
+    *position += 1;
+
+    for c in &children {
+        expand_subtree(c, tree, position, expanded);
+    }
+}
+
+/// Shows the trees of a directory in the terminal until the user quits.
+pub fn browse(directory_name: &str, config: &Config) -> Failures {
+    let mut failures = Failures::default();
+    let (_, attack_trees) = load_directory(
+        directory_name,
+        config,
+        &mut Progress::hidden(),
+        &mut failures,
+    );
+    let mut browser = Browser::new(attack_trees);
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut browser, directory_name, config);
+    ratatui::restore();
+
+    if let Err(e) = result {
+        failures.record(Path::new(directory_name), e);
+    }
+    failures
+}
+
+fn run(
+    terminal: &mut DefaultTerminal,
+    browser: &mut Browser,
+    directory_name: &str,
+    config: &Config,
+) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, browser, directory_name, config))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => browser.move_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => browser.move_by(1),
+            KeyCode::PageUp => browser.move_by(-10),
+            KeyCode::PageDown => browser.move_by(10),
+            KeyCode::Home => browser.selected = 0,
+            KeyCode::End => browser.selected = browser.rows.len().saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => browser.expand(),
+            KeyCode::Left | KeyCode::Char('h') => browser.collapse(),
+            KeyCode::Char(' ') => browser.toggle(),
+            KeyCode::Char('e') => browser.expand_all(),
+            KeyCode::Char('c') => browser.collapse_all(),
+            KeyCode::Char('r') => {
+                // parse errors are printed to stderr, which messes up the screen
+                let (_, attack_trees) = load_directory(
+                    directory_name,
+                    config,
+                    &mut Progress::hidden(),
+                    &mut Failures::default(),
+                );
+                browser.reload(attack_trees);
+                terminal.clear()?;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, browser: &Browser, directory_name: &str, config: &Config) {
+    let [main, help] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [tree_area, details_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+
+    let items: Vec<ListItem> = browser
+        .rows
+        .iter()
+        .map(|row| {
+            let marker = if row.node.get_children().is_empty() {
+                " "
+            } else if browser.expanded.contains(&row.key) {
+                "▾"
+            } else {
+                "▸"
+            };
+            let kind = match row.node.kind() {
+                NodeKind::And => " &".to_string(),
+                NodeKind::Or => " |".to_string(),
+                NodeKind::Voting => format!(
+                    " {}/{}",
+                    row.node.threshold().unwrap_or(1),
+                    row.node.get_children().len()
+                ),
+                NodeKind::Leaf => String::new(),
+            };
+            ListItem::new(format!(
+                "{}{} {}{}  [{}]",
+                "  ".repeat(row.depth),
+                marker,
+                row.node.title(),
+                kind,
+                row.node.feasibility_value()
+            ))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" {} ", directory_name)))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(browser.selected));
+    frame.render_stateful_widget(list, tree_area, &mut state);
+
+    let details = match browser.selected_row() {
+        Some(row) => describe(row, browser, config),
+        None => vec![Line::from("No .att files found.")],
+    };
+    frame.render_widget(
+        Paragraph::new(details)
+            .block(Block::bordered().title(" Details "))
+            .wrap(Wrap { trim: false }),
+        details_area,
+    );
+
+    frame.render_widget(Paragraph::new(HELP), help);
+}
+
+fn describe<'a>(row: &'a Row, browser: &'a Browser, config: &Config) -> Vec<Line<'a>> {
+    let node = &row.node;
+    let value = node.feasibility_value();
+
+    let mut lines = vec![
+        Line::styled(node.title(), Style::new().add_modifier(Modifier::BOLD)),
+        Line::from(""),
+        Line::from(format!("Type: {}", node.kind())),
+        Line::from(format!("Feasibility: {}", value)),
+    ];
+    if let Some(rating) = rate(&config.ratings, value) {
+        lines.push(Line::from(format!("Rating: {}", rating)));
+    }
+    if let Err(e) = node.feasibility() {
+        lines.push(Line::from(format!("Error: {}", e)));
+    }
+    if let Some(d) = node.detection() {
+        lines.push(Line::from(format!("Detection: {}", format_probability(d))));
+    }
+    if let Some(date) = node.assessed() {
+        lines.push(Line::from(format!("Assessed: {}", date.format("%Y-%m-%d"))));
+    }
+    if let Some(owner) = &node.review().owner {
+        lines.push(Line::from(format!("Owner: {}", owner)));
+    }
+    if let Some(status) = node.review().status {
+        lines.push(Line::from(format!("Status: {}", status)));
+    }
+    if !node.techniques().is_empty() {
+        lines.push(Line::from(format!(
+            "ATT&CK: {}",
+            node.techniques().join(", ")
+        )));
+    }
+    if !node.requirements().is_empty() {
+        lines.push(Line::from(format!(
+            "Requirements: {}",
+            node.requirements().join(", ")
+        )));
+    }
+    if !node.profiles().is_empty() {
+        lines.push(Line::from(format!(
+            "Profiles: {}",
+            node.profiles().join(", ")
+        )));
+    }
+    for (key, value) in node.attributes() {
+        lines.push(Line::from(format!("{}: {}", key, value)));
+    }
+    for d in node.defenses() {
+        lines.push(Line::from(format!(
+            "Defense: {} ({})",
+            d.description,
+            d.describe_effect()
+        )));
+    }
+    for a in node.assumptions() {
+        lines.push(Line::from(format!("Assumption: {}", a.description)));
+    }
+    if let Some(note) = node.note() {
+        lines.push(Line::from(""));
+        lines.extend(note.lines().map(Line::from));
+    }
+
+    let (file, _) = &browser.attack_trees[row.key.0];
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!("File: {}", file.display())));
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, rc::Rc};
+
+    use att::model::{feasible_step::FeasibleStep, generate_id, AndNode};
+
+    use super::Browser;
+
+    fn build_trees() -> super::AttackTrees {
+        let root: Rc<dyn FeasibleStep> = Rc::new(AndNode::new("Root", None, generate_id));
+        let inner: Rc<dyn FeasibleStep> =
+            Rc::new(AndNode::new("Inner", Some(root.clone()), generate_id));
+        let empty: Rc<dyn FeasibleStep> =
+            Rc::new(AndNode::new("Empty", Some(inner.clone()), generate_id));
+        inner.add_child(&empty);
+        root.add_child(&inner);
+
+        vec![(PathBuf::from("a.att"), root)]
+    }
+
+    fn titles(browser: &Browser) -> Vec<&str> {
+        browser.rows.iter().map(|r| r.node.title()).collect()
+    }
+
+    #[test]
+    fn nodes_are_expanded_and_collapsed() {
+        let mut browser = Browser::new(build_trees());
+        assert_eq!(titles(&browser), vec!["Root", "Inner"]);
+
+        browser.move_by(1);
+        browser.expand();
+        assert_eq!(titles(&browser), vec!["Root", "Inner", "Empty"]);
+
+        browser.collapse();
+        assert_eq!(titles(&browser), vec!["Root", "Inner"]);
+
+        // collapsing a collapsed node selects its parent
+        browser.collapse();
+        assert_eq!(browser.selected, 0);
+        browser.toggle();
+        assert_eq!(titles(&browser), vec!["Root"]);
+
+        browser.expand_all();
+        assert_eq!(titles(&browser), vec!["Root", "Inner", "Empty"]);
+        assert_eq!(browser.rows[2].key, (0, 2));
+    }
+}